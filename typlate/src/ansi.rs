@@ -0,0 +1,82 @@
+//! Filters that style interpolated values with ANSI escape codes, for terminal output.
+//!
+//! With the `ansi` feature enabled, the following built-in filters are available:
+//!
+//! - `color(name)` sets the foreground color and `bg(name)` sets the background color, where `name` is one of `black`,
+//!   `red`, `green`, `yellow`, `blue`, `magenta`, `cyan` and `white`, optionally prefixed with `bright_`
+//! - `bold`, `dim`, `italic` and `underline` set the text style
+//!
+//! Styling can be turned off globally with [`set_enabled`], e.g. when the output is not a terminal, in which case the
+//! filters leave values unchanged.
+//!
+//! ## Examples
+//!
+//! ```
+//! # use typlate::{TemplateParams, TemplateString};
+//! #[derive(TemplateParams)]
+//! struct Status {
+//!     level: &'static str,
+//!     msg: &'static str,
+//! }
+//!
+//! let template: TemplateString<Status> = "[{level|color(red)}] {msg|bold}".parse().unwrap();
+//! let status = Status { level: "error", msg: "disk full" };
+//! assert_eq!(template.format(&status), "[\x1b[31merror\x1b[0m] \x1b[1mdisk full\x1b[0m");
+//!
+//! typlate::ansi::set_enabled(false);
+//! assert_eq!(template.format(&status), "[error] disk full");
+//! # typlate::ansi::set_enabled(true);
+//! ```
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Enable or disable styling by the ANSI filters for the whole process. Styling is enabled by default.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether the ANSI filters currently style values.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// The names of the ANSI filters.
+pub(crate) const FILTERS: &[&str] = &["color", "bg", "bold", "dim", "italic", "underline"];
+
+/// Apply an ANSI filter, or return `None` if the name is not an ANSI filter or its color is unknown.
+pub(crate) fn apply(name: &str, args: &[String], value: &str) -> Option<String> {
+    let code = match name {
+        "color" => color_code(args.first()?)?,
+        "bg" => color_code(args.first()?)? + 10,
+        "bold" => 1,
+        "dim" => 2,
+        "italic" => 3,
+        "underline" => 4,
+        _ => return None,
+    };
+    if !is_enabled() {
+        return Some(value.to_string());
+    }
+    Some(format!("\x1b[{code}m{value}\x1b[0m"))
+}
+
+fn color_code(name: &str) -> Option<u8> {
+    let (base, name) = match name.strip_prefix("bright_") {
+        Some(name) => (90, name),
+        None => (30, name),
+    };
+    let offset = match name {
+        "black" => 0,
+        "red" => 1,
+        "green" => 2,
+        "yellow" => 3,
+        "blue" => 4,
+        "magenta" => 5,
+        "cyan" => 6,
+        "white" => 7,
+        _ => return None,
+    };
+    Some(base + offset)
+}
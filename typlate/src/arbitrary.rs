@@ -0,0 +1,114 @@
+use std::ops::ControlFlow;
+
+use ::arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::cond::{Comparison, Condition, Operand};
+use crate::expr::{Expr, Op};
+use crate::filter::FilterCall;
+use crate::string::{Builder, TemplateElement};
+use crate::{TemplateString, TemplateStringParams};
+
+/// How deep select blocks and conditional sections are nested at most.
+const MAX_DEPTH: usize = 2;
+
+/// The keys that generated select blocks choose from, in addition to the `other` arm they always have.
+const SELECT_KEYS: [&str; 4] = ["zero", "one", "two", "few"];
+
+/// Generate structurally valid templates over the fields of `T`, e.g. to fuzz code that consumes templates.
+///
+/// Generated templates only use the built-in filters and reference fields by index, so they always render, and their
+/// source form parses back to the same template. Templates for a parameter type without fields are plain text.
+impl<'a, T: TemplateStringParams> Arbitrary<'a> for TemplateString<T> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(TemplateString::from_elements(arbitrary_elements::<T>(u, 0)?))
+    }
+}
+
+fn arbitrary_elements<T: TemplateStringParams>(u: &mut Unstructured, depth: usize) -> Result<Vec<TemplateElement>> {
+    let mut builder = Builder::<T>::new();
+    u.arbitrary_loop(None, Some(8), |u| {
+        builder.push_element(arbitrary_element::<T>(u, depth)?);
+        Ok(ControlFlow::Continue(()))
+    })?;
+    Ok(builder.into_elements())
+}
+
+fn arbitrary_element<T: TemplateStringParams>(u: &mut Unstructured, depth: usize) -> Result<TemplateElement> {
+    let fields = T::FIELDS.len();
+    if fields == 0 {
+        return arbitrary_text(u, depth);
+    }
+    let kinds = if depth < MAX_DEPTH { 6 } else { 4 };
+    Ok(match u.choose_index(kinds)? {
+        0 => return arbitrary_text(u, depth),
+        1 => TemplateElement::Var(u.choose_index(fields)?),
+        2 => TemplateElement::Filtered {
+            index: u.choose_index(fields)?,
+            filters: {
+                let mut filters = vec![];
+                u.arbitrary_loop(Some(1), Some(3), |u| {
+                    filters.push(arbitrary_filter(u)?);
+                    Ok(ControlFlow::Continue(()))
+                })?;
+                filters
+            },
+        },
+        3 => TemplateElement::Expr(Expr::Binary(
+            Box::new(Expr::Field(u.choose_index(fields)?)),
+            *u.choose(&[Op::Add, Op::Sub, Op::Mul, Op::Div])?,
+            Box::new(Expr::Literal(u.int_in_range(1..=100u8)?.to_string())),
+        )),
+        4 => {
+            let index = u.choose_index(fields)?;
+            let mut arms = vec![];
+            for key in SELECT_KEYS {
+                if u.arbitrary()? {
+                    arms.push((key.to_string(), arbitrary_elements::<T>(u, depth + 1)?));
+                }
+            }
+            arms.push(("other".to_string(), arbitrary_elements::<T>(u, depth + 1)?));
+            TemplateElement::Select {
+                index,
+                facet: None,
+                arms,
+            }
+        }
+        _ => TemplateElement::Conditional {
+            condition: Condition {
+                lhs: Operand::Expr(Expr::Field(u.choose_index(fields)?)),
+                comparison: match u.arbitrary()? {
+                    true => {
+                        let (comparison, _) = *u.choose(&Comparison::ALL)?;
+                        let rhs = Expr::Literal(u.int_in_range(0..=100u8)?.to_string());
+                        Some((comparison, Operand::Expr(rhs)))
+                    }
+                    false => None,
+                },
+            },
+            then: arbitrary_elements::<T>(u, depth + 1)?,
+            otherwise: arbitrary_elements::<T>(u, depth + 1)?,
+        },
+    })
+}
+
+/// Generate literal text, without brackets in nested branches since a `}` at the end of a select arm would close it.
+fn arbitrary_text(u: &mut Unstructured, depth: usize) -> Result<TemplateElement> {
+    let mut text: String = u.arbitrary()?;
+    if depth > 0 {
+        text.retain(|char| char != '{' && char != '}');
+    }
+    Ok(TemplateElement::Text(text))
+}
+
+fn arbitrary_filter(u: &mut Unstructured) -> Result<FilterCall> {
+    let (name, args) = match u.choose_index(4)? {
+        0 => ("upper", vec![]),
+        1 => ("lower", vec![]),
+        2 => ("trim", vec![]),
+        _ => ("truncate", vec![u.int_in_range(1..=40u8)?.to_string()]),
+    };
+    Ok(FilterCall {
+        name: name.to_string(),
+        args,
+    })
+}
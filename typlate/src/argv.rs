@@ -0,0 +1,53 @@
+use crate::string::Segment;
+use crate::{TemplateString, TemplateStringParams};
+
+impl<T: TemplateStringParams> TemplateString<T> {
+    /// Render the template as the arguments of a command, e.g. for [`std::process::Command`].
+    ///
+    /// Literal text is split on whitespace, while every interpolated value stays within a single argument, even if
+    /// it contains whitespace or is empty. A value directly adjacent to literal text joins the argument of that text,
+    /// so `--out={path}` renders as one argument. Values are rendered without bidirectional isolation.
+    ///
+    /// Since the arguments are never passed through a shell, values cannot inject additional arguments or commands.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Copy {
+    ///     src: String,
+    ///     dst: String,
+    /// }
+    ///
+    /// let template: TemplateString<Copy> = "cp -r {src} --target-directory={dst}".parse().unwrap();
+    /// let copy = Copy { src: "my files; rm -rf ~".into(), dst: "/tmp/backup dir".into() };
+    /// assert_eq!(
+    ///     template.format_argv(&copy),
+    ///     ["cp", "-r", "my files; rm -rf ~", "--target-directory=/tmp/backup dir"],
+    /// );
+    /// ```
+    pub fn format_argv(&self, params: &T) -> Vec<String> {
+        let mut argv = vec![];
+        let mut current: Option<String> = None;
+        for segment in self.segments(params) {
+            match segment {
+                Segment::Text(text) => {
+                    for char in text.chars() {
+                        if char.is_whitespace() {
+                            argv.extend(current.take());
+                        } else {
+                            current.get_or_insert_default().push(char);
+                        }
+                    }
+                }
+                Segment::Placeholder(element) => {
+                    let value = self.render_placeholder(element, params, false);
+                    current.get_or_insert_default().push_str(&value);
+                }
+            }
+        }
+        argv.extend(current);
+        argv
+    }
+}
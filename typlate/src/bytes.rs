@@ -0,0 +1,562 @@
+use std::fmt;
+
+use crate::cond::{Comparison, Condition, Operand};
+use crate::expr::{Expr, Op};
+use crate::filter::FilterCall;
+use crate::format_spec::{Align, Count, FormatSpec, Kind};
+use crate::parse::InvalidElement;
+use crate::string::TemplateElement;
+use crate::{ParseOptions, TemplateString, TemplateStringParams};
+
+/// The first bytes of every encoded template, followed by a format version.
+const MAGIC: &[u8; 4] = b"TPL\x02";
+
+const TEXT: u8 = 0;
+const VAR: u8 = 1;
+const FILTERED: u8 = 2;
+const SELECT: u8 = 3;
+const BLOCK: u8 = 4;
+const EXPR: u8 = 5;
+const CONDITIONAL: u8 = 6;
+const NAMESPACED: u8 = 7;
+const FORMATTED: u8 = 8;
+
+const EXPR_FIELD: u8 = 0;
+const EXPR_LITERAL: u8 = 1;
+const EXPR_BINARY: u8 = 2;
+
+const OPERAND_EXPR: u8 = 0;
+const OPERAND_STR: u8 = 1;
+
+const COUNT_NONE: u8 = 0;
+const COUNT_LITERAL: u8 = 1;
+const COUNT_FIELD: u8 = 2;
+
+/// An error that occurred while decoding a template with [`TemplateString::from_bytes`].
+///
+/// Offsets are byte positions in the encoded input.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DecodeError {
+    /// The input does not start with the header written by [`TemplateString::to_bytes`], or was written by an
+    /// incompatible version.
+    InvalidHeader,
+    /// The input ends in the middle of a template.
+    UnexpectedEnd,
+    /// The input has an unknown element tag.
+    InvalidTag { tag: u8, offset: usize },
+    /// A string in the input is not valid UTF-8.
+    InvalidUtf8 { offset: usize },
+    /// The template was encoded for a parameter type with other field names, or with the fields in another order.
+    FieldMismatch { fields: Vec<String> },
+    /// A placeholder refers to a field index that the parameter type does not have.
+    FieldOutOfBounds { index: usize },
+    /// The input has bytes after the end of the template.
+    TrailingBytes { offset: usize },
+    /// Sections or expressions are nested more levels deep than [`ParseOptions::max_depth`].
+    TooDeep { offset: usize },
+    /// A placeholder such as `{env:HOME}` uses a namespace that the parse options do not accept.
+    UnknownNamespace { namespace: String },
+    /// A placeholder uses a filter that is neither built in nor accepted by the parse options.
+    UnknownFilter { name: String },
+    /// A placeholder of the field at `index` has a format spec that the parser rejects, e.g. an out-of-range width.
+    InvalidFormatSpec { index: usize },
+    /// A placeholder of the field at `index` has a format spec that the field does not support, e.g. a radix format on a
+    /// field that is not an integer.
+    UnsupportedFormat { index: usize },
+    /// A select block on the field at `index` has no `other` branch, or an invalid key or facet.
+    InvalidSelect { index: usize },
+    /// A block has an invalid name, or a name used by another block.
+    InvalidBlock { name: String },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidHeader => f.write_str("Invalid template header"),
+            Self::UnexpectedEnd => f.write_str("Unexpected end of input"),
+            Self::InvalidTag { tag, offset } => write!(f, "Invalid element tag {tag} at offset {offset}"),
+            Self::InvalidUtf8 { offset } => write!(f, "Invalid UTF-8 string at offset {offset}"),
+            Self::FieldMismatch { fields } => write!(f, "Template fields {fields:?} do not match the parameter fields"),
+            Self::FieldOutOfBounds { index } => write!(f, "Field index out of bounds: {index}"),
+            Self::TrailingBytes { offset } => write!(f, "Trailing bytes at offset {offset}"),
+            Self::TooDeep { offset } => write!(f, "Nesting too deep at offset {offset}"),
+            Self::UnknownNamespace { namespace } => write!(f, "Unknown namespace: {namespace}"),
+            Self::UnknownFilter { name } => write!(f, "Unknown filter: {name}"),
+            Self::InvalidFormatSpec { index } => write!(f, "Invalid format spec for field index {index}"),
+            Self::UnsupportedFormat { index } => write!(f, "Unsupported format for field index {index}"),
+            Self::InvalidSelect { index } => write!(f, "Invalid select block for field index {index}"),
+            Self::InvalidBlock { name } => write!(f, "Invalid block name: {name}"),
+        }
+    }
+}
+
+impl DecodeError {
+    fn invalid(error: InvalidElement) -> Self {
+        match error {
+            InvalidElement::FieldOutOfBounds(index) => Self::FieldOutOfBounds { index },
+            InvalidElement::UnknownNamespace(namespace) => Self::UnknownNamespace { namespace },
+            InvalidElement::UnknownFilter(name) => Self::UnknownFilter { name },
+            InvalidElement::InvalidFormatSpec(index) => Self::InvalidFormatSpec { index },
+            InvalidElement::UnsupportedFormat(index) => Self::UnsupportedFormat { index },
+            InvalidElement::InvalidSelect(index) => Self::InvalidSelect { index },
+            InvalidElement::InvalidBlock(name) => Self::InvalidBlock { name },
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl<T: TemplateStringParams> TemplateString<T> {
+    /// Encode the parsed template in a compact binary format, e.g. to embed a preprocessed catalog with
+    /// `include_bytes!` and load it without parsing.
+    ///
+    /// Strings and sequences are prefixed with their length as a little-endian `u32`. Placeholders store field
+    /// indices, so the field names of `T` are stored in the header, and the bytes can only be decoded for a parameter
+    /// type with the same fields in the same order.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Data {
+    ///     name: String,
+    /// }
+    ///
+    /// let template: TemplateString<Data> = "Hello {name|upper}!".parse().unwrap();
+    /// let bytes = template.to_bytes();
+    /// assert_eq!(TemplateString::<Data>::from_bytes(&bytes).unwrap(), template);
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut output = MAGIC.to_vec();
+        output.push(self.bidi_isolation() as u8);
+        encode_len(&mut output, T::FIELDS.len());
+        T::FIELDS.iter().for_each(|field| encode_str(&mut output, field));
+        encode_elements(&mut output, self.elements());
+        output
+    }
+
+    /// Decode a template encoded with [`to_bytes`](Self::to_bytes).
+    ///
+    /// The input is checked to be well-formed and to be encoded for the fields of `T`, but the template syntax is not
+    /// parsed again. As when parsing with the default options, namespaces other than `global` and filters that are not
+    /// built in are rejected.
+    pub fn from_bytes(input: &[u8]) -> Result<Self, DecodeError> {
+        Self::from_bytes_with(input, &ParseOptions::default())
+    }
+
+    /// Decode a template encoded with [`to_bytes`](Self::to_bytes), accepting the namespaces and filters accepted by
+    /// the options, and nesting up to their maximum depth.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{DecodeError, ParseOptions, TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Data {
+    ///     name: String,
+    /// }
+    ///
+    /// let options = ParseOptions::builder().env_vars(true).build();
+    /// let template = TemplateString::<Data>::parse_with("{name} in {env:HOME}", &options).unwrap();
+    /// let bytes = template.to_bytes();
+    /// assert_eq!(TemplateString::<Data>::from_bytes_with(&bytes, &options), Ok(template));
+    /// assert_eq!(
+    ///     TemplateString::<Data>::from_bytes(&bytes),
+    ///     Err(DecodeError::UnknownNamespace { namespace: "env".into() })
+    /// );
+    /// ```
+    pub fn from_bytes_with(input: &[u8], options: &ParseOptions) -> Result<Self, DecodeError> {
+        let rest = input.strip_prefix(MAGIC).ok_or(DecodeError::InvalidHeader)?;
+        let mut decoder = Decoder {
+            input,
+            offset: input.len() - rest.len(),
+            fields: T::FIELDS.len(),
+            max_depth: options.max_depth(),
+            depth: 0,
+        };
+        let isolate = match decoder.u8()? {
+            0 => false,
+            1 => true,
+            _ => return Err(DecodeError::InvalidHeader),
+        };
+        let fields = (0..decoder.len()?)
+            .map(|_| decoder.string())
+            .collect::<Result<Vec<_>, _>>()?;
+        if fields != T::FIELDS {
+            return Err(DecodeError::FieldMismatch { fields });
+        }
+        let elements = decoder.elements()?;
+        if decoder.offset < input.len() {
+            return Err(DecodeError::TrailingBytes { offset: decoder.offset });
+        }
+        crate::parse::validate_elements::<T>(&elements, options).map_err(DecodeError::invalid)?;
+        Ok(Self::from_elements(elements).with_bidi_isolation(isolate))
+    }
+}
+
+fn encode_len(output: &mut Vec<u8>, len: usize) {
+    let len = u32::try_from(len).expect("template too large to encode");
+    output.extend_from_slice(&len.to_le_bytes());
+}
+
+fn encode_str(output: &mut Vec<u8>, value: &str) {
+    encode_len(output, value.len());
+    output.extend_from_slice(value.as_bytes());
+}
+
+fn encode_elements(output: &mut Vec<u8>, elements: &[TemplateElement]) {
+    encode_len(output, elements.len());
+    for element in elements {
+        match element {
+            TemplateElement::Text(text) => {
+                output.push(TEXT);
+                encode_str(output, text);
+            }
+            TemplateElement::Var(index) => {
+                output.push(VAR);
+                encode_len(output, *index);
+            }
+            TemplateElement::Filtered { index, filters } => {
+                output.push(FILTERED);
+                encode_len(output, *index);
+                encode_len(output, filters.len());
+                for filter in filters {
+                    encode_str(output, &filter.name);
+                    encode_len(output, filter.args.len());
+                    filter.args.iter().for_each(|arg| encode_str(output, arg));
+                }
+            }
+            TemplateElement::Formatted { index, spec } => {
+                output.push(FORMATTED);
+                encode_len(output, *index);
+                encode_len(output, spec.fill as usize);
+                output.push(spec.align.map_or(0, |align| align.as_char() as u8));
+                output.push(u8::from(spec.sign) | u8::from(spec.zero) << 1 | u8::from(spec.alternate) << 2);
+                encode_count(output, spec.width);
+                encode_count(output, spec.precision);
+                let kind = Kind::ALL.iter().position(|(kind, _)| *kind == spec.kind).unwrap();
+                output.push(kind as u8);
+            }
+            TemplateElement::Expr(expr) => {
+                output.push(EXPR);
+                encode_expr(output, expr);
+            }
+            TemplateElement::Namespaced { namespace, name } => {
+                output.push(NAMESPACED);
+                encode_str(output, namespace);
+                encode_str(output, name);
+            }
+            TemplateElement::Select { index, facet, arms } => {
+                output.push(SELECT);
+                encode_len(output, *index);
+                match facet {
+                    Some(facet) => {
+                        output.push(1);
+                        encode_str(output, facet);
+                    }
+                    None => output.push(0),
+                }
+                encode_len(output, arms.len());
+                for (key, branch) in arms {
+                    encode_str(output, key);
+                    encode_elements(output, branch);
+                }
+            }
+            TemplateElement::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => {
+                output.push(CONDITIONAL);
+                encode_operand(output, &condition.lhs);
+                match &condition.comparison {
+                    Some((comparison, rhs)) => {
+                        let position = Comparison::ALL.iter().position(|(op, _)| op == comparison).unwrap();
+                        output.push(1 + position as u8);
+                        encode_operand(output, rhs);
+                    }
+                    None => output.push(0),
+                }
+                encode_elements(output, then);
+                encode_elements(output, otherwise);
+            }
+            TemplateElement::Block { name, elements } => {
+                output.push(BLOCK);
+                encode_str(output, name);
+                encode_elements(output, elements);
+            }
+        }
+    }
+}
+
+fn encode_expr(output: &mut Vec<u8>, expr: &Expr) {
+    match expr {
+        Expr::Field(index) => {
+            output.push(EXPR_FIELD);
+            encode_len(output, *index);
+        }
+        Expr::Literal(literal) => {
+            output.push(EXPR_LITERAL);
+            encode_str(output, literal);
+        }
+        Expr::Binary(lhs, op, rhs) => {
+            output.push(EXPR_BINARY);
+            output.push(op.as_char() as u8);
+            encode_expr(output, lhs);
+            encode_expr(output, rhs);
+        }
+    }
+}
+
+fn encode_operand(output: &mut Vec<u8>, operand: &Operand) {
+    match operand {
+        Operand::Expr(expr) => {
+            output.push(OPERAND_EXPR);
+            encode_expr(output, expr);
+        }
+        Operand::Str(text) => {
+            output.push(OPERAND_STR);
+            encode_str(output, text);
+        }
+    }
+}
+
+fn encode_count(output: &mut Vec<u8>, count: Option<Count>) {
+    match count {
+        None => output.push(COUNT_NONE),
+        Some(Count::Literal(count)) => {
+            output.push(COUNT_LITERAL);
+            encode_len(output, count);
+        }
+        Some(Count::Field(index)) => {
+            output.push(COUNT_FIELD);
+            encode_len(output, index);
+        }
+    }
+}
+
+struct Decoder<'i> {
+    input: &'i [u8],
+    offset: usize,
+    /// The number of fields of the parameter type.
+    fields: usize,
+    max_depth: usize,
+    /// The number of sections that enclose the current offset.
+    depth: usize,
+}
+
+impl Decoder<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8], DecodeError> {
+        let bytes = self
+            .input
+            .get(self.offset..self.offset.saturating_add(len))
+            .ok_or(DecodeError::UnexpectedEnd)?;
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn len(&mut self) -> Result<usize, DecodeError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+    }
+
+    fn index(&mut self) -> Result<usize, DecodeError> {
+        let index = self.len()?;
+        match index < self.fields {
+            true => Ok(index),
+            false => Err(DecodeError::FieldOutOfBounds { index }),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, DecodeError> {
+        let len = self.len()?;
+        let offset = self.offset;
+        let bytes = self.take(len)?;
+        match std::str::from_utf8(bytes) {
+            Ok(value) => Ok(value.to_string()),
+            Err(_) => Err(DecodeError::InvalidUtf8 { offset }),
+        }
+    }
+
+    /// Decode the elements of a section, failing if that exceeds the maximum depth.
+    fn nested(&mut self) -> Result<Vec<TemplateElement>, DecodeError> {
+        if self.depth >= self.max_depth {
+            return Err(DecodeError::TooDeep { offset: self.offset });
+        }
+        self.depth += 1;
+        let elements = self.elements();
+        self.depth -= 1;
+        elements
+    }
+
+    fn elements(&mut self) -> Result<Vec<TemplateElement>, DecodeError> {
+        let len = self.len()?;
+        // Every element takes at least 5 bytes, which bounds the allocation for corrupted lengths.
+        let mut elements = Vec::with_capacity(len.min(self.input.len() / 5));
+        for _ in 0..len {
+            let offset = self.offset;
+            let element = match self.u8()? {
+                TEXT => TemplateElement::Text(self.string()?),
+                VAR => TemplateElement::Var(self.index()?),
+                FILTERED => {
+                    let index = self.index()?;
+                    let filters = (0..self.len()?)
+                        .map(|_| {
+                            let name = self.string()?;
+                            let args = (0..self.len()?).map(|_| self.string()).collect::<Result<_, _>>()?;
+                            Ok(FilterCall { name, args })
+                        })
+                        .collect::<Result<_, _>>()?;
+                    TemplateElement::Filtered { index, filters }
+                }
+                SELECT => {
+                    let index = self.index()?;
+                    let facet = match self.u8()? {
+                        0 => None,
+                        1 => Some(self.string()?),
+                        tag => {
+                            return Err(DecodeError::InvalidTag {
+                                tag,
+                                offset: self.offset - 1,
+                            });
+                        }
+                    };
+                    let arms = (0..self.len()?)
+                        .map(|_| Ok((self.string()?, self.nested()?)))
+                        .collect::<Result<_, _>>()?;
+                    TemplateElement::Select { index, facet, arms }
+                }
+                BLOCK => TemplateElement::Block {
+                    name: self.string()?,
+                    elements: self.nested()?,
+                },
+                EXPR => TemplateElement::Expr(self.expr(0)?),
+                FORMATTED => TemplateElement::Formatted {
+                    index: self.index()?,
+                    spec: self.format_spec()?,
+                },
+                NAMESPACED => TemplateElement::Namespaced {
+                    namespace: self.string()?,
+                    name: self.string()?,
+                },
+                CONDITIONAL => {
+                    let lhs = self.operand()?;
+                    let offset = self.offset;
+                    let comparison = match self.u8()? {
+                        0 => None,
+                        tag => {
+                            let comparison = Comparison::ALL
+                                .get(usize::from(tag) - 1)
+                                .ok_or(DecodeError::InvalidTag { tag, offset })?;
+                            Some((comparison.0, self.operand()?))
+                        }
+                    };
+                    TemplateElement::Conditional {
+                        condition: Condition { lhs, comparison },
+                        then: self.nested()?,
+                        otherwise: self.nested()?,
+                    }
+                }
+                tag => return Err(DecodeError::InvalidTag { tag, offset }),
+            };
+            elements.push(element);
+        }
+        Ok(elements)
+    }
+
+    /// Decode an expression nested in `depth` binary operations.
+    fn expr(&mut self, depth: usize) -> Result<Expr, DecodeError> {
+        let offset = self.offset;
+        if depth > self.max_depth {
+            return Err(DecodeError::TooDeep { offset });
+        }
+        match self.u8()? {
+            EXPR_FIELD => Ok(Expr::Field(self.index()?)),
+            EXPR_LITERAL => Ok(Expr::Literal(self.string()?)),
+            EXPR_BINARY => {
+                let offset = self.offset;
+                let tag = self.u8()?;
+                let op = Op::from_char(tag.into()).ok_or(DecodeError::InvalidTag { tag, offset })?;
+                Ok(Expr::Binary(
+                    Box::new(self.expr(depth + 1)?),
+                    op,
+                    Box::new(self.expr(depth + 1)?),
+                ))
+            }
+            tag => Err(DecodeError::InvalidTag { tag, offset }),
+        }
+    }
+
+    fn format_spec(&mut self) -> Result<FormatSpec, DecodeError> {
+        let offset = self.offset;
+        let fill = u32::try_from(self.len()?)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or(DecodeError::InvalidUtf8 { offset })?;
+        let offset = self.offset;
+        let align = match self.u8()? {
+            0 => None,
+            tag => Some(Align::from_char(tag.into()).ok_or(DecodeError::InvalidTag { tag, offset })?),
+        };
+        let flags = self.u8()?;
+        Ok(FormatSpec {
+            fill,
+            align,
+            sign: flags & 1 != 0,
+            zero: flags & 2 != 0,
+            alternate: flags & 4 != 0,
+            width: self.count()?,
+            precision: self.count()?,
+            kind: {
+                let offset = self.offset;
+                let tag = self.u8()?;
+                Kind::ALL
+                    .get(usize::from(tag))
+                    .ok_or(DecodeError::InvalidTag { tag, offset })?
+                    .0
+            },
+        })
+    }
+
+    fn count(&mut self) -> Result<Option<Count>, DecodeError> {
+        let offset = self.offset;
+        match self.u8()? {
+            COUNT_NONE => Ok(None),
+            COUNT_LITERAL => Ok(Some(Count::Literal(self.len()?))),
+            COUNT_FIELD => Ok(Some(Count::Field(self.index()?))),
+            tag => Err(DecodeError::InvalidTag { tag, offset }),
+        }
+    }
+
+    fn operand(&mut self) -> Result<Operand, DecodeError> {
+        let offset = self.offset;
+        match self.u8()? {
+            OPERAND_EXPR => Ok(Operand::Expr(self.expr(0)?)),
+            OPERAND_STR => Ok(Operand::Str(self.string()?)),
+            tag => Err(DecodeError::InvalidTag { tag, offset }),
+        }
+    }
+}
+
+/// Borsh stores the [`to_bytes`](TemplateString::to_bytes) encoding as a length-prefixed byte vector, so that loading
+/// does not need to parse the template again.
+#[cfg(feature = "borsh")]
+impl<T: TemplateStringParams> borsh::BorshSerialize for TemplateString<T> {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.to_bytes().serialize(writer)
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl<T: TemplateStringParams> borsh::BorshDeserialize for TemplateString<T> {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let bytes = Vec::<u8>::deserialize_reader(reader)?;
+        Self::from_bytes(&bytes).map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+    }
+}
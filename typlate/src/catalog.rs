@@ -0,0 +1,258 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Display};
+
+use crate::{TemplateParseError, TemplateString, TemplateStringParams};
+
+/// A problem found while validating a message catalog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CatalogError<K> {
+    /// The template failed to parse for a reason other than an unknown placeholder.
+    Parse { key: K, error: TemplateParseError },
+    /// The template references a placeholder that is not a field of the parameter type.
+    UnknownPlaceholder { key: K, name: String },
+    /// The key exists in the reference catalog but not in the validated catalog.
+    MissingKey { key: K },
+    /// The key exists in the validated catalog but not in the reference catalog.
+    ExtraKey { key: K },
+    /// The template omits a placeholder that the reference template uses.
+    MissingPlaceholder { key: K, name: &'static str },
+    /// The template uses a placeholder that the reference template does not.
+    ExtraPlaceholder { key: K, name: &'static str },
+}
+
+impl<K> CatalogError<K> {
+    /// The catalog key this error belongs to.
+    pub fn key(&self) -> &K {
+        match self {
+            Self::Parse { key, .. }
+            | Self::UnknownPlaceholder { key, .. }
+            | Self::MissingKey { key }
+            | Self::ExtraKey { key }
+            | Self::MissingPlaceholder { key, .. }
+            | Self::ExtraPlaceholder { key, .. } => key,
+        }
+    }
+
+    fn from_parse(key: K, error: TemplateParseError) -> Self {
+        match error {
+            TemplateParseError::UnknownField { name, .. } => Self::UnknownPlaceholder { key, name },
+            error => Self::Parse { key, error },
+        }
+    }
+}
+
+impl<K: Display> Display for CatalogError<K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Parse { key, error } => write!(f, "{key}: {error}"),
+            Self::UnknownPlaceholder { key, name } => write!(f, "{key}: Unknown placeholder {{{name}}}"),
+            Self::MissingKey { key } => write!(f, "{key}: Missing key"),
+            Self::ExtraKey { key } => write!(f, "{key}: Key not present in reference catalog"),
+            Self::MissingPlaceholder { key, name } => write!(f, "{key}: Missing placeholder {{{name}}}"),
+            Self::ExtraPlaceholder { key, name } => write!(f, "{key}: Placeholder {{{name}}} not used in reference"),
+        }
+    }
+}
+
+impl<K: fmt::Debug + Display> std::error::Error for CatalogError<K> {}
+
+/// Parse every entry of a catalog, reporting parse failures and unknown placeholders per key.
+///
+/// ## Examples
+///
+/// ```
+/// # use typlate::{CatalogError, TemplateParams};
+/// #[derive(TemplateParams)]
+/// struct User {
+///     name: String,
+/// }
+///
+/// let errors = typlate::validate_catalog::<User, _, _>([
+///     ("welcome", "Hello {name}"),
+///     ("farewell", "Bye {nmae}"),
+/// ]);
+/// assert_eq!(errors, [CatalogError::UnknownPlaceholder { key: "farewell", name: "nmae".into() }]);
+/// ```
+pub fn validate_catalog<T, K, S>(entries: impl IntoIterator<Item = (K, S)>) -> Vec<CatalogError<K>>
+where
+    T: TemplateStringParams,
+    S: AsRef<str>,
+{
+    let mut errors = vec![];
+    for (key, source) in entries {
+        if let Err(error) = source.as_ref().parse::<TemplateString<T>>() {
+            errors.push(CatalogError::from_parse(key, error));
+        }
+    }
+    errors
+}
+
+/// Validate a catalog against a reference catalog (usually the source locale).
+///
+/// In addition to the checks of [`validate_catalog`], this reports keys that are missing from or not present in the
+/// reference catalog, and placeholders whose coverage differs from the reference template. Entries of the reference
+/// catalog that fail to parse are not reported; validate the reference catalog separately.
+///
+/// ## Examples
+///
+/// ```
+/// # use typlate::{CatalogError, TemplateParams};
+/// #[derive(TemplateParams)]
+/// struct Cart {
+///     count: u32,
+///     user: String,
+/// }
+///
+/// let errors = typlate::validate_catalog_against::<Cart, _, _, _>(
+///     [("items", "{user} has {count} items"), ("empty", "{user} has no items")],
+///     [("items", "{user} a des articles")],
+/// );
+/// assert_eq!(errors, [
+///     CatalogError::MissingPlaceholder { key: "items", name: "count" },
+///     CatalogError::MissingKey { key: "empty" },
+/// ]);
+/// ```
+pub fn validate_catalog_against<T, K, R, S>(
+    reference: impl IntoIterator<Item = (K, R)>,
+    entries: impl IntoIterator<Item = (K, S)>,
+) -> Vec<CatalogError<K>>
+where
+    T: TemplateStringParams,
+    K: Ord + Clone,
+    R: AsRef<str>,
+    S: AsRef<str>,
+{
+    let mut reference: BTreeMap<K, Option<BTreeSet<&'static str>>> = reference
+        .into_iter()
+        .map(|(key, source)| (key, placeholder_set::<T>(source.as_ref())))
+        .collect();
+
+    let mut errors = vec![];
+    for (key, source) in entries {
+        let template = match source.as_ref().parse::<TemplateString<T>>() {
+            Ok(template) => template,
+            Err(error) => {
+                reference.remove(&key);
+                errors.push(CatalogError::from_parse(key, error));
+                continue;
+            }
+        };
+        let Some(expected) = reference.remove(&key) else {
+            errors.push(CatalogError::ExtraKey { key });
+            continue;
+        };
+        if let Some(expected) = expected {
+            errors.extend(placeholder_diff(&key, &expected, &template.placeholders().collect()));
+        }
+    }
+
+    errors.extend(reference.into_keys().map(|key| CatalogError::MissingKey { key }));
+    errors
+}
+
+fn placeholder_set<T: TemplateStringParams>(source: &str) -> Option<BTreeSet<&'static str>> {
+    let template = source.parse::<TemplateString<T>>().ok()?;
+    Some(template.placeholders().collect())
+}
+
+fn placeholder_diff<K: Clone>(
+    key: &K,
+    expected: &BTreeSet<&'static str>,
+    actual: &BTreeSet<&'static str>,
+) -> Vec<CatalogError<K>> {
+    let missing = expected
+        .difference(actual)
+        .map(|&name| CatalogError::MissingPlaceholder { key: key.clone(), name });
+    let extra = actual
+        .difference(expected)
+        .map(|&name| CatalogError::ExtraPlaceholder { key: key.clone(), name });
+    missing.chain(extra).collect()
+}
+
+/// Placeholders of a translated template that differ from the reference template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaceholderMismatch<L, K> {
+    /// The locale of the translated catalog.
+    pub locale: L,
+    /// The catalog key of the template.
+    pub key: K,
+    /// Placeholders used by the reference template but dropped by the translation.
+    pub missing: Vec<&'static str>,
+    /// Placeholders used by the translation but not by the reference template.
+    pub added: Vec<&'static str>,
+}
+
+impl<L: Display, K: Display> Display for PlaceholderMismatch<L, K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}] {}:", self.locale, self.key)?;
+        for name in &self.missing {
+            write!(f, " -{{{name}}}")?;
+        }
+        for name in &self.added {
+            write!(f, " +{{{name}}}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Compare the placeholders of every translated catalog with a reference catalog.
+///
+/// Returns one mismatch per locale and key whose placeholder set differs from the reference. Keys that are missing
+/// from either side and templates that fail to parse are skipped; use [`validate_catalog_against`] to report those.
+///
+/// ## Examples
+///
+/// ```
+/// # use typlate::TemplateParams;
+/// #[derive(TemplateParams)]
+/// struct Cart {
+///     count: u32,
+///     user: String,
+/// }
+///
+/// let reference = [("items", "{user} has {count} items")];
+/// let translations = [
+///     ("fr", vec![("items", "{user} a {count} articles")]),
+///     ("de", vec![("items", "{user} hat Artikel")]),
+/// ];
+/// let mismatches = typlate::check_translations::<Cart, _, _, _, _, _>(reference, translations);
+/// assert_eq!(mismatches.len(), 1);
+/// assert_eq!(mismatches[0].to_string(), "[de] items: -{count}");
+/// ```
+pub fn check_translations<T, L, K, R, S, C>(
+    reference: impl IntoIterator<Item = (K, R)>,
+    translations: impl IntoIterator<Item = (L, C)>,
+) -> Vec<PlaceholderMismatch<L, K>>
+where
+    T: TemplateStringParams,
+    L: Clone,
+    K: Ord,
+    R: AsRef<str>,
+    S: AsRef<str>,
+    C: IntoIterator<Item = (K, S)>,
+{
+    let reference: BTreeMap<K, BTreeSet<&'static str>> = reference
+        .into_iter()
+        .filter_map(|(key, source)| Some((key, placeholder_set::<T>(source.as_ref())?)))
+        .collect();
+
+    let mut mismatches = vec![];
+    for (locale, catalog) in translations {
+        for (key, source) in catalog {
+            let (Some(expected), Some(actual)) = (reference.get(&key), placeholder_set::<T>(source.as_ref())) else {
+                continue;
+            };
+            let missing: Vec<_> = expected.difference(&actual).copied().collect();
+            let added: Vec<_> = actual.difference(expected).copied().collect();
+            if !missing.is_empty() || !added.is_empty() {
+                mismatches.push(PlaceholderMismatch {
+                    locale: locale.clone(),
+                    key,
+                    missing,
+                    added,
+                });
+            }
+        }
+    }
+    mismatches
+}
@@ -0,0 +1,108 @@
+use std::ops::Range;
+
+/// A suggestion returned by [`complete`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Completion {
+    /// The suggested field name.
+    pub label: String,
+    /// The byte range of the partial name to replace with the label.
+    pub span: Range<usize>,
+}
+
+/// Where the scanner is in the template.
+enum State {
+    /// Literal text, at the top level or in a select branch.
+    Text,
+    /// Inside a placeholder or the condition of a conditional section.
+    Placeholder { filtered: bool, quoted: bool },
+    /// Inside a select block, after the field name.
+    Select,
+}
+
+/// Suggest field names for the placeholder at `cursor`, a byte offset in a template that is being edited and may be
+/// incomplete, e.g. to power the autocompletion of a template editor.
+///
+/// Fields whose names start with the partial name around the cursor are suggested in the order of `fields`. Nothing is
+/// suggested outside of placeholders, in filters, in select arms or in string literals.
+///
+/// ## Examples
+///
+/// ```
+/// let completions = typlate::complete("Hello {na", 9, &["name", "nickname", "namespace"]);
+/// assert_eq!(completions.iter().map(|c| c.label.as_str()).collect::<Vec<_>>(), ["name", "namespace"]);
+/// assert_eq!(completions[0].span, 7..9);
+/// ```
+pub fn complete(partial_template: &str, cursor: usize, fields: &[&str]) -> Vec<Completion> {
+    if !partial_template.is_char_boundary(cursor) {
+        return vec![];
+    }
+    let source = partial_template;
+    let mut stack = vec![State::Text];
+    let mut i = 0;
+    while i < cursor {
+        let rest = &source[i..];
+        let char = rest.chars().next().unwrap();
+        match stack.last_mut().unwrap() {
+            State::Text => {
+                if rest.starts_with("{{") || rest.starts_with("}}") && stack.len() == 1 {
+                    i += 2;
+                    continue;
+                } else if rest.starts_with("{%") {
+                    match rest.find("%}") {
+                        Some(end) => i += end + 2,
+                        None => return vec![],
+                    }
+                    continue;
+                } else if rest.starts_with("{:}") || rest.starts_with("{/}") {
+                    i += 3;
+                    continue;
+                } else if char == '{' {
+                    stack.push(State::Placeholder {
+                        filtered: false,
+                        quoted: false,
+                    });
+                } else if char == '}' && stack.len() > 1 {
+                    stack.pop();
+                }
+            }
+            State::Placeholder { filtered, quoted } => match char {
+                '"' => *quoted = !*quoted,
+                _ if *quoted => {}
+                '|' => *filtered = true,
+                ',' if !*filtered => *stack.last_mut().unwrap() = State::Select,
+                '}' => {
+                    stack.pop();
+                }
+                _ => {}
+            },
+            State::Select => match char {
+                '{' => stack.push(State::Text),
+                '}' => {
+                    stack.pop();
+                }
+                _ => {}
+            },
+        }
+        i += char.len_utf8();
+    }
+
+    let Some(State::Placeholder {
+        filtered: false,
+        quoted: false,
+    }) = stack.last()
+    else {
+        return vec![];
+    };
+    let is_name = |char: char| char.is_alphanumeric() || char == '_';
+    let start = source[..cursor].trim_end_matches(is_name).len();
+    let end = cursor + source[cursor..].len() - source[cursor..].trim_start_matches(is_name).len();
+    let prefix = &source[start..cursor];
+    fields
+        .iter()
+        .filter(|field| field.starts_with(prefix))
+        .map(|field| Completion {
+            label: field.to_string(),
+            span: start..end,
+        })
+        .collect()
+}
@@ -0,0 +1,233 @@
+use std::cmp::Ordering;
+use std::fmt::{self, Write};
+
+use crate::ctx::FieldDisplay;
+use crate::depth::render;
+use crate::expr::Expr;
+use crate::{Number, TemplateParseError, TemplateStringParams};
+
+/// The condition of a conditional section, e.g. `count > 1` in `{?count > 1}items{:}item{/}`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) struct Condition {
+    pub(crate) lhs: Operand,
+    /// The comparison, or `None` if the condition tests whether `lhs` is truthy.
+    pub(crate) comparison: Option<(Comparison, Operand)>,
+}
+
+/// A side of a condition.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) enum Operand {
+    /// A field, number or arithmetic expression.
+    Expr(Expr),
+    /// A string literal in double quotes.
+    Str(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    /// The comparison operators, with two-character operators before their one-character prefixes.
+    pub(crate) const ALL: [(Self, &'static str); 6] = [
+        (Self::Eq, "=="),
+        (Self::Ne, "!="),
+        (Self::Le, "<="),
+        (Self::Ge, ">="),
+        (Self::Lt, "<"),
+        (Self::Gt, ">"),
+    ];
+
+    pub(crate) fn as_str(self) -> &'static str {
+        Self::ALL.iter().find(|(op, _)| *op == self).unwrap().1
+    }
+
+    fn matches(self, ordering: Ordering) -> bool {
+        match self {
+            Self::Eq => ordering.is_eq(),
+            Self::Ne => ordering.is_ne(),
+            Self::Lt => ordering.is_lt(),
+            Self::Le => ordering.is_le(),
+            Self::Gt => ordering.is_gt(),
+            Self::Ge => ordering.is_ge(),
+        }
+    }
+}
+
+/// The value of an operand while evaluating a condition.
+enum Value {
+    Number(Number),
+    Text(String),
+    Missing,
+}
+
+impl Condition {
+    /// Parse the condition of a `{?...}` tag, where `offset` is the offset of `source` in the template and `max_depth`
+    /// limits its expressions as in [`Expr::parse`].
+    ///
+    /// ```text
+    /// condition = operand (("==" | "!=" | "<" | "<=" | ">" | ">=") operand)?
+    /// operand   = expr | '"' text '"'
+    /// ```
+    pub(crate) fn parse<T: TemplateStringParams>(
+        source: &str,
+        offset: usize,
+        max_depth: usize,
+    ) -> Result<Self, TemplateParseError> {
+        let mut quoted = false;
+        let mut split = None;
+        for (i, char) in source.char_indices() {
+            match char {
+                '"' => quoted = !quoted,
+                _ if quoted => {}
+                _ => {
+                    if let Some(&(comparison, op)) = Comparison::ALL.iter().find(|(_, op)| source[i..].starts_with(op))
+                    {
+                        split = Some((i, comparison, op.len()));
+                        break;
+                    }
+                }
+            }
+        }
+        match split {
+            Some((i, comparison, len)) => Ok(Self {
+                lhs: Operand::parse::<T>(&source[..i], offset, max_depth)?,
+                comparison: Some((
+                    comparison,
+                    Operand::parse::<T>(&source[i + len..], offset + i + len, max_depth)?,
+                )),
+            }),
+            None => Ok(Self {
+                lhs: Operand::parse::<T>(source, offset, max_depth)?,
+                comparison: None,
+            }),
+        }
+    }
+
+    /// Call `f` with the index of every field referenced by the condition.
+    pub(crate) fn visit_fields(&self, f: &mut impl FnMut(usize)) {
+        self.lhs.visit_fields(f);
+        if let Some((_, rhs)) = &self.comparison {
+            rhs.visit_fields(f);
+        }
+    }
+
+    /// Evaluate the condition.
+    ///
+    /// Two numbers are compared numerically and any other values as strings, where a number is compared in its
+    /// rendered form. A comparison with a field that has no value is false. Without a comparison, the condition holds
+    /// if the value is present, non-empty, not zero and not `false`.
+    pub(crate) fn eval<T: TemplateStringParams>(&self, params: &T) -> bool {
+        let lhs = self.lhs.eval(params);
+        let Some((comparison, rhs)) = &self.comparison else {
+            return match lhs {
+                Value::Number(number) => number.to_f64() != 0.0,
+                Value::Text(text) => !text.is_empty() && text != "false",
+                Value::Missing => false,
+            };
+        };
+        let ordering = match (lhs, rhs.eval(params)) {
+            (Value::Missing, _) | (_, Value::Missing) => return false,
+            (Value::Number(lhs), Value::Number(rhs)) => match (lhs.to_i128(), rhs.to_i128()) {
+                (Some(lhs), Some(rhs)) => Some(lhs.cmp(&rhs)),
+                _ => lhs.to_f64().partial_cmp(&rhs.to_f64()),
+            },
+            (lhs, rhs) => Some(lhs.into_string().cmp(&rhs.into_string())),
+        };
+        ordering.is_some_and(|ordering| comparison.matches(ordering))
+    }
+
+    /// Write the condition in template syntax.
+    pub(crate) fn write_source(&self, f: &mut impl Write, fields: &[&str]) -> fmt::Result {
+        self.lhs.write_source(f, fields)?;
+        if let Some((comparison, rhs)) = &self.comparison {
+            write!(f, " {} ", comparison.as_str())?;
+            rhs.write_source(f, fields)?;
+        }
+        Ok(())
+    }
+
+    /// Whether two conditions have the same structure, comparing fields with `same_field`.
+    pub(crate) fn equivalent(&self, other: &Self, same_field: &impl Fn(&usize, &usize) -> bool) -> bool {
+        self.lhs.equivalent(&other.lhs, same_field)
+            && match (&self.comparison, &other.comparison) {
+                (None, None) => true,
+                (Some((comparison, rhs)), Some((other_comparison, other_rhs))) => {
+                    comparison == other_comparison && rhs.equivalent(other_rhs, same_field)
+                }
+                _ => false,
+            }
+    }
+}
+
+impl Operand {
+    fn parse<T: TemplateStringParams>(
+        source: &str,
+        offset: usize,
+        max_depth: usize,
+    ) -> Result<Self, TemplateParseError> {
+        let trimmed = source.trim();
+        let start = offset + source.len() - source.trim_start().len();
+        if trimmed.is_empty() {
+            return Err(TemplateParseError::InvalidCondition { span: start..start + 1 });
+        }
+        if let Some(quoted) = trimmed.strip_prefix('"') {
+            return match quoted.strip_suffix('"') {
+                Some(text) if !text.contains('"') => Ok(Self::Str(text.to_string())),
+                _ => Err(TemplateParseError::InvalidCondition {
+                    span: start..start + trimmed.len(),
+                }),
+            };
+        }
+        Expr::parse::<T>(source, offset, max_depth).map(Self::Expr)
+    }
+
+    fn visit_fields(&self, f: &mut impl FnMut(usize)) {
+        if let Self::Expr(expr) = self {
+            expr.visit_fields(f);
+        }
+    }
+
+    fn eval<T: TemplateStringParams>(&self, params: &T) -> Value {
+        match self {
+            Self::Str(text) => Value::Text(text.clone()),
+            Self::Expr(Expr::Field(index)) if !params.has_field(*index) => Value::Missing,
+            Self::Expr(Expr::Field(index)) => match params.field_number(*index) {
+                Some(number) => Value::Number(number),
+                None => Value::Text(render(FieldDisplay(None, params, *index))),
+            },
+            Self::Expr(expr) => expr.eval(params).map_or(Value::Missing, Value::Number),
+        }
+    }
+
+    fn write_source(&self, f: &mut impl Write, fields: &[&str]) -> fmt::Result {
+        match self {
+            Self::Expr(expr) => expr.write_source(f, fields),
+            Self::Str(text) => write!(f, "\"{text}\""),
+        }
+    }
+
+    fn equivalent(&self, other: &Self, same_field: &impl Fn(&usize, &usize) -> bool) -> bool {
+        match (self, other) {
+            (Self::Expr(left), Self::Expr(right)) => left.equivalent(right, same_field),
+            (Self::Str(left), Self::Str(right)) => left == right,
+            _ => false,
+        }
+    }
+}
+
+impl Value {
+    fn into_string(self) -> String {
+        match self {
+            Self::Number(number) => number.to_string(),
+            Self::Text(text) => text,
+            Self::Missing => String::new(),
+        }
+    }
+}
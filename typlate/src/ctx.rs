@@ -0,0 +1,497 @@
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::filter::{FilterCall, FilterRegistry, apply_builtin, encode_base64, is_base64};
+use crate::format_spec::{FormatSpec, Kind};
+use crate::{Number, RenderObserver, TemplateStringParams};
+
+type EscaperFn = dyn Fn(&str) -> String + Send + Sync;
+type ResolverFn = dyn Fn(&str) -> Option<String> + Send + Sync;
+type ClockFn = dyn Fn() -> SystemTime + Send + Sync;
+
+/// Per-render settings that affect how field values are formatted.
+///
+/// A context carries the locale used for numeric fields, an escaper applied to every interpolated value, the registry
+/// of filters available to `{field|filter}` placeholders, the resolvers of namespaced placeholders such as
+/// `{secret:token}`, the clock used by relative times, and an observer notified of every render.
+///
+/// ## Examples
+///
+/// ```
+/// # #[cfg(feature = "icu")] {
+/// # use typlate::{RenderCtx, TemplateParams, TemplateString};
+/// #[derive(TemplateParams)]
+/// struct Order {
+///     total: f64,
+/// }
+///
+/// let template: TemplateString<Order> = "Total: {total} EUR".parse().unwrap();
+/// let ctx = RenderCtx::new().with_locale(&"de".parse().unwrap()).unwrap();
+/// assert_eq!(template.format_with_ctx(&Order { total: 1234.5 }, &ctx), "Total: 1.234,5 EUR");
+/// # }
+/// ```
+#[derive(Default)]
+pub struct RenderCtx {
+    #[cfg(feature = "icu")]
+    locale: Option<(icu_locale_core::Locale, icu_decimal::DecimalFormatter)>,
+    escaper: Option<Arc<EscaperFn>>,
+    filters: FilterRegistry,
+    resolvers: HashMap<String, Arc<ResolverFn>>,
+    observer: Option<Arc<dyn RenderObserver>>,
+    clock: Option<Arc<ClockFn>>,
+    /// The ordinal plural rules of the locale, for the `ordinal` filter.
+    #[cfg(feature = "icu")]
+    ordinals: Option<icu_plurals::PluralRules>,
+}
+
+impl RenderCtx {
+    /// Create a context with default settings, which renders values exactly like [`TemplateString::format`].
+    ///
+    /// [`TemplateString::format`]: crate::TemplateString::format
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Format numeric fields with the digit grouping and decimal separator of the given locale.
+    ///
+    /// Only fields exposed through [`TemplateStringParams::field_number`] are affected. Fails if no decimal
+    /// formatting data is available for the locale.
+    #[cfg(feature = "icu")]
+    pub fn with_locale(mut self, locale: &icu_locale_core::Locale) -> Result<Self, icu_provider::DataError> {
+        let formatter = icu_decimal::DecimalFormatter::try_new(locale.into(), Default::default())?;
+        self.ordinals = Some(icu_plurals::PluralRules::try_new_ordinal(locale.into())?);
+        self.locale = Some((locale.clone(), formatter));
+        Ok(self)
+    }
+
+    /// The locale used for numeric fields, if any.
+    #[cfg(feature = "icu")]
+    pub fn locale(&self) -> Option<&icu_locale_core::Locale> {
+        self.locale.as_ref().map(|(locale, _)| locale)
+    }
+
+    /// Escape every interpolated value (after its filters are applied), e.g. for HTML output.
+    ///
+    /// Literal template text is not escaped.
+    pub fn with_escaper(mut self, escaper: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+        self.escaper = Some(Arc::new(escaper));
+        self
+    }
+
+    /// Set the filters available to `{field|filter}` placeholders, in addition to the built-in ones.
+    pub fn with_filters(mut self, filters: FilterRegistry) -> Self {
+        self.filters = filters;
+        self
+    }
+
+    /// The filters available to `{field|filter}` placeholders.
+    pub fn filters(&self) -> &FilterRegistry {
+        &self.filters
+    }
+
+    /// Resolve `{namespace:name}` placeholders in the given namespace with a function from a name to its value,
+    /// replacing any resolver previously registered for the namespace. A name without a value renders as an empty
+    /// string.
+    ///
+    /// Templates can only use namespaces accepted by their [`ParseOptions`](crate::ParseOptions), see
+    /// [`ParseOptionsBuilder::namespace`](crate::ParseOptionsBuilder::namespace). A resolver takes precedence over the
+    /// built-in `env` and `global` namespaces.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{ParseOptions, RenderCtx, TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Request {
+    ///     path: String,
+    /// }
+    ///
+    /// let options = ParseOptions::builder().namespace("secret").build();
+    /// let template = TemplateString::<Request>::parse_with("{path}?token={secret:api_token}", &options).unwrap();
+    /// let ctx = RenderCtx::new().with_resolver("secret", |name| (name == "api_token").then(|| "t0k3n".to_string()));
+    /// let request = Request { path: "/users".into() };
+    /// assert_eq!(template.format_with_ctx(&request, &ctx), "/users?token=t0k3n");
+    /// ```
+    pub fn with_resolver(
+        mut self,
+        namespace: impl Into<String>,
+        resolver: impl Fn(&str) -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.resolvers.insert(namespace.into(), Arc::new(resolver));
+        self
+    }
+
+    /// Report every render with this context to the observer, e.g. to collect metrics.
+    pub fn with_observer(mut self, observer: impl RenderObserver + 'static) -> Self {
+        self.observer = Some(Arc::new(observer));
+        self
+    }
+
+    pub(crate) fn observer(&self) -> Option<&dyn RenderObserver> {
+        self.observer.as_deref()
+    }
+
+    /// Set the clock that `{field|relative}` placeholders measure times against, instead of the system clock, e.g. to
+    /// render deterministic output in tests.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use std::time::{Duration, SystemTime};
+    /// # use typlate::{RenderCtx, TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Comment {
+    ///     #[template(time)]
+    ///     created_at: SystemTime,
+    /// }
+    ///
+    /// let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    /// let template: TemplateString<Comment> = "Posted {created_at|relative}".parse().unwrap();
+    /// let ctx = RenderCtx::new().with_clock(move || now);
+    /// let comment = Comment { created_at: now - Duration::from_secs(300) };
+    /// assert_eq!(template.format_with_ctx(&comment, &ctx), "Posted 5 minutes ago");
+    /// ```
+    pub fn with_clock(mut self, clock: impl Fn() -> SystemTime + Send + Sync + 'static) -> Self {
+        self.clock = Some(Arc::new(clock));
+        self
+    }
+
+    /// Format the field at the given index according to this context, before filters and escaping.
+    fn fmt_field<T: TemplateStringParams>(&self, params: &T, f: &mut fmt::Formatter, index: usize) -> fmt::Result {
+        #[cfg(feature = "icu")]
+        if let Some((_, formatter)) = &self.locale
+            && let Some(decimal) = params.field_number(index).and_then(crate::Number::to_decimal)
+        {
+            return fmt::Display::fmt(&formatter.format(&decimal), f);
+        }
+        params.fmt_field(f, index)
+    }
+}
+
+impl fmt::Debug for RenderCtx {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut debug = f.debug_struct("RenderCtx");
+        #[cfg(feature = "icu")]
+        debug.field("locale", &self.locale());
+        debug
+            .field("escaper", &self.escaper.is_some())
+            .field("filters", &self.filters)
+            .field("resolvers", &self.resolvers.keys().collect::<Vec<_>>())
+            .field("observer", &self.observer.is_some())
+            .field("clock", &self.clock.is_some())
+            .finish()
+    }
+}
+
+/// Write the value of a placeholder: the field formatted according to the context (if any), passed through the
+/// filters, the default filters of the field and the escaper of the context. The default filters and the escaper are
+/// skipped with a `raw` filter.
+pub(crate) fn write_value<T: TemplateStringParams>(
+    ctx: Option<&RenderCtx>,
+    params: &T,
+    f: &mut fmt::Formatter,
+    index: usize,
+    filters: &[FilterCall],
+) -> fmt::Result {
+    // A `raw` filter skips the escaper, like the default filters of the field.
+    let raw = filters.iter().any(|filter| filter.name == "raw");
+    let escaper = ctx.and_then(|ctx| ctx.escaper.as_deref()).filter(|_| !raw);
+    let field = FieldDisplay(ctx, params, index);
+    if filters.is_empty() && escaper.is_none() && T::default_filters(index).is_empty() {
+        return fmt::Display::fmt(&field, f);
+    }
+
+    let mut filters = filters.iter().peekable();
+    let builtin = |filter: &FilterCall| ctx.is_none_or(|ctx| !ctx.filters.contains(&filter.name));
+    let join = filters.next_if(|filter| filter.name == "join" && builtin(filter));
+    // Numeric filters on numeric fields use the number itself rather than its rendered value.
+    let number = params.field_number(index);
+    let numeric = filters.next_if(|filter| join.is_none() && number.is_some() && is_numeric(filter) && builtin(filter));
+    // So does the `relative` filter on time fields, with the time itself.
+    let relative = filters.next_if(|filter| {
+        join.is_none()
+            && numeric.is_none()
+            && filter.name == "relative"
+            && builtin(filter)
+            && params.field_time(index).is_some()
+    });
+    // And base64 filters on byte fields, with the bytes.
+    let bytes = params.field_bytes(index);
+    let base64 = filters.next_if(|filter| {
+        join.is_none()
+            && numeric.is_none()
+            && relative.is_none()
+            && bytes.is_some()
+            && is_base64(filter)
+            && builtin(filter)
+    });
+    let mut value = if let Some((join, items)) = join.zip(params.field_items(index)) {
+        items.join(join.args.first().map_or(", ", String::as_str))
+    } else if let Some((filter, number)) = numeric.zip(number) {
+        format_numeric(ctx, number, filter)
+    } else if let Some((time, now)) = relative.and_then(|_| params.field_time(index).zip(now(ctx))) {
+        crate::time::format_relative(time, now)
+    } else if let Some((filter, bytes)) = base64.zip(bytes) {
+        encode_base64(&bytes, filter.name == "base64url")
+    } else {
+        let mut value = String::new();
+        write!(value, "{field}")?;
+        value
+    };
+    for filter in filters {
+        value = apply_filter(ctx, filter, value);
+    }
+    if !raw {
+        value = apply_default_filters(ctx, T::default_filters(index), value);
+    }
+    match escaper {
+        Some(escape) => f.write_str(&escape(&value)),
+        None => f.write_str(&value),
+    }
+}
+
+/// Write the value of a field formatted with a `std::fmt`-style spec, passed through the default filters of the field
+/// and the escaper of the context.
+///
+/// With `?` in the spec, the field is formatted with its `Debug` implementation, and is only treated as a number if
+/// its output is one, so that e.g. an `Option` field is not padded with zeros. With a radix such as `x`, an integer
+/// field is formatted in that radix, and any other field as with `Display`.
+pub(crate) fn write_formatted<T: TemplateStringParams>(
+    ctx: Option<&RenderCtx>,
+    params: &T,
+    f: &mut fmt::Formatter,
+    index: usize,
+    spec: &FormatSpec,
+) -> fmt::Result {
+    let number = params.field_number(index);
+    let display = || {
+        let mut value = String::new();
+        write!(value, "{}", FieldDisplay(ctx, params, index)).map(|_| value)
+    };
+    let (value, number) = match spec.kind {
+        Kind::Display => (display()?, number),
+        Kind::Debug => {
+            let value = match spec.alternate {
+                true => format!("{:#}", FieldDebug(params, index)),
+                false => FieldDebug(params, index).to_string(),
+            };
+            let number = number.filter(|_| value.parse::<f64>().is_ok());
+            (value, number)
+        }
+        kind => match number.and_then(|number| kind.format_radix(number, spec.alternate)) {
+            Some(value) => (value, number),
+            None => (display()?, number),
+        },
+    };
+    let value = apply_default_filters(ctx, T::default_filters(index), spec.apply(params, value, number));
+    match ctx.and_then(|ctx| ctx.escaper.as_deref()) {
+        Some(escape) => f.write_str(&escape(&value)),
+        None => f.write_str(&value),
+    }
+}
+
+/// Apply a filter with the registry of the context, or as a built-in filter without a context.
+fn apply_filter(ctx: Option<&RenderCtx>, filter: &FilterCall, value: String) -> String {
+    match ctx {
+        Some(ctx) => ctx.filters.apply(filter, value),
+        None => apply_builtin(filter, value),
+    }
+}
+
+/// Apply [default filters](TemplateStringParams::default_filters) by name.
+fn apply_default_filters(ctx: Option<&RenderCtx>, names: &[&str], mut value: String) -> String {
+    for name in names {
+        let filter = FilterCall {
+            name: name.to_string(),
+            args: vec![],
+        };
+        value = apply_filter(ctx, &filter, value);
+    }
+    value
+}
+
+/// Whether the filter is one of the built-in filters that format numbers: `percent`, `permille` and `ordinal`.
+fn is_numeric(filter: &FilterCall) -> bool {
+    matches!(filter.name.as_str(), "percent" | "permille" | "ordinal")
+}
+
+/// Apply one of the built-in filters that format numbers.
+pub(crate) fn format_numeric(ctx: Option<&RenderCtx>, number: Number, filter: &FilterCall) -> String {
+    match filter.name.as_str() {
+        "ordinal" => format_ordinal(ctx, number),
+        _ => format_ratio(ctx, number.to_f64(), filter),
+    }
+}
+
+/// Format an integer as an ordinal number for the `ordinal` filter, e.g. `1st`, `2nd`, `3rd` and `11th`. Other numbers
+/// are rendered as is.
+///
+/// With a locale, the suffix follows the locale's ordinal plural rules, e.g. `1er` and `2e` in French or `1.` in
+/// German. Numbers in languages without known suffixes are rendered without one.
+fn format_ordinal(ctx: Option<&RenderCtx>, number: Number) -> String {
+    let Some(integer) = number.to_i128() else {
+        return number.to_string();
+    };
+    #[cfg(feature = "icu")]
+    if let Some(((locale, formatter), rules)) = ctx.and_then(|ctx| ctx.locale.as_ref().zip(ctx.ordinals.as_ref())) {
+        use icu_plurals::PluralCategory;
+
+        let category = rules.category_for(integer.unsigned_abs());
+        let suffix = match (locale.id.language.as_str(), category) {
+            ("en", PluralCategory::One) => "st",
+            ("en", PluralCategory::Two) => "nd",
+            ("en", PluralCategory::Few) => "rd",
+            ("en", _) => "th",
+            ("fr", PluralCategory::One) => "er",
+            ("fr", _) | ("nl", _) => "e",
+            ("sv", PluralCategory::One) => ":a",
+            ("sv", _) => ":e",
+            ("es" | "it" | "pt", _) => "\u{ba}",
+            ("cs" | "da" | "de" | "fi" | "nb" | "no" | "pl" | "sk", _) => ".",
+            _ => "",
+        };
+        return format!("{}{suffix}", formatter.format(&integer.into()));
+    }
+    #[cfg(not(feature = "icu"))]
+    let _ = ctx;
+    let suffix = match (integer.unsigned_abs() % 10, integer.unsigned_abs() % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{integer}{suffix}")
+}
+
+/// Format a ratio as a percentage or per-mille value for the `percent` and `permille` filters, rounded to the number of
+/// decimals given as argument (0 by default).
+///
+/// With a locale, the number is formatted with its digit grouping and decimal separator, and the sign is placed as in
+/// the locale's percent format, e.g. `12,5 %` in German and `%12,5` in Turkish.
+fn format_ratio(ctx: Option<&RenderCtx>, ratio: f64, filter: &FilterCall) -> String {
+    let (scale, sign): (i16, char) = match filter.name.as_str() {
+        "permille" => (3, '\u{2030}'),
+        _ => (2, '%'),
+    };
+    let digits = filter
+        .args
+        .first()
+        .and_then(|digits| digits.parse::<u8>().ok())
+        .unwrap_or(0);
+    #[cfg(feature = "icu")]
+    if let Some((locale, formatter)) = ctx.and_then(|ctx| ctx.locale.as_ref())
+        && let Ok(mut decimal) = fixed_decimal::Decimal::try_from_f64(ratio, fixed_decimal::FloatPrecision::RoundTrip)
+    {
+        decimal.multiply_pow10(scale);
+        decimal.trim_start();
+        decimal.round(-i16::from(digits));
+        decimal.pad_end(-i16::from(digits));
+        let number = formatter.format(&decimal);
+        return match locale.id.language.as_str() {
+            "tr" => format!("{sign}{number}"),
+            "cs" | "da" | "de" | "es" | "fi" | "nb" | "no" | "ru" | "sk" | "sv" | "uk" => {
+                format!("{number}\u{a0}{sign}")
+            }
+            "fr" => format!("{number}\u{202f}{sign}"),
+            _ => format!("{number}{sign}"),
+        };
+    }
+    #[cfg(not(feature = "icu"))]
+    let _ = ctx;
+    format!("{:.*}{sign}", usize::from(digits), ratio * 10f64.powi(i32::from(scale)))
+}
+
+/// The current time for the `relative` filter: from the clock of the context if it has one, otherwise from the system
+/// clock, which is not available on `wasm32-unknown-unknown`.
+fn now(ctx: Option<&RenderCtx>) -> Option<SystemTime> {
+    if let Some(clock) = ctx.and_then(|ctx| ctx.clock.as_deref()) {
+        return Some(clock());
+    }
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    return Some(SystemTime::now());
+    #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+    return None;
+}
+
+/// Write the result of an arithmetic expression: formatted according to the locale of the context (if any), passed
+/// through the [default value filters](TemplateStringParams::default_value_filters) and the escaper of the context.
+pub(crate) fn write_number<T: TemplateStringParams>(
+    ctx: Option<&RenderCtx>,
+    number: Number,
+    f: &mut fmt::Formatter,
+) -> fmt::Result {
+    #[cfg(feature = "icu")]
+    let value = match ctx.and_then(|ctx| ctx.locale.as_ref()).zip(number.to_decimal()) {
+        Some(((_, formatter), decimal)) => formatter.format(&decimal).to_string(),
+        None => number.to_string(),
+    };
+    #[cfg(not(feature = "icu"))]
+    let value = number.to_string();
+    let value = apply_default_filters(ctx, T::default_value_filters(), value);
+    match ctx.and_then(|ctx| ctx.escaper.as_deref()) {
+        Some(escape) => f.write_str(&escape(&value)),
+        None => f.write_str(&value),
+    }
+}
+
+/// Write the value of a namespaced placeholder, resolved by the resolver of the context for the namespace or by a
+/// built-in namespace, passed through the [default value filters](TemplateStringParams::default_value_filters) and the
+/// escaper of the context. A name without a value writes nothing.
+pub(crate) fn write_namespaced<T: TemplateStringParams>(
+    ctx: Option<&RenderCtx>,
+    namespace: &str,
+    name: &str,
+    f: &mut fmt::Formatter,
+) -> fmt::Result {
+    let value = match ctx.and_then(|ctx| ctx.resolvers.get(namespace)) {
+        Some(resolve) => resolve(name),
+        None => resolve_builtin(namespace, name),
+    };
+    let Some(value) = value else {
+        return Ok(());
+    };
+    let value = apply_default_filters(ctx, T::default_value_filters(), value);
+    match ctx.and_then(|ctx| ctx.escaper.as_deref()) {
+        Some(escape) => f.write_str(&escape(&value)),
+        None => f.write_str(&value),
+    }
+}
+
+/// Whether the namespace is resolved without a resolver: `env` for environment variables and `global` for
+/// [global parameters](crate::set_global_params).
+pub(crate) fn is_builtin_namespace(namespace: &str) -> bool {
+    matches!(namespace, "env" | "global")
+}
+
+/// Resolve a name in a built-in namespace, or return `None` for other namespaces.
+pub(crate) fn resolve_builtin(namespace: &str, name: &str) -> Option<String> {
+    match namespace {
+        "env" => std::env::var_os(name).map(|value| value.to_string_lossy().into_owned()),
+        "global" => crate::global::global_param(name),
+        _ => None,
+    }
+}
+
+/// Displays a single field of the parameters according to the context, if any.
+pub(crate) struct FieldDisplay<'i, T>(pub(crate) Option<&'i RenderCtx>, pub(crate) &'i T, pub(crate) usize);
+
+impl<T: TemplateStringParams> fmt::Display for FieldDisplay<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Some(ctx) => ctx.fmt_field(self.1, f, self.2),
+            None => self.1.fmt_field(f, self.2),
+        }
+    }
+}
+
+/// Displays a single field of the parameters with its `Debug` implementation, keeping the alternate flag.
+struct FieldDebug<'i, T>(&'i T, usize);
+
+impl<T: TemplateStringParams> fmt::Display for FieldDebug<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt_field_debug(f, self.1)
+    }
+}
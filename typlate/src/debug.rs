@@ -0,0 +1,29 @@
+//! Dispatch for plain placeholders of fields marked with `#[template(debug)]`, which render with `Display` if the
+//! field type implements it and with `Debug` otherwise.
+//!
+//! The derive macro calls `(&DisplayOrDebug(&field)).fmt_value(f)` with both traits in scope. Method resolution finds
+//! [`ViaDisplay`] first, and only falls back to [`ViaDebug`] on an autoref when the field type is not `Display`.
+
+use std::fmt;
+
+pub struct DisplayOrDebug<'a, T: ?Sized>(pub &'a T);
+
+pub trait ViaDisplay {
+    fn fmt_value(&self, f: &mut fmt::Formatter) -> fmt::Result;
+}
+
+impl<T: fmt::Display + ?Sized> ViaDisplay for DisplayOrDebug<'_, T> {
+    fn fmt_value(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self.0, f)
+    }
+}
+
+pub trait ViaDebug {
+    fn fmt_value(&self, f: &mut fmt::Formatter) -> fmt::Result;
+}
+
+impl<T: fmt::Debug + ?Sized> ViaDebug for &DisplayOrDebug<'_, T> {
+    fn fmt_value(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.0, f)
+    }
+}
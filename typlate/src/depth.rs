@@ -0,0 +1,82 @@
+use std::cell::Cell;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{RenderError, TemplateParams, TemplateString, TemplateStringParams};
+
+/// The default limit for [`set_max_render_depth`].
+pub const DEFAULT_MAX_RENDER_DEPTH: usize = 32;
+
+static MAX_DEPTH: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_RENDER_DEPTH);
+
+thread_local! {
+    /// The number of nested templates currently being rendered on this thread.
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+    /// `Some` during a dry run on this thread, holding whether the depth limit was exceeded.
+    static DRY_RUN: Cell<Option<bool>> = const { Cell::new(None) };
+}
+
+/// Set the maximum depth of nested template fields for the whole process.
+///
+/// A template whose nested templates render more than `depth` levels deep, e.g. because a nested template ends up
+/// rendering itself, stops rendering at the limit instead of overflowing the stack: displaying it fails with
+/// [`fmt::Error`], [`TemplateString::format`] returns the output rendered so far, and [`TemplateString::dry_run`] and
+/// [`TemplateString::try_format`] fail with [`RenderError::DepthExceeded`]. The limit defaults to
+/// [`DEFAULT_MAX_RENDER_DEPTH`].
+pub fn set_max_render_depth(depth: usize) {
+    MAX_DEPTH.store(depth, Ordering::Relaxed);
+}
+
+/// The maximum depth of nested template fields.
+pub fn max_render_depth() -> usize {
+    MAX_DEPTH.load(Ordering::Relaxed)
+}
+
+/// Render a nested template field one level deeper, as generated by the derive macro for
+/// `#[template(params = "...")]` fields. Fails with [`fmt::Error`] if the depth limit is exceeded.
+pub fn fmt_nested<T: TemplateStringParams>(
+    template: &TemplateString<T>,
+    params: &T,
+    f: &mut fmt::Formatter,
+) -> fmt::Result {
+    let depth = DEPTH.get();
+    let limit = max_render_depth();
+    if depth >= limit {
+        if DRY_RUN.get().is_some() {
+            DRY_RUN.set(Some(true));
+        }
+        return Err(fmt::Error);
+    }
+    let _guard = DepthGuard(depth);
+    DEPTH.set(depth + 1);
+    fmt::Display::fmt(&params.format_template(template), f)
+}
+
+/// Restores the depth when a nested render ends, including by panicking.
+struct DepthGuard(usize);
+
+impl Drop for DepthGuard {
+    fn drop(&mut self) {
+        DEPTH.set(self.0);
+    }
+}
+
+/// Run a dry run, during which exceeding the depth limit is reported as [`RenderError::DepthExceeded`].
+pub(crate) fn dry_run(f: impl FnOnce() -> Result<(), RenderError>) -> Result<(), RenderError> {
+    let previous = DRY_RUN.replace(Some(false));
+    let result = f();
+    match DRY_RUN.replace(previous) {
+        Some(true) => Err(RenderError::DepthExceeded {
+            limit: max_render_depth(),
+        }),
+        _ => result,
+    }
+}
+
+/// Format a value into a string like [`ToString::to_string`], but keep the output written before a nested template
+/// exceeded the depth limit instead of panicking.
+pub(crate) fn render(value: impl fmt::Display) -> String {
+    let mut output = String::new();
+    let _ = fmt::Write::write_fmt(&mut output, format_args!("{value}"));
+    output
+}
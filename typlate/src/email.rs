@@ -0,0 +1,94 @@
+//! Render emails with [lettre](https://docs.rs/lettre).
+//!
+//! [`EmailTemplates`] pairs a subject template and a body template for the same parameters, and renders both into a
+//! [`lettre::message::MessageBuilder`].
+//!
+//! ## Examples
+//!
+//! ```
+//! # use typlate::TemplateParams;
+//! # use typlate::email::EmailTemplates;
+//! # use lettre::Message;
+//! #[derive(TemplateParams)]
+//! struct Order {
+//!     id: u32,
+//!     name: String,
+//! }
+//!
+//! let templates = EmailTemplates::<Order>::parse("Order #{id} shipped", "Hello {name}, your order is on its way.")
+//!     .unwrap();
+//! let builder = Message::builder()
+//!     .from("shop@example.com".parse().unwrap())
+//!     .to("alice@example.com".parse().unwrap());
+//! let order = Order { id: 42, name: "Alice".into() };
+//! let message = templates.message(builder, &order).unwrap();
+//! assert_eq!(message.headers().get_raw("Subject"), Some("Order #42 shipped"));
+//! ```
+
+use std::fmt;
+
+use lettre::Message;
+use lettre::message::MessageBuilder;
+
+use crate::{TemplateParseError, TemplateString, TemplateStringParams};
+
+/// A subject template and a body template for the same parameters.
+pub struct EmailTemplates<T> {
+    subject: TemplateString<T>,
+    body: TemplateString<T>,
+}
+
+impl<T> EmailTemplates<T> {
+    /// Pair a subject template with a body template.
+    pub fn new(subject: TemplateString<T>, body: TemplateString<T>) -> Self {
+        Self { subject, body }
+    }
+
+    /// The subject template.
+    pub fn subject(&self) -> &TemplateString<T> {
+        &self.subject
+    }
+
+    /// The body template.
+    pub fn body(&self) -> &TemplateString<T> {
+        &self.body
+    }
+}
+
+impl<T: TemplateStringParams> EmailTemplates<T> {
+    /// Parse a subject template and a body template.
+    pub fn parse(subject: &str, body: &str) -> Result<Self, TemplateParseError> {
+        Ok(Self::new(subject.parse()?, body.parse()?))
+    }
+
+    /// Render the subject and the body with the provided parameter values.
+    pub fn render(&self, params: &T) -> (String, String) {
+        (self.subject.format(params), self.body.format(params))
+    }
+
+    /// Render the subject and the body into the builder and build the message.
+    ///
+    /// The builder holds everything else, such as the sender, the recipients and the content type.
+    pub fn message(&self, builder: MessageBuilder, params: &T) -> Result<Message, lettre::error::Error> {
+        let (subject, body) = self.render(params);
+        builder.subject(subject).body(body)
+    }
+}
+
+impl<T> Clone for EmailTemplates<T> {
+    fn clone(&self) -> Self {
+        Self {
+            subject: self.subject.clone(),
+            body: self.body.clone(),
+        }
+    }
+}
+
+impl<T: TemplateStringParams> fmt::Debug for EmailTemplates<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EmailTemplates")
+            .field("subject", &self.subject)
+            .field("body", &self.body)
+            .finish()
+    }
+}
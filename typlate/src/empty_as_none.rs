@@ -0,0 +1,51 @@
+//! Deserialize optional templates, treating empty templates like missing ones.
+//!
+//! By default, an `Option<TemplateString<T>>` is `None` for `null` (and, with `#[serde(default)]`, for a missing
+//! field), but `Some` of an empty template for an empty string. Use this module with `#[serde(default, with =
+//! "typlate::empty_as_none")]` to get `None` for all three. Templates are serialized as usual, and `None` as `null`.
+//!
+//! ## Examples
+//!
+//! ```
+//! # use serde::Deserialize;
+//! # use typlate::{TemplateParams, TemplateString};
+//! #[derive(TemplateParams)]
+//! struct User {
+//!     name: String,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Messages {
+//!     #[serde(default, with = "typlate::empty_as_none")]
+//!     greeting: Option<TemplateString<User>>,
+//!     #[serde(default, with = "typlate::empty_as_none")]
+//!     farewell: Option<TemplateString<User>>,
+//!     #[serde(default, with = "typlate::empty_as_none")]
+//!     footer: Option<TemplateString<User>>,
+//!     signature: Option<TemplateString<User>>,
+//! }
+//!
+//! let messages: Messages = serde_json::from_str(r#"{"greeting": "", "farewell": null, "signature": ""}"#).unwrap();
+//! assert!(messages.greeting.is_none() && messages.farewell.is_none() && messages.footer.is_none());
+//! assert_eq!(messages.signature.unwrap().to_string(), "");
+//! ```
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{TemplateString, TemplateStringParams};
+
+/// Serialize an optional template, with `None` as `null`.
+pub fn serialize<S: Serializer, T: TemplateStringParams>(
+    template: &Option<TemplateString<T>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    template.serialize(serializer)
+}
+
+/// Deserialize an optional template, with `None` for `null` and for an empty template.
+pub fn deserialize<'de, D: Deserializer<'de>, T: TemplateStringParams>(
+    deserializer: D,
+) -> Result<Option<TemplateString<T>>, D::Error> {
+    let template = Option::<TemplateString<T>>::deserialize(deserializer)?;
+    Ok(template.filter(|template| !template.elements().is_empty()))
+}
@@ -0,0 +1,108 @@
+//! Formatting templates into legacy and non-UTF-8 encodings.
+//!
+//! ## Examples
+//!
+//! ```
+//! # use typlate::encoding::Encoding;
+//! # use typlate::{TemplateParams, TemplateString};
+//! #[derive(TemplateParams)]
+//! struct Data {
+//!     city: &'static str,
+//! }
+//!
+//! let template: TemplateString<Data> = "Grüße aus {city}".parse().unwrap();
+//! let bytes = template.format_encoded(&Data { city: "Köln" }, Encoding::Latin1).unwrap();
+//! assert_eq!(bytes, b"Gr\xfc\xdfe aus K\xf6ln");
+//!
+//! let error = template.format_encoded(&Data { city: "Kyiv €" }, Encoding::Latin1).unwrap_err();
+//! assert_eq!(error.to_string(), "Character '€' cannot be encoded in Latin-1");
+//! ```
+
+use std::fmt::{self, Write as _};
+use std::io;
+
+use crate::{TemplateParams, TemplateString, TemplateStringParams};
+
+/// A character encoding for formatted output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    Utf8,
+    /// ISO-8859-1, which can only encode the characters U+0000 to U+00FF.
+    Latin1,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl<T: TemplateStringParams> TemplateString<T> {
+    /// Format the template into bytes of the given encoding.
+    ///
+    /// Fails with [`io::ErrorKind::InvalidData`] if the output contains a character that the encoding cannot
+    /// represent.
+    pub fn format_encoded(&self, params: &T, encoding: Encoding) -> io::Result<Vec<u8>> {
+        let mut output = vec![];
+        self.write_encoded(params, &mut output, encoding)?;
+        Ok(output)
+    }
+
+    /// Format the template directly into a writer, in the given encoding.
+    ///
+    /// Fails with [`io::ErrorKind::InvalidData`] if the output contains a character that the encoding cannot
+    /// represent. In that case, the output before that character has already been written.
+    pub fn write_encoded(&self, params: &T, writer: &mut impl io::Write, encoding: Encoding) -> io::Result<()> {
+        let mut encoder = Encoder {
+            writer,
+            encoding,
+            error: None,
+        };
+        match write!(encoder, "{}", params.format_template(self)) {
+            Ok(()) => Ok(()),
+            Err(fmt::Error) => Err(encoder.error.unwrap_or_else(|| io::Error::other("Formatter error"))),
+        }
+    }
+}
+
+/// Adapts an [`io::Write`] into a [`fmt::Write`] that encodes the written text.
+struct Encoder<'w, W> {
+    writer: &'w mut W,
+    encoding: Encoding,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> Encoder<'_, W> {
+    fn encode(&mut self, text: &str) -> io::Result<()> {
+        match self.encoding {
+            Encoding::Utf8 => self.writer.write_all(text.as_bytes()),
+            Encoding::Latin1 => {
+                let bytes = text
+                    .chars()
+                    .map(|char| {
+                        u8::try_from(char).map_err(|_| {
+                            io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                format!("Character '{char}' cannot be encoded in Latin-1"),
+                            )
+                        })
+                    })
+                    .collect::<io::Result<Vec<_>>>()?;
+                self.writer.write_all(&bytes)
+            }
+            Encoding::Utf16Le => {
+                let bytes: Vec<_> = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+                self.writer.write_all(&bytes)
+            }
+            Encoding::Utf16Be => {
+                let bytes: Vec<_> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+                self.writer.write_all(&bytes)
+            }
+        }
+    }
+}
+
+impl<W: io::Write> fmt::Write for Encoder<'_, W> {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        self.encode(text).map_err(|error| {
+            self.error = Some(error);
+            fmt::Error
+        })
+    }
+}
@@ -0,0 +1,156 @@
+use std::fmt;
+use std::ops::Range;
+
+/// An error that occurred while parsing a template string.
+///
+/// Offsets and spans are byte positions in the template source.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum TemplateParseError {
+    /// A placeholder names a field that does not exist in the parameter type.
+    UnknownField { name: String, span: Range<usize> },
+    /// A placeholder name contains characters that field names cannot contain. Field names are Unicode identifiers,
+    /// such as `name` or `prénom`, or indices of tuple struct fields, such as `0`.
+    InvalidFieldName { name: String, span: Range<usize> },
+    /// An opening bracket is never closed.
+    UnclosedBracket { offset: usize },
+    /// A closing bracket appears without a matching opening bracket.
+    UnmatchedClosingBracket { offset: usize },
+    /// A printf-style conversion (e.g. in a gettext `python-format` string) that cannot be mapped to a placeholder.
+    UnsupportedConversion { conversion: String, span: Range<usize> },
+    /// A tag of a foreign template syntax (e.g. a Mustache section or partial) that has no equivalent in a template.
+    UnsupportedTag { tag: String, span: Range<usize> },
+    /// A select block is malformed, e.g. it has an unknown kind, a branch without key, or no `other` branch.
+    InvalidSelect { span: Range<usize> },
+    /// A filter is malformed, e.g. it has no name or an empty argument.
+    InvalidFilter { span: Range<usize> },
+    /// A filter is neither built in nor accepted by the parse options, see
+    /// [`ParseOptionsBuilder::filter`](crate::ParseOptionsBuilder::filter).
+    UnknownFilter { name: String, span: Range<usize> },
+    /// An arithmetic expression is malformed, e.g. it has a missing operand or an invalid number.
+    InvalidExpression { span: Range<usize> },
+    /// The format spec of a placeholder such as `{price:>8.2}` is malformed, e.g. it has an unknown flag or its width
+    /// refers to an unknown field.
+    InvalidFormatSpec { span: Range<usize> },
+    /// The format spec of a placeholder is not supported by the type of its field: `{state:?}` requires a field
+    /// marked with `#[template(debug)]`, and radix formats such as `{id:x}` require an integer field.
+    UnsupportedFormat { name: String, span: Range<usize> },
+    /// A conditional section or inline conditional is malformed, e.g. it has an empty condition, is never closed by
+    /// `{/}`, has a `{:}` or `{/}` tag outside of a conditional section, or has an unquoted inline branch.
+    InvalidCondition { span: Range<usize> },
+    /// A placeholder has no field name, such as `{}`, or (with positional empty placeholders) there are more empty
+    /// placeholders than fields.
+    EmptyPlaceholder { span: Range<usize> },
+    /// The template source is longer than the limit set with
+    /// [`ParseOptionsBuilder::max_length`](crate::ParseOptionsBuilder::max_length).
+    TooLong { limit: usize, length: usize },
+    /// Select branches, conditional sections, blocks or parenthesized expressions are nested more levels deep than the
+    /// limit set with [`ParseOptionsBuilder::max_depth`](crate::ParseOptionsBuilder::max_depth), where `offset` is the
+    /// start of the section that exceeds it.
+    TooDeep { limit: usize, offset: usize },
+    /// A `{% ... %}` tag is malformed or unexpected, e.g. an `endblock` without a block or a duplicate block name.
+    InvalidBlock { span: Range<usize> },
+    /// A block is never closed by `{%endblock%}`.
+    UnclosedBlock { name: String, offset: usize },
+    /// A placeholder names an inline field (declared with `#[template(inline(...))]`) whose template is invalid or
+    /// refers back to the field itself.
+    InvalidInlineField { name: String, span: Range<usize> },
+    /// A placeholder such as `{secret:token}` uses a namespace that the parse options do not accept.
+    UnknownNamespace { namespace: String, span: Range<usize> },
+}
+
+impl TemplateParseError {
+    /// The byte range in the template source where the error occurred.
+    pub fn span(&self) -> Range<usize> {
+        match self {
+            Self::UnknownField { span, .. }
+            | Self::InvalidFieldName { span, .. }
+            | Self::UnknownNamespace { span, .. }
+            | Self::UnknownFilter { span, .. }
+            | Self::InvalidInlineField { span, .. }
+            | Self::UnsupportedFormat { span, .. }
+            | Self::UnsupportedConversion { span, .. }
+            | Self::UnsupportedTag { span, .. }
+            | Self::InvalidSelect { span }
+            | Self::InvalidFilter { span }
+            | Self::InvalidExpression { span }
+            | Self::InvalidFormatSpec { span }
+            | Self::InvalidCondition { span }
+            | Self::EmptyPlaceholder { span }
+            | Self::InvalidBlock { span } => span.clone(),
+            Self::UnclosedBracket { offset } | Self::UnmatchedClosingBracket { offset } => *offset..*offset + 1,
+            Self::UnclosedBlock { offset, .. } => *offset..*offset + 2,
+            Self::TooDeep { offset, .. } => *offset..*offset + 1,
+            Self::TooLong { limit, length } => *limit..*length,
+        }
+    }
+}
+
+impl fmt::Display for TemplateParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnknownField { name, .. } => write!(f, "Unknown field name: {name}"),
+            Self::InvalidFieldName { name, .. } => write!(f, "Invalid field name: {name}"),
+            Self::UnclosedBracket { .. } => f.write_str("Unclosed bracket in template"),
+            Self::UnmatchedClosingBracket { .. } => f.write_str("Unmatched closing bracket"),
+            Self::UnsupportedConversion { conversion, .. } => write!(f, "Unsupported conversion: {conversion}"),
+            Self::UnsupportedTag { tag, .. } => write!(f, "Unsupported tag: {tag}"),
+            Self::InvalidSelect { .. } => f.write_str("Invalid select block"),
+            Self::InvalidFilter { .. } => f.write_str("Invalid filter"),
+            Self::UnknownFilter { name, .. } => write!(f, "Unknown filter: {name}"),
+            Self::InvalidExpression { .. } => f.write_str("Invalid expression"),
+            Self::InvalidFormatSpec { .. } => f.write_str("Invalid format spec"),
+            Self::UnsupportedFormat { name, .. } => write!(f, "Unsupported format for field: {name}"),
+            Self::InvalidCondition { .. } => f.write_str("Invalid conditional section"),
+            Self::EmptyPlaceholder { .. } => f.write_str("Empty placeholder"),
+            Self::TooLong { limit, .. } => write!(f, "Template exceeds the maximum length of {limit} bytes"),
+            Self::TooDeep { limit, .. } => write!(f, "Template exceeds the maximum nesting depth of {limit}"),
+            Self::InvalidBlock { .. } => f.write_str("Invalid block tag"),
+            Self::UnclosedBlock { name, .. } => write!(f, "Unclosed block: {name}"),
+            Self::InvalidInlineField { name, .. } => write!(f, "Invalid template for inline field: {name}"),
+            Self::UnknownNamespace { namespace, .. } => write!(f, "Unknown namespace: {namespace}"),
+        }
+    }
+}
+
+impl std::error::Error for TemplateParseError {}
+
+/// An error that a render would run into, reported by [`TemplateString::dry_run`](crate::TemplateString::dry_run), or
+/// that a checked render ran into.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RenderError {
+    /// A field used by the render has no value, e.g. an `Option` field that is `None`.
+    MissingField { name: &'static str },
+    /// Formatting the value of a field returned an error.
+    Format { name: &'static str },
+    /// A field used in an arithmetic expression has no numeric value.
+    NotNumeric { name: &'static str },
+    /// The value of a field contains a character that is not allowed in the output, e.g. a line break in an HTTP
+    /// header value. The name is `namespace` for the value of a namespaced placeholder such as `{env:HOME}`.
+    InvalidChar { name: &'static str, char: char },
+    /// Nested template fields render more levels deep than allowed by
+    /// [`set_max_render_depth`](crate::set_max_render_depth), e.g. because a nested template renders itself.
+    DepthExceeded { limit: usize },
+    /// A namespaced placeholder has no value, e.g. `{env:HOME}` if the environment variable is not set.
+    Unresolved { namespace: String, name: String },
+    /// A filter is not built in, e.g. because of a typo, so rendering without a [`RenderCtx`](crate::RenderCtx) that
+    /// registers it would leave the value unchanged.
+    UnknownFilter { name: String },
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MissingField { name } => write!(f, "Missing value for field: {name}"),
+            Self::Format { name } => write!(f, "Failed to format field: {name}"),
+            Self::NotNumeric { name } => write!(f, "Field used in expression is not numeric: {name}"),
+            Self::InvalidChar { name, char } => write!(f, "Invalid character {char:?} in field: {name}"),
+            Self::DepthExceeded { limit } => write!(f, "Nested templates exceed the maximum render depth of {limit}"),
+            Self::Unresolved { namespace, name } => write!(f, "Unresolved placeholder: {namespace}:{name}"),
+            Self::UnknownFilter { name } => write!(f, "Unknown filter: {name}"),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
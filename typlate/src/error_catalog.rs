@@ -0,0 +1,122 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::hash::BuildHasher;
+use std::sync::RwLock;
+
+use crate::{CatalogError, TemplateString, TemplateStringParams};
+
+/// A provider of localized error messages by catalog key, used by types deriving
+/// [`TemplateError`](derive@crate::TemplateError).
+///
+/// Register a provider with [`set_error_catalog`]. It is implemented for maps from keys to template sources and for
+/// functions from a key to an optional source.
+pub trait ErrorCatalog: Send + Sync {
+    /// The template source of the message with the given key, or `None` if the catalog has no such message.
+    fn message(&self, key: &str) -> Option<Cow<'_, str>>;
+}
+
+impl<S: BuildHasher + Send + Sync> ErrorCatalog for HashMap<String, String, S> {
+    fn message(&self, key: &str) -> Option<Cow<'_, str>> {
+        self.get(key).map(|source| Cow::Borrowed(source.as_str()))
+    }
+}
+
+impl ErrorCatalog for BTreeMap<String, String> {
+    fn message(&self, key: &str) -> Option<Cow<'_, str>> {
+        self.get(key).map(|source| Cow::Borrowed(source.as_str()))
+    }
+}
+
+impl<F: Fn(&str) -> Option<String> + Send + Sync> ErrorCatalog for F {
+    fn message(&self, key: &str) -> Option<Cow<'_, str>> {
+        self(key).map(Cow::Owned)
+    }
+}
+
+/// An error type whose messages are templates loaded from an [`ErrorCatalog`], implemented by
+/// `#[derive(TemplateError)]`.
+pub trait TemplateError: std::error::Error {
+    /// The catalog keys of the messages, one per variant.
+    const KEYS: &'static [&'static str];
+
+    /// The catalog key of this error's message.
+    fn message_key(&self) -> &'static str;
+
+    /// Check that the catalog has a message for every variant, and that each message parses with the fields of its
+    /// variant.
+    fn validate_catalog(catalog: &dyn ErrorCatalog) -> Vec<CatalogError<&'static str>>;
+}
+
+static CATALOG: RwLock<Option<Box<dyn ErrorCatalog>>> = RwLock::new(None);
+
+/// Register the catalog of error messages for the whole process, replacing any previous catalog.
+///
+/// Errors deriving [`TemplateError`](derive@crate::TemplateError) look up their message in the catalog every time they
+/// are displayed, and fall back to the message given in the source code (or the key) if the catalog has no valid
+/// message for them.
+///
+/// ## Examples
+///
+/// ```
+/// # use std::collections::HashMap;
+/// # use typlate::TemplateError;
+/// #[derive(Debug, TemplateError)]
+/// enum UploadError {
+///     #[template(key = "upload.too_large", fallback = "File is larger than {limit} bytes")]
+///     TooLarge { size: u64, limit: u64 },
+/// }
+///
+/// let error = UploadError::TooLarge { size: 2048, limit: 1024 };
+/// assert_eq!(error.to_string(), "File is larger than 1024 bytes");
+///
+/// typlate::set_error_catalog(HashMap::from([(
+///     "upload.too_large".to_string(),
+///     "Die Datei ist größer als {limit} Bytes".to_string(),
+/// )]));
+/// assert_eq!(error.to_string(), "Die Datei ist größer als 1024 Bytes");
+/// # typlate::clear_error_catalog();
+/// ```
+pub fn set_error_catalog(catalog: impl ErrorCatalog + 'static) {
+    *CATALOG.write().unwrap_or_else(|error| error.into_inner()) = Some(Box::new(catalog));
+}
+
+/// Remove the catalog of error messages registered with [`set_error_catalog`].
+pub fn clear_error_catalog() {
+    *CATALOG.write().unwrap_or_else(|error| error.into_inner()) = None;
+}
+
+/// Render the message of an error variant: the catalog message if it parses for `T`, otherwise the fallback template,
+/// otherwise the key.
+pub fn fmt_error_message<T: TemplateStringParams>(
+    key: &str,
+    fallback: Option<&str>,
+    params: &T,
+    f: &mut fmt::Formatter,
+) -> fmt::Result {
+    let source = {
+        let catalog = CATALOG.read().unwrap_or_else(|error| error.into_inner());
+        catalog
+            .as_ref()
+            .and_then(|catalog| catalog.message(key).map(Cow::into_owned))
+    };
+    let template = source
+        .and_then(|source| source.parse::<TemplateString<T>>().ok())
+        .or_else(|| fallback.and_then(|fallback| fallback.parse().ok()));
+    match template {
+        Some(template) => fmt::Display::fmt(&template.display(params), f),
+        None => f.write_str(key),
+    }
+}
+
+/// Validate the catalog message of an error variant, where `T` holds the fields of the variant.
+pub fn validate_error_message<T: TemplateStringParams>(
+    catalog: &dyn ErrorCatalog,
+    key: &'static str,
+    errors: &mut Vec<CatalogError<&'static str>>,
+) {
+    match catalog.message(key) {
+        Some(source) => errors.extend(crate::validate_catalog::<T, _, _>([(key, source)])),
+        None => errors.push(CatalogError::MissingKey { key }),
+    }
+}
@@ -0,0 +1,76 @@
+//! Escapers for [`RenderCtx::with_escaper`](crate::RenderCtx::with_escaper).
+//!
+//! ## Examples
+//!
+//! ```
+//! # use typlate::{RenderCtx, TemplateParams, TemplateString};
+//! #[derive(TemplateParams)]
+//! struct Report {
+//!     customer: String,
+//!     date: String,
+//! }
+//!
+//! let template: TemplateString<Report> = "report-{customer}-{date}.pdf".parse().unwrap();
+//! let ctx = RenderCtx::new().with_escaper(typlate::escape::filename);
+//! let report = Report { customer: "../../etc/passwd".into(), date: "2024-01-31".into() };
+//! assert_eq!(template.format_with_ctx(&report, &ctx), "report-....etcpasswd-2024-01-31.pdf");
+//! ```
+
+/// Escape the characters of a value that are special in HTML text and attribute values: `&`, `<`, `>`, `"` and `'`.
+///
+/// This is also the built-in `html_escape` filter.
+pub fn html(value: &str) -> String {
+    let mut output = String::with_capacity(value.len());
+    for char in value.chars() {
+        match char {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            '\'' => output.push_str("&#39;"),
+            _ => output.push(char),
+        }
+    }
+    output
+}
+
+/// The maximum length in bytes of a value escaped by [`filename`], which is the file name limit of most file systems.
+pub const MAX_FILENAME_LEN: usize = 255;
+
+/// Names that Windows reserves for devices, with or without an extension.
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "LPT1", "LPT2",
+    "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Make a value safe to use within a file name.
+///
+/// - Path separators (`/` and `\`), control characters including NUL, and the characters `<>:"|?*` that Windows does
+///   not allow are removed, so the value cannot traverse directories.
+/// - Trailing dots and spaces are removed, so a value of `.` or `..` becomes empty.
+/// - A value that is a reserved Windows device name such as `CON` or `lpt1.txt` is prefixed with `_`.
+/// - The value is truncated to [`MAX_FILENAME_LEN`] bytes at a character boundary, removing the dots and spaces that
+///   end up trailing after the cut.
+///
+/// The literal text of the template is not escaped, and the rendered file name may still exceed the limit of the
+/// file system if the template combines several long values.
+pub fn filename(value: &str) -> String {
+    let mut output: String = value
+        .chars()
+        .filter(|&char| !char.is_control() && !matches!(char, '/' | '\\' | '<' | '>' | ':' | '"' | '|' | '?' | '*'))
+        .collect();
+    output.truncate(output.trim_end_matches(['.', ' ']).len());
+    let stem = output.split('.').next().unwrap_or_default();
+    if RESERVED_NAMES.iter().any(|name| name.eq_ignore_ascii_case(stem)) {
+        output.insert(0, '_');
+    }
+    if output.len() > MAX_FILENAME_LEN {
+        let end = (0..=MAX_FILENAME_LEN)
+            .rev()
+            .find(|&i| output.is_char_boundary(i))
+            .unwrap_or(0);
+        output.truncate(end);
+        output.truncate(output.trim_end_matches(['.', ' ']).len());
+    }
+    output
+}
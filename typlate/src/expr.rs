@@ -0,0 +1,294 @@
+use std::fmt::{self, Write};
+use std::ops::Range;
+
+use crate::{Number, TemplateParseError, TemplateStringParams};
+
+/// An arithmetic expression over numeric fields, e.g. `price * quantity` in `{price * quantity}`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) enum Expr {
+    Field(usize),
+    /// A numeric literal, kept as written.
+    Literal(String),
+    Binary(Box<Expr>, Op, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl Op {
+    pub(crate) fn from_char(char: char) -> Option<Self> {
+        match char {
+            '+' => Some(Self::Add),
+            '-' => Some(Self::Sub),
+            '*' => Some(Self::Mul),
+            '/' => Some(Self::Div),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_char(self) -> char {
+        match self {
+            Self::Add => '+',
+            Self::Sub => '-',
+            Self::Mul => '*',
+            Self::Div => '/',
+        }
+    }
+
+    fn precedence(self) -> u8 {
+        match self {
+            Self::Add | Self::Sub => 0,
+            Self::Mul | Self::Div => 1,
+        }
+    }
+}
+
+impl Expr {
+    /// Whether the content of a placeholder is an expression rather than a field name.
+    pub(crate) fn is_expression(source: &str) -> bool {
+        source.contains(|c| Op::from_char(c).is_some())
+    }
+
+    /// Parse an expression, where `offset` is the offset of `source` in the template. Expressions with more than
+    /// `max_depth` operators or levels of parentheses fail with [`TemplateParseError::TooDeep`].
+    ///
+    /// ```text
+    /// expr = term (("+" | "-") term)*
+    /// term = atom (("*" | "/") atom)*
+    /// atom = number | name | "(" expr ")"
+    /// ```
+    pub(crate) fn parse<T: TemplateStringParams>(
+        source: &str,
+        offset: usize,
+        max_depth: usize,
+    ) -> Result<Self, TemplateParseError> {
+        let mut parser = ExprParser::<T> {
+            source,
+            offset,
+            position: 0,
+            max_depth,
+            depth: 0,
+            operators: 0,
+            phantom: std::marker::PhantomData,
+        };
+        let expr = parser.parse_expr()?;
+        parser.skip_whitespace();
+        match parser.peek() {
+            None => Ok(expr),
+            Some(_) => Err(parser.invalid()),
+        }
+    }
+
+    /// Call `f` with the index of every field referenced by the expression.
+    pub(crate) fn visit_fields(&self, f: &mut impl FnMut(usize)) {
+        match self {
+            Self::Field(index) => f(*index),
+            Self::Literal(_) => {}
+            Self::Binary(lhs, _, rhs) => {
+                lhs.visit_fields(f);
+                rhs.visit_fields(f);
+            }
+        }
+    }
+
+    /// Evaluate the expression, or return `None` if a field has no numeric value.
+    pub(crate) fn eval<T: TemplateStringParams>(&self, params: &T) -> Option<Number> {
+        match self {
+            Self::Field(index) => params.field_number(*index),
+            Self::Literal(literal) => parse_number(literal),
+            Self::Binary(lhs, op, rhs) => Some(apply(*op, lhs.eval(params)?, rhs.eval(params)?)),
+        }
+    }
+
+    /// Write the expression in template syntax, with parentheses only where needed.
+    pub(crate) fn write_source(&self, f: &mut impl Write, fields: &[&str]) -> fmt::Result {
+        match self {
+            Self::Field(index) => f.write_str(fields[*index]),
+            Self::Literal(literal) => f.write_str(literal),
+            Self::Binary(lhs, op, rhs) => {
+                let parenthesize = |expr: &Expr, right: bool| match expr {
+                    Self::Binary(_, inner, _) => {
+                        inner.precedence() < op.precedence() || right && inner.precedence() == op.precedence()
+                    }
+                    _ => false,
+                };
+                for (i, expr) in [lhs, rhs].into_iter().enumerate() {
+                    if i == 1 {
+                        write!(f, " {} ", op.as_char())?;
+                    }
+                    if parenthesize(expr, i == 1) {
+                        f.write_char('(')?;
+                        expr.write_source(f, fields)?;
+                        f.write_char(')')?;
+                    } else {
+                        expr.write_source(f, fields)?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Whether two expressions have the same structure, comparing fields with `same_field`.
+    pub(crate) fn equivalent(&self, other: &Self, same_field: &impl Fn(&usize, &usize) -> bool) -> bool {
+        match (self, other) {
+            (Self::Field(left), Self::Field(right)) => same_field(left, right),
+            (Self::Literal(left), Self::Literal(right)) => left == right,
+            (Self::Binary(lhs, op, rhs), Self::Binary(other_lhs, other_op, other_rhs)) => {
+                op == other_op && lhs.equivalent(other_lhs, same_field) && rhs.equivalent(other_rhs, same_field)
+            }
+            _ => false,
+        }
+    }
+}
+
+struct ExprParser<'s, T> {
+    source: &'s str,
+    offset: usize,
+    position: usize,
+    max_depth: usize,
+    /// The number of parentheses open at the current position.
+    depth: usize,
+    /// The number of operators seen so far, which bounds the depth of the expression tree.
+    operators: usize,
+    phantom: std::marker::PhantomData<T>,
+}
+
+impl<T: TemplateStringParams> ExprParser<'_, T> {
+    fn parse_expr(&mut self) -> Result<Expr, TemplateParseError> {
+        self.parse_binary(0)
+    }
+
+    /// Parse operands joined by operators of the given precedence, where the operands bind tighter.
+    fn parse_binary(&mut self, precedence: u8) -> Result<Expr, TemplateParseError> {
+        let mut lhs = match precedence {
+            0 => self.parse_binary(1)?,
+            _ => self.parse_atom()?,
+        };
+        loop {
+            self.skip_whitespace();
+            let Some(op) = self
+                .peek()
+                .and_then(Op::from_char)
+                .filter(|op| op.precedence() == precedence)
+            else {
+                return Ok(lhs);
+            };
+            self.operators += 1;
+            self.check_depth(self.operators)?;
+            self.position += 1;
+            let rhs = match precedence {
+                0 => self.parse_binary(1)?,
+                _ => self.parse_atom()?,
+            };
+            lhs = Expr::Binary(Box::new(lhs), op, Box::new(rhs));
+        }
+    }
+
+    fn parse_atom(&mut self) -> Result<Expr, TemplateParseError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('(') => {
+                self.depth += 1;
+                self.check_depth(self.depth)?;
+                self.position += 1;
+                let expr = self.parse_expr()?;
+                self.skip_whitespace();
+                if self.peek() != Some(')') {
+                    return Err(self.invalid());
+                }
+                self.position += 1;
+                self.depth -= 1;
+                Ok(expr)
+            }
+            Some(char) if char.is_alphanumeric() || char == '_' || char == '.' => {
+                let start = self.position;
+                let rest = &self.source[start..];
+                let len = rest
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+                    .unwrap_or(rest.len());
+                self.position += len;
+                let token = &rest[..len];
+                if token.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+                    return match parse_number(token) {
+                        Some(_) => Ok(Expr::Literal(token.to_string())),
+                        None => Err(TemplateParseError::InvalidExpression {
+                            span: self.span(start..self.position),
+                        }),
+                    };
+                }
+                let span = self.span(start..self.position);
+                crate::parse::field_index::<T>(token, span).map(Expr::Field)
+            }
+            _ => Err(self.invalid()),
+        }
+    }
+
+    /// Fail at the current character if `depth` exceeds the limit.
+    fn check_depth(&self, depth: usize) -> Result<(), TemplateParseError> {
+        match depth > self.max_depth {
+            true => Err(TemplateParseError::TooDeep {
+                limit: self.max_depth,
+                offset: self.offset + self.position,
+            }),
+            false => Ok(()),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.position..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.source[self.position..];
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn span(&self, range: Range<usize>) -> Range<usize> {
+        self.offset + range.start..self.offset + range.end
+    }
+
+    /// An error at the current character, or at the end of the expression.
+    fn invalid(&self) -> TemplateParseError {
+        let len = self.peek().map_or(0, char::len_utf8);
+        TemplateParseError::InvalidExpression {
+            span: self.span(self.position..self.position + len),
+        }
+    }
+}
+
+/// Parse a numeric literal: an integer, or a float if it contains a `.`.
+pub(crate) fn parse_number(literal: &str) -> Option<Number> {
+    if literal.contains('.') {
+        literal.parse::<f64>().ok().map(Number::Float)
+    } else {
+        literal.parse::<i128>().ok().map(Number::Int)
+    }
+}
+
+/// Apply an operator, using integer arithmetic while both operands are integers and the result is an exact integer.
+fn apply(op: Op, lhs: Number, rhs: Number) -> Number {
+    if let (Some(a), Some(b)) = (lhs.to_i128(), rhs.to_i128()) {
+        let result = match op {
+            Op::Add => a.checked_add(b),
+            Op::Sub => a.checked_sub(b),
+            Op::Mul => a.checked_mul(b),
+            Op::Div => a.checked_rem(b).filter(|&rem| rem == 0).map(|_| a / b),
+        };
+        if let Some(result) = result {
+            return Number::Int(result);
+        }
+    }
+    let (a, b) = (lhs.to_f64(), rhs.to_f64());
+    Number::Float(match op {
+        Op::Add => a + b,
+        Op::Sub => a - b,
+        Op::Mul => a * b,
+        Op::Div => a / b,
+    })
+}
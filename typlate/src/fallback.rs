@@ -0,0 +1,75 @@
+use crate::{TemplateParams, TemplateString, TemplateStringParams};
+
+/// An ordered list of templates, formatted with the first one whose referenced fields are all present.
+///
+/// This is useful for progressively detailed messages, where the most specific template is listed first and
+/// less specific templates follow as fallbacks for when optional fields are missing.
+///
+/// ## Examples
+///
+/// ```
+/// # use typlate::{FallbackTemplate, TemplateParams};
+/// #[derive(TemplateParams)]
+/// struct Order {
+///     id: u32,
+///     carrier: Option<String>,
+/// }
+///
+/// let template: FallbackTemplate<Order> = [
+///     "Order {id} was shipped via {carrier}",
+///     "Order {id} was shipped",
+/// ]
+/// .into_iter()
+/// .map(str::parse)
+/// .collect::<Result<_, _>>()
+/// .unwrap();
+///
+/// let order = Order { id: 1, carrier: Some("UPS".into()) };
+/// assert_eq!(template.format(&order).unwrap(), "Order 1 was shipped via UPS");
+/// let order = Order { id: 2, carrier: None };
+/// assert_eq!(template.format(&order).unwrap(), "Order 2 was shipped");
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent, bound = "T: TemplateStringParams"))]
+pub struct FallbackTemplate<T> {
+    templates: Vec<TemplateString<T>>,
+}
+
+impl<T: TemplateStringParams> FallbackTemplate<T> {
+    /// Create a fallback chain from templates ordered by preference.
+    pub fn new(templates: Vec<TemplateString<T>>) -> Self {
+        Self { templates }
+    }
+
+    /// The templates in this chain, ordered by preference.
+    pub fn templates(&self) -> &[TemplateString<T>] {
+        &self.templates
+    }
+
+    /// Select the first template whose referenced fields are all present in `params`.
+    pub fn select(&self, params: &T) -> Option<&TemplateString<T>> {
+        self.templates.iter().find(|template| template.has_fields(params))
+    }
+
+    /// Format the first applicable template, or return `None` if no template applies.
+    pub fn format(&self, params: &T) -> Option<String> {
+        let template = self.select(params)?;
+        Some(crate::depth::render(params.format_template(template)))
+    }
+}
+
+impl<T> FromIterator<TemplateString<T>> for FallbackTemplate<T> {
+    fn from_iter<I: IntoIterator<Item = TemplateString<T>>>(iter: I) -> Self {
+        Self {
+            templates: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<T> Clone for FallbackTemplate<T> {
+    fn clone(&self) -> Self {
+        Self {
+            templates: self.templates.clone(),
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! A C API for validating templates from other languages, e.g. Python tooling loading a `cdylib` with `ctypes`.
+//!
+//! C functions cannot be generic, so [`validate`] is exported through a wrapper for each parameter type, while the
+//! functions reading its result are exported by this crate:
+//!
+//! ```
+//! # use std::ffi::{CStr, c_char};
+//! # use typlate::TemplateParams;
+//! # use typlate::ffi::{TyplateValidation, typlate_validation_error_message, typlate_validation_free};
+//! #[derive(TemplateParams)]
+//! struct Cart {
+//!     name: String,
+//!     count: u32,
+//! }
+//!
+//! #[unsafe(no_mangle)]
+//! pub unsafe extern "C" fn cart_validate(source: *const c_char) -> *mut TyplateValidation {
+//!     unsafe { typlate::ffi::validate::<Cart>(source) }
+//! }
+//!
+//! unsafe {
+//!     let validation = cart_validate(c"{name} has {cout} items".as_ptr());
+//!     let message = CStr::from_ptr(typlate_validation_error_message(validation));
+//!     assert_eq!(message, c"Unknown field name: cout");
+//!     typlate_validation_free(validation);
+//! }
+//! ```
+//!
+//! Offsets are byte positions in the UTF-8 encoded source. Strings returned by the functions are owned by the
+//! validation result and valid until it is freed with [`typlate_validation_free`].
+
+use std::ffi::{CStr, CString, c_char};
+use std::ptr;
+
+use crate::{TemplateString, TemplateStringParams};
+
+/// The result of validating a template with [`validate`].
+pub struct TyplateValidation {
+    /// The first error, with its byte range in the source.
+    error: Option<(CString, usize, usize)>,
+    /// The names of the placeholders of a valid template, in order of appearance.
+    placeholders: Vec<CString>,
+}
+
+/// Parse a template for the parameter type `T`, returning a validation result to be freed with
+/// [`typlate_validation_free`].
+///
+/// # Safety
+///
+/// `source` must be a valid pointer to a NUL-terminated string.
+pub unsafe fn validate<T: TemplateStringParams>(source: *const c_char) -> *mut TyplateValidation {
+    let source = unsafe { CStr::from_ptr(source) };
+    let validation = match source.to_str() {
+        Ok(source) => match source.parse::<TemplateString<T>>() {
+            Ok(template) => TyplateValidation {
+                error: None,
+                placeholders: template.placeholders().map(c_string).collect(),
+            },
+            Err(error) => {
+                let span = error.span();
+                TyplateValidation {
+                    error: Some((c_string(&error.to_string()), span.start, span.end)),
+                    placeholders: vec![],
+                }
+            }
+        },
+        Err(error) => {
+            let offset = error.valid_up_to();
+            TyplateValidation {
+                error: Some((c_string("Template is not valid UTF-8"), offset, offset + 1)),
+                placeholders: vec![],
+            }
+        }
+    };
+    Box::into_raw(Box::new(validation))
+}
+
+fn c_string(value: &str) -> CString {
+    CString::new(value).unwrap_or_default()
+}
+
+/// Whether the template is valid.
+///
+/// # Safety
+///
+/// `validation` must be null or a pointer returned by [`validate`] that has not been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn typlate_validation_is_ok(validation: *const TyplateValidation) -> bool {
+    unsafe { validation.as_ref() }.is_some_and(|validation| validation.error.is_none())
+}
+
+/// The error message, or null if the template is valid.
+///
+/// # Safety
+///
+/// `validation` must be null or a pointer returned by [`validate`] that has not been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn typlate_validation_error_message(validation: *const TyplateValidation) -> *const c_char {
+    match unsafe { validation.as_ref() }.and_then(|validation| validation.error.as_ref()) {
+        Some((message, _, _)) => message.as_ptr(),
+        None => ptr::null(),
+    }
+}
+
+/// The byte offset of the start of the error, or 0 if the template is valid.
+///
+/// # Safety
+///
+/// `validation` must be null or a pointer returned by [`validate`] that has not been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn typlate_validation_error_start(validation: *const TyplateValidation) -> usize {
+    unsafe { validation.as_ref() }
+        .and_then(|validation| validation.error.as_ref())
+        .map_or(0, |&(_, start, _)| start)
+}
+
+/// The byte offset of the end of the error, or 0 if the template is valid.
+///
+/// # Safety
+///
+/// `validation` must be null or a pointer returned by [`validate`] that has not been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn typlate_validation_error_end(validation: *const TyplateValidation) -> usize {
+    unsafe { validation.as_ref() }
+        .and_then(|validation| validation.error.as_ref())
+        .map_or(0, |&(_, _, end)| end)
+}
+
+/// The number of placeholders of a valid template, or 0 if the template is invalid.
+///
+/// # Safety
+///
+/// `validation` must be null or a pointer returned by [`validate`] that has not been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn typlate_validation_placeholder_count(validation: *const TyplateValidation) -> usize {
+    unsafe { validation.as_ref() }.map_or(0, |validation| validation.placeholders.len())
+}
+
+/// The field name of the placeholder at `index`, or null if `index` is out of bounds.
+///
+/// # Safety
+///
+/// `validation` must be null or a pointer returned by [`validate`] that has not been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn typlate_validation_placeholder(
+    validation: *const TyplateValidation,
+    index: usize,
+) -> *const c_char {
+    match unsafe { validation.as_ref() }.and_then(|validation| validation.placeholders.get(index)) {
+        Some(name) => name.as_ptr(),
+        None => ptr::null(),
+    }
+}
+
+/// Free a validation result. Does nothing if `validation` is null.
+///
+/// # Safety
+///
+/// `validation` must be null or a pointer returned by [`validate`] that has not been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn typlate_validation_free(validation: *mut TyplateValidation) {
+    if !validation.is_null() {
+        drop(unsafe { Box::from_raw(validation) });
+    }
+}
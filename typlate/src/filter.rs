@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::sync::Arc;
+
+type FilterFn = dyn Fn(&str, &[String]) -> String + Send + Sync;
+
+/// A filter applied to a placeholder, e.g. `truncate(40)` in `{title|truncate(40)}`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) struct FilterCall {
+    pub(crate) name: String,
+    pub(crate) args: Vec<String>,
+}
+
+impl FilterCall {
+    /// Write the filter in template syntax, including the leading `|`.
+    pub(crate) fn write_source(&self, f: &mut impl Write) -> fmt::Result {
+        write!(f, "|{}", self.name)?;
+        for (i, arg) in self.args.iter().enumerate() {
+            f.write_str(if i == 0 { "(" } else { ", " })?;
+            let quoted = arg.len() >= 2 && arg.starts_with('"') && arg.ends_with('"');
+            if arg.is_empty() || arg.trim() != arg || quoted {
+                write!(f, "\"{arg}\"")?;
+            } else {
+                f.write_str(arg)?;
+            }
+        }
+        if !self.args.is_empty() {
+            f.write_char(')')?;
+        }
+        Ok(())
+    }
+}
+
+/// Named filters that templates can apply to placeholders with `{field|name}` or `{field|name(arg, ...)}`.
+///
+/// A filter receives the rendered field value and the arguments given in the template, and returns the new value.
+/// Arguments are trimmed unless they are wrapped in double quotes, as in `{tags|join(" / ")}`.
+/// Registered filters take precedence over the built-in filters (`upper`, `lower`, `trim`, `truncate`, `join`,
+/// `percent`, `permille`, `ordinal`, `relative`, `mask`, `mask_email`, `slug`, `base64`, `base64url`, `html_escape`
+/// and `raw`, plus the [`ansi`](crate::ansi) filters with the `ansi` feature). `raw` leaves the value unchanged and
+/// skips the [default filters](crate::TemplateStringParams::default_filters) of the field and the escaper of the
+/// [`RenderCtx`](crate::RenderCtx). Templates that use other filters must be parsed with options that accept them (see
+/// [`ParseOptionsBuilder::filter`](crate::ParseOptionsBuilder::filter)). Such a filter leaves the value unchanged if it
+/// is not registered when rendering, and is reported as
+/// [`RenderError::UnknownFilter`](crate::RenderError::UnknownFilter) by
+/// [`TemplateString::try_format`](crate::TemplateString::try_format).
+///
+/// ## Examples
+///
+/// ```
+/// # use typlate::{FilterRegistry, ParseOptions, RenderCtx, TemplateParams, TemplateString};
+/// #[derive(TemplateParams)]
+/// struct Data {
+///     name: String,
+/// }
+///
+/// let mut filters = FilterRegistry::new();
+/// filters.register("repeat", |value, args| value.repeat(args[0].parse().unwrap()));
+/// let ctx = RenderCtx::new().with_filters(filters);
+///
+/// let options = ParseOptions::builder().filter("repeat").build();
+/// let template = TemplateString::<Data>::parse_with("{name|upper|repeat(2)}", &options).unwrap();
+/// assert_eq!(template.format_with_ctx(&Data { name: "ha".into() }, &ctx), "HAHA");
+/// ```
+#[derive(Clone, Default)]
+pub struct FilterRegistry {
+    filters: HashMap<String, Arc<FilterFn>>,
+}
+
+impl FilterRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a filter, replacing any filter previously registered with the same name.
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        filter: impl Fn(&str, &[String]) -> String + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.filters.insert(name.into(), Arc::new(filter));
+        self
+    }
+
+    /// Whether a filter with the given name is registered.
+    pub fn contains(&self, name: &str) -> bool {
+        self.filters.contains_key(name)
+    }
+
+    /// Apply the filter to a value, falling back to the built-in filters.
+    pub(crate) fn apply(&self, filter: &FilterCall, value: String) -> String {
+        match self.filters.get(&filter.name) {
+            Some(apply) => apply(&value, &filter.args),
+            None => apply_builtin(filter, value),
+        }
+    }
+}
+
+impl fmt::Debug for FilterRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.filters.keys()).finish()
+    }
+}
+
+/// The names of the built-in filters, except for the [`ansi`](crate::ansi) filters.
+const BUILTIN_FILTERS: &[&str] = &[
+    "upper",
+    "lower",
+    "trim",
+    "truncate",
+    "join",
+    "percent",
+    "permille",
+    "ordinal",
+    "relative",
+    "mask",
+    "mask_email",
+    "slug",
+    "base64",
+    "base64url",
+    "html_escape",
+    "raw",
+];
+
+/// Whether a filter with the given name is built in. The derive macro checks default filters with it at compile time.
+pub const fn is_builtin(name: &str) -> bool {
+    #[cfg(feature = "ansi")]
+    if contains(crate::ansi::FILTERS, name) {
+        return true;
+    }
+    contains(BUILTIN_FILTERS, name)
+}
+
+/// Whether `names` contains `name`, usable in constants.
+const fn contains(names: &[&str], name: &str) -> bool {
+    let name = name.as_bytes();
+    let mut k = 0;
+    'names: while k < names.len() {
+        let candidate = names[k].as_bytes();
+        k += 1;
+        if candidate.len() != name.len() {
+            continue;
+        }
+        let mut j = 0;
+        while j < name.len() {
+            if candidate[j] != name[j] {
+                continue 'names;
+            }
+            j += 1;
+        }
+        return true;
+    }
+    false
+}
+
+/// Apply one of the built-in filters, leaving the value unchanged for unknown filters.
+pub(crate) fn apply_builtin(filter: &FilterCall, value: String) -> String {
+    #[cfg(feature = "ansi")]
+    if let Some(value) = crate::ansi::apply(&filter.name, &filter.args, &value) {
+        return value;
+    }
+    match &*filter.name {
+        "upper" => value.to_uppercase(),
+        "lower" => value.to_lowercase(),
+        "trim" => value.trim().to_string(),
+        "percent" | "permille" => match value.trim().parse() {
+            Ok(ratio) => crate::ctx::format_numeric(None, crate::Number::Float(ratio), filter),
+            Err(_) => value,
+        },
+        "ordinal" => match value.trim().parse() {
+            Ok(integer) => crate::ctx::format_numeric(None, crate::Number::Int(integer), filter),
+            Err(_) => value,
+        },
+        "mask" => {
+            let visible = filter.args.first().and_then(|len| len.parse().ok()).unwrap_or(4);
+            mask(&value, visible, mask_char(filter.args.get(1)))
+        }
+        "slug" => slug(&value),
+        "html_escape" => crate::escape::html(&value),
+        "base64" | "base64url" => encode_base64(value.as_bytes(), filter.name == "base64url"),
+        "mask_email" => mask_email(&value, mask_char(filter.args.first())),
+        "truncate" => match filter.args.first().and_then(|len| len.parse().ok()) {
+            Some(len) => truncate(value, len, filter.args.get(1).map_or("\u{2026}", String::as_str)),
+            None => value,
+        },
+        _ => value,
+    }
+}
+
+/// Truncate a value to at most `len` characters including the ellipsis, which is appended if the value was
+/// truncated and fits in `len`. With the `unicode-segmentation` feature, characters are grapheme clusters, so that
+/// emoji sequences and combining marks are never split.
+fn truncate(value: String, len: usize, ellipsis: &str) -> String {
+    #[cfg(feature = "unicode-segmentation")]
+    let boundaries: Vec<usize> = unicode_segmentation::UnicodeSegmentation::grapheme_indices(&*value, true)
+        .map(|(i, _)| i)
+        .collect();
+    #[cfg(not(feature = "unicode-segmentation"))]
+    let boundaries: Vec<usize> = value.char_indices().map(|(i, _)| i).collect();
+
+    if boundaries.len() <= len {
+        return value;
+    }
+    let Some(kept) = len.checked_sub(ellipsis.chars().count()) else {
+        return value[..boundaries[len]].to_string();
+    };
+    let mut output = value[..boundaries[kept]].to_string();
+    output.push_str(ellipsis);
+    output
+}
+
+/// Turn a value into a URL-safe slug: lowercase letters and digits, with every run of other characters replaced by a
+/// single dash, e.g. `hello-world` for `Hello, World!`.
+///
+/// With the `deunicode` feature, the value is transliterated to ASCII first, e.g. `creme-brulee` for `Crème brûlée`.
+/// Otherwise, non-ASCII letters and digits are kept as they are.
+fn slug(value: &str) -> String {
+    #[cfg(feature = "deunicode")]
+    let value = deunicode::deunicode(value);
+    let mut slug = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    if slug.ends_with('-') {
+        slug.pop();
+    }
+    slug
+}
+
+/// Whether the filter is one of the base64 filters, which encode the bytes of byte fields rather than their rendered
+/// value.
+pub(crate) fn is_base64(filter: &FilterCall) -> bool {
+    matches!(filter.name.as_str(), "base64" | "base64url")
+}
+
+/// Encode bytes in base64: with the standard alphabet and padding, or with the URL-safe alphabet and without padding
+/// if `url` is set.
+pub(crate) fn encode_base64(bytes: &[u8], url: bool) -> String {
+    const STANDARD: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    const URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+    let alphabet = if url { URL } else { STANDARD };
+    let mut output = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |group, (i, &byte)| group | u32::from(byte) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            output.push(alphabet[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+        if !url {
+            for _ in chunk.len()..3 {
+                output.push('=');
+            }
+        }
+    }
+    output
+}
+
+/// The character that replaces masked characters: the first character of the argument, or `*` by default.
+fn mask_char(arg: Option<&String>) -> char {
+    arg.and_then(|arg| arg.chars().next()).unwrap_or('*')
+}
+
+/// Mask every letter and digit of a value except the last `visible` ones, keeping separators such as spaces and
+/// dashes, e.g. `**** **** **** 1234` for a card number with `mask(4)`.
+fn mask(value: &str, visible: usize, mask: char) -> String {
+    let masked = value
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .count()
+        .saturating_sub(visible);
+    let mut seen = 0;
+    value
+        .chars()
+        .map(|c| {
+            if !c.is_alphanumeric() {
+                return c;
+            }
+            seen += 1;
+            if seen <= masked { mask } else { c }
+        })
+        .collect()
+}
+
+/// Mask the local part of an email address except its first character, keeping the domain, e.g. `a****@example.com`.
+/// A value without an `@` is masked entirely.
+fn mask_email(value: &str, mask: char) -> String {
+    let Some((local, domain)) = value.rsplit_once('@') else {
+        return value.chars().map(|_| mask).collect();
+    };
+    let mut chars = local.chars();
+    let first = chars.next().map(String::from).unwrap_or_default();
+    let masked: String = chars.map(|_| mask).collect();
+    format!("{first}{masked}@{domain}")
+}
@@ -0,0 +1,342 @@
+use std::fmt::{self, Write};
+
+use crate::{Number, TemplateParseError, TemplateStringParams};
+
+/// A `std::fmt`-style format spec of a placeholder, e.g. `>width$.2` in `{price:>width$.2}`, `#?` in `{state:#?}` or
+/// `08x` in `{id:08x}`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) struct FormatSpec {
+    pub(crate) fill: char,
+    pub(crate) align: Option<Align>,
+    /// Whether non-negative numbers get a `+` sign.
+    pub(crate) sign: bool,
+    /// Whether the value is formatted in alternate mode, i.e. pretty-printed with `#?` or prefixed with `0x`, `0o` or
+    /// `0b` in radix formats.
+    pub(crate) alternate: bool,
+    /// Whether numbers are padded with zeros after their sign.
+    pub(crate) zero: bool,
+    pub(crate) width: Option<Count>,
+    pub(crate) precision: Option<Count>,
+    pub(crate) kind: Kind,
+}
+
+/// How the value is formatted, from the type at the end of the spec.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) enum Kind {
+    /// With `Display`, without a type.
+    Display,
+    /// With `Debug`, for `?`.
+    Debug,
+    /// Integers in lowercase hexadecimal, for `x`.
+    LowerHex,
+    /// Integers in uppercase hexadecimal, for `X`.
+    UpperHex,
+    /// Integers in octal, for `o`.
+    Octal,
+    /// Integers in binary, for `b`.
+    Binary,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// The largest width or precision, matching the limit of `std::fmt`.
+pub(crate) const MAX_COUNT: usize = u16::MAX as usize;
+
+/// A width or precision: a literal number, or the value of a numeric field, as in `width$`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) enum Count {
+    Literal(usize),
+    Field(usize),
+}
+
+impl Align {
+    pub(crate) fn from_char(char: char) -> Option<Self> {
+        match char {
+            '<' => Some(Self::Left),
+            '^' => Some(Self::Center),
+            '>' => Some(Self::Right),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_char(self) -> char {
+        match self {
+            Self::Left => '<',
+            Self::Center => '^',
+            Self::Right => '>',
+        }
+    }
+}
+
+impl Kind {
+    pub(crate) const ALL: [(Self, &'static str); 6] = [
+        (Self::Display, ""),
+        (Self::Debug, "?"),
+        (Self::LowerHex, "x"),
+        (Self::UpperHex, "X"),
+        (Self::Octal, "o"),
+        (Self::Binary, "b"),
+    ];
+
+    pub(crate) fn as_str(self) -> &'static str {
+        Self::ALL.iter().find(|(kind, _)| *kind == self).unwrap().1
+    }
+
+    /// Whether this is a radix format for integers.
+    pub(crate) fn is_radix(self) -> bool {
+        matches!(self, Self::LowerHex | Self::UpperHex | Self::Octal | Self::Binary)
+    }
+
+    /// Format an integer in this radix, with a `-` sign for negative values rather than in two's complement, and with
+    /// a `0x`, `0o` or `0b` prefix in alternate mode. Returns `None` for floats and non-radix formats.
+    pub(crate) fn format_radix(self, number: Number, alternate: bool) -> Option<String> {
+        let (negative, magnitude) = match number {
+            Number::Int(value) => (value < 0, value.unsigned_abs()),
+            Number::UInt(value) => (false, value),
+            Number::Float(_) => return None,
+        };
+        let (prefix, digits) = match self {
+            Self::LowerHex => ("0x", format!("{magnitude:x}")),
+            Self::UpperHex => ("0x", format!("{magnitude:X}")),
+            Self::Octal => ("0o", format!("{magnitude:o}")),
+            Self::Binary => ("0b", format!("{magnitude:b}")),
+            Self::Display | Self::Debug => return None,
+        };
+        let sign = if negative { "-" } else { "" };
+        let prefix = if alternate { prefix } else { "" };
+        Some(format!("{sign}{prefix}{digits}"))
+    }
+}
+
+impl FormatSpec {
+    /// Parse the spec after the `:` of a placeholder, where `offset` is the offset of `source` in the template.
+    ///
+    /// ```text
+    /// spec  = ((fill)? ("<" | "^" | ">"))? "+"? "#"? "0"? count? ("." count)? ("?" | "x" | "X" | "o" | "b")?
+    /// count = integer | name "$"
+    /// ```
+    pub(crate) fn parse<T: TemplateStringParams>(source: &str, offset: usize) -> Result<Self, TemplateParseError> {
+        let mut spec = Self {
+            fill: ' ',
+            align: None,
+            sign: false,
+            alternate: false,
+            zero: false,
+            width: None,
+            precision: None,
+            kind: Kind::Display,
+        };
+        let mut rest = source;
+        let mut chars = rest.chars();
+        match (chars.next(), chars.next().and_then(Align::from_char)) {
+            (Some(fill), Some(align)) => {
+                spec.fill = fill;
+                spec.align = Some(align);
+                rest = &rest[fill.len_utf8() + 1..];
+            }
+            (Some(char), _) if Align::from_char(char).is_some() => {
+                spec.align = Align::from_char(char);
+                rest = &rest[1..];
+            }
+            _ => {}
+        }
+        if let Some(after) = rest.strip_prefix('+') {
+            spec.sign = true;
+            rest = after;
+        }
+        let alternate = offset + source.len() - rest.len();
+        if let Some(after) = rest.strip_prefix('#') {
+            spec.alternate = true;
+            rest = after;
+        }
+        if let Some(after) = rest.strip_prefix('0')
+            && !after.starts_with('$')
+        {
+            spec.zero = true;
+            rest = after;
+        }
+        let (width, after) = parse_count::<T>(rest, offset + source.len() - rest.len())?;
+        spec.width = width;
+        rest = after;
+        if let Some(after) = rest.strip_prefix('.') {
+            let start = offset + source.len() - after.len();
+            let (precision, after) = parse_count::<T>(after, start)?;
+            if precision.is_none() {
+                return Err(TemplateParseError::InvalidFormatSpec { span: start - 1..start });
+            }
+            spec.precision = precision;
+            rest = after;
+        }
+        if let Some((kind, after)) = Kind::ALL[1..]
+            .iter()
+            .find_map(|&(kind, suffix)| Some((kind, rest.strip_prefix(suffix)?)))
+        {
+            spec.kind = kind;
+            rest = after;
+        }
+        if !rest.is_empty() {
+            let start = offset + source.len() - rest.len();
+            return Err(TemplateParseError::InvalidFormatSpec {
+                span: start..offset + source.len(),
+            });
+        }
+        // Values are only formatted in alternate mode with `Debug` or a radix.
+        if spec.alternate && spec.kind == Kind::Display {
+            return Err(TemplateParseError::InvalidFormatSpec {
+                span: alternate..alternate + 1,
+            });
+        }
+        Ok(spec)
+    }
+
+    /// Call `f` with the index of every field referenced by the width and the precision.
+    pub(crate) fn visit_fields(&self, f: &mut impl FnMut(usize)) {
+        for count in [self.width, self.precision].into_iter().flatten() {
+            if let Count::Field(index) = count {
+                f(index);
+            }
+        }
+    }
+
+    /// Whether two specs are the same, comparing fields with `same_field`.
+    pub(crate) fn equivalent(&self, other: &Self, same_field: &impl Fn(&usize, &usize) -> bool) -> bool {
+        let same_count = |left: Option<Count>, right: Option<Count>| match (left, right) {
+            (Some(Count::Field(left)), Some(Count::Field(right))) => same_field(&left, &right),
+            (left, right) => left == right,
+        };
+        let flags = |spec: &Self| (spec.fill, spec.align, spec.sign, spec.alternate, spec.zero, spec.kind);
+        flags(self) == flags(other)
+            && same_count(self.width, other.width)
+            && same_count(self.precision, other.precision)
+    }
+
+    /// Format a rendered value, where `number` is the numeric value of the field, if any.
+    ///
+    /// As in `std::fmt`, the precision is the number of decimals of floats and the maximum length of other values, and
+    /// is ignored for integers. Numbers are aligned to the right and other values to the left by default. The width is
+    /// measured in characters, or with the `unicode-width` feature in terminal columns, so that CJK values line up. A
+    /// width or precision that refers to a field without an integer value between 0 and [`MAX_COUNT`] is ignored.
+    pub(crate) fn apply<T: TemplateStringParams>(&self, params: &T, value: String, number: Option<Number>) -> String {
+        let resolve = |count: Option<Count>| {
+            let count = match count? {
+                Count::Literal(count) => count,
+                Count::Field(index) => usize::try_from(params.field_number(index)?.to_i128()?).ok()?,
+            };
+            (count <= MAX_COUNT).then_some(count)
+        };
+        let mut value = match (number, resolve(self.precision)) {
+            (Some(Number::Float(float)), Some(precision)) => format!("{float:.precision$}"),
+            (None, Some(precision)) => value.chars().take(precision).collect(),
+            _ => value,
+        };
+        if self.sign && number.is_some_and(|number| number.to_f64() >= 0.0) {
+            value.insert(0, '+');
+        }
+        let width = crate::string::display_width(&value);
+        let Some(padding) = resolve(self.width).and_then(|padded| padded.checked_sub(width)) else {
+            return value;
+        };
+        if self.zero && self.align.is_none() && number.is_some() {
+            let sign = value.starts_with(['+', '-']) as usize;
+            let prefix = if self.alternate && self.kind.is_radix() { 2 } else { 0 };
+            value.insert_str(sign + prefix, &"0".repeat(padding));
+            return value;
+        }
+        let fill = |count: usize| self.fill.to_string().repeat(count);
+        let default = if number.is_some() { Align::Right } else { Align::Left };
+        match self.align.unwrap_or(default) {
+            Align::Left => value + &fill(padding),
+            Align::Center => fill(padding / 2) + &value + &fill(padding - padding / 2),
+            Align::Right => fill(padding) + &value,
+        }
+    }
+
+    /// Write the spec in template syntax, without the leading `:`.
+    pub(crate) fn write_source(&self, f: &mut impl Write, fields: &[&str]) -> fmt::Result {
+        if let Some(align) = self.align {
+            if self.fill != ' ' {
+                f.write_char(self.fill)?;
+            }
+            f.write_char(align.as_char())?;
+        }
+        if self.sign {
+            f.write_char('+')?;
+        }
+        if self.alternate {
+            f.write_char('#')?;
+        }
+        if self.zero {
+            f.write_char('0')?;
+        }
+        if let Some(width) = self.width {
+            width.write_source(f, fields)?;
+        }
+        if let Some(precision) = self.precision {
+            f.write_char('.')?;
+            precision.write_source(f, fields)?;
+        }
+        f.write_str(self.kind.as_str())
+    }
+}
+
+impl FormatSpec {
+    /// Whether the field at `index` supports the kind of the spec: `Debug` requires a field marked with
+    /// `#[template(debug)]`, and radix formats an integer field.
+    pub(crate) fn supports<T: TemplateStringParams>(&self, index: usize) -> bool {
+        match self.kind {
+            Kind::Display => true,
+            Kind::Debug => T::is_debug_field(index),
+            _ => T::is_integer_field(index),
+        }
+    }
+
+    /// Whether the spec is one that [`FormatSpec::parse`] accepts, for specs that were not parsed: literal counts are
+    /// at most [`MAX_COUNT`], and alternate mode requires `Debug` or a radix.
+    pub(crate) fn is_well_formed(&self) -> bool {
+        let count = |count| !matches!(count, Some(Count::Literal(count)) if count > MAX_COUNT);
+        count(self.width) && count(self.precision) && !(self.alternate && self.kind == Kind::Display)
+    }
+}
+
+impl Count {
+    fn write_source(self, f: &mut impl Write, fields: &[&str]) -> fmt::Result {
+        match self {
+            Self::Literal(count) => write!(f, "{count}"),
+            Self::Field(index) => write!(f, "{}$", fields[index]),
+        }
+    }
+}
+
+/// Parse an optional count at the start of `source`, returning it and the rest of the source.
+fn parse_count<T: TemplateStringParams>(
+    source: &str,
+    offset: usize,
+) -> Result<(Option<Count>, &str), TemplateParseError> {
+    let len = source
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(source.len());
+    let (token, rest) = source.split_at(len);
+    if !token.is_empty()
+        && let Some(rest) = rest.strip_prefix('$')
+    {
+        let index = crate::parse::field_index::<T>(token, offset..offset + len)?;
+        return Ok((Some(Count::Field(index)), rest));
+    }
+    // A literal count is followed directly by the rest of the spec, such as the type in `8x`.
+    let len = source.find(|c: char| !c.is_ascii_digit()).unwrap_or(source.len());
+    let (digits, rest) = source.split_at(len);
+    if digits.is_empty() {
+        return Ok((None, rest));
+    }
+    match digits.parse() {
+        Ok(count) if count <= MAX_COUNT => Ok((Some(Count::Literal(count)), rest)),
+        _ => Err(TemplateParseError::InvalidFormatSpec {
+            span: offset..offset + len,
+        }),
+    }
+}
@@ -0,0 +1,313 @@
+//! Import and export of gettext PO/POT files.
+//!
+//! Each template becomes one PO entry whose `msgctxt` is the catalog key, `msgid` is the reference template and
+//! `msgstr` is the translated template. Placeholders are written either in brace style (`{name}`, flagged as
+//! `python-brace-format`) or in Python style (`%(name)s`, flagged as `python-format`), so that PO tooling can check
+//! them. Python style only supports plain placeholders.
+//!
+//! ## Examples
+//!
+//! ```
+//! # use typlate::gettext::{self, PlaceholderStyle};
+//! # use typlate::{TemplateParams, TemplateString};
+//! #[derive(TemplateParams)]
+//! struct User {
+//!     name: String,
+//! }
+//!
+//! let source: TemplateString<User> = "Hello {name}".parse().unwrap();
+//! let translation: TemplateString<User> = "Bonjour {name}".parse().unwrap();
+//! let po = gettext::export_po([("greeting", &source, Some(&translation))], PlaceholderStyle::Python).unwrap();
+//! assert!(po.contains("msgid \"Hello %(name)s\"\nmsgstr \"Bonjour %(name)s\""));
+//!
+//! let entries = gettext::import_po::<User>(&po, PlaceholderStyle::Python).unwrap();
+//! assert_eq!(entries[0].0, "greeting");
+//! assert_eq!(entries[0].1.to_string(), "Bonjour {name}");
+//! ```
+
+use std::fmt::{self, Display, Write};
+
+use crate::string::{Builder, TemplateElement};
+use crate::{TemplateParseError, TemplateString, TemplateStringParams};
+
+/// How placeholders are written in PO strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PlaceholderStyle {
+    /// Placeholders keep the typlate syntax, e.g. `{name}`.
+    Brace,
+    /// Placeholders use Python's named printf syntax, e.g. `%(name)s`.
+    Python,
+}
+
+impl PlaceholderStyle {
+    fn flag(self) -> &'static str {
+        match self {
+            Self::Brace => "python-brace-format",
+            Self::Python => "python-format",
+        }
+    }
+}
+
+/// An error that occurred while importing or exporting a PO file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PoError {
+    /// The PO file is malformed at the given (1-based) line.
+    Syntax { line: usize, message: String },
+    /// The translation of an entry is not a valid template.
+    Template { key: String, error: TemplateParseError },
+    /// A template of an exported entry cannot be written in the placeholder style.
+    Unsupported { key: String, error: UnsupportedPlaceholder },
+}
+
+impl Display for PoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Syntax { line, message } => write!(f, "line {line}: {message}"),
+            Self::Template { key, error } => write!(f, "{key}: {error}"),
+            Self::Unsupported { key, error } => write!(f, "{key}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for PoError {}
+
+/// A placeholder that has no Python-style equivalent, such as a filtered placeholder, a select block or a
+/// conditional section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedPlaceholder {
+    /// The placeholder in template syntax.
+    pub placeholder: String,
+}
+
+impl Display for UnsupportedPlaceholder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Placeholder has no Python-style equivalent: {}", self.placeholder)
+    }
+}
+
+impl std::error::Error for UnsupportedPlaceholder {}
+
+/// Convert a template into a PO string with the given placeholder style.
+///
+/// Python style only supports plain `{name}` placeholders. Other placeholders, such as `{name|upper}`, are rejected, so
+/// catalogs that use them should be exported in brace style.
+pub fn to_po_string<T: TemplateStringParams>(
+    template: &TemplateString<T>,
+    style: PlaceholderStyle,
+) -> Result<String, UnsupportedPlaceholder> {
+    match style {
+        PlaceholderStyle::Brace => Ok(template.to_string()),
+        PlaceholderStyle::Python => {
+            let mut output = String::new();
+            for element in template.elements() {
+                match element {
+                    TemplateElement::Text(text) => output.push_str(&text.replace('%', "%%")),
+                    TemplateElement::Var(index) => write!(output, "%({})s", T::FIELDS[*index]).unwrap(),
+                    element => {
+                        let mut placeholder = String::new();
+                        element.write_source(&mut placeholder, T::FIELDS).unwrap();
+                        return Err(UnsupportedPlaceholder { placeholder });
+                    }
+                }
+            }
+            Ok(output)
+        }
+    }
+}
+
+/// Parse a PO string with the given placeholder style into a template.
+///
+/// In Python style, `%%` is a literal percent sign and `%(name)s`, `%(name)d` and `%(name)i` are placeholders.
+/// Positional conversions such as `%s` cannot be mapped to a field and are rejected.
+pub fn from_po_string<T: TemplateStringParams>(
+    input: &str,
+    style: PlaceholderStyle,
+) -> Result<TemplateString<T>, TemplateParseError> {
+    if style == PlaceholderStyle::Brace {
+        return input.parse();
+    }
+
+    let mut builder = Builder::new();
+    let mut chars = input.char_indices().peekable();
+    while let Some((offset, char)) = chars.next() {
+        if char != '%' {
+            builder.push_char(char);
+            continue;
+        }
+        if chars.next_if(|&(_, c)| c == '%').is_some() {
+            builder.push_char('%');
+            continue;
+        }
+        let unsupported = |end: usize| TemplateParseError::UnsupportedConversion {
+            conversion: input[offset..end].to_string(),
+            span: offset..end,
+        };
+        if chars.next_if(|&(_, c)| c == '(').is_none() {
+            let end = chars.next().map_or(input.len(), |(i, c)| i + c.len_utf8());
+            return Err(unsupported(end));
+        }
+        let Some((close, _)) = chars.by_ref().find(|&(_, c)| c == ')') else {
+            return Err(TemplateParseError::UnclosedBracket { offset: offset + 1 });
+        };
+        match chars.next() {
+            Some((_, 's' | 'd' | 'i')) => builder.push_var(&input[offset + 2..close], offset + 2..close)?,
+            Some((i, c)) => return Err(unsupported(i + c.len_utf8())),
+            None => return Err(unsupported(input.len())),
+        }
+    }
+    Ok(builder.finish())
+}
+
+/// Export templates as a PO file.
+///
+/// Each entry is a catalog key, the reference template and an optional translation. Passing `None` for every
+/// translation produces a POT template file. Fails if a template cannot be written in the placeholder style, see
+/// [`to_po_string`].
+pub fn export_po<'a, T, K>(
+    entries: impl IntoIterator<Item = (K, &'a TemplateString<T>, Option<&'a TemplateString<T>>)>,
+    style: PlaceholderStyle,
+) -> Result<String, PoError>
+where
+    T: TemplateStringParams + 'a,
+    K: Display,
+{
+    let mut output = String::from("msgid \"\"\nmsgstr \"\"\n\"Content-Type: text/plain; charset=UTF-8\\n\"\n");
+    for (key, source, translation) in entries {
+        let key = key.to_string();
+        let convert = |template| {
+            to_po_string(template, style).map_err(|error| PoError::Unsupported {
+                key: key.clone(),
+                error,
+            })
+        };
+        let source = convert(source)?;
+        let translation = translation.map(convert).transpose()?.unwrap_or_default();
+        write!(output, "\n#, {}\n", style.flag()).unwrap();
+        write_po_string(&mut output, "msgctxt", &key);
+        write_po_string(&mut output, "msgid", &source);
+        write_po_string(&mut output, "msgstr", &translation);
+    }
+    Ok(output)
+}
+
+/// Import the translations of a PO file as `(key, template)` pairs.
+///
+/// The key is the `msgctxt` of the entry, or its `msgid` if it has no context. The header entry and untranslated
+/// entries are skipped. Plural entries are not supported.
+pub fn import_po<T: TemplateStringParams>(
+    input: &str,
+    style: PlaceholderStyle,
+) -> Result<Vec<(String, TemplateString<T>)>, PoError> {
+    let mut templates = vec![];
+    for entry in parse_po(input)? {
+        if entry.id.is_empty() || entry.translation.is_empty() {
+            continue;
+        }
+        let key = entry.context.unwrap_or(entry.id);
+        match from_po_string(&entry.translation, style) {
+            Ok(template) => templates.push((key, template)),
+            Err(error) => return Err(PoError::Template { key, error }),
+        }
+    }
+    Ok(templates)
+}
+
+#[derive(Default)]
+struct PoEntry {
+    context: Option<String>,
+    id: String,
+    translation: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PoField {
+    Context,
+    Id,
+    Translation,
+}
+
+fn parse_po(input: &str) -> Result<Vec<PoEntry>, PoError> {
+    let mut entries = vec![];
+    let mut entry = PoEntry::default();
+    let mut field = None;
+
+    for (index, line) in input.lines().enumerate() {
+        let line = line.trim();
+        let syntax = |message: &str| PoError::Syntax {
+            line: index + 1,
+            message: message.to_string(),
+        };
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (keyword, rest) = match line.split_once(char::is_whitespace) {
+            Some((keyword, rest)) if !line.starts_with('"') => (keyword, rest.trim_start()),
+            _ => ("", line),
+        };
+        let next = match keyword {
+            "" => field.ok_or_else(|| syntax("String continuation without a keyword"))?,
+            "msgctxt" => PoField::Context,
+            "msgid" => PoField::Id,
+            "msgstr" => PoField::Translation,
+            _ if keyword.starts_with("msgid_plural") || keyword.starts_with("msgstr[") => {
+                return Err(syntax("Plural entries are not supported"));
+            }
+            _ => return Err(syntax(&format!("Unknown keyword: {keyword}"))),
+        };
+        let starts_entry = keyword == "msgctxt" || (keyword == "msgid" && field != Some(PoField::Context));
+        if starts_entry && field.is_some() {
+            entries.push(std::mem::take(&mut entry));
+        }
+        field = Some(next);
+
+        let value = unquote(rest).ok_or_else(|| syntax("Invalid string literal"))?;
+        match next {
+            PoField::Context => entry.context.get_or_insert_default().push_str(&value),
+            PoField::Id => entry.id.push_str(&value),
+            PoField::Translation => entry.translation.push_str(&value),
+        }
+    }
+    if field.is_some() {
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+fn unquote(literal: &str) -> Option<String> {
+    let inner = literal.strip_prefix('"')?.strip_suffix('"')?;
+    let mut output = String::new();
+    let mut chars = inner.chars();
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' => output.push(match chars.next()? {
+                'n' => '\n',
+                't' => '\t',
+                'r' => '\r',
+                c @ ('\\' | '"') => c,
+                _ => return None,
+            }),
+            '"' => return None,
+            _ => output.push(char),
+        }
+    }
+    Some(output)
+}
+
+fn write_po_string(output: &mut String, keyword: &str, value: &str) {
+    let escape = |line: &str| {
+        line.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\t', "\\t")
+            .replace('\r', "\\r")
+            .replace('\n', "\\n")
+    };
+    let lines: Vec<_> = value.split_inclusive('\n').collect();
+    if lines.len() <= 1 {
+        writeln!(output, "{keyword} \"{}\"", escape(value)).unwrap();
+    } else {
+        writeln!(output, "{keyword} \"\"").unwrap();
+        for line in lines {
+            writeln!(output, "\"{}\"", escape(line)).unwrap();
+        }
+    }
+}
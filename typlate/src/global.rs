@@ -0,0 +1,77 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::BuildHasher;
+use std::sync::RwLock;
+
+/// A provider of process-wide parameters, such as `{global:app_name}` or `{global:support_email}`, that every template
+/// can reference in addition to the fields of its parameter type.
+///
+/// Register a provider with [`set_global_params`]. It is implemented for maps from names to values and for functions
+/// from a name to an optional value.
+pub trait GlobalParams: Send + Sync {
+    /// The value of the global parameter with the given name, or `None` if there is no such parameter.
+    fn global(&self, name: &str) -> Option<Cow<'_, str>>;
+}
+
+impl<S: BuildHasher + Send + Sync> GlobalParams for HashMap<String, String, S> {
+    fn global(&self, name: &str) -> Option<Cow<'_, str>> {
+        self.get(name).map(|value| Cow::Borrowed(value.as_str()))
+    }
+}
+
+impl GlobalParams for BTreeMap<String, String> {
+    fn global(&self, name: &str) -> Option<Cow<'_, str>> {
+        self.get(name).map(|value| Cow::Borrowed(value.as_str()))
+    }
+}
+
+impl<F: Fn(&str) -> Option<String> + Send + Sync> GlobalParams for F {
+    fn global(&self, name: &str) -> Option<Cow<'_, str>> {
+        self(name).map(Cow::Owned)
+    }
+}
+
+static GLOBALS: RwLock<Option<Box<dyn GlobalParams>>> = RwLock::new(None);
+
+/// Register the provider of global parameters for the whole process, replacing any previous provider.
+///
+/// A placeholder such as `{global:app_name}` renders the value of a global parameter, looked up in the provider when
+/// the template is rendered, so templates can be parsed before the provider is registered. A plain `{app_name}`
+/// placeholder refers to the same parameter when the name is declared with
+/// [`ParseOptionsBuilder::global`](crate::ParseOptionsBuilder::global) and is not a field. A name that the provider does
+/// not know renders as an empty string with [`TemplateString::format`](crate::TemplateString::format), and fails
+/// [`TemplateString::try_format`](crate::TemplateString::try_format) with
+/// [`RenderError::Unresolved`](crate::RenderError::Unresolved).
+///
+/// ## Examples
+///
+/// ```
+/// # use std::collections::HashMap;
+/// # use typlate::{ParseOptions, TemplateParams, TemplateString};
+/// #[derive(TemplateParams)]
+/// struct Reset {
+///     name: String,
+/// }
+///
+/// typlate::set_global_params(HashMap::from([("app_name".to_string(), "Acme".to_string())]));
+/// let template: TemplateString<Reset> = "Hi {name}, reset your {global:app_name} password".parse().unwrap();
+/// assert_eq!(template.format(&Reset { name: "Alice".into() }), "Hi Alice, reset your Acme password");
+///
+/// let options = ParseOptions::builder().global("app_name").build();
+/// let template = TemplateString::<Reset>::parse_with("Welcome to {app_name}, {name}", &options).unwrap();
+/// assert_eq!(template.format(&Reset { name: "Bob".into() }), "Welcome to Acme, Bob");
+/// ```
+pub fn set_global_params(provider: impl GlobalParams + 'static) {
+    *GLOBALS.write().unwrap_or_else(|error| error.into_inner()) = Some(Box::new(provider));
+}
+
+/// Remove the provider of global parameters registered with [`set_global_params`].
+pub fn clear_global_params() {
+    *GLOBALS.write().unwrap_or_else(|error| error.into_inner()) = None;
+}
+
+/// Look up a global parameter in the registered provider.
+pub(crate) fn global_param(name: &str) -> Option<String> {
+    let globals = GLOBALS.read().unwrap_or_else(|error| error.into_inner());
+    globals.as_ref()?.global(name).map(Cow::into_owned)
+}
@@ -0,0 +1,75 @@
+use crate::string::{Segment, TemplateElement};
+use crate::{RenderError, TemplateString, TemplateStringParams};
+
+impl<T: TemplateStringParams> TemplateString<T> {
+    /// Format the template as an HTTP header value, failing if an interpolated value contains a character that is not
+    /// allowed there.
+    ///
+    /// Values may only contain visible ASCII characters, spaces and tabs. In particular, rejecting CR and LF prevents
+    /// header injection from user data. Literal template text is not checked, and values are rendered without
+    /// bidirectional isolation.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{RenderError, TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Download {
+    ///     filename: String,
+    /// }
+    ///
+    /// let template: TemplateString<Download> = "attachment; filename=\"{filename}\"".parse().unwrap();
+    /// let download = Download { filename: "report.pdf".into() };
+    /// assert_eq!(template.format_header_value(&download).unwrap(), "attachment; filename=\"report.pdf\"");
+    ///
+    /// let download = Download { filename: "x\r\nSet-Cookie: session=evil".into() };
+    /// assert_eq!(
+    ///     template.format_header_value(&download),
+    ///     Err(RenderError::InvalidChar { name: "filename", char: '\r' }),
+    /// );
+    /// ```
+    pub fn format_header_value(&self, params: &T) -> Result<String, RenderError> {
+        let mut output = String::new();
+        for segment in self.segments(params) {
+            match segment {
+                Segment::Text(text) => output.push_str(text),
+                Segment::Placeholder(element) => {
+                    let value = self.render_placeholder(element, params, false);
+                    if let Some(char) = value.chars().find(|&char| !is_header_char(char)) {
+                        let name = match element {
+                            TemplateElement::Var(index)
+                            | TemplateElement::Filtered { index, .. }
+                            | TemplateElement::Formatted { index, .. } => T::FIELDS[*index],
+                            TemplateElement::Namespaced { .. } => "namespace",
+                            _ => unreachable!("expressions render as numbers"),
+                        };
+                        return Err(RenderError::InvalidChar { name, char });
+                    }
+                    output.push_str(&value);
+                }
+            }
+        }
+        Ok(output)
+    }
+
+    /// Format the template as an HTTP header value, removing every character of an interpolated value that is not
+    /// allowed there. See [`TemplateString::format_header_value`] for the allowed characters.
+    pub fn format_header_value_lossy(&self, params: &T) -> String {
+        let mut output = String::new();
+        for segment in self.segments(params) {
+            match segment {
+                Segment::Text(text) => output.push_str(text),
+                Segment::Placeholder(element) => {
+                    let value = self.render_placeholder(element, params, false);
+                    output.extend(value.chars().filter(|&char| is_header_char(char)));
+                }
+            }
+        }
+        output
+    }
+}
+
+/// Whether the character may appear in an HTTP header value: visible ASCII, space or tab.
+fn is_header_char(char: char) -> bool {
+    matches!(char, ' ' | '\t' | '!'..='~')
+}
@@ -0,0 +1,341 @@
+use crate::TemplateStringParams;
+
+/// Embed a template file as a [`TemplateString`](crate::TemplateString), checking at compile time that its
+/// placeholders name fields of the parameter type.
+///
+/// The path is resolved like [`include_str!`]. Unknown or empty field names fail the build, as do namespaced
+/// placeholders outside of the built-in `global` and `env` namespaces. Other syntax errors, such as an unknown select
+/// kind, are reported when the template is parsed (see
+/// [`TemplateString::parse_trusted`](crate::TemplateString::parse_trusted)).
+///
+/// ## Examples
+///
+/// ```ignore
+/// # use typlate::{TemplateParams, TemplateString, include_template};
+/// #[derive(TemplateParams)]
+/// struct Person {
+///     name: String,
+/// }
+///
+/// let template: TemplateString<Person> = include_template!("templates/welcome.txt", Person);
+/// ```
+#[macro_export]
+macro_rules! include_template {
+    ($path:literal, $ty:ty $(,)?) => {{
+        const SOURCE: &str = include_str!($path);
+        const _: () = $crate::__private::validate_source::<$ty>(SOURCE);
+        match $crate::TemplateString::<$ty>::parse_trusted(SOURCE) {
+            Ok(template) => template,
+            Err(error) => panic!("Invalid template {}: {error}", $path),
+        }
+    }};
+}
+
+/// Check at compile time that the placeholders of a template source name fields of the parameter type, e.g. to catch
+/// message templates that drift from the struct without embedding them as templates.
+///
+/// The source must be a constant expression. The macro expands to an item, so it can be used at module level as well
+/// as in functions and tests. As with [`include_template!`], unknown or empty field names fail the build, and other
+/// syntax errors are only reported when the template is parsed. Placeholders in the `global` and `env` namespaces,
+/// such as `{env:HOME}`, are accepted without checking the name, which is only known at render time. Any other prefix
+/// before a `:` must be a field with a format spec, so that a misspelled field such as `{nmae:>10}` is not mistaken
+/// for a namespace.
+///
+/// ## Examples
+///
+/// ```
+/// # use typlate::{TemplateParams, assert_template_fields};
+/// #[derive(TemplateParams)]
+/// struct Person {
+///     name: String,
+///     title: String,
+/// }
+///
+/// const GREETING: &str = "Hello {title} {name}";
+/// assert_template_fields!(Person, GREETING);
+/// assert_template_fields!(Person, "{name:>10}|{title|upper}");
+/// ```
+///
+/// ```compile_fail
+/// # use typlate::{TemplateParams, assert_template_fields};
+/// #[derive(TemplateParams)]
+/// struct Person {
+///     name: String,
+/// }
+///
+/// assert_template_fields!(Person, "Hello {name} {title}");
+/// ```
+///
+/// ```compile_fail
+/// # use typlate::{TemplateParams, assert_template_fields};
+/// #[derive(TemplateParams)]
+/// struct Person {
+///     name: String,
+/// }
+///
+/// assert_template_fields!(Person, "{nmae:>10}");
+/// ```
+#[macro_export]
+macro_rules! assert_template_fields {
+    ($ty:ty, $source:expr $(,)?) => {
+        const _: () = $crate::__private::validate_source::<$ty>($source);
+    };
+}
+
+/// Check the field names of the placeholders in a template source, panicking (and thus failing the build when called
+/// in a constant) on the first invalid one.
+///
+/// This is not a full parser: it only understands the subset of the syntax needed to find the names, i.e. escaped
+/// brackets, skipped `{% ... %}` tags, conditional sections, inline conditionals, select branches, filters after a
+/// `|`, format specs and namespaces after a `:`, and arithmetic expressions. Every other error is left to the parser.
+/// `tests/include.rs` checks that both agree on which names are valid.
+pub const fn validate_source<T: TemplateStringParams>(source: &str) {
+    let bytes = source.as_bytes();
+    validate_sequence::<T>(bytes, 0, false);
+}
+
+/// Validate a sequence starting at `i` and return the offset after it. In a select branch, the sequence ends after the
+/// closing `}`.
+const fn validate_sequence<T: TemplateStringParams>(bytes: &[u8], mut i: usize, branch: bool) -> usize {
+    while i < bytes.len() {
+        let next = if i + 1 < bytes.len() { bytes[i + 1] } else { 0 };
+        match bytes[i] {
+            b'{' if next == b'{' => i += 2,
+            b'{' if next == b'%' => {
+                i += 2;
+                while i + 1 < bytes.len() && !(bytes[i] == b'%' && bytes[i + 1] == b'}') {
+                    i += 1;
+                }
+                assert!(i + 1 < bytes.len(), "Unclosed block tag in template");
+                i += 2;
+            }
+            b'{' if next == b'?' => i = validate_condition::<T>(bytes, i + 2),
+            b'{' if (next == b':' || next == b'/') && i + 2 < bytes.len() && bytes[i + 2] == b'}' => i += 3,
+            b'{' => i = validate_placeholder::<T>(bytes, i + 1),
+            b'}' if branch => return i + 1,
+            b'}' if next == b'}' => i += 2,
+            b'}' => panic!("Unmatched closing bracket in template"),
+            _ => i += 1,
+        }
+    }
+    assert!(!branch, "Unclosed bracket in template");
+    i
+}
+
+/// Validate a placeholder whose name starts at `start` and return the offset after its closing `}`.
+const fn validate_placeholder<T: TemplateStringParams>(bytes: &[u8], start: usize) -> usize {
+    if let Some(question) = find_ternary(bytes, start) {
+        return validate_ternary::<T>(bytes, start, question);
+    }
+    let mut i = find(bytes, start, b"},|");
+    let (name_start, name_end) = trim(bytes, start, i);
+    assert!(name_start < name_end, "Empty placeholder in template");
+    // Names of plain placeholders are not trimmed, and may refer to inline fields.
+    let known = match bytes[i] {
+        b'}' => {
+            is_field::<T>(bytes, start, i)
+                || is_inline_field::<T>(bytes, start, i)
+                || is_expression::<T>(bytes, start, i)
+                || is_qualified::<T>(bytes, start, i)
+        }
+        _ => is_field::<T>(bytes, name_start, name_end),
+    };
+    assert!(known, "Unknown field name in template");
+    match bytes[i] {
+        b'}' => i + 1,
+        b'|' => find(bytes, i, b"}") + 1,
+        _ => {
+            i = find(bytes, i + 1, b",") + 1;
+            loop {
+                while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                    i += 1;
+                }
+                assert!(i < bytes.len(), "Unclosed bracket in template");
+                if bytes[i] == b'}' {
+                    return i + 1;
+                }
+                i = find(bytes, i, b"{}");
+                assert!(bytes[i] == b'{', "Invalid select block in template");
+                i = validate_sequence::<T>(bytes, i + 1, true);
+            }
+        }
+    }
+}
+
+/// Validate the condition of a conditional section starting at `start` and return the offset after its closing `}`.
+const fn validate_condition<T: TemplateStringParams>(bytes: &[u8], start: usize) -> usize {
+    let end = find(bytes, start, b"}");
+    validate_operands::<T>(bytes, start, end);
+    end + 1
+}
+
+/// Validate an inline conditional whose condition starts at `start` and ends at the `?` at `question`, and return the
+/// offset after its closing `}`.
+const fn validate_ternary<T: TemplateStringParams>(bytes: &[u8], start: usize, question: usize) -> usize {
+    validate_operands::<T>(bytes, start, question);
+    let mut quoted = false;
+    let mut i = question + 1;
+    while i < bytes.len() {
+        if bytes[i] == b'}' && !quoted {
+            return i + 1;
+        }
+        quoted ^= bytes[i] == b'"';
+        i += 1;
+    }
+    panic!("Unclosed bracket in template")
+}
+
+/// The offset of the `?` of an inline conditional whose condition starts at `start`, or `None` if the placeholder is
+/// not an inline conditional.
+const fn find_ternary(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut quoted = false;
+    let mut i = start;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => quoted = !quoted,
+            _ if quoted => {}
+            b'?' => return Some(i),
+            b'{' | b'}' | b',' | b'|' => return None,
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Check that the operands of a condition in `bytes[start..end]` outside of string literals are fields or numbers. The
+/// rest of the syntax is checked when the template is parsed.
+const fn validate_operands<T: TemplateStringParams>(bytes: &[u8], start: usize, end: usize) {
+    let mut quoted = false;
+    let mut i = start;
+    while i < end {
+        if !quoted && is_operand(bytes[i]) {
+            let operand = i;
+            while i < end && is_operand(bytes[i]) {
+                i += 1;
+            }
+            let number = bytes[operand].is_ascii_digit() || bytes[operand] == b'.';
+            assert!(
+                number || is_field::<T>(bytes, operand, i),
+                "Unknown field name in template"
+            );
+            continue;
+        }
+        quoted ^= bytes[i] == b'"';
+        i += 1;
+    }
+}
+
+/// The offset of the first of `chars` at or after `i`.
+const fn find(bytes: &[u8], mut i: usize, chars: &[u8]) -> usize {
+    while i < bytes.len() {
+        let mut j = 0;
+        while j < chars.len() {
+            if bytes[i] == chars[j] {
+                return i;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    panic!("Unclosed bracket in template")
+}
+
+/// The range `start..end` without leading and trailing ASCII whitespace.
+const fn trim(bytes: &[u8], mut start: usize, mut end: usize) -> (usize, usize) {
+    while start < end && bytes[start].is_ascii_whitespace() {
+        start += 1;
+    }
+    while start < end && bytes[end - 1].is_ascii_whitespace() {
+        end -= 1;
+    }
+    (start, end)
+}
+
+/// Whether `bytes[start..end]` is the name of a field of `T`.
+const fn is_field<T: TemplateStringParams>(bytes: &[u8], start: usize, end: usize) -> bool {
+    let mut k = 0;
+    while k < T::FIELDS.len() {
+        if equals(bytes, start, end, T::FIELDS[k]) {
+            return true;
+        }
+        k += 1;
+    }
+    false
+}
+
+/// Whether `bytes[start..end]` is the name of an inline field of `T`.
+const fn is_inline_field<T: TemplateStringParams>(bytes: &[u8], start: usize, end: usize) -> bool {
+    let mut k = 0;
+    while k < T::INLINE_FIELDS.len() {
+        if equals(bytes, start, end, T::INLINE_FIELDS[k].0) {
+            return true;
+        }
+        k += 1;
+    }
+    false
+}
+
+/// Whether the placeholder is an arithmetic expression whose operands are all fields or numbers. The syntax of the
+/// expression is checked when the template is parsed, which reads a placeholder with a `:` as namespaced instead.
+const fn is_expression<T: TemplateStringParams>(bytes: &[u8], start: usize, end: usize) -> bool {
+    let mut has_operator = false;
+    let mut i = start;
+    while i < end {
+        if is_operand(bytes[i]) {
+            let operand = i;
+            while i < end && is_operand(bytes[i]) {
+                i += 1;
+            }
+            let number = bytes[operand].is_ascii_digit() || bytes[operand] == b'.';
+            if !number && !is_field::<T>(bytes, operand, i) {
+                return false;
+            }
+            continue;
+        }
+        if bytes[i] == b':' {
+            return false;
+        }
+        has_operator |= matches!(bytes[i], b'+' | b'-' | b'*' | b'/');
+        i += 1;
+    }
+    has_operator
+}
+
+/// Whether the placeholder is a field with a format spec, as in `price:>8.2`, or a name in one of the built-in
+/// namespaces, as in `global:app_name` or `env:HOME`. The spec and the name are checked when the template is parsed.
+const fn is_qualified<T: TemplateStringParams>(bytes: &[u8], start: usize, end: usize) -> bool {
+    let mut colon = start;
+    while colon < end && bytes[colon] != b':' {
+        colon += 1;
+    }
+    if colon == start || colon == end {
+        return false;
+    }
+    if is_field::<T>(bytes, start, colon) {
+        return true;
+    }
+    let (namespace_start, namespace_end) = trim(bytes, start, colon);
+    equals(bytes, namespace_start, namespace_end, "global") || equals(bytes, namespace_start, namespace_end, "env")
+}
+
+/// Whether the byte can be part of a field name or number in an expression.
+const fn is_operand(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'.' || byte >= 0x80
+}
+
+/// Whether `bytes[start..end]` equals `name`.
+const fn equals(bytes: &[u8], start: usize, end: usize, name: &str) -> bool {
+    let name = name.as_bytes();
+    if name.len() != end - start {
+        return false;
+    }
+    let mut j = 0;
+    while j < name.len() {
+        if name[j] != bytes[start + j] {
+            return false;
+        }
+        j += 1;
+    }
+    true
+}
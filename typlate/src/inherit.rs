@@ -0,0 +1,107 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::TemplateString;
+use crate::string::TemplateElement;
+
+/// An error that occurred while applying a child template to a base template with [`TemplateString::extends`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ExtendError {
+    /// The child template overrides a block that the base template does not define.
+    UnknownBlock { name: String },
+    /// The child template has content other than whitespace outside of its blocks.
+    ContentOutsideBlock,
+}
+
+impl fmt::Display for ExtendError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnknownBlock { name } => write!(f, "Unknown block: {name}"),
+            Self::ContentOutsideBlock => f.write_str("Content outside of blocks in child template"),
+        }
+    }
+}
+
+impl std::error::Error for ExtendError {}
+
+impl<T> TemplateString<T> {
+    /// Apply this template as a child of `base`: every block of this template replaces the content of the block with
+    /// the same name in `base`, and blocks that are not overridden keep their content from `base`.
+    ///
+    /// The child template may only contain blocks (and whitespace between them) at the top level. Blocks nested in the
+    /// overriding content remain overridable, so the result can itself be extended by another child. The result keeps
+    /// the bidirectional isolation setting of `base`.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Email {
+    ///     name: &'static str,
+    ///     code: u32,
+    /// }
+    ///
+    /// let layout: TemplateString<Email> =
+    ///     "Hi {name},\n{%block body%}{%endblock%}\n{%block footer%}-- The Team{%endblock%}".parse().unwrap();
+    /// let reset: TemplateString<Email> = "{%block body%}Your reset code is {code}.{%endblock%}".parse().unwrap();
+    ///
+    /// let template = reset.extends(&layout).unwrap();
+    /// let email = Email { name: "Alice", code: 1234 };
+    /// assert_eq!(template.format(&email), "Hi Alice,\nYour reset code is 1234.\n-- The Team");
+    /// ```
+    pub fn extends(&self, base: &TemplateString<T>) -> Result<TemplateString<T>, ExtendError> {
+        let mut blocks = BTreeMap::new();
+        for element in self.elements() {
+            match element {
+                TemplateElement::Text(text) if text.trim().is_empty() => {}
+                TemplateElement::Block { name, elements } => {
+                    blocks.insert(name.as_str(), elements.as_slice());
+                }
+                _ => return Err(ExtendError::ContentOutsideBlock),
+            }
+        }
+        let elements = override_blocks(base.elements(), &mut blocks);
+        if let Some(name) = blocks.into_keys().next() {
+            return Err(ExtendError::UnknownBlock { name: name.to_string() });
+        }
+        Ok(TemplateString::from_elements(elements).with_bidi_isolation(base.bidi_isolation()))
+    }
+}
+
+/// Copy the elements, replacing the content of blocks found in `blocks`, which are removed from the map once used.
+fn override_blocks(
+    elements: &[TemplateElement],
+    blocks: &mut BTreeMap<&str, &[TemplateElement]>,
+) -> Vec<TemplateElement> {
+    elements
+        .iter()
+        .map(|element| match element {
+            TemplateElement::Block { name, elements } => TemplateElement::Block {
+                name: name.clone(),
+                elements: match blocks.remove(name.as_str()) {
+                    Some(elements) => elements.to_vec(),
+                    None => override_blocks(elements, blocks),
+                },
+            },
+            TemplateElement::Select { index, facet, arms } => TemplateElement::Select {
+                index: *index,
+                facet: facet.clone(),
+                arms: arms
+                    .iter()
+                    .map(|(key, branch)| (key.clone(), override_blocks(branch, blocks)))
+                    .collect(),
+            },
+            TemplateElement::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => TemplateElement::Conditional {
+                condition: condition.clone(),
+                then: override_blocks(then, blocks),
+                otherwise: override_blocks(otherwise, blocks),
+            },
+            element => element.clone(),
+        })
+        .collect()
+}
@@ -0,0 +1,152 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::time::SystemTime;
+
+use crate::{Number, TemplateStringParams};
+
+/// The maximum number of fields, and of inline fields, of a pair of parameter types.
+const MAX_LAYERED_FIELDS: usize = 256;
+
+/// Concatenate two slices at compile time, into an array padded with `fill` that is sliced to the total length.
+const fn concat<T: Copy>(a: &[T], b: &[T], fill: T) -> [T; MAX_LAYERED_FIELDS] {
+    assert!(
+        a.len() + b.len() <= MAX_LAYERED_FIELDS,
+        "a pair of parameter types can have at most 256 fields"
+    );
+    let mut output = [fill; MAX_LAYERED_FIELDS];
+    let mut i = 0;
+    while i < a.len() {
+        output[i] = a[i];
+        i += 1;
+    }
+    while i < a.len() + b.len() {
+        output[i] = b[i - a.len()];
+        i += 1;
+    }
+    output
+}
+
+/// Combine two parameter types, e.g. shared parameters such as the app name with the parameters of a message.
+///
+/// The fields of `A` come first, followed by the fields of `B`, so a name declared by both resolves to the field of
+/// `A`. Combined with the implementations for references, a `TemplateString<(&A, &B)>` formats a pair of borrowed
+/// values.
+impl<A: TemplateStringParams, B: TemplateStringParams> TemplateStringParams for (A, B) {
+    const FIELDS: &'static [&'static str] = concat(A::FIELDS, B::FIELDS, "")
+        .split_at(A::FIELDS.len() + B::FIELDS.len())
+        .0;
+    const INLINE_FIELDS: &'static [(&'static str, &'static str)] = concat(A::INLINE_FIELDS, B::INLINE_FIELDS, ("", ""))
+        .split_at(A::INLINE_FIELDS.len() + B::INLINE_FIELDS.len())
+        .0;
+
+    fn fmt_field(&self, f: &mut fmt::Formatter, index: usize) -> fmt::Result {
+        match index.checked_sub(A::FIELDS.len()) {
+            None => self.0.fmt_field(f, index),
+            Some(index) => self.1.fmt_field(f, index),
+        }
+    }
+
+    fn has_field(&self, index: usize) -> bool {
+        match index.checked_sub(A::FIELDS.len()) {
+            None => self.0.has_field(index),
+            Some(index) => self.1.has_field(index),
+        }
+    }
+
+    fn field_facet(&self, index: usize, facet: &str) -> Option<Cow<'_, str>> {
+        match index.checked_sub(A::FIELDS.len()) {
+            None => self.0.field_facet(index, facet),
+            Some(index) => self.1.field_facet(index, facet),
+        }
+    }
+
+    fn field_number(&self, index: usize) -> Option<Number> {
+        match index.checked_sub(A::FIELDS.len()) {
+            None => self.0.field_number(index),
+            Some(index) => self.1.field_number(index),
+        }
+    }
+
+    fn len_hint(&self, index: usize) -> usize {
+        match index.checked_sub(A::FIELDS.len()) {
+            None => self.0.len_hint(index),
+            Some(index) => self.1.len_hint(index),
+        }
+    }
+
+    fn field_position(index: usize) -> usize {
+        match index.checked_sub(A::FIELDS.len()) {
+            None => A::field_position(index),
+            Some(index) => A::FIELDS.len() + B::field_position(index),
+        }
+    }
+
+    fn is_integer_field(index: usize) -> bool {
+        match index.checked_sub(A::FIELDS.len()) {
+            None => A::is_integer_field(index),
+            Some(index) => B::is_integer_field(index),
+        }
+    }
+
+    fn field_time(&self, index: usize) -> Option<SystemTime> {
+        match index.checked_sub(A::FIELDS.len()) {
+            None => self.0.field_time(index),
+            Some(index) => self.1.field_time(index),
+        }
+    }
+
+    fn field_bytes(&self, index: usize) -> Option<Cow<'_, [u8]>> {
+        match index.checked_sub(A::FIELDS.len()) {
+            None => self.0.field_bytes(index),
+            Some(index) => self.1.field_bytes(index),
+        }
+    }
+
+    fn default_filters(index: usize) -> &'static [&'static str] {
+        match index.checked_sub(A::FIELDS.len()) {
+            None => A::default_filters(index),
+            Some(index) => B::default_filters(index),
+        }
+    }
+
+    /// The filters of `A`, or those of `B` if `A` has none.
+    fn default_value_filters() -> &'static [&'static str] {
+        match A::default_value_filters() {
+            [] => B::default_value_filters(),
+            filters => filters,
+        }
+    }
+
+    fn is_debug_field(index: usize) -> bool {
+        match index.checked_sub(A::FIELDS.len()) {
+            None => A::is_debug_field(index),
+            Some(index) => B::is_debug_field(index),
+        }
+    }
+
+    fn fmt_field_debug(&self, f: &mut fmt::Formatter, index: usize) -> fmt::Result {
+        match index.checked_sub(A::FIELDS.len()) {
+            None => self.0.fmt_field_debug(f, index),
+            Some(index) => self.1.fmt_field_debug(f, index),
+        }
+    }
+
+    fn field_items(&self, index: usize) -> Option<Vec<String>> {
+        match index.checked_sub(A::FIELDS.len()) {
+            None => self.0.field_items(index),
+            Some(index) => self.1.field_items(index),
+        }
+    }
+}
+
+/// Find the part of a type declared with [`compose_params!`](crate::compose_params) that declares the field at the
+/// given index, from the number of fields of each part, and return the part and the index of the field within it.
+pub fn locate_field(lens: &[usize], mut index: usize) -> (usize, usize) {
+    for (part, &len) in lens.iter().enumerate() {
+        if index < len {
+            return (part, index);
+        }
+        index -= len;
+    }
+    panic!("Index out of bounds")
+}
@@ -3,12 +3,117 @@
 use std::fmt::Display;
 use std::str::FromStr;
 
+#[cfg(feature = "ansi")]
+pub mod ansi;
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+mod argv;
+mod bytes;
+mod catalog;
+mod complete;
+mod cond;
+mod ctx;
+mod debug;
+mod depth;
+#[cfg(feature = "lettre")]
+pub mod email;
+#[cfg(feature = "serde")]
+pub mod empty_as_none;
+#[cfg(feature = "encoding")]
+pub mod encoding;
+mod error;
+mod error_catalog;
+pub mod escape;
+mod expr;
+mod fallback;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod fields;
+mod filter;
+mod format_spec;
+#[cfg(feature = "gettext")]
+pub mod gettext;
+mod global;
+mod header;
+mod include;
+mod inherit;
+mod layered;
+#[cfg(feature = "serde")]
+pub mod load;
+pub mod loader;
+#[cfg(feature = "mobile")]
+pub mod mobile;
+mod mustache;
+mod number;
+mod observer;
+mod options;
+mod parse;
+mod partial;
+mod preview;
+mod printf;
+mod reload;
+mod resolve;
+mod rewrite;
+mod schema;
+mod set;
+mod sql;
+mod std_format;
 mod string;
+mod templated_error;
+pub mod testing;
+mod time;
+mod tokenize;
+#[cfg(feature = "wasm-bindgen")]
+pub mod wasm;
+#[cfg(feature = "web")]
+pub mod web;
+#[cfg(feature = "serde")]
+mod with_options;
+#[cfg(feature = "xliff")]
+pub mod xliff;
 
 #[cfg(feature = "derive")]
-pub use typlate_derive::TemplateParams;
+pub use typlate_derive::{TemplateError, TemplateParams, compose_params};
 
-pub use crate::string::{TemplateString, TemplateStringParams};
+pub use crate::bytes::DecodeError;
+pub use crate::catalog::{
+    CatalogError, PlaceholderMismatch, check_translations, validate_catalog, validate_catalog_against,
+};
+pub use crate::complete::{Completion, complete};
+pub use crate::ctx::RenderCtx;
+pub use crate::depth::{DEFAULT_MAX_RENDER_DEPTH, max_render_depth, set_max_render_depth};
+pub use crate::error::{RenderError, TemplateParseError};
+pub use crate::error_catalog::{ErrorCatalog, TemplateError, clear_error_catalog, set_error_catalog};
+pub use crate::fallback::FallbackTemplate;
+pub use crate::fields::FieldSet;
+pub use crate::filter::FilterRegistry;
+pub use crate::global::{GlobalParams, clear_global_params, set_global_params};
+pub use crate::inherit::ExtendError;
+pub use crate::number::Number;
+pub use crate::observer::{RenderEvent, RenderObserver};
+pub use crate::options::{DEFAULT_MAX_PARSE_DEPTH, EmptyPlaceholders, ParseOptions, ParseOptionsBuilder};
+pub use crate::reload::ReloadableTemplate;
+pub use crate::resolve::AsyncTemplateParams;
+pub use crate::schema::SchemaVersion;
+pub use crate::set::{TemplateSet, TemplateSetError};
+pub use crate::sql::BindStyle;
+pub use crate::std_format::FormatString;
+pub use crate::string::{ElementOrigin, Facets, TemplateDisplay, TemplateString, TemplateStringParams};
+pub use crate::templated_error::TemplatedError;
+pub use crate::tokenize::{TokenKind, tokenize};
+#[cfg(feature = "serde")]
+pub use crate::with_options::{Lenient, LenientOptions, OptionsProvider, ParseWithOptions, WithOptions};
+
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::debug::{DisplayOrDebug, ViaDebug, ViaDisplay};
+    pub use crate::depth::fmt_nested;
+    pub use crate::error_catalog::{fmt_error_message, validate_error_message};
+    pub use crate::filter::is_builtin as is_builtin_filter;
+    pub use crate::include::validate_source;
+    pub use crate::layered::locate_field;
+    pub use crate::time::fmt_system_time;
+}
 
 pub trait TemplateParams {
     type Template;
@@ -36,7 +141,7 @@ impl<T: TemplateParams> Template<T> {
     /// assert_eq!(template.format(&data), "Point: (10, 20)");
     /// ```
     pub fn format(&self, params: &T) -> String {
-        params.format_template(&self.0).to_string()
+        crate::depth::render(params.format_template(&self.0))
     }
 }
 
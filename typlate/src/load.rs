@@ -0,0 +1,137 @@
+//! Helpers for loading whole message files into user-defined structs.
+//!
+//! Each helper deserializes a document into `T` and, if a template inside fails to parse, reports the key path of
+//! the failing value along with the error message. Formats without a dedicated helper can go through
+//! [`from_deserializer`].
+//!
+//! ## Examples
+//!
+//! ```
+//! # use serde::Deserialize;
+//! # use typlate::{Template, TemplateParams};
+//! #[derive(TemplateParams)]
+//! struct User {
+//!     name: String,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Messages {
+//!     greeting: Template<User>,
+//! }
+//!
+//! let error = typlate::load::from_json_str::<Messages>(r#"{"greeting": "Hello {nmae}"}"#).err().unwrap();
+//! assert_eq!(error.path(), "greeting");
+//! ```
+
+use std::fmt::{self, Display};
+
+#[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+use serde::de::DeserializeOwned;
+use serde::de::{Deserialize, Deserializer};
+
+/// An error that occurred while loading a message file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadError {
+    path: String,
+    message: String,
+}
+
+impl LoadError {
+    /// The key path of the value that failed to load, e.g. `errors.not_found`.
+    ///
+    /// Returns `.` if the error is not associated with a specific key.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The underlying error message, without the key path.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    fn new(path: impl Display, message: impl Display) -> Self {
+        Self {
+            path: path.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl<E: Display> From<serde_path_to_error::Error<E>> for LoadError {
+    fn from(error: serde_path_to_error::Error<E>) -> Self {
+        Self::new(error.path(), error.inner())
+    }
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.path == "." {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+/// Deserialize a value from any serde [`Deserializer`], tracking the key path of the value that fails.
+///
+/// ## Examples
+///
+/// ```
+/// # use std::collections::BTreeMap;
+/// # use typlate::{Template, TemplateParams};
+/// #[derive(TemplateParams)]
+/// struct User {
+///     name: String,
+/// }
+///
+/// let mut deserializer = serde_json::Deserializer::from_str(r#"{"welcome": "Hi {nmae}"}"#);
+/// let result = typlate::load::from_deserializer::<_, BTreeMap<String, Template<User>>>(&mut deserializer);
+/// assert_eq!(result.err().unwrap().to_string(), "welcome: Unknown field name: nmae at line 1 column 23");
+/// ```
+pub fn from_deserializer<'de, D: Deserializer<'de>, T: Deserialize<'de>>(deserializer: D) -> Result<T, LoadError> {
+    Ok(serde_path_to_error::deserialize(deserializer)?)
+}
+
+/// Deserialize a JSON document.
+#[cfg(feature = "json")]
+pub fn from_json_str<T: DeserializeOwned>(input: &str) -> Result<T, LoadError> {
+    let mut deserializer = serde_json::Deserializer::from_str(input);
+    let value = from_deserializer(&mut deserializer)?;
+    deserializer.end().map_err(|error| LoadError::new(".", error))?;
+    Ok(value)
+}
+
+/// Deserialize a TOML document.
+#[cfg(feature = "toml")]
+pub fn from_toml_str<T: DeserializeOwned>(input: &str) -> Result<T, LoadError> {
+    let deserializer = toml::Deserializer::parse(input).map_err(|error| LoadError::new(".", error))?;
+    from_deserializer(deserializer)
+}
+
+/// Deserialize a single YAML document.
+#[cfg(feature = "yaml")]
+pub fn from_yaml_str<T: DeserializeOwned>(input: &str) -> Result<T, LoadError> {
+    from_deserializer(serde_norway::Deserializer::from_str(input))
+}
+
+/// Deserialize every document of a multi-document YAML stream (documents separated by `---`).
+///
+/// Errors in later documents are prefixed with the document index, e.g. `[1].greeting`.
+#[cfg(feature = "yaml")]
+pub fn from_yaml_documents<T: DeserializeOwned>(input: &str) -> Result<Vec<T>, LoadError> {
+    serde_norway::Deserializer::from_str(input)
+        .enumerate()
+        .map(|(index, document)| {
+            serde_path_to_error::deserialize(document).map_err(|error| {
+                let path = match error.path().to_string().as_str() {
+                    "." => format!("[{index}]"),
+                    path => format!("[{index}].{path}"),
+                };
+                LoadError::new(path, error.inner())
+            })
+        })
+        .collect()
+}
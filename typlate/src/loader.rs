@@ -0,0 +1,315 @@
+//! Load every template under a directory into a [`TemplateSet`].
+//!
+//! Each template file holds a single template, whose name is its path relative to the directory, without the
+//! extension and with `.` as separator: `emails/welcome.txt` becomes `emails.welcome`. A single trailing newline is
+//! dropped from the file content. Structured files hold several templates, named by their key after the name of the
+//! file:
+//!
+//! - `.templates` files in the [`TemplateSet`] syntax (`[name] body`),
+//! - with the `json`, `toml` or `yaml` features, `.json`, `.toml`, `.yaml` and `.yml` files mapping keys to template
+//!   strings, where nested maps are flattened with `.`.
+//!
+//! With the `notify` feature enabled, [`Loader::watch`] reloads the templates whenever a file changes, e.g. to
+//! hot-reload templates in a development server.
+//!
+//! ## Examples
+//!
+//! ```no_run
+//! # use typlate::{TemplateParams, TemplateSet};
+//! # use typlate::loader::Loader;
+//! #[derive(TemplateParams)]
+//! struct User {
+//!     name: String,
+//! }
+//!
+//! let set: TemplateSet<User> = Loader::new("templates").glob("emails/**").load().unwrap();
+//! let user = User { name: "Alice".into() };
+//! println!("{}", set.format("emails.welcome", &user).unwrap());
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::{fmt, io};
+
+use crate::{TemplateParseError, TemplateSet, TemplateSetError, TemplateString, TemplateStringParams};
+
+/// An error that occurred while loading a directory of templates.
+#[derive(Debug)]
+pub enum LoaderError {
+    /// A file or directory could not be read.
+    Io { path: PathBuf, error: io::Error },
+    /// The template with the given name failed to parse.
+    Parse {
+        path: PathBuf,
+        name: String,
+        error: TemplateParseError,
+    },
+    /// A structured file is malformed.
+    Format { path: PathBuf, message: String },
+    /// Two files define a template with the same name.
+    DuplicateName { path: PathBuf, name: String },
+    /// The directory could not be watched for changes.
+    #[cfg(feature = "notify")]
+    Watch(notify::Error),
+}
+
+impl fmt::Display for LoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io { path, error } => write!(f, "{}: {error}", path.display()),
+            Self::Parse { path, name, error } => write!(f, "{}: {name}: {error}", path.display()),
+            Self::Format { path, message } => write!(f, "{}: {message}", path.display()),
+            Self::DuplicateName { path, name } => write!(f, "{}: Duplicate template name: {name}", path.display()),
+            #[cfg(feature = "notify")]
+            Self::Watch(error) => write!(f, "Failed to watch templates: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for LoaderError {}
+
+/// Loads the templates under a directory. See the [module documentation](self) for the file layout.
+#[derive(Debug, Clone)]
+pub struct Loader {
+    root: PathBuf,
+    extensions: Vec<String>,
+    glob: Option<String>,
+}
+
+impl Loader {
+    /// Create a loader for the given directory, where files with the `txt` extension are template files.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            extensions: vec!["txt".into()],
+            glob: None,
+        }
+    }
+
+    /// Set the extensions of template files, replacing the default `txt`.
+    pub fn extensions<S: Into<String>>(mut self, extensions: impl IntoIterator<Item = S>) -> Self {
+        self.extensions = extensions.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Only load files whose path relative to the directory (with `/` as separator) matches the glob pattern.
+    ///
+    /// `*` matches any characters except `/`, `**` matches any characters including `/`, and `?` matches a single
+    /// character other than `/`.
+    pub fn glob(mut self, pattern: impl Into<String>) -> Self {
+        self.glob = Some(pattern.into());
+        self
+    }
+
+    /// Load and validate every template under the directory.
+    pub fn load<T: TemplateStringParams>(&self) -> Result<TemplateSet<T>, LoaderError> {
+        let mut files = vec![];
+        collect_files(&self.root, &mut files)?;
+        files.sort();
+
+        let mut templates = BTreeMap::new();
+        for path in files {
+            let relative = path.strip_prefix(&self.root).unwrap_or(&path);
+            let relative = relative
+                .iter()
+                .map(|component| component.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            if self.glob.as_ref().is_some_and(|glob| !glob_match(glob, &relative)) {
+                continue;
+            }
+            let Some((stem, extension)) = relative.rsplit_once('.').filter(|(stem, _)| !stem.ends_with('/')) else {
+                continue;
+            };
+            let prefix = stem.replace('/', ".");
+            let parse = |name: String, source: &str| match source.parse::<TemplateString<T>>() {
+                Ok(template) => Ok((name, template)),
+                Err(error) => Err(LoaderError::Parse {
+                    path: path.clone(),
+                    name,
+                    error,
+                }),
+            };
+            let entries = match extension {
+                _ if self.extensions.iter().any(|e| e == extension) => {
+                    let source = read(&path)?;
+                    let source = source.strip_suffix('\n').unwrap_or(&source);
+                    vec![parse(prefix, source.strip_suffix('\r').unwrap_or(source))?]
+                }
+                "templates" => read_set::<T>(&path, &prefix)?,
+                _ => match read_structured(&path, extension)? {
+                    Some(sources) => sources
+                        .into_iter()
+                        .map(|(key, source)| parse(format!("{prefix}.{key}"), &source))
+                        .collect::<Result<_, _>>()?,
+                    None => continue,
+                },
+            };
+            for (name, template) in entries {
+                if templates.contains_key(&name) {
+                    return Err(LoaderError::DuplicateName { path, name });
+                }
+                templates.insert(name, template);
+            }
+        }
+        Ok(TemplateSet::from_templates(templates))
+    }
+
+    /// Load the templates, then watch the directory and load them again whenever a file changes, passing every result
+    /// to `on_reload`.
+    ///
+    /// The directory is watched until the returned [`Watcher`] is dropped.
+    #[cfg(feature = "notify")]
+    pub fn watch<T, F>(self, mut on_reload: F) -> Result<Watcher, LoaderError>
+    where
+        T: TemplateStringParams,
+        F: FnMut(Result<TemplateSet<T>, LoaderError>) + Send + 'static,
+    {
+        use notify::Watcher as _;
+
+        on_reload(self.load());
+        let root = self.root.clone();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+            Ok(event) if event.kind.is_access() => {}
+            Ok(_) => on_reload(self.load()),
+            Err(error) => on_reload(Err(LoaderError::Watch(error))),
+        })
+        .map_err(LoaderError::Watch)?;
+        watcher
+            .watch(&root, notify::RecursiveMode::Recursive)
+            .map_err(LoaderError::Watch)?;
+        Ok(Watcher { _watcher: watcher })
+    }
+}
+
+/// A handle that keeps watching a template directory, returned by [`Loader::watch`].
+#[cfg(feature = "notify")]
+pub struct Watcher {
+    _watcher: notify::RecommendedWatcher,
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), LoaderError> {
+    let io_error = |error| LoaderError::Io {
+        path: dir.to_path_buf(),
+        error,
+    };
+    for entry in std::fs::read_dir(dir).map_err(io_error)? {
+        let entry = entry.map_err(io_error)?;
+        let path = entry.path();
+        // Symlinks to directories are skipped, since they may point back up the tree.
+        if entry.file_type().map_err(io_error)?.is_dir() {
+            collect_files(&path, files)?;
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn read(path: &Path) -> Result<String, LoaderError> {
+    std::fs::read_to_string(path).map_err(|error| LoaderError::Io {
+        path: path.to_path_buf(),
+        error,
+    })
+}
+
+/// Read the templates of a file in the [`TemplateSet`] syntax, with names prefixed by `prefix`.
+fn read_set<T: TemplateStringParams>(
+    path: &Path,
+    prefix: &str,
+) -> Result<Vec<(String, TemplateString<T>)>, LoaderError> {
+    let set = read(path)?.parse::<TemplateSet<T>>().map_err(|error| match error {
+        TemplateSetError::Parse { name, error } => LoaderError::Parse {
+            path: path.to_path_buf(),
+            name: format!("{prefix}.{name}"),
+            error,
+        },
+        error => LoaderError::Format {
+            path: path.to_path_buf(),
+            message: error.to_string(),
+        },
+    })?;
+    Ok(set
+        .into_templates()
+        .map(|(name, template)| (format!("{prefix}.{name}"), template))
+        .collect())
+}
+
+/// Read the template sources of a structured file, or return `None` if the extension is not supported.
+#[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+fn read_structured(path: &Path, extension: &str) -> Result<Option<Vec<(String, String)>>, LoaderError> {
+    let map: BTreeMap<String, Entry> = match extension {
+        #[cfg(feature = "json")]
+        "json" => crate::load::from_json_str(&read(path)?),
+        #[cfg(feature = "toml")]
+        "toml" => crate::load::from_toml_str(&read(path)?),
+        #[cfg(feature = "yaml")]
+        "yaml" | "yml" => crate::load::from_yaml_str(&read(path)?),
+        _ => return Ok(None),
+    }
+    .map_err(|error| LoaderError::Format {
+        path: path.to_path_buf(),
+        message: error.to_string(),
+    })?;
+    let mut entries = vec![];
+    flatten(String::new(), map, &mut entries);
+    Ok(Some(entries))
+}
+
+/// Read the template sources of a structured file, or return `None` if the extension is not supported.
+#[cfg(not(any(feature = "json", feature = "toml", feature = "yaml")))]
+fn read_structured(_path: &Path, _extension: &str) -> Result<Option<Vec<(String, String)>>, LoaderError> {
+    Ok(None)
+}
+
+/// A value in a structured file: a template source or a nested map.
+#[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum Entry {
+    Template(String),
+    Nested(BTreeMap<String, Entry>),
+}
+
+#[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+fn flatten(prefix: String, map: BTreeMap<String, Entry>, entries: &mut Vec<(String, String)>) {
+    for (key, entry) in map {
+        let key = if prefix.is_empty() {
+            key
+        } else {
+            format!("{prefix}.{key}")
+        };
+        match entry {
+            Entry::Template(source) => entries.push((key, source)),
+            Entry::Nested(map) => flatten(key, map, entries),
+        }
+    }
+}
+
+/// Whether `path` matches the glob `pattern`, where `*` and `?` do not match `/` but `**` does.
+fn glob_match(pattern: &str, path: &str) -> bool {
+    match pattern.strip_prefix("**") {
+        Some(rest) => {
+            let rest = rest.strip_prefix('/').filter(|_| !rest.is_empty()).unwrap_or(rest);
+            (0..=path.len())
+                .filter(|&i| path.is_char_boundary(i) && (i == 0 || path[..i].ends_with('/') || rest.is_empty()))
+                .any(|i| glob_match(rest, &path[i..]))
+        }
+        None => match pattern.chars().next() {
+            None => path.is_empty(),
+            Some('*') => {
+                let rest = &pattern[1..];
+                let segment = path.find('/').unwrap_or(path.len());
+                (0..=segment)
+                    .filter(|&i| path.is_char_boundary(i))
+                    .any(|i| glob_match(rest, &path[i..]))
+            }
+            Some(char) => match path.chars().next() {
+                Some(c) if c == char || char == '?' && c != '/' => {
+                    glob_match(&pattern[char.len_utf8()..], &path[c.len_utf8()..])
+                }
+                _ => false,
+            },
+        },
+    }
+}
@@ -0,0 +1,258 @@
+//! Exporters for Android `strings.xml` and Apple `.strings` resource files.
+//!
+//! Placeholders are converted to positional format arguments numbered by
+//! [`TemplateStringParams::field_position`], so by default `{name}` for the first field becomes `%1$s` on Android and
+//! `%1$@` on Apple platforms. Positions can be pinned with `#[template(position = N)]` so that reordering the fields
+//! of the struct does not change exported resources. Conversions that cannot preserve the
+//! catalog exactly are listed in [`Export::lossy`].
+//!
+//! ## Examples
+//!
+//! ```
+//! # use typlate::{TemplateParams, TemplateString};
+//! #[derive(TemplateParams)]
+//! struct Cart {
+//!     user: String,
+//!     count: u32,
+//! }
+//!
+//! let template: TemplateString<Cart> = "{user} has {count} items".parse().unwrap();
+//! let export = typlate::mobile::to_android_xml([("cart.items", &template)]);
+//! assert!(export.content.contains(r#"<string name="cart_items">%1$s has %2$s items</string>"#));
+//! assert_eq!(export.lossy.len(), 1);
+//! ```
+
+use std::collections::BTreeSet;
+use std::fmt::{self, Display, Write};
+
+use crate::expr::Expr;
+use crate::string::TemplateElement;
+use crate::{TemplateString, TemplateStringParams};
+
+/// The result of an export: the file content and the conversions that lost information.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Export {
+    /// The content of the exported file.
+    pub content: String,
+    /// Conversions that could not preserve the catalog exactly.
+    pub lossy: Vec<LossyConversion>,
+}
+
+/// A conversion that could not preserve the catalog exactly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LossyConversion {
+    /// The key is not a valid resource name and was renamed.
+    RenamedKey { key: String, renamed: String },
+    /// The key (possibly after renaming) appeared more than once; only the first entry was exported.
+    DuplicateKey { key: String },
+    /// A select block was replaced by the value of the field it branches on.
+    FlattenedSelect { key: String },
+    /// The filters of a placeholder were dropped.
+    DroppedFilters { key: String },
+    /// The format spec of a placeholder, such as `{price:>8.2}`, was dropped.
+    DroppedFormatSpec { key: String },
+    /// An arithmetic expression was replaced by the value of its first field, or dropped if it has no fields.
+    FlattenedExpression { key: String },
+    /// A conditional section was replaced by the content of its first branch.
+    FlattenedConditional { key: String },
+    /// A namespaced placeholder, such as `{env:HOME}`, was dropped.
+    DroppedNamespaced { key: String },
+}
+
+impl Display for LossyConversion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::RenamedKey { key, renamed } => write!(f, "{key}: Renamed to {renamed}"),
+            Self::DuplicateKey { key } => write!(f, "{key}: Duplicate key dropped"),
+            Self::FlattenedSelect { key } => write!(f, "{key}: Select block replaced by its field value"),
+            Self::DroppedFilters { key } => write!(f, "{key}: Placeholder filters dropped"),
+            Self::DroppedFormatSpec { key } => write!(f, "{key}: Placeholder format spec dropped"),
+            Self::FlattenedExpression { key } => write!(f, "{key}: Expression replaced by its first field value"),
+            Self::FlattenedConditional { key } => write!(f, "{key}: Conditional section replaced by its first branch"),
+            Self::DroppedNamespaced { key } => write!(f, "{key}: Namespaced placeholder dropped"),
+        }
+    }
+}
+
+/// Export templates as an Android `strings.xml` resource file.
+///
+/// Keys are converted into valid resource names by lowercasing them and replacing other characters with `_`.
+pub fn to_android_xml<'a, T, K>(entries: impl IntoIterator<Item = (K, &'a TemplateString<T>)>) -> Export
+where
+    T: TemplateStringParams + 'a,
+    K: Display,
+{
+    let mut content = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<resources>\n");
+    let mut lossy = vec![];
+    let mut seen = BTreeSet::new();
+    for (key, template) in entries {
+        let key = key.to_string();
+        let name = android_resource_name(&key);
+        if name != key {
+            lossy.push(LossyConversion::RenamedKey {
+                key: key.clone(),
+                renamed: name.clone(),
+            });
+        }
+        if !seen.insert(name.clone()) {
+            lossy.push(LossyConversion::DuplicateKey { key });
+            continue;
+        }
+
+        let mut value = String::new();
+        let mut flattened = false;
+        for element in inline_blocks(template.elements(), &mut flattened) {
+            match element {
+                TemplateElement::Text(text) => {
+                    for char in text.chars() {
+                        match char {
+                            '&' => value.push_str("&amp;"),
+                            '<' => value.push_str("&lt;"),
+                            '>' => value.push_str("&gt;"),
+                            '\'' | '"' | '\\' => write!(value, "\\{char}").unwrap(),
+                            '@' | '?' if value.is_empty() => write!(value, "\\{char}").unwrap(),
+                            '\n' => value.push_str("\\n"),
+                            '\t' => value.push_str("\\t"),
+                            '%' => value.push_str("%%"),
+                            _ => value.push(char),
+                        }
+                    }
+                }
+                TemplateElement::Var(index) => write!(value, "%{}$s", T::field_position(*index)).unwrap(),
+                TemplateElement::Filtered { index, .. } => {
+                    lossy.push(LossyConversion::DroppedFilters { key: key.clone() });
+                    write!(value, "%{}$s", T::field_position(*index)).unwrap();
+                }
+                TemplateElement::Formatted { index, .. } => {
+                    lossy.push(LossyConversion::DroppedFormatSpec { key: key.clone() });
+                    write!(value, "%{}$s", T::field_position(*index)).unwrap();
+                }
+                TemplateElement::Select { index, .. } => {
+                    lossy.push(LossyConversion::FlattenedSelect { key: key.clone() });
+                    write!(value, "%{}$s", T::field_position(*index)).unwrap();
+                }
+                TemplateElement::Expr(expr) => {
+                    lossy.push(LossyConversion::FlattenedExpression { key: key.clone() });
+                    if let Some(index) = first_field(expr) {
+                        write!(value, "%{}$s", T::field_position(index)).unwrap();
+                    }
+                }
+                TemplateElement::Namespaced { .. } => {
+                    lossy.push(LossyConversion::DroppedNamespaced { key: key.clone() });
+                }
+                TemplateElement::Block { .. } | TemplateElement::Conditional { .. } => {
+                    unreachable!("blocks and conditional sections are inlined")
+                }
+            }
+        }
+        if flattened {
+            lossy.push(LossyConversion::FlattenedConditional { key: key.clone() });
+        }
+        writeln!(content, "    <string name=\"{name}\">{value}</string>").unwrap();
+    }
+    content.push_str("</resources>\n");
+    Export { content, lossy }
+}
+
+/// Export templates as an Apple `.strings` file.
+pub fn to_apple_strings<'a, T, K>(entries: impl IntoIterator<Item = (K, &'a TemplateString<T>)>) -> Export
+where
+    T: TemplateStringParams + 'a,
+    K: Display,
+{
+    let mut content = String::new();
+    let mut lossy = vec![];
+    let mut seen = BTreeSet::new();
+    for (key, template) in entries {
+        let key = key.to_string();
+        if !seen.insert(key.clone()) {
+            lossy.push(LossyConversion::DuplicateKey { key });
+            continue;
+        }
+
+        let mut value = String::new();
+        let mut flattened = false;
+        for element in inline_blocks(template.elements(), &mut flattened) {
+            match element {
+                TemplateElement::Text(text) => value.push_str(&apple_escape(text).replace('%', "%%")),
+                TemplateElement::Var(index) => write!(value, "%{}$@", T::field_position(*index)).unwrap(),
+                TemplateElement::Filtered { index, .. } => {
+                    lossy.push(LossyConversion::DroppedFilters { key: key.clone() });
+                    write!(value, "%{}$@", T::field_position(*index)).unwrap();
+                }
+                TemplateElement::Formatted { index, .. } => {
+                    lossy.push(LossyConversion::DroppedFormatSpec { key: key.clone() });
+                    write!(value, "%{}$@", T::field_position(*index)).unwrap();
+                }
+                TemplateElement::Select { index, .. } => {
+                    lossy.push(LossyConversion::FlattenedSelect { key: key.clone() });
+                    write!(value, "%{}$@", T::field_position(*index)).unwrap();
+                }
+                TemplateElement::Expr(expr) => {
+                    lossy.push(LossyConversion::FlattenedExpression { key: key.clone() });
+                    if let Some(index) = first_field(expr) {
+                        write!(value, "%{}$@", T::field_position(index)).unwrap();
+                    }
+                }
+                TemplateElement::Namespaced { .. } => {
+                    lossy.push(LossyConversion::DroppedNamespaced { key: key.clone() });
+                }
+                TemplateElement::Block { .. } | TemplateElement::Conditional { .. } => {
+                    unreachable!("blocks and conditional sections are inlined")
+                }
+            }
+        }
+        if flattened {
+            lossy.push(LossyConversion::FlattenedConditional { key: key.clone() });
+        }
+        writeln!(content, "\"{}\" = \"{value}\";", apple_escape(&key)).unwrap();
+    }
+    Export { content, lossy }
+}
+
+/// The index of the first field referenced by an expression.
+fn first_field(expr: &Expr) -> Option<usize> {
+    let mut first = None;
+    expr.visit_fields(&mut |index| {
+        first.get_or_insert(index);
+    });
+    first
+}
+
+/// The elements of a template with the content of blocks inlined in place of the blocks, and the first branch of
+/// conditional sections inlined in place of the sections, which sets `flattened`.
+fn inline_blocks<'e>(elements: &'e [TemplateElement], flattened: &mut bool) -> Vec<&'e TemplateElement> {
+    let mut inlined = vec![];
+    for element in elements {
+        match element {
+            TemplateElement::Block { elements, .. } => inlined.extend(inline_blocks(elements, flattened)),
+            TemplateElement::Conditional { then, .. } => {
+                *flattened = true;
+                inlined.extend(inline_blocks(then, flattened));
+            }
+            element => inlined.push(element),
+        }
+    }
+    inlined
+}
+
+fn android_resource_name(key: &str) -> String {
+    let mut name: String = key
+        .chars()
+        .map(|char| match char.to_ascii_lowercase() {
+            char @ ('a'..='z' | '0'..='9' | '_') => char,
+            _ => '_',
+        })
+        .collect();
+    if !name.starts_with(|char: char| char.is_ascii_lowercase() || char == '_') {
+        name.insert(0, '_');
+    }
+    name
+}
+
+fn apple_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+}
@@ -0,0 +1,64 @@
+use crate::string::Builder;
+use crate::{TemplateParseError, TemplateString, TemplateStringParams};
+
+impl<T: TemplateStringParams> TemplateString<T> {
+    /// Parse the variable subset of a Mustache (or Handlebars) template, e.g. to migrate simple templates off
+    /// handlebars.
+    ///
+    /// `{{name}}`, `{{{name}}}` and `{{&name}}` become placeholders (templates do not escape HTML, so the three are
+    /// equivalent) and `{{! comments}}` are dropped. Sections (`{{#name}}`, `{{^name}}`, `{{/name}}`), partials
+    /// (`{{>name}}`) and delimiter changes (`{{=<% %>=}}`) are rejected with
+    /// [`TemplateParseError::UnsupportedTag`].
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{TemplateParams, TemplateParseError, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Data {
+    ///     name: String,
+    ///     count: u32,
+    /// }
+    ///
+    /// let template = TemplateString::<Data>::from_mustache("{{! greeting }}Hi {{ name }}, {{{count}}} new").unwrap();
+    /// assert_eq!(template.to_string(), "Hi {name}, {count} new");
+    ///
+    /// let error = TemplateString::<Data>::from_mustache("{{#count}}new{{/count}}").unwrap_err();
+    /// assert_eq!(error, TemplateParseError::UnsupportedTag { tag: "{{#count}}".into(), span: 0..10 });
+    /// ```
+    pub fn from_mustache(input: &str) -> Result<Self, TemplateParseError> {
+        let mut builder = Builder::<T>::new();
+        let mut rest = 0;
+        while let Some(open) = input[rest..].find("{{").map(|i| rest + i) {
+            input[rest..open].chars().for_each(|char| builder.push_char(char));
+            let (start, delimiter) = match input[open..].starts_with("{{{") {
+                true => (open + 3, "}}}"),
+                false => (open + 2, "}}"),
+            };
+            let Some(close) = input[start..].find(delimiter).map(|i| start + i) else {
+                return Err(TemplateParseError::UnclosedBracket { offset: open });
+            };
+            rest = close + delimiter.len();
+            let mut content = start..close;
+            match input[start..close].trim_start().chars().next() {
+                Some('!') => continue,
+                Some('&') if delimiter == "}}" => content.start = start + input[start..].find('&').unwrap() + 1,
+                Some('#' | '^' | '/' | '>' | '=') if delimiter == "}}" => {
+                    return Err(TemplateParseError::UnsupportedTag {
+                        tag: input[open..rest].to_string(),
+                        span: open..rest,
+                    });
+                }
+                _ => {}
+            }
+            let name = input[content.clone()].trim();
+            let offset = content.start + input[content].find(name).unwrap_or(0);
+            if name.is_empty() {
+                return Err(TemplateParseError::EmptyPlaceholder { span: open..rest });
+            }
+            builder.push_var(name, offset..offset + name.len())?;
+        }
+        input[rest..].chars().for_each(|char| builder.push_char(char));
+        Ok(builder.finish())
+    }
+}
@@ -0,0 +1,76 @@
+use std::fmt;
+
+/// The value of a numeric field, used for locale-aware formatting and arithmetic expressions.
+///
+/// The derive macro exposes fields of primitive integer and float types (and `Option`s of them) through
+/// [`TemplateStringParams::field_number`](crate::TemplateStringParams::field_number).
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Number {
+    Int(i128),
+    UInt(u128),
+    Float(f64),
+}
+
+macro_rules! impl_from {
+    ($variant:ident($target:ty): $($source:ty),*) => {
+        $(impl From<$source> for Number {
+            fn from(value: $source) -> Self {
+                Self::$variant(value as $target)
+            }
+        })*
+    };
+}
+
+impl_from!(Int(i128): i8, i16, i32, i64, i128, isize);
+impl_from!(UInt(u128): u8, u16, u32, u64, u128, usize);
+impl_from!(Float(f64): f64);
+
+impl From<f32> for Number {
+    /// Converts through the shortest decimal representation, so that `0.1f32` does not become `0.10000000149011612`.
+    fn from(value: f32) -> Self {
+        Self::Float(value.to_string().parse().unwrap())
+    }
+}
+
+impl Number {
+    /// The value as an integer, or `None` for floats and integers out of range.
+    pub(crate) fn to_i128(self) -> Option<i128> {
+        match self {
+            Self::Int(value) => Some(value),
+            Self::UInt(value) => value.try_into().ok(),
+            Self::Float(_) => None,
+        }
+    }
+
+    pub(crate) fn to_f64(self) -> f64 {
+        match self {
+            Self::Int(value) => value as f64,
+            Self::UInt(value) => value as f64,
+            Self::Float(value) => value,
+        }
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Int(value) => value.fmt(f),
+            Self::UInt(value) => value.fmt(f),
+            Self::Float(value) => value.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "icu")]
+impl Number {
+    /// Convert into a decimal for `icu_decimal`, or `None` for non-finite floats.
+    pub(crate) fn to_decimal(self) -> Option<fixed_decimal::Decimal> {
+        match self {
+            Self::Int(value) => Some(value.into()),
+            Self::UInt(value) => Some(value.into()),
+            Self::Float(value) => {
+                fixed_decimal::Decimal::try_from_f64(value, fixed_decimal::FloatPrecision::RoundTrip).ok()
+            }
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+/// A render reported to a [`RenderObserver`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RenderEvent<'a> {
+    /// The name of the template, if it was rendered through a [`TemplateSet`](crate::TemplateSet).
+    pub name: Option<&'a str>,
+    /// A hash of the template, which identifies it within the running process.
+    pub id: u64,
+    /// The length of the output in bytes.
+    pub output_len: usize,
+    /// The time spent rendering, which is zero on `wasm32-unknown-unknown` where no clock is available.
+    pub duration: Duration,
+}
+
+/// Measures the duration of a render.
+pub(crate) struct Stopwatch {
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    start: std::time::Instant,
+}
+
+impl Stopwatch {
+    pub(crate) fn start() -> Self {
+        Self {
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            start: std::time::Instant::now(),
+        }
+    }
+
+    pub(crate) fn elapsed(&self) -> Duration {
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        return self.start.elapsed();
+        #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+        return Duration::ZERO;
+    }
+}
+
+/// A hook called after every render with a [`RenderCtx`](crate::RenderCtx) that has the observer set, e.g. to record
+/// metrics on which templates are rendered how often and how large their output is.
+///
+/// It is implemented for closures taking a [`RenderEvent`].
+///
+/// ## Examples
+///
+/// ```
+/// # use std::sync::Arc;
+/// # use std::sync::atomic::{AtomicUsize, Ordering};
+/// # use typlate::{RenderCtx, RenderEvent, TemplateParams, TemplateSet};
+/// #[derive(TemplateParams)]
+/// struct User {
+///     name: &'static str,
+/// }
+///
+/// let bytes = Arc::new(AtomicUsize::new(0));
+/// let counter = bytes.clone();
+/// let ctx = RenderCtx::new().with_observer(move |event: &RenderEvent| {
+///     assert_eq!(event.name, Some("greeting"));
+///     counter.fetch_add(event.output_len, Ordering::Relaxed);
+/// });
+///
+/// let set: TemplateSet<User> = "[greeting] Hello {name}".parse().unwrap();
+/// set.format_with_ctx("greeting", &User { name: "Alice" }, &ctx);
+/// assert_eq!(bytes.load(Ordering::Relaxed), 11);
+/// ```
+pub trait RenderObserver: Send + Sync {
+    /// Called after a template has been rendered.
+    fn on_render(&self, event: &RenderEvent);
+}
+
+impl<F: Fn(&RenderEvent) + Send + Sync> RenderObserver for F {
+    fn on_render(&self, event: &RenderEvent) {
+        self(event)
+    }
+}
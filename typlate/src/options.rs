@@ -0,0 +1,231 @@
+/// Options that control how template strings are parsed, used with [`TemplateString::parse_with`].
+///
+/// The default options are the ones used by [`FromStr`](std::str::FromStr). Other options are created with
+/// [`ParseOptions::builder`].
+///
+/// ## Examples
+///
+/// ```
+/// # use typlate::{ParseOptions, TemplateParams, TemplateString};
+/// #[derive(TemplateParams)]
+/// struct User {
+///     name: String,
+/// }
+///
+/// let options = ParseOptions::builder().trim_names(true).lenient(true).build();
+/// let template = TemplateString::<User>::parse_with("Hi { name }, {unknown}", &options).unwrap();
+/// assert_eq!(template.format(&User { name: "Alice".into() }), "Hi Alice, {unknown}");
+/// ```
+///
+/// [`TemplateString::parse_with`]: crate::TemplateString::parse_with
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ParseOptions {
+    empty_placeholders: EmptyPlaceholders,
+    lenient: bool,
+    trim_names: bool,
+    max_length: Option<usize>,
+    max_depth: usize,
+    env_vars: bool,
+    namespaces: Vec<String>,
+    filters: Vec<String>,
+    globals: Vec<String>,
+}
+
+/// The default maximum nesting depth of a template, see [`ParseOptionsBuilder::max_depth`].
+pub const DEFAULT_MAX_PARSE_DEPTH: usize = 128;
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            empty_placeholders: EmptyPlaceholders::default(),
+            lenient: false,
+            trim_names: false,
+            max_length: None,
+            max_depth: DEFAULT_MAX_PARSE_DEPTH,
+            env_vars: false,
+            namespaces: vec![],
+            filters: vec![],
+            globals: vec![],
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Create the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a builder starting from the default options.
+    pub fn builder() -> ParseOptionsBuilder {
+        ParseOptionsBuilder::default()
+    }
+
+    /// How placeholders without a field name are handled.
+    pub fn empty_placeholders(&self) -> EmptyPlaceholders {
+        self.empty_placeholders
+    }
+
+    /// Whether placeholders naming unknown fields are kept as literal text instead of failing.
+    pub fn lenient(&self) -> bool {
+        self.lenient
+    }
+
+    /// Whether whitespace around the field name of a plain placeholder, as in `{ name }`, is ignored.
+    pub fn trim_names(&self) -> bool {
+        self.trim_names
+    }
+
+    /// The maximum length of a template source in bytes, if any.
+    pub fn max_length(&self) -> Option<usize> {
+        self.max_length
+    }
+
+    /// The maximum nesting depth of select branches, conditional sections, blocks and parenthesized expressions.
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Whether `{env:NAME}` placeholders read environment variables.
+    pub fn env_vars(&self) -> bool {
+        self.env_vars
+    }
+
+    /// Whether `{namespace:name}` placeholders are accepted in the namespace: the namespaces added with
+    /// [`ParseOptionsBuilder::namespace`], `env` with [`ParseOptionsBuilder::env_vars`], and `global`.
+    pub fn accepts_namespace(&self, namespace: &str) -> bool {
+        match namespace {
+            "env" => self.env_vars || self.namespaces.iter().any(|accepted| accepted == namespace),
+            "global" => true,
+            _ => self.namespaces.iter().any(|accepted| accepted == namespace),
+        }
+    }
+
+    /// Whether `{field|name}` filters with the name are accepted: the built-in filters and the filters added with
+    /// [`ParseOptionsBuilder::filter`].
+    pub fn accepts_filter(&self, name: &str) -> bool {
+        crate::filter::is_builtin(name) || self.filters.iter().any(|accepted| accepted == name)
+    }
+
+    /// Whether a plain `{name}` placeholder that does not name a field refers to the global parameter with the name:
+    /// the names added with [`ParseOptionsBuilder::global`].
+    pub fn accepts_global(&self, name: &str) -> bool {
+        self.globals.iter().any(|accepted| accepted == name)
+    }
+}
+
+/// A builder for [`ParseOptions`].
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptionsBuilder {
+    options: ParseOptions,
+}
+
+impl ParseOptionsBuilder {
+    /// Set how placeholders without a field name, such as `{}`, are handled. Defaults to
+    /// [`EmptyPlaceholders::Reject`].
+    pub fn empty_placeholders(mut self, empty_placeholders: EmptyPlaceholders) -> Self {
+        self.options.empty_placeholders = empty_placeholders;
+        self
+    }
+
+    /// Keep placeholders (including select blocks) that name unknown fields as literal text, instead of failing with
+    /// [`TemplateParseError::UnknownField`](crate::TemplateParseError::UnknownField) or
+    /// [`TemplateParseError::InvalidFieldName`](crate::TemplateParseError::InvalidFieldName). Defaults to `false`.
+    ///
+    /// This is useful for templates written against a newer version of the parameter type.
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.options.lenient = lenient;
+        self
+    }
+
+    /// Ignore whitespace around the field name of a plain placeholder, as in `{ name }`. Defaults to `false`.
+    ///
+    /// Whitespace around the field name of placeholders with filters or select blocks is always ignored.
+    pub fn trim_names(mut self, trim_names: bool) -> Self {
+        self.options.trim_names = trim_names;
+        self
+    }
+
+    /// Reject template sources longer than the given number of bytes with
+    /// [`TemplateParseError::TooLong`](crate::TemplateParseError::TooLong), e.g. for templates from untrusted input.
+    /// Unlimited by default.
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.options.max_length = Some(max_length);
+        self
+    }
+
+    /// Reject templates whose select branches, conditional sections, blocks or parenthesized expressions are nested
+    /// more than the given number of levels deep with
+    /// [`TemplateParseError::TooDeep`](crate::TemplateParseError::TooDeep). Parsing and rendering recurse into nested
+    /// sections, so the limit keeps templates from untrusted input from overflowing the stack. Defaults to
+    /// [`DEFAULT_MAX_PARSE_DEPTH`].
+    ///
+    /// An arithmetic expression may also have at most this many operators, so that its evaluation is bounded as well.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.options.max_depth = max_depth;
+        self
+    }
+
+    /// Accept `{env:NAME}` placeholders, which render the environment variable `NAME` of the process at render time,
+    /// e.g. for paths in configuration files. Defaults to `false`.
+    ///
+    /// An unset variable renders as an empty string with [`TemplateString::format`](crate::TemplateString::format), and
+    /// fails [`TemplateString::try_format`](crate::TemplateString::try_format) with
+    /// [`RenderError::Unresolved`](crate::RenderError::Unresolved).
+    pub fn env_vars(mut self, env_vars: bool) -> Self {
+        self.options.env_vars = env_vars;
+        self
+    }
+
+    /// Accept `{namespace:name}` placeholders in the namespace, which are resolved at render time by the resolver
+    /// registered with [`RenderCtx::with_resolver`](crate::RenderCtx::with_resolver).
+    ///
+    /// Placeholders in other namespaces fail with
+    /// [`TemplateParseError::UnknownNamespace`](crate::TemplateParseError::UnknownNamespace), except for `global`,
+    /// which is always accepted and refers to the [global parameters](crate::set_global_params).
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.options.namespaces.push(namespace.into());
+        self
+    }
+
+    /// Accept `{field|name}` filters with the name, which are applied at render time by the filter registered with
+    /// [`RenderCtx::with_filters`](crate::RenderCtx::with_filters).
+    ///
+    /// Filters that are neither added this way nor built in fail with
+    /// [`TemplateParseError::UnknownFilter`](crate::TemplateParseError::UnknownFilter), so that a typo such as
+    /// `{card|mask4}` cannot silently render the unfiltered value. An accepted filter that is not registered on the
+    /// render context leaves the value unchanged.
+    pub fn filter(mut self, name: impl Into<String>) -> Self {
+        self.options.filters.push(name.into());
+        self
+    }
+
+    /// Accept plain `{name}` placeholders for the [global parameter](crate::set_global_params) with the name, as a
+    /// shorthand for `{global:name}`.
+    ///
+    /// Fields and inline templates of the parameter type take precedence, so a global parameter with the same name as a
+    /// field is only reachable as `{global:name}`. Only plain placeholders are accepted this way: the name cannot take
+    /// filters, a format spec or select branches. Other unknown names still fail with
+    /// [`TemplateParseError::UnknownField`](crate::TemplateParseError::UnknownField), so that a typo in a field name
+    /// does not silently render a missing global parameter.
+    pub fn global(mut self, name: impl Into<String>) -> Self {
+        self.options.globals.push(name.into());
+        self
+    }
+
+    /// Build the options.
+    pub fn build(self) -> ParseOptions {
+        self.options
+    }
+}
+
+/// How placeholders without a field name, such as `{}` or `{ }`, are handled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum EmptyPlaceholders {
+    /// Fail with [`TemplateParseError::EmptyPlaceholder`](crate::TemplateParseError::EmptyPlaceholder).
+    #[default]
+    Reject,
+    /// Refer to the fields in declaration order, like implicit positional arguments in `format!`: the first empty
+    /// placeholder is the first field, the second one is the second field, and so on.
+    Positional,
+}
@@ -0,0 +1,749 @@
+use std::iter::Peekable;
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::str::CharIndices;
+
+use crate::cond::Condition;
+use crate::expr::Expr;
+use crate::filter::FilterCall;
+use crate::format_spec::FormatSpec;
+use crate::string::{Builder, TemplateElement};
+use crate::{EmptyPlaceholders, ParseOptions, TemplateParseError, TemplateStringParams};
+
+/// A recursive-descent parser for the template syntax.
+///
+/// ```text
+/// template    = (text | "{{" | "}}" | placeholder | block | conditional)*
+/// block       = "{%" "block" name "%}" template "{%" "endblock" name? "%}"
+/// conditional = "{?" condition "}" template ("{:}" template)? "{/}"
+///             | "{" condition "?" quoted ":" quoted "}"
+/// placeholder = "{" name "}"
+///             | "{" name ":" spec "}"
+///             | "{" namespace ":" name "}"
+///             | "{" expr "}"
+///             | "{" name ("|" filter ("(" arg ("," arg)* ")")?)+ "}"
+///             | "{" name "," ("select" | "select-" facet) "," (key "{" branch "}")+ "}"
+/// ```
+///
+/// A `name` that is empty or only whitespace is handled according to [`ParseOptions::empty_placeholders`]. With
+/// [`ParseOptions::lenient`], placeholders naming unknown fields are kept as literal text. A name that is not an
+/// identifier (see [`is_field_name`]) fails with [`TemplateParseError::InvalidFieldName`] rather than as an unknown
+/// field. A plain placeholder naming one of [`TemplateStringParams::INLINE_FIELDS`] is replaced by the elements of its
+/// template. A namespaced placeholder is resolved at render time, and its namespace must be accepted by the options,
+/// unless it is a field name, in which case the rest is a `std::fmt`-style [`FormatSpec`]. A plain placeholder that
+/// is not a field name but contains an operator is parsed as an arithmetic [`Expr`], and one that names neither a field
+/// nor an inline template but is accepted by [`ParseOptions::accepts_global`] is a `{global:name}` placeholder. The condition of a conditional
+/// section is parsed as a [`Condition`], and an inline conditional is parsed as a conditional section whose branches
+/// are the quoted texts. Select branches, conditional sections and blocks nest up to [`ParseOptions::max_depth`]
+/// levels.
+///
+/// Inside select branches, a `}` always closes the branch, so `}}` cannot be used as an escape there. Filters must be
+/// accepted by the options, and their arguments are trimmed unless they are wrapped in double quotes.
+pub(crate) struct Parser<'s, T> {
+    source: &'s str,
+    chars: Peekable<CharIndices<'s>>,
+    options: &'s ParseOptions,
+    /// Names of the blocks seen so far, which must be unique.
+    blocks: Vec<&'s str>,
+    /// The number of empty placeholders seen so far, used for positional placeholders.
+    position: usize,
+    /// The errors recovered from so far, or `None` if parsing stops at the first error.
+    errors: Option<Vec<TemplateParseError>>,
+    /// Names of the inline fields being expanded, used to detect cycles.
+    inlining: Vec<&'static str>,
+    /// The number of sections that enclose the current position, limited by [`ParseOptions::max_depth`].
+    depth: usize,
+    phantom: PhantomData<T>,
+}
+
+/// Where the sequence being parsed ends.
+#[derive(Clone, Copy)]
+enum Scope<'s> {
+    /// At the end of the source.
+    Template,
+    /// At the `}` closing a select branch opened at the offset.
+    Branch(usize),
+    /// At the `{%endblock%}` tag of the named block opened at the offset.
+    Block(usize, &'s str),
+    /// At the `{:}` or `{/}` tag of the conditional section opened at the offset.
+    Conditional(usize),
+}
+
+/// A parsed `{ ... }` placeholder.
+enum Placeholder {
+    Element(TemplateElement),
+    /// The elements of an inline field.
+    Inline(Vec<TemplateElement>),
+}
+
+/// A `{% ... %}` tag.
+enum Tag<'s> {
+    Block(&'s str),
+    EndBlock(Option<&'s str>),
+}
+
+impl<'s, T: TemplateStringParams> Parser<'s, T> {
+    pub(crate) fn new(source: &'s str, options: &'s ParseOptions) -> Self {
+        Self {
+            source,
+            chars: source.char_indices().peekable(),
+            options,
+            blocks: vec![],
+            position: 0,
+            errors: None,
+            inlining: vec![],
+            depth: 0,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Parse the whole template.
+    pub(crate) fn parse(mut self) -> Result<Vec<TemplateElement>, TemplateParseError> {
+        self.check_length()?;
+        self.parse_sequence(Scope::Template)
+    }
+
+    /// Parse the whole template, recovering from errors to report all of them.
+    pub(crate) fn parse_all(mut self) -> Result<Vec<TemplateElement>, Vec<TemplateParseError>> {
+        self.check_length().map_err(|error| vec![error])?;
+        self.errors = Some(vec![]);
+        let elements = self.parse_sequence(Scope::Template).map_err(|error| vec![error])?;
+        match self.errors {
+            Some(errors) if !errors.is_empty() => Err(errors),
+            _ => Ok(elements),
+        }
+    }
+
+    fn check_length(&self) -> Result<(), TemplateParseError> {
+        match self.options.max_length() {
+            Some(limit) if self.source.len() > limit => Err(TemplateParseError::TooLong {
+                limit,
+                length: self.source.len(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Report an error: when recovering, record it and continue, otherwise stop parsing. Exceeding the maximum depth
+    /// always stops parsing, as the rest of the input is likely nested as deeply.
+    fn fail(&mut self, error: TemplateParseError) -> Result<(), TemplateParseError> {
+        match &mut self.errors {
+            Some(errors) if !matches!(error, TemplateParseError::TooDeep { .. }) => {
+                errors.push(error);
+                Ok(())
+            }
+            _ => Err(error),
+        }
+    }
+
+    /// Parse a sequence of elements up to the end of the given scope.
+    fn parse_sequence(&mut self, scope: Scope<'s>) -> Result<Vec<TemplateElement>, TemplateParseError> {
+        let mut builder = Builder::<T>::new();
+        while let Some((offset, char)) = self.chars.next() {
+            match char {
+                '{' if self.chars.next_if(|&(_, c)| c == '{').is_some() => builder.push_char('{'),
+                '{' if self.chars.next_if(|&(_, c)| c == '%').is_some() => match self.parse_tag(offset) {
+                    Ok((Tag::Block(name), _)) => {
+                        let elements = self.parse_nested(Scope::Block(offset, name))?;
+                        builder.push_element(TemplateElement::Block {
+                            name: name.to_string(),
+                            elements,
+                        });
+                    }
+                    Ok((Tag::EndBlock(end), end_offset)) => match scope {
+                        Scope::Block(_, name) if end.is_none_or(|end| end == name) => {
+                            return Ok(builder.into_elements());
+                        }
+                        _ => self.fail(TemplateParseError::InvalidBlock {
+                            span: offset..end_offset,
+                        })?,
+                    },
+                    Err(error) => self.fail(error)?,
+                },
+                '{' if self.chars.next_if(|&(_, c)| c == '?').is_some() => match self.parse_conditional(offset) {
+                    Ok(element) => builder.push_element(element),
+                    Err(error) => self.fail(error)?,
+                },
+                '{' if self.source[offset..].starts_with("{:}") || self.source[offset..].starts_with("{/}") => {
+                    self.chars.nth(1);
+                    match scope {
+                        Scope::Conditional(_) => return Ok(builder.into_elements()),
+                        _ => self.fail(TemplateParseError::InvalidCondition {
+                            span: offset..offset + 3,
+                        })?,
+                    }
+                }
+                '{' => match self.parse_placeholder(offset) {
+                    Ok(Placeholder::Element(element)) => builder.push_element(element),
+                    Ok(Placeholder::Inline(elements)) => elements.into_iter().for_each(|e| builder.push_element(e)),
+                    Err(error) => {
+                        let lenient = self.options.lenient()
+                            && matches!(
+                                error,
+                                TemplateParseError::UnknownField { .. } | TemplateParseError::InvalidFieldName { .. }
+                            );
+                        if !lenient {
+                            self.fail(error)?;
+                        }
+                        self.skip_placeholder();
+                        if lenient {
+                            builder.push_str(&self.source[offset..self.offset()]);
+                        }
+                    }
+                },
+                '}' if matches!(scope, Scope::Branch(_)) => return Ok(builder.into_elements()),
+                '}' if self.chars.next_if(|&(_, c)| c == '}').is_some() => builder.push_char('}'),
+                '}' => self.fail(TemplateParseError::UnmatchedClosingBracket { offset })?,
+                _ => builder.push_char(char),
+            }
+        }
+        match scope {
+            Scope::Template => {}
+            Scope::Branch(offset) => self.fail(TemplateParseError::UnclosedBracket { offset })?,
+            Scope::Block(offset, name) => self.fail(TemplateParseError::UnclosedBlock {
+                name: name.to_string(),
+                offset,
+            })?,
+            Scope::Conditional(offset) => self.fail(TemplateParseError::InvalidCondition {
+                span: offset..offset + 2,
+            })?,
+        }
+        Ok(builder.into_elements())
+    }
+
+    /// Parse a sequence nested in a section, failing if that exceeds the maximum depth.
+    fn parse_nested(&mut self, scope: Scope<'s>) -> Result<Vec<TemplateElement>, TemplateParseError> {
+        let (Scope::Branch(offset) | Scope::Block(offset, _) | Scope::Conditional(offset)) = scope else {
+            return self.parse_sequence(scope);
+        };
+        let limit = self.options.max_depth();
+        if self.depth >= limit {
+            return Err(TemplateParseError::TooDeep { limit, offset });
+        }
+        self.depth += 1;
+        let elements = self.parse_sequence(scope);
+        self.depth -= 1;
+        elements
+    }
+
+    /// After an error in a placeholder, skip the rest of it up to its closing `}`, unless that was already consumed.
+    fn skip_placeholder(&mut self) {
+        if self.source[..self.offset()].ends_with('}') {
+            return;
+        }
+        let mut depth = 1;
+        for (_, char) in self.chars.by_ref() {
+            match char {
+                '{' => depth += 1,
+                '}' if depth == 1 => return,
+                '}' => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+
+    /// Parse a `{% ... %}` tag opened at `open`, after the `{%`. Returns the tag and the offset after its end.
+    fn parse_tag(&mut self, open: usize) -> Result<(Tag<'s>, usize), TemplateParseError> {
+        let start = open + 2;
+        let (close, char) = self
+            .chars
+            .find(|&(_, c)| c == '%' || c == '{' || c == '}')
+            .ok_or(TemplateParseError::UnclosedBracket { offset: open })?;
+        if char != '%' || self.chars.next_if(|&(_, c)| c == '}').is_none() {
+            return Err(TemplateParseError::InvalidBlock { span: open..close + 1 });
+        }
+        let end = close + 2;
+        let tag = match *self.source[start..close].split_whitespace().collect::<Vec<_>>() {
+            ["block", name] if is_block_name(name) => {
+                if self.blocks.contains(&name) {
+                    return Err(TemplateParseError::InvalidBlock { span: open..end });
+                }
+                self.blocks.push(name);
+                Tag::Block(name)
+            }
+            ["endblock"] => Tag::EndBlock(None),
+            ["endblock", name] => Tag::EndBlock(Some(name)),
+            _ => return Err(TemplateParseError::InvalidBlock { span: open..end }),
+        };
+        Ok((tag, end))
+    }
+
+    /// Parse a conditional section opened at `open`, after the `{?`, up to and including its `{/}` tag.
+    fn parse_conditional(&mut self, open: usize) -> Result<TemplateElement, TemplateParseError> {
+        let start = open + 2;
+        let (close, _) = self
+            .chars
+            .find(|&(_, c)| c == '}')
+            .ok_or(TemplateParseError::UnclosedBracket { offset: open })?;
+        let condition = Condition::parse::<T>(&self.source[start..close], start, self.options.max_depth())?;
+        let then = self.parse_nested(Scope::Conditional(open))?;
+        let mut otherwise = vec![];
+        if self.source[..self.offset()].ends_with("{:}") {
+            otherwise = self.parse_nested(Scope::Conditional(open))?;
+            let end = self.offset();
+            if self.source[..end].ends_with("{:}") {
+                return Err(TemplateParseError::InvalidCondition { span: end - 3..end });
+            }
+        }
+        Ok(TemplateElement::Conditional {
+            condition,
+            then,
+            otherwise,
+        })
+    }
+
+    fn parse_placeholder(&mut self, open: usize) -> Result<Placeholder, TemplateParseError> {
+        let start = open + 1;
+        if let Some(question) = self.find_ternary(start) {
+            return self.parse_ternary(open, question).map(Placeholder::Element);
+        }
+        let (end, delimiter) = self
+            .chars
+            .find(|&(_, c)| c == '}' || c == ',' || c == '|')
+            .ok_or(TemplateParseError::UnclosedBracket { offset: open })?;
+        let name = &self.source[start..end];
+        if delimiter == '}' {
+            if let Some((field, spec)) = name.split_once(':')
+                && T::FIELDS.contains(&field.trim())
+            {
+                let index = self.field_index(field, start..start + field.len())?;
+                let spec = FormatSpec::parse::<T>(spec, start + field.len() + 1)?;
+                if !spec.supports::<T>(index) {
+                    return Err(TemplateParseError::UnsupportedFormat {
+                        name: T::FIELDS[index].to_string(),
+                        span: start..start + field.len(),
+                    });
+                }
+                return Ok(Placeholder::Element(TemplateElement::Formatted { index, spec }));
+            }
+            if let Some((namespace, var)) = name.split_once(':') {
+                return self.parse_namespaced(namespace, var, start).map(Placeholder::Element);
+            }
+            if let Some(elements) = self.expand_inline(name, start..start + name.len())? {
+                return Ok(Placeholder::Inline(elements));
+            }
+            if Expr::is_expression(name) && !T::FIELDS.contains(&name.trim()) {
+                return Ok(Placeholder::Element(TemplateElement::Expr(Expr::parse::<T>(
+                    name,
+                    start,
+                    self.options.max_depth(),
+                )?)));
+            }
+            let global = if self.options.trim_names() { name.trim() } else { name };
+            if !T::FIELDS.contains(&global) && self.options.accepts_global(global) {
+                return Ok(Placeholder::Element(TemplateElement::Namespaced {
+                    namespace: "global".to_string(),
+                    name: global.to_string(),
+                }));
+            }
+            return Ok(Placeholder::Element(TemplateElement::Var(
+                self.field_index(name, start..end)?,
+            )));
+        }
+        if delimiter == '|' {
+            let index = self.field_index(name.trim(), start..end)?;
+            let filters = self.parse_filters(open)?;
+            return Ok(Placeholder::Element(TemplateElement::Filtered { index, filters }));
+        }
+
+        let index = self.field_index(name.trim(), start..end)?;
+        let (kind_end, _) = self
+            .chars
+            .find(|&(_, c)| c == ',')
+            .ok_or(TemplateParseError::UnclosedBracket { offset: open })?;
+        let facet = match self.source[end + 1..kind_end].trim() {
+            "select" => None,
+            kind => match kind.strip_prefix("select-") {
+                Some(facet) if !facet.is_empty() => Some(facet.to_string()),
+                _ => {
+                    return Err(TemplateParseError::InvalidSelect {
+                        span: end + 1..kind_end,
+                    });
+                }
+            },
+        };
+
+        let mut arms = vec![];
+        loop {
+            self.skip_whitespace();
+            let Some(&(key_start, char)) = self.chars.peek() else {
+                return Err(TemplateParseError::UnclosedBracket { offset: open });
+            };
+            if char == '}' {
+                self.chars.next();
+                if !has_other_arm(&arms) {
+                    return Err(TemplateParseError::InvalidSelect {
+                        span: open..key_start + 1,
+                    });
+                }
+                return Ok(Placeholder::Element(TemplateElement::Select { index, facet, arms }));
+            }
+            let mut key_end = key_start;
+            while let Some((offset, _)) = self.chars.next_if(|&(_, c)| c != '{' && c != '}' && !c.is_whitespace()) {
+                key_end = offset + 1;
+            }
+            self.skip_whitespace();
+            let Some((branch_open, _)) = self.chars.next_if(|&(_, c)| c == '{') else {
+                return Err(TemplateParseError::InvalidSelect {
+                    span: key_start..key_end.max(key_start + 1),
+                });
+            };
+            if key_end == key_start {
+                return Err(TemplateParseError::InvalidSelect {
+                    span: key_start..key_start + 1,
+                });
+            }
+            let branch = self.parse_nested(Scope::Branch(branch_open))?;
+            arms.push((self.source[key_start..key_end].to_string(), branch));
+        }
+    }
+
+    /// The offset of the `?` of an inline conditional whose condition starts at `start`, or `None` if the placeholder
+    /// is not an inline conditional. A `?` after a `:` is part of a format spec, as in `{state:?}`.
+    fn find_ternary(&self, start: usize) -> Option<usize> {
+        let mut quoted = false;
+        for (i, char) in self.source[start..].char_indices() {
+            match char {
+                '"' => quoted = !quoted,
+                _ if quoted => {}
+                '?' => return Some(start + i),
+                '{' | '}' | ',' | '|' | ':' => return None,
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Parse an inline conditional opened at `open` whose condition ends at `question`, up to and including the closing
+    /// `}`.
+    fn parse_ternary(&mut self, open: usize, question: usize) -> Result<TemplateElement, TemplateParseError> {
+        let condition = Condition::parse::<T>(&self.source[open + 1..question], open + 1, self.options.max_depth())?;
+        while self.chars.next_if(|&(offset, _)| offset <= question).is_some() {}
+        let then = self.parse_quoted(open)?;
+        self.expect(open, ':')?;
+        let otherwise = self.parse_quoted(open)?;
+        self.expect(open, '}')?;
+        let text = |text: &str| match text.is_empty() {
+            true => vec![],
+            false => vec![TemplateElement::Text(text.to_string())],
+        };
+        Ok(TemplateElement::Conditional {
+            condition,
+            then: text(then),
+            otherwise: text(otherwise),
+        })
+    }
+
+    /// Parse a text in double quotes, after optional whitespace, in an inline conditional opened at `open`.
+    fn parse_quoted(&mut self, open: usize) -> Result<&'s str, TemplateParseError> {
+        self.expect(open, '"')?;
+        let start = self.offset();
+        let (end, _) = self
+            .chars
+            .find(|&(_, c)| c == '"')
+            .ok_or(TemplateParseError::UnclosedBracket { offset: open })?;
+        Ok(&self.source[start..end])
+    }
+
+    /// Consume `expected` after optional whitespace, in an inline conditional opened at `open`.
+    fn expect(&mut self, open: usize, expected: char) -> Result<(), TemplateParseError> {
+        self.skip_whitespace();
+        match self.chars.next() {
+            Some((_, char)) if char == expected => Ok(()),
+            Some((offset, char)) => Err(TemplateParseError::InvalidCondition {
+                span: offset..offset + char.len_utf8(),
+            }),
+            None => Err(TemplateParseError::UnclosedBracket { offset: open }),
+        }
+    }
+
+    /// Parse the filters of a placeholder opened at `open`, after the first `|`, up to and including the closing `}`.
+    fn parse_filters(&mut self, open: usize) -> Result<Vec<FilterCall>, TemplateParseError> {
+        let mut filters = vec![];
+        loop {
+            self.skip_whitespace();
+            let start = self.offset();
+            while self.chars.next_if(|&(_, c)| c.is_alphanumeric() || c == '_').is_some() {}
+            let end = self.offset();
+            if start == end {
+                return Err(self.invalid_filter(open, start));
+            }
+            let name = self.source[start..end].to_string();
+            if !self.options.accepts_filter(&name) {
+                return Err(TemplateParseError::UnknownFilter { name, span: start..end });
+            }
+
+            self.skip_whitespace();
+            let mut args = vec![];
+            if self.chars.next_if(|&(_, c)| c == '(').is_some() {
+                let args_start = self.offset();
+                let (close, char) = self
+                    .chars
+                    .find(|&(_, c)| c == ')' || c == '{' || c == '}')
+                    .ok_or(TemplateParseError::UnclosedBracket { offset: open })?;
+                if char != ')' {
+                    return Err(TemplateParseError::InvalidFilter { span: close..close + 1 });
+                }
+                let inner = self.source[args_start..close].trim();
+                if !inner.is_empty() {
+                    for arg in inner.split(',').map(str::trim) {
+                        if arg.is_empty() {
+                            return Err(TemplateParseError::InvalidFilter {
+                                span: args_start..close,
+                            });
+                        }
+                        let unquoted = arg.strip_prefix('"').and_then(|arg| arg.strip_suffix('"'));
+                        args.push(unquoted.unwrap_or(arg).to_string());
+                    }
+                }
+                self.skip_whitespace();
+            }
+            filters.push(FilterCall { name, args });
+
+            match self.chars.next() {
+                Some((_, '|')) => {}
+                Some((_, '}')) => return Ok(filters),
+                Some((offset, _)) => return Err(self.invalid_filter(open, offset)),
+                None => return Err(TemplateParseError::UnclosedBracket { offset: open }),
+            }
+        }
+    }
+
+    fn invalid_filter(&self, open: usize, offset: usize) -> TemplateParseError {
+        match self.source[offset..].chars().next() {
+            Some(char) => TemplateParseError::InvalidFilter {
+                span: offset..offset + char.len_utf8(),
+            },
+            None => TemplateParseError::UnclosedBracket { offset: open },
+        }
+    }
+
+    /// Parse the template of the inline field with the given name, if there is one, where `span` is the location of the
+    /// name in the source.
+    ///
+    /// Inline templates are part of the parameter type rather than of the source, so an invalid or recursive one is
+    /// reported as [`TemplateParseError::InvalidInlineField`] at the placeholder that uses it.
+    fn expand_inline(
+        &self,
+        name: &str,
+        span: Range<usize>,
+    ) -> Result<Option<Vec<TemplateElement>>, TemplateParseError> {
+        let name = if self.options.trim_names() { name.trim() } else { name };
+        let Some(&(name, source)) = T::INLINE_FIELDS.iter().find(|&&(inline, _)| inline == name) else {
+            return Ok(None);
+        };
+        let invalid = || TemplateParseError::InvalidInlineField {
+            name: name.to_string(),
+            span: span.clone(),
+        };
+        if self.inlining.contains(&name) {
+            return Err(invalid());
+        }
+        let options = ParseOptions::default();
+        let mut parser = Parser::<T>::new(source, &options);
+        parser.inlining = self.inlining.clone();
+        parser.inlining.push(name);
+        parser.depth = self.depth;
+        match parser.parse_sequence(Scope::Template) {
+            Ok(elements) => Ok(Some(elements)),
+            Err(_) => Err(invalid()),
+        }
+    }
+
+    /// Parse a `{namespace:name}` placeholder, where `start` is the offset of the namespace.
+    fn parse_namespaced(
+        &self,
+        namespace: &str,
+        name: &str,
+        start: usize,
+    ) -> Result<TemplateElement, TemplateParseError> {
+        let end = start + namespace.len() + 1 + name.len();
+        let namespace_start = start + namespace.len() - namespace.trim_start().len();
+        let (namespace, name) = (namespace.trim(), name.trim());
+        if !self.options.accepts_namespace(namespace) {
+            return Err(TemplateParseError::UnknownNamespace {
+                namespace: namespace.to_string(),
+                span: namespace_start..namespace_start + namespace.len(),
+            });
+        }
+        if name.is_empty() {
+            return Err(TemplateParseError::EmptyPlaceholder {
+                span: start - 1..end + 1,
+            });
+        }
+        Ok(TemplateElement::Namespaced {
+            namespace: namespace.to_string(),
+            name: name.to_string(),
+        })
+    }
+
+    /// Resolve the name of a placeholder at `span` to a field index, handling empty names according to the options.
+    fn field_index(&mut self, name: &str, span: Range<usize>) -> Result<usize, TemplateParseError> {
+        if !name.trim().is_empty() {
+            let name = if self.options.trim_names() { name.trim() } else { name };
+            return field_index::<T>(name, span);
+        }
+        let span = span.start - 1..span.end + 1;
+        match self.options.empty_placeholders() {
+            EmptyPlaceholders::Reject => Err(TemplateParseError::EmptyPlaceholder { span }),
+            EmptyPlaceholders::Positional => {
+                let index = self.position;
+                self.position += 1;
+                if index < T::FIELDS.len() {
+                    Ok(index)
+                } else {
+                    Err(TemplateParseError::EmptyPlaceholder { span })
+                }
+            }
+        }
+    }
+
+    /// The offset of the next character, or the length of the source at its end.
+    fn offset(&mut self) -> usize {
+        self.chars.peek().map_or(self.source.len(), |&(offset, _)| offset)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|&(_, c)| c.is_whitespace()).is_some() {}
+    }
+}
+
+/// An element that was not parsed from a source, such as a deserialized one, that the parser would have rejected.
+#[derive(Debug)]
+pub(crate) enum InvalidElement {
+    FieldOutOfBounds(usize),
+    UnknownNamespace(String),
+    UnknownFilter(String),
+    InvalidFormatSpec(usize),
+    UnsupportedFormat(usize),
+    InvalidSelect(usize),
+    InvalidBlock(String),
+}
+
+impl std::fmt::Display for InvalidElement {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::FieldOutOfBounds(index) => write!(f, "Field index out of bounds: {index}"),
+            Self::UnknownNamespace(namespace) => write!(f, "Unknown namespace: {namespace}"),
+            Self::UnknownFilter(name) => write!(f, "Unknown filter: {name}"),
+            Self::InvalidFormatSpec(index) => write!(f, "Invalid format spec for field index {index}"),
+            Self::UnsupportedFormat(index) => write!(f, "Unsupported format for field index {index}"),
+            Self::InvalidSelect(index) => write!(f, "Invalid select block for field index {index}"),
+            Self::InvalidBlock(name) => write!(f, "Invalid block name: {name}"),
+        }
+    }
+}
+
+/// Check elements that were not parsed from a source like the parser checks them: fields must exist in `T` and support
+/// their format specs, namespaces and filters must be accepted by the options, select blocks must have valid keys and an
+/// `other` branch, and block names must be valid and unique.
+pub(crate) fn validate_elements<T: TemplateStringParams>(
+    elements: &[TemplateElement],
+    options: &ParseOptions,
+) -> Result<(), InvalidElement> {
+    let mut max_index = None;
+    for element in elements {
+        element.visit_fields(&mut |index| max_index = max_index.max(Some(index)));
+    }
+    if let Some(index) = max_index
+        && index >= T::FIELDS.len()
+    {
+        return Err(InvalidElement::FieldOutOfBounds(index));
+    }
+    validate_placeholders::<T>(elements, options, &mut vec![])
+}
+
+fn validate_placeholders<'e, T: TemplateStringParams>(
+    elements: &'e [TemplateElement],
+    options: &ParseOptions,
+    blocks: &mut Vec<&'e str>,
+) -> Result<(), InvalidElement> {
+    for element in elements {
+        match element {
+            TemplateElement::Namespaced { namespace, .. } if !options.accepts_namespace(namespace) => {
+                return Err(InvalidElement::UnknownNamespace(namespace.clone()));
+            }
+            TemplateElement::Filtered { filters, .. } => {
+                if let Some(filter) = filters.iter().find(|filter| !options.accepts_filter(&filter.name)) {
+                    return Err(InvalidElement::UnknownFilter(filter.name.clone()));
+                }
+            }
+            TemplateElement::Formatted { index, spec } => {
+                if !spec.is_well_formed() {
+                    return Err(InvalidElement::InvalidFormatSpec(*index));
+                }
+                if !spec.supports::<T>(*index) {
+                    return Err(InvalidElement::UnsupportedFormat(*index));
+                }
+            }
+            TemplateElement::Select { index, facet, arms } => {
+                let is_key =
+                    |key: &str| !key.is_empty() && !key.contains(|c: char| c == '{' || c == '}' || c.is_whitespace());
+                let valid_facet = facet
+                    .as_deref()
+                    .is_none_or(|facet| is_key(facet) && !facet.contains(','));
+                if !valid_facet || !has_other_arm(arms) || !arms.iter().all(|(key, _)| is_key(key)) {
+                    return Err(InvalidElement::InvalidSelect(*index));
+                }
+                for (_, branch) in arms {
+                    validate_placeholders::<T>(branch, options, blocks)?;
+                }
+            }
+            TemplateElement::Conditional { then, otherwise, .. } => {
+                validate_placeholders::<T>(then, options, blocks)?;
+                validate_placeholders::<T>(otherwise, options, blocks)?;
+            }
+            TemplateElement::Block { name, elements } => {
+                if !is_block_name(name) || blocks.contains(&name.as_str()) {
+                    return Err(InvalidElement::InvalidBlock(name.clone()));
+                }
+                blocks.push(name);
+                validate_placeholders::<T>(elements, options, blocks)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Whether a name can be used in a `{%block name%}` tag.
+fn is_block_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+/// Whether a select block has the `other` branch that is rendered when no key matches.
+fn has_other_arm(arms: &[(String, Vec<TemplateElement>)]) -> bool {
+    arms.iter().any(|(key, _)| key == "other")
+}
+
+pub(crate) fn field_index<T: TemplateStringParams>(
+    name: &str,
+    span: std::ops::Range<usize>,
+) -> Result<usize, TemplateParseError> {
+    match T::FIELDS.iter().position(|&f| f == name) {
+        Some(index) => Ok(index),
+        None if is_field_name(name) => Err(TemplateParseError::UnknownField {
+            name: name.to_string(),
+            span,
+        }),
+        None => Err(TemplateParseError::InvalidFieldName {
+            name: name.to_string(),
+            span,
+        }),
+    }
+}
+
+/// Whether a placeholder name is a valid field name: a Unicode identifier (as in Rust, without the `r#` prefix of raw
+/// identifiers) or the index of a tuple struct field.
+pub(crate) fn is_field_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(char) if char.is_ascii_digit() => name.bytes().all(|byte| byte.is_ascii_digit()),
+        Some('_') => name.len() > 1 && chars.all(unicode_ident::is_xid_continue),
+        Some(char) => unicode_ident::is_xid_start(char) && chars.all(unicode_ident::is_xid_continue),
+        None => false,
+    }
+}
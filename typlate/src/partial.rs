@@ -0,0 +1,93 @@
+use crate::string::{Builder, TemplateElement};
+use crate::{FieldSet, TemplateString, TemplateStringParams};
+
+impl<T: TemplateStringParams> TemplateString<T> {
+    /// Substitute the placeholders of the fields in `fields` with their values in `params`, and keep every other
+    /// placeholder, producing a template to be formatted later with the remaining values.
+    ///
+    /// Values are rendered with their filters applied, and isolated if the template uses bidirectional isolation.
+    /// Arithmetic expressions are substituted once all of their fields are in `fields`. Select blocks and conditional
+    /// sections that only depend on fields in `fields` are replaced with the branch they would render. Fields without a
+    /// value (see [`TemplateStringParams::has_field`]) are kept as placeholders. Named blocks are kept, with their
+    /// content partially applied.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{FieldSet, TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Greeting {
+    ///     tenant: String,
+    ///     user: String,
+    /// }
+    ///
+    /// let template: TemplateString<Greeting> = "Welcome to {tenant|upper}, {user}!".parse().unwrap();
+    /// let params = Greeting { tenant: "acme".into(), user: String::new() };
+    /// let baked = template.partial_apply(&params, FieldSet::from_iter([0]));
+    /// assert_eq!(baked.to_string(), "Welcome to ACME, {user}!");
+    /// ```
+    pub fn partial_apply(&self, params: &T, fields: FieldSet) -> TemplateString<T> {
+        let mut builder = Builder::new();
+        self.partial_apply_into(&mut builder, self.elements(), params, &fields);
+        builder.finish().with_bidi_isolation(self.bidi_isolation())
+    }
+
+    fn partial_apply_into(
+        &self,
+        builder: &mut Builder<T>,
+        elements: &[TemplateElement],
+        params: &T,
+        fields: &FieldSet,
+    ) {
+        let bound = |index: usize| fields.contains(index) && params.has_field(index);
+        let branch = |elements: &[TemplateElement]| {
+            let mut builder = Builder::new();
+            self.partial_apply_into(&mut builder, elements, params, fields);
+            builder.into_elements()
+        };
+        for element in elements {
+            match element {
+                TemplateElement::Text(_) | TemplateElement::Namespaced { .. } => builder.push_element(element.clone()),
+                TemplateElement::Var(_)
+                | TemplateElement::Filtered { .. }
+                | TemplateElement::Formatted { .. }
+                | TemplateElement::Expr(_) => {
+                    let mut applied = true;
+                    element.visit_fields(&mut |index| applied &= bound(index));
+                    match applied {
+                        true => builder.push_str(&self.render_placeholder(element, params, self.bidi_isolation())),
+                        false => builder.push_element(element.clone()),
+                    }
+                }
+                TemplateElement::Select { index, facet, arms } => match bound(*index) {
+                    true => self.partial_apply_into(builder, element.nested(params), params, fields),
+                    false => builder.push_element(TemplateElement::Select {
+                        index: *index,
+                        facet: facet.clone(),
+                        arms: arms.iter().map(|(key, arm)| (key.clone(), branch(arm))).collect(),
+                    }),
+                },
+                TemplateElement::Conditional {
+                    condition,
+                    then,
+                    otherwise,
+                } => {
+                    let mut applied = true;
+                    condition.visit_fields(&mut |index| applied &= bound(index));
+                    match applied {
+                        true => self.partial_apply_into(builder, element.nested(params), params, fields),
+                        false => builder.push_element(TemplateElement::Conditional {
+                            condition: condition.clone(),
+                            then: branch(then),
+                            otherwise: branch(otherwise),
+                        }),
+                    }
+                }
+                TemplateElement::Block { name, elements } => builder.push_element(TemplateElement::Block {
+                    name: name.clone(),
+                    elements: branch(elements),
+                }),
+            }
+        }
+    }
+}
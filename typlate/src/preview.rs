@@ -0,0 +1,72 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::depth::render;
+use crate::string::Parameterized;
+use crate::{TemplateString, TemplateStringParams};
+
+impl<T: TemplateStringParams> TemplateString<T> {
+    /// Render the template with a sample value in place of every field, without needing parameter values.
+    ///
+    /// Each field renders as its name in angle brackets, such as `<name>`, and filters are applied to that sample
+    /// value. Select blocks take their `other` branch. This is meant for showing roughly what a configured template
+    /// will look like, e.g. in an admin UI.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Cart {
+    ///     name: String,
+    ///     count: u32,
+    /// }
+    ///
+    /// let template: TemplateString<Cart> =
+    ///     "{name|upper} has {count, select, 1 {one item} other {{count} items}}".parse().unwrap();
+    /// assert_eq!(template.preview(), "<NAME> has <count> items");
+    /// ```
+    pub fn preview(&self) -> String {
+        render(Parameterized {
+            params: &Sample::<T>(PhantomData),
+            elements: self.elements(),
+            isolate: self.bidi_isolation(),
+            ctx: None,
+        })
+    }
+}
+
+/// Parameters that render every field as its name in angle brackets.
+struct Sample<T>(PhantomData<T>);
+
+impl<T: TemplateStringParams> TemplateStringParams for Sample<T> {
+    const FIELDS: &'static [&'static str] = T::FIELDS;
+
+    fn fmt_field(&self, f: &mut fmt::Formatter, index: usize) -> fmt::Result {
+        write!(f, "<{}>", T::FIELDS[index])
+    }
+
+    fn len_hint(&self, index: usize) -> usize {
+        T::FIELDS[index].len() + 2
+    }
+
+    fn field_position(index: usize) -> usize {
+        T::field_position(index)
+    }
+
+    fn is_integer_field(index: usize) -> bool {
+        T::is_integer_field(index)
+    }
+
+    fn default_filters(index: usize) -> &'static [&'static str] {
+        T::default_filters(index)
+    }
+
+    fn default_value_filters() -> &'static [&'static str] {
+        T::default_value_filters()
+    }
+
+    fn is_debug_field(index: usize) -> bool {
+        T::is_debug_field(index)
+    }
+}
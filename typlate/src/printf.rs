@@ -0,0 +1,80 @@
+use crate::string::Builder;
+use crate::{TemplateParseError, TemplateString, TemplateStringParams};
+
+impl<T: TemplateStringParams> TemplateString<T> {
+    /// Parse a template written in C printf syntax, mapping each conversion onto the field at the same position in
+    /// `names`, e.g. to migrate a C-era message catalog.
+    ///
+    /// `%%` is a literal percent sign. Conversions may have flags, a width, a precision and a length modifier, which
+    /// are ignored, and may select their argument explicitly with POSIX `%N$` syntax (1-based). Conversions that
+    /// cannot be mapped to a name, such as `%n`, a `*` width, or a position beyond `names`, are rejected.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Data {
+    ///     name: String,
+    ///     count: u32,
+    /// }
+    ///
+    /// let template = TemplateString::<Data>::parse_printf("%s bought %3d items (100%%)", &["name", "count"]).unwrap();
+    /// assert_eq!(template.to_string(), "{name} bought {count} items (100%)");
+    ///
+    /// let template = TemplateString::<Data>::parse_printf("%2$lu items for %1$s", &["name", "count"]).unwrap();
+    /// assert_eq!(template.to_string(), "{count} items for {name}");
+    /// ```
+    pub fn parse_printf(input: &str, names: &[&str]) -> Result<Self, TemplateParseError> {
+        let mut builder = Builder::<T>::new();
+        let mut chars = input.char_indices().peekable();
+        let mut next = 0;
+        while let Some((offset, char)) = chars.next() {
+            if char != '%' {
+                builder.push_char(char);
+                continue;
+            }
+            if chars.next_if(|&(_, c)| c == '%').is_some() {
+                builder.push_char('%');
+                continue;
+            }
+            let unsupported = |end: usize| TemplateParseError::UnsupportedConversion {
+                conversion: input[offset..end].to_string(),
+                span: offset..end,
+            };
+
+            // Everything up to the conversion character: position, flags, width, precision and length modifier.
+            let mut spec = String::new();
+            let conversion = loop {
+                match chars.next() {
+                    Some((_, c @ ('0'..='9' | '$' | '-' | '+' | ' ' | '#' | '\'' | '.' | '*'))) => spec.push(c),
+                    Some((_, c @ ('h' | 'l' | 'L' | 'q' | 'j' | 'z' | 't'))) => spec.push(c),
+                    Some((i, c)) => break Some((i, c)),
+                    None => break None,
+                }
+            };
+            let Some((i, conversion)) = conversion else {
+                return Err(unsupported(input.len()));
+            };
+            let end = i + conversion.len_utf8();
+            if !"diouxXeEfFgGaAcsp".contains(conversion) || spec.contains('*') {
+                return Err(unsupported(end));
+            }
+            let index = match spec.split_once('$') {
+                Some((position, _)) => match position.parse::<usize>() {
+                    Ok(position) if position > 0 => position - 1,
+                    _ => return Err(unsupported(end)),
+                },
+                None => {
+                    next += 1;
+                    next - 1
+                }
+            };
+            let Some(name) = names.get(index) else {
+                return Err(unsupported(end));
+            };
+            builder.push_var(name, offset..end)?;
+        }
+        Ok(builder.finish())
+    }
+}
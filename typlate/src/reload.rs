@@ -0,0 +1,81 @@
+use std::fmt;
+use std::sync::{Arc, PoisonError, RwLock};
+
+use crate::{TemplateParseError, TemplateString, TemplateStringParams};
+
+/// A shared handle to a template that can be replaced while it is in use, e.g. when the configuration of a
+/// long-lived service changes.
+///
+/// Clones of the handle share the same template. Replacing it is atomic: a render uses either the old or the new
+/// template, and renders that are in progress keep using the template they started with.
+///
+/// ## Examples
+///
+/// ```
+/// # use typlate::{ReloadableTemplate, TemplateParams};
+/// #[derive(TemplateParams)]
+/// struct User {
+///     name: String,
+/// }
+///
+/// let template = ReloadableTemplate::<User>::new("Hello {name}".parse().unwrap());
+/// let handle = template.clone();
+/// let user = User { name: "Alice".into() };
+///
+/// handle.update("Welcome back, {name}").unwrap();
+/// assert_eq!(template.format(&user), "Welcome back, Alice");
+///
+/// assert!(handle.update("Hi {nmae}").is_err());
+/// assert_eq!(template.format(&user), "Welcome back, Alice");
+/// ```
+pub struct ReloadableTemplate<T> {
+    current: Arc<RwLock<Arc<TemplateString<T>>>>,
+}
+
+impl<T> ReloadableTemplate<T> {
+    /// Create a handle holding the given template.
+    pub fn new(template: TemplateString<T>) -> Self {
+        Self {
+            current: Arc::new(RwLock::new(Arc::new(template))),
+        }
+    }
+
+    /// The current template. It stays valid after the handle is updated.
+    pub fn load(&self) -> Arc<TemplateString<T>> {
+        self.current.read().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// Replace the template, returning the previous one.
+    pub fn store(&self, template: TemplateString<T>) -> Arc<TemplateString<T>> {
+        let mut current = self.current.write().unwrap_or_else(PoisonError::into_inner);
+        std::mem::replace(&mut *current, Arc::new(template))
+    }
+}
+
+impl<T: TemplateStringParams> ReloadableTemplate<T> {
+    /// Parse a new template source and replace the template with it. If the source fails to parse, the current
+    /// template is kept.
+    pub fn update(&self, source: &str) -> Result<(), TemplateParseError> {
+        self.store(source.parse()?);
+        Ok(())
+    }
+
+    /// Format the current template with the provided parameter values.
+    pub fn format(&self, params: &T) -> String {
+        self.load().format(params)
+    }
+}
+
+impl<T> Clone for ReloadableTemplate<T> {
+    fn clone(&self) -> Self {
+        Self {
+            current: self.current.clone(),
+        }
+    }
+}
+
+impl<T: TemplateStringParams> fmt::Debug for ReloadableTemplate<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("ReloadableTemplate").field(&*self.load()).finish()
+    }
+}
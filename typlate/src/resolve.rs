@@ -0,0 +1,187 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::time::SystemTime;
+
+use crate::depth::render;
+use crate::expr::parse_number;
+use crate::string::{Parameterized, TemplateElement};
+use crate::{Number, TemplateString, TemplateStringParams};
+
+/// A trait for parameters whose field values may need to be resolved asynchronously, e.g. by a database lookup.
+///
+/// Used by [`TemplateString::format_async`], which only resolves the fields that the rendered output actually uses.
+/// Fields for which [`resolve_field`](AsyncTemplateParams::resolve_field) returns `None` are rendered with
+/// [`TemplateStringParams::fmt_field`] as usual.
+///
+/// ## Examples
+///
+/// ```
+/// # use std::task::{Context, Poll, Waker};
+/// # use typlate::{AsyncTemplateParams, TemplateParams, TemplateString, TemplateStringParams};
+/// #[derive(TemplateParams)]
+/// struct Notification {
+///     user_id: u32,
+///     user_name: Option<String>,
+/// }
+///
+/// impl AsyncTemplateParams for Notification {
+///     async fn resolve_field(&self, index: usize) -> Option<String> {
+///         match Self::FIELDS[index] {
+///             "user_name" if self.user_name.is_none() => Some(fetch_user_name(self.user_id).await),
+///             _ => None,
+///         }
+///     }
+/// }
+///
+/// async fn fetch_user_name(id: u32) -> String {
+///     format!("user #{id}")
+/// }
+///
+/// let template: TemplateString<Notification> = "Hello {user_name}".parse().unwrap();
+/// let notification = Notification { user_id: 7, user_name: None };
+/// # fn block_on<F: Future>(future: F) -> F::Output {
+/// #     let Poll::Ready(output) = std::pin::pin!(future).poll(&mut Context::from_waker(Waker::noop())) else {
+/// #         unreachable!()
+/// #     };
+/// #     output
+/// # }
+/// let output = block_on(template.format_async(&notification));
+/// assert_eq!(output, "Hello user #7");
+/// ```
+pub trait AsyncTemplateParams: TemplateStringParams {
+    /// Resolve the value of the field at the given index, or return `None` to render it synchronously.
+    fn resolve_field(&self, index: usize) -> impl Future<Output = Option<String>> + Send;
+}
+
+impl<T: AsyncTemplateParams> TemplateString<T> {
+    /// Format the template, resolving field values through [`AsyncTemplateParams::resolve_field`].
+    ///
+    /// Each field is resolved at most once per render, and fields that are only used in branches of select blocks
+    /// that are not taken are not resolved at all.
+    pub async fn format_async(&self, params: &T) -> String {
+        let mut resolved = Resolved {
+            params,
+            values: vec![None; T::FIELDS.len()],
+        };
+        let mut visited = vec![false; T::FIELDS.len()];
+        let mut stack = vec![self.elements().iter()];
+        while let Some(elements) = stack.last_mut() {
+            let Some(element) = elements.next() else {
+                stack.pop();
+                continue;
+            };
+            let mut fields = vec![];
+            match element {
+                TemplateElement::Text(_) | TemplateElement::Namespaced { .. } => continue,
+                TemplateElement::Block { elements, .. } => {
+                    stack.push(elements.iter());
+                    continue;
+                }
+                TemplateElement::Conditional { condition, .. } => {
+                    condition.visit_fields(&mut |index| fields.push(index))
+                }
+                TemplateElement::Expr(expr) => expr.visit_fields(&mut |index| fields.push(index)),
+                TemplateElement::Formatted { index, spec } => {
+                    fields.push(*index);
+                    spec.visit_fields(&mut |index| fields.push(index));
+                }
+                TemplateElement::Var(index)
+                | TemplateElement::Filtered { index, .. }
+                | TemplateElement::Select { index, .. } => fields.push(*index),
+            }
+            for index in fields {
+                if !std::mem::replace(&mut visited[index], true) {
+                    resolved.values[index] = params.resolve_field(index).await;
+                }
+            }
+            if let TemplateElement::Select { .. } | TemplateElement::Conditional { .. } = element {
+                stack.push(element.nested(&resolved).iter());
+            }
+        }
+
+        render(Parameterized {
+            params: &resolved,
+            elements: self.elements(),
+            isolate: self.bidi_isolation(),
+            ctx: None,
+        })
+    }
+}
+
+/// Parameters with some field values replaced by resolved values.
+struct Resolved<'i, T> {
+    params: &'i T,
+    values: Vec<Option<String>>,
+}
+
+impl<T: TemplateStringParams> TemplateStringParams for Resolved<'_, T> {
+    const FIELDS: &'static [&'static str] = T::FIELDS;
+
+    fn fmt_field(&self, f: &mut fmt::Formatter, index: usize) -> fmt::Result {
+        match &self.values[index] {
+            Some(value) => f.write_str(value),
+            None => self.params.fmt_field(f, index),
+        }
+    }
+
+    fn has_field(&self, index: usize) -> bool {
+        self.values[index].is_some() || self.params.has_field(index)
+    }
+
+    fn field_facet(&self, index: usize, facet: &str) -> Option<Cow<'_, str>> {
+        self.params.field_facet(index, facet)
+    }
+
+    fn field_number(&self, index: usize) -> Option<Number> {
+        match &self.values[index] {
+            Some(value) => parse_number(value.trim()).or_else(|| self.params.field_number(index)),
+            None => self.params.field_number(index),
+        }
+    }
+
+    fn len_hint(&self, index: usize) -> usize {
+        match &self.values[index] {
+            Some(value) => value.len(),
+            None => self.params.len_hint(index),
+        }
+    }
+
+    fn field_position(index: usize) -> usize {
+        T::field_position(index)
+    }
+
+    fn is_integer_field(index: usize) -> bool {
+        T::is_integer_field(index)
+    }
+
+    fn field_time(&self, index: usize) -> Option<SystemTime> {
+        self.values[index].is_none().then(|| self.params.field_time(index))?
+    }
+
+    fn field_bytes(&self, index: usize) -> Option<Cow<'_, [u8]>> {
+        self.values[index].is_none().then(|| self.params.field_bytes(index))?
+    }
+
+    fn default_filters(index: usize) -> &'static [&'static str] {
+        T::default_filters(index)
+    }
+
+    fn default_value_filters() -> &'static [&'static str] {
+        T::default_value_filters()
+    }
+
+    fn is_debug_field(index: usize) -> bool {
+        T::is_debug_field(index)
+    }
+
+    fn fmt_field_debug(&self, f: &mut fmt::Formatter, index: usize) -> fmt::Result {
+        match &self.values[index] {
+            Some(value) => fmt::Debug::fmt(value, f),
+            None => self.params.fmt_field_debug(f, index),
+        }
+    }
+
+    fn field_items(&self, index: usize) -> Option<Vec<String>> {
+        self.values[index].is_none().then(|| self.params.field_items(index))?
+    }
+}
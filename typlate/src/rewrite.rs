@@ -0,0 +1,89 @@
+use crate::string::TemplateElement;
+use crate::{TemplateParseError, TemplateString, TemplateStringParams};
+
+impl<T: TemplateStringParams> TemplateString<T> {
+    /// Return a copy of the template with every placeholder of the field `old` referring to the field `new` instead,
+    /// including in filters, select blocks, expressions and conditions.
+    ///
+    /// Fails with [`TemplateParseError::UnknownField`] if the template uses `old` and `new` is not a field of `T`. To
+    /// migrate a template to another parameter type, use [`revalidate_with`](Self::revalidate_with).
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct User {
+    ///     name: String,
+    ///     display_name: String,
+    /// }
+    ///
+    /// let template: TemplateString<User> = "Hi {name|upper}, {{name}}".parse().unwrap();
+    /// let renamed = template.rename_placeholder("name", "display_name").unwrap();
+    /// assert_eq!(renamed.to_string(), "Hi {display_name|upper}, {{name}}");
+    /// ```
+    pub fn rename_placeholder(&self, old: &str, new: &str) -> Result<Self, TemplateParseError> {
+        self.revalidate_with::<T>(|name| if name == old { new } else { name })
+            .map_err(|mut errors| errors.remove(0))
+    }
+
+    /// Return a copy of the template with every occurrence of `pattern` in its literal text replaced by `replacement`.
+    ///
+    /// Placeholders and escaped brackets are never affected, and the text of every branch of select blocks and
+    /// conditional sections is replaced as well.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Order {
+    ///     order: u32,
+    /// }
+    ///
+    /// let template: TemplateString<Order> = "{{order}} #{order}: order shipped".parse().unwrap();
+    /// let replaced = template.replace_literal("order", "parcel");
+    /// assert_eq!(replaced.to_string(), "{{parcel}} #{order}: parcel shipped");
+    /// ```
+    pub fn replace_literal(&self, pattern: &str, replacement: &str) -> Self {
+        fn replace(elements: &[TemplateElement], pattern: &str, replacement: &str) -> Vec<TemplateElement> {
+            elements
+                .iter()
+                .filter_map(|element| match element {
+                    TemplateElement::Text(text) => {
+                        let text = text.replace(pattern, replacement);
+                        (!text.is_empty()).then_some(TemplateElement::Text(text))
+                    }
+                    TemplateElement::Select { index, facet, arms } => Some(TemplateElement::Select {
+                        index: *index,
+                        facet: facet.clone(),
+                        arms: arms
+                            .iter()
+                            .map(|(key, branch)| (key.clone(), replace(branch, pattern, replacement)))
+                            .collect(),
+                    }),
+                    TemplateElement::Conditional {
+                        condition,
+                        then,
+                        otherwise,
+                    } => Some(TemplateElement::Conditional {
+                        condition: condition.clone(),
+                        then: replace(then, pattern, replacement),
+                        otherwise: replace(otherwise, pattern, replacement),
+                    }),
+                    TemplateElement::Block { name, elements } => Some(TemplateElement::Block {
+                        name: name.clone(),
+                        elements: replace(elements, pattern, replacement),
+                    }),
+                    element => Some(element.clone()),
+                })
+                .collect()
+        }
+
+        if pattern.is_empty() {
+            return self.clone();
+        }
+        TemplateString::from_elements(replace(self.elements(), pattern, replacement))
+            .with_bidi_isolation(self.bidi_isolation())
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{TemplateParseError, TemplateString, TemplateStringParams};
+
+/// A snapshot of the field names of a parameter type, as returned by [`TemplateString::schema`].
+///
+/// Storing the schema alongside a template (e.g. in a database) makes it possible to tell whether the template was
+/// written against an older version of the parameter type. With the `serde` feature enabled, it serializes as the list
+/// of field names.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct SchemaVersion {
+    fields: Vec<String>,
+}
+
+impl SchemaVersion {
+    /// The schema of the parameter type `T`.
+    pub fn of<T: TemplateStringParams>() -> Self {
+        Self {
+            fields: T::FIELDS.iter().map(|&name| name.to_string()).collect(),
+        }
+    }
+
+    /// The field names, in declaration order.
+    pub fn fields(&self) -> impl Iterator<Item = &str> {
+        self.fields.iter().map(String::as_str)
+    }
+
+    /// Whether the schema has the same fields, in the same order, as the parameter type `T`.
+    pub fn matches<T: TemplateStringParams>(&self) -> bool {
+        self.fields.iter().eq(T::FIELDS)
+    }
+
+    /// The fields of this schema that the parameter type `T` no longer has.
+    pub fn removed_fields<T: TemplateStringParams>(&self) -> Vec<&str> {
+        self.fields().filter(|name| !T::FIELDS.contains(name)).collect()
+    }
+}
+
+impl<T: TemplateStringParams> TemplateString<T> {
+    /// The schema that the template was validated against, i.e. the field names of `T`.
+    pub fn schema(&self) -> SchemaVersion {
+        SchemaVersion::of::<T>()
+    }
+
+    /// Validate the template against another parameter type, e.g. a newer version of `T`, by field name.
+    ///
+    /// Every placeholder whose field does not exist in `U` is reported as a
+    /// [`TemplateParseError::UnknownField`], with a span in the source of the template as returned by
+    /// [`to_string`](ToString::to_string).
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{TemplateParams, TemplateParseError, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct UserV1 {
+    ///     name: String,
+    ///     email: String,
+    /// }
+    ///
+    /// #[derive(TemplateParams)]
+    /// struct UserV2 {
+    ///     name: String,
+    /// }
+    ///
+    /// let template: TemplateString<UserV1> = "{name} <{email}>".parse().unwrap();
+    /// let errors = template.revalidate::<UserV2>().unwrap_err();
+    /// assert_eq!(errors, [TemplateParseError::UnknownField { name: "email".into(), span: 9..14 }]);
+    /// ```
+    pub fn revalidate<U: TemplateStringParams>(&self) -> Result<TemplateString<U>, Vec<TemplateParseError>> {
+        self.revalidate_with(|name| name)
+    }
+
+    /// Like [`revalidate`](Self::revalidate), but first passes each field name through a migration hook, which
+    /// returns the new name of a renamed field and the name itself otherwise.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct UserV1 {
+    ///     name: String,
+    /// }
+    ///
+    /// #[derive(TemplateParams)]
+    /// struct UserV2 {
+    ///     display_name: String,
+    /// }
+    ///
+    /// let template: TemplateString<UserV1> = "Hi {name}".parse().unwrap();
+    /// let migrated = template
+    ///     .revalidate_with::<UserV2>(|name| if name == "name" { "display_name" } else { name })
+    ///     .unwrap();
+    /// assert_eq!(migrated.to_string(), "Hi {display_name}");
+    /// ```
+    pub fn revalidate_with<'a, U: TemplateStringParams>(
+        &self,
+        migrate: impl Fn(&'static str) -> &'a str,
+    ) -> Result<TemplateString<U>, Vec<TemplateParseError>> {
+        let fields: Vec<_> = T::FIELDS.iter().map(|&name| migrate(name)).collect();
+        let mut source = String::new();
+        for element in self.elements() {
+            element.write_source(&mut source, &fields).unwrap();
+        }
+        TemplateString::parse_all_errors(&source).map(|template| template.with_bidi_isolation(self.bidi_isolation()))
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{RenderCtx, TemplateParseError, TemplateString, TemplateStringParams};
+
+/// A problem found while parsing a [`TemplateSet`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateSetError {
+    /// A line with content precedes the first `[name]` header. Lines are numbered from 1.
+    MissingHeader { line: usize },
+    /// Two templates have the same name.
+    DuplicateName { name: String },
+    /// The template with the given name failed to parse. Offsets in the error are relative to the template body.
+    Parse { name: String, error: TemplateParseError },
+}
+
+impl fmt::Display for TemplateSetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MissingHeader { line } => write!(f, "Line {line}: Expected a [name] header"),
+            Self::DuplicateName { name } => write!(f, "Duplicate template name: {name}"),
+            Self::Parse { name, error } => write!(f, "{name}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for TemplateSetError {}
+
+/// Named templates for the same parameter type, parsed from a single source string.
+///
+/// Every template starts with a line of the form `[name] body`. Lines up to the next header continue the body of the
+/// template, joined with newlines, and empty lines at the end of a body are dropped. Names consist of letters, digits,
+/// `_`, `-` and `.`.
+///
+/// ## Examples
+///
+/// ```
+/// # use typlate::{TemplateParams, TemplateSet};
+/// #[derive(TemplateParams)]
+/// struct User {
+///     name: &'static str,
+/// }
+///
+/// let set: TemplateSet<User> = "[greeting] Hello {name}\n[farewell] Bye {name}\nSee you soon!".parse().unwrap();
+/// let user = User { name: "Alice" };
+/// assert_eq!(set.format("greeting", &user).unwrap(), "Hello Alice");
+/// assert_eq!(set.format("farewell", &user).unwrap(), "Bye Alice\nSee you soon!");
+/// assert_eq!(set.format("welcome", &user), None);
+/// ```
+pub struct TemplateSet<T> {
+    templates: BTreeMap<String, TemplateString<T>>,
+}
+
+impl<T> TemplateSet<T> {
+    pub(crate) fn from_templates(templates: BTreeMap<String, TemplateString<T>>) -> Self {
+        Self { templates }
+    }
+
+    pub(crate) fn into_templates(self) -> impl Iterator<Item = (String, TemplateString<T>)> {
+        self.templates.into_iter()
+    }
+
+    /// The template with the given name.
+    pub fn get(&self, name: &str) -> Option<&TemplateString<T>> {
+        self.templates.get(name)
+    }
+
+    /// Iterate over the template names, in sorted order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.templates.keys().map(String::as_str)
+    }
+
+    /// Iterate over the templates and their names, in sorted order of names.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &TemplateString<T>)> {
+        self.templates.iter().map(|(name, template)| (name.as_str(), template))
+    }
+
+    /// The number of templates in the set.
+    pub fn len(&self) -> usize {
+        self.templates.len()
+    }
+
+    /// Whether the set contains no templates.
+    pub fn is_empty(&self) -> bool {
+        self.templates.is_empty()
+    }
+}
+
+impl<T: TemplateStringParams> TemplateSet<T> {
+    /// Format the template with the given name, or return `None` if there is no such template.
+    pub fn format(&self, name: &str, params: &T) -> Option<String> {
+        Some(self.get(name)?.format(params))
+    }
+
+    /// Format the template with the given name with the settings of a render context, or return `None` if there is
+    /// no such template.
+    ///
+    /// The observer of the context, if any, receives the name of the template.
+    pub fn format_with_ctx(&self, name: &str, params: &T, ctx: &RenderCtx) -> Option<String> {
+        Some(self.get(name)?.format_observed(params, ctx, Some(name)))
+    }
+}
+
+impl<T: TemplateStringParams> FromStr for TemplateSet<T> {
+    type Err = TemplateSetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut sources: Vec<(&str, String)> = vec![];
+        for (i, line) in s.lines().enumerate() {
+            match parse_header(line) {
+                Some((name, body)) => sources.push((name, body.to_string())),
+                None => match sources.last_mut() {
+                    Some((_, source)) => {
+                        source.push('\n');
+                        source.push_str(line);
+                    }
+                    None if line.trim().is_empty() => {}
+                    None => return Err(TemplateSetError::MissingHeader { line: i + 1 }),
+                },
+            }
+        }
+
+        let mut templates = BTreeMap::new();
+        for (name, mut source) in sources {
+            source.truncate(source.trim_end_matches('\n').len());
+            let template = source.parse().map_err(|error| TemplateSetError::Parse {
+                name: name.to_string(),
+                error,
+            })?;
+            if templates.insert(name.to_string(), template).is_some() {
+                return Err(TemplateSetError::DuplicateName { name: name.to_string() });
+            }
+        }
+        Ok(Self { templates })
+    }
+}
+
+/// Split a `[name] body` header line into the name and the body, or return `None` if the line is not a header.
+fn parse_header(line: &str) -> Option<(&str, &str)> {
+    let (name, body) = line.strip_prefix('[')?.split_once(']')?;
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    valid.then(|| (name, body.strip_prefix(' ').unwrap_or(body)))
+}
+
+impl<T> Clone for TemplateSet<T> {
+    fn clone(&self) -> Self {
+        Self {
+            templates: self.templates.clone(),
+        }
+    }
+}
+
+impl<T: TemplateStringParams> fmt::Debug for TemplateSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
@@ -0,0 +1,58 @@
+use crate::string::Segment;
+use crate::{TemplateString, TemplateStringParams};
+
+/// The bind markers written in place of placeholders by [`TemplateString::format_query`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BindStyle {
+    /// Numbered markers `$1`, `$2`, ..., as used by PostgreSQL.
+    Numbered,
+    /// Anonymous `?` markers, as used by MySQL and SQLite.
+    Question,
+}
+
+impl<T: TemplateStringParams> TemplateString<T> {
+    /// Render the template as a parameterized query: the literal text is kept, every placeholder is replaced with a
+    /// bind marker, and the values of the placeholders are returned separately, in the order of their markers.
+    ///
+    /// Values are rendered as strings, with their filters applied and without bidirectional isolation. Select blocks
+    /// are resolved as usual, and only the placeholders of the chosen branch are bound. A field referenced several
+    /// times is bound once per placeholder.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{BindStyle, TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Filter {
+    ///     name: String,
+    ///     limit: u32,
+    /// }
+    ///
+    /// let template: TemplateString<Filter> =
+    ///     "SELECT * FROM users WHERE name = {name} LIMIT {limit}".parse().unwrap();
+    /// let filter = Filter { name: "Robert'); DROP TABLE users;--".into(), limit: 10 };
+    /// let (sql, values) = template.format_query(&filter, BindStyle::Numbered);
+    /// assert_eq!(sql, "SELECT * FROM users WHERE name = $1 LIMIT $2");
+    /// assert_eq!(values, ["Robert'); DROP TABLE users;--", "10"]);
+    /// ```
+    pub fn format_query(&self, params: &T, style: BindStyle) -> (String, Vec<String>) {
+        let mut sql = String::new();
+        let mut values = vec![];
+        for segment in self.segments(params) {
+            match segment {
+                Segment::Text(text) => sql.push_str(text),
+                Segment::Placeholder(element) => {
+                    values.push(self.render_placeholder(element, params, false));
+                    match style {
+                        BindStyle::Numbered => {
+                            sql.push('$');
+                            sql.push_str(&values.len().to_string());
+                        }
+                        BindStyle::Question => sql.push('?'),
+                    }
+                }
+            }
+        }
+        (sql, values)
+    }
+}
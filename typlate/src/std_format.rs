@@ -0,0 +1,155 @@
+use std::marker::PhantomData;
+
+use crate::ctx::FieldDisplay;
+use crate::depth::render;
+use crate::string::{Builder, TemplateElement};
+use crate::{TemplateParseError, TemplateString, TemplateStringParams};
+
+/// A template converted into the syntax of [`format!`], as returned by [`TemplateString::to_format_string`].
+///
+/// The literal uses implicit positional arguments (`{}`), which are provided by [`FormatString::args`] in order.
+pub struct FormatString<T> {
+    literal: String,
+    indices: Vec<usize>,
+    phantom: PhantomData<T>,
+}
+
+impl<T: TemplateStringParams> FormatString<T> {
+    /// The format string literal, e.g. `Hello {}!`.
+    pub fn literal(&self) -> &str {
+        &self.literal
+    }
+
+    /// The names of the fields passed as arguments, in order.
+    pub fn fields(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.indices.iter().map(|&index| T::FIELDS[index])
+    }
+
+    /// The rendered values of the fields passed as arguments, in order.
+    pub fn args(&self, params: &T) -> Vec<String> {
+        self.indices
+            .iter()
+            .map(|&index| render(FieldDisplay(None, params, index)))
+            .collect()
+    }
+}
+
+impl<T: TemplateStringParams> TemplateString<T> {
+    /// Convert the template into a [`format!`]-compatible literal with positional arguments, e.g. for generating Rust
+    /// code.
+    ///
+    /// Blocks are inlined. Returns `None` if the template uses filters or select blocks, which have no equivalent in
+    /// the standard format syntax.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Data {
+    ///     name: String,
+    ///     count: u32,
+    /// }
+    ///
+    /// let template: TemplateString<Data> = "{{{name}}} has {count} items".parse().unwrap();
+    /// let format = template.to_format_string().unwrap();
+    /// assert_eq!(format.literal(), "{{{}}} has {} items");
+    /// assert_eq!(format.fields().collect::<Vec<_>>(), ["name", "count"]);
+    /// assert_eq!(format.args(&Data { name: "Alice".into(), count: 3 }), ["Alice", "3"]);
+    /// ```
+    pub fn to_format_string(&self) -> Option<FormatString<T>> {
+        fn convert(elements: &[TemplateElement], literal: &mut String, indices: &mut Vec<usize>) -> Option<()> {
+            for element in elements {
+                match element {
+                    TemplateElement::Text(_) => element.write_source(literal, &[]).unwrap(),
+                    TemplateElement::Var(index) => {
+                        literal.push_str("{}");
+                        indices.push(*index);
+                    }
+                    TemplateElement::Block { elements, .. } => convert(elements, literal, indices)?,
+                    TemplateElement::Filtered { .. }
+                    | TemplateElement::Formatted { .. }
+                    | TemplateElement::Expr(_)
+                    | TemplateElement::Namespaced { .. }
+                    | TemplateElement::Select { .. }
+                    | TemplateElement::Conditional { .. } => return None,
+                }
+            }
+            Some(())
+        }
+
+        let mut literal = String::new();
+        let mut indices = vec![];
+        convert(self.elements(), &mut literal, &mut indices)?;
+        Some(FormatString {
+            literal,
+            indices,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Parse a template written in the syntax of [`format!`], e.g. to migrate hand-written `format!` calls.
+    ///
+    /// Arguments can be named (`{name}`), explicitly positional (`{0}`, referring to the fields in declaration order)
+    /// or implicitly positional (`{}`). Format specs such as `{count:>4}` or `{name:?}` are ignored.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Data {
+    ///     name: String,
+    ///     count: u32,
+    /// }
+    ///
+    /// let template = TemplateString::<Data>::from_format_string("{1:>3} items for {name:?}, {{ok}}").unwrap();
+    /// assert_eq!(template.to_string(), "{count} items for {name}, {{ok}}");
+    /// ```
+    pub fn from_format_string(input: &str) -> Result<Self, TemplateParseError> {
+        let mut builder = Builder::<T>::new();
+        let mut chars = input.char_indices().peekable();
+        let mut next = 0;
+        while let Some((offset, char)) = chars.next() {
+            match char {
+                '{' if chars.next_if(|&(_, c)| c == '{').is_some() => builder.push_char('{'),
+                '}' if chars.next_if(|&(_, c)| c == '}').is_some() => builder.push_char('}'),
+                '}' => return Err(TemplateParseError::UnmatchedClosingBracket { offset }),
+                '{' => {
+                    let Some((close, _)) = chars.by_ref().find(|&(_, c)| c == '}') else {
+                        return Err(TemplateParseError::UnclosedBracket { offset });
+                    };
+                    let end = input[offset + 1..close]
+                        .find(':')
+                        .map_or(close, |colon| offset + 1 + colon);
+                    let name = input[offset + 1..end].trim();
+                    let index = match name.parse::<usize>() {
+                        Err(_) if !name.is_empty() => {
+                            builder.push_var(name, offset + 1..end)?;
+                            continue;
+                        }
+                        Ok(index) if index < T::FIELDS.len() => index,
+                        Ok(_) => {
+                            return Err(TemplateParseError::UnknownField {
+                                name: name.to_string(),
+                                span: offset + 1..end,
+                            });
+                        }
+                        Err(_) => {
+                            next += 1;
+                            if next > T::FIELDS.len() {
+                                return Err(TemplateParseError::EmptyPlaceholder {
+                                    span: offset..close + 1,
+                                });
+                            }
+                            next - 1
+                        }
+                    };
+                    builder.push_element(TemplateElement::Var(index));
+                }
+                _ => builder.push_char(char),
+            }
+        }
+        Ok(builder.finish())
+    }
+}
@@ -1,28 +1,564 @@
 #![doc = include_str!("../README.md")]
 
+use std::borrow::Cow;
 use std::fmt::{self, Display, Write};
 use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
+use std::ops::Range;
 use std::str::FromStr;
+use std::time::SystemTime;
 
-use crate::TemplateParams;
+use crate::cond::Condition;
+use crate::ctx::{
+    FieldDisplay, is_builtin_namespace, resolve_builtin, write_formatted, write_namespaced, write_number, write_value,
+};
+use crate::depth::render;
+use crate::expr::Expr;
+use crate::filter::{FilterCall, is_builtin};
+use crate::format_spec::FormatSpec;
+use crate::observer::Stopwatch;
+use crate::parse::Parser;
+use crate::{FieldSet, Number, ParseOptions, RenderCtx, RenderError, RenderEvent, TemplateParams, TemplateParseError};
 
 /// A trait for types that can provide template parameters.
 ///
 /// This trait is typically implemented using the `#[derive(TemplateParams)]` macro.
 /// It provides the field names and values that can be used in templates.
+///
+/// It is also implemented for references and smart pointers (`&T`, `Box<T>`, `Rc<T>`, `Arc<T>` and `Cow<'_, T>`), which
+/// accept the same templates as `T`, and for pairs `(A, B)`, which accept the fields of both types, resolving names
+/// declared by both to the field of `A`.
 pub trait TemplateStringParams {
     /// Array of field names available for use in templates.
     const FIELDS: &'static [&'static str];
 
+    /// Virtual fields, as pairs of a name and a template source over the other fields.
+    ///
+    /// A placeholder naming a virtual field, such as `{full_name}`, is replaced by the elements of its template at
+    /// parse time. The derive macro declares these with `#[template(inline(full_name = "{first} {last}"))]` on the
+    /// struct.
+    const INLINE_FIELDS: &'static [(&'static str, &'static str)] = &[];
+
     /// Format the field at the given index into the provided formatter.
     fn fmt_field(&self, f: &mut fmt::Formatter, index: usize) -> fmt::Result;
+
+    /// Whether the field at the given index has a value to render.
+    ///
+    /// The derive macro returns `false` for `Option` fields that are `None`, unless they have a
+    /// `#[template(default = "...")]`.
+    fn has_field(&self, index: usize) -> bool {
+        let _ = index;
+        true
+    }
+
+    /// Look up an auxiliary facet (e.g. grammatical gender) of the field at the given index.
+    ///
+    /// Facets are used by `{field, select-facet, ...}` blocks. The derive macro forwards this to [`Facets`] for
+    /// fields marked with `#[template(facets)]`.
+    fn field_facet(&self, index: usize, facet: &str) -> Option<Cow<'_, str>> {
+        let _ = (index, facet);
+        None
+    }
+
+    /// Expose the value of the field at the given index as a number, for locale-aware formatting and arithmetic
+    /// expressions such as `{count + 1}`.
+    ///
+    /// The derive macro implements this for fields of primitive integer and float types.
+    fn field_number(&self, index: usize) -> Option<Number> {
+        let _ = index;
+        None
+    }
+
+    /// Whether the field at the given index always has an integer [`field_number`](Self::field_number), which radix
+    /// formats such as `{field:x}` require.
+    ///
+    /// The derive macro returns `true` for fields of primitive integer types (and `Option`s of them).
+    fn is_integer_field(index: usize) -> bool {
+        let _ = index;
+        false
+    }
+
+    /// Expose the value of the field at the given index as a point in time, for the `relative` filter.
+    ///
+    /// The derive macro implements this for fields of type `std::time::SystemTime`, `time::OffsetDateTime`,
+    /// `time::UtcDateTime` and `chrono::DateTime` written with these paths, for fields marked with `#[template(time)]`,
+    /// and for `Option`s of them.
+    fn field_time(&self, index: usize) -> Option<SystemTime> {
+        let _ = index;
+        None
+    }
+
+    /// Expose the value of the field at the given index as bytes, for the `base64` and `base64url` filters, which
+    /// encode the rendered value otherwise.
+    ///
+    /// The derive macro implements this for fields of type `Vec<u8>`, `&[u8]`, `Box<[u8]>` and `[u8; N]` (and
+    /// `Option`s of them).
+    fn field_bytes(&self, index: usize) -> Option<Cow<'_, [u8]>> {
+        let _ = index;
+        None
+    }
+
+    /// The names of the filters applied to every placeholder of the field at the given index after its own filters,
+    /// e.g. `html_escape` to escape values by default. A placeholder with a `raw` filter, as in `{body|raw}`, skips
+    /// them.
+    ///
+    /// The derive macro returns the filters given with `#[template(default_filter = "...")]` on the struct for every
+    /// field, which must be built-in filters.
+    ///
+    /// ```compile_fail
+    /// # use typlate::TemplateParams;
+    /// #[derive(TemplateParams)]
+    /// #[template(default_filter = "html_escpae")]
+    /// struct Page {
+    ///     title: String,
+    /// }
+    /// ```
+    fn default_filters(index: usize) -> &'static [&'static str] {
+        let _ = index;
+        &[]
+    }
+
+    /// The names of the filters applied to the placeholders that do not render a single field, i.e. arithmetic
+    /// expressions such as `{count + 1}` and namespaced values such as `{global:app_name}` or `{env:HOME}`, so that
+    /// default escaping also covers them.
+    ///
+    /// The derive macro returns the filters given with `#[template(default_filter = "...")]` on the struct.
+    fn default_value_filters() -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Estimate the length in bytes of the rendered value of the field at the given index.
+    ///
+    /// Used by [`TemplateString::size_hint`]. The derive macro returns the exact length for `String` and `&str`
+    /// fields, and the default estimate of 8 bytes for other fields.
+    fn len_hint(&self, index: usize) -> usize {
+        let _ = index;
+        8
+    }
+
+    /// The 1-based position of the field at the given index in positional formats, such as `%1$s` in Android string
+    /// resources.
+    ///
+    /// Defaults to the declaration order. The derive macro uses the positions set with `#[template(position = N)]`,
+    /// and gives the remaining fields the lowest unused positions in declaration order. Positions must be unique and at
+    /// most the number of fields, so that every field has a distinct position, also in a pair of parameter types
+    /// `(A, B)`, which places the fields of `B` after those of `A`.
+    ///
+    /// ```compile_fail
+    /// # use typlate::TemplateParams;
+    /// #[derive(TemplateParams)]
+    /// struct Cart {
+    ///     #[template(position = 3)]
+    ///     user: String,
+    ///     count: u32,
+    /// }
+    /// ```
+    ///
+    /// ```compile_fail
+    /// # use typlate::TemplateParams;
+    /// #[derive(TemplateParams)]
+    /// struct Cart {
+    ///     #[template(position = 1)]
+    ///     user: String,
+    ///     #[template(position = 1)]
+    ///     count: u32,
+    /// }
+    /// ```
+    fn field_position(index: usize) -> usize {
+        index + 1
+    }
+
+    /// Whether the field at the given index can be formatted with [`Debug`](fmt::Debug), as in `{field:?}`.
+    ///
+    /// The derive macro returns `true` for fields marked with `#[template(debug)]`, or for all fields if the struct
+    /// is marked with it.
+    fn is_debug_field(index: usize) -> bool {
+        let _ = index;
+        false
+    }
+
+    /// Format the field at the given index with its [`Debug`](fmt::Debug) implementation, for `{field:?}` and
+    /// `{field:#?}` placeholders. The formatter is in alternate mode for the latter.
+    ///
+    /// Only called for fields for which [`is_debug_field`](Self::is_debug_field) returns `true`. Defaults to
+    /// [`fmt_field`](Self::fmt_field).
+    fn fmt_field_debug(&self, f: &mut fmt::Formatter, index: usize) -> fmt::Result {
+        self.fmt_field(f, index)
+    }
+
+    /// Render the items of a list field (such as an array) separately, for the `join` filter.
+    ///
+    /// The derive macro implements this for fields of array types, which otherwise render as comma-separated lists.
+    fn field_items(&self, index: usize) -> Option<Vec<String>> {
+        let _ = index;
+        None
+    }
+}
+
+/// Forward [`TemplateStringParams`] through references and smart pointers, so that e.g. a `Template<&T>` or a
+/// `TemplateString<Arc<T>>` accepts the same templates as `T` and can be formatted without dereferencing.
+macro_rules! forward_params {
+    ($($ty:ty $(where T: $bound:path)?),* $(,)?) => {$(
+        impl<T: TemplateStringParams + ?Sized $(+ $bound)?> TemplateStringParams for $ty {
+            const FIELDS: &'static [&'static str] = T::FIELDS;
+            const INLINE_FIELDS: &'static [(&'static str, &'static str)] = T::INLINE_FIELDS;
+
+            fn fmt_field(&self, f: &mut fmt::Formatter, index: usize) -> fmt::Result {
+                (**self).fmt_field(f, index)
+            }
+
+            fn has_field(&self, index: usize) -> bool {
+                (**self).has_field(index)
+            }
+
+            fn field_facet(&self, index: usize, facet: &str) -> Option<Cow<'_, str>> {
+                (**self).field_facet(index, facet)
+            }
+
+            fn field_number(&self, index: usize) -> Option<Number> {
+                (**self).field_number(index)
+            }
+
+            fn len_hint(&self, index: usize) -> usize {
+                (**self).len_hint(index)
+            }
+
+            fn field_position(index: usize) -> usize {
+                T::field_position(index)
+            }
+
+            fn is_integer_field(index: usize) -> bool {
+                T::is_integer_field(index)
+            }
+
+            fn field_time(&self, index: usize) -> Option<SystemTime> {
+                (**self).field_time(index)
+            }
+
+            fn field_bytes(&self, index: usize) -> Option<Cow<'_, [u8]>> {
+                (**self).field_bytes(index)
+            }
+
+            fn default_filters(index: usize) -> &'static [&'static str] {
+                T::default_filters(index)
+            }
+
+            fn default_value_filters() -> &'static [&'static str] {
+                T::default_value_filters()
+            }
+
+            fn is_debug_field(index: usize) -> bool {
+                T::is_debug_field(index)
+            }
+
+            fn fmt_field_debug(&self, f: &mut fmt::Formatter, index: usize) -> fmt::Result {
+                (**self).fmt_field_debug(f, index)
+            }
+
+            fn field_items(&self, index: usize) -> Option<Vec<String>> {
+                (**self).field_items(index)
+            }
+        }
+    )*};
+}
+
+forward_params!(&T, &mut T, Box<T>, std::rc::Rc<T>, std::sync::Arc<T>, Cow<'_, T> where T: ToOwned);
+
+/// A trait for field types that expose auxiliary facets, such as grammatical gender or case.
+///
+/// Select blocks can branch on a facet of a field with `{field, select-facet, key {...} other {...}}`, which is
+/// needed for languages where surrounding words inflect based on the value.
+///
+/// ## Examples
+///
+/// ```
+/// # use std::borrow::Cow;
+/// # use std::fmt;
+/// # use typlate::{Facets, Template, TemplateParams};
+/// struct User {
+///     name: String,
+///     gender: &'static str,
+/// }
+///
+/// impl fmt::Display for User {
+///     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+///         f.write_str(&self.name)
+///     }
+/// }
+///
+/// impl Facets for User {
+///     fn facet(&self, name: &str) -> Option<Cow<'_, str>> {
+///         (name == "gender").then_some(Cow::Borrowed(self.gender))
+///     }
+/// }
+///
+/// #[derive(TemplateParams)]
+/// struct Data {
+///     #[template(facets)]
+///     user: User,
+/// }
+///
+/// let template: Template<Data> =
+///     "{user} {user, select-gender, female {hat ihre} other {hat seine}} Tasche".parse().unwrap();
+/// let data = Data { user: User { name: "Anna".into(), gender: "female" } };
+/// assert_eq!(template.format(&data), "Anna hat ihre Tasche");
+/// ```
+pub trait Facets {
+    /// Return the value of the named facet, or `None` if this value has no such facet.
+    fn facet(&self, name: &str) -> Option<Cow<'_, str>>;
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-enum TemplateElement {
+pub(crate) enum TemplateElement {
     Text(String),
     Var(usize),
+    /// A placeholder whose value is passed through filters, e.g. `{name|upper}`.
+    Filtered {
+        index: usize,
+        filters: Vec<FilterCall>,
+    },
+    /// A placeholder formatted with a `std::fmt`-style spec, e.g. `{price:>width$.2}`.
+    Formatted {
+        index: usize,
+        spec: FormatSpec,
+    },
+    /// An arithmetic expression over numeric fields, e.g. `{count + 1}`.
+    Expr(Expr),
+    /// A name resolved in a namespace at render time, e.g. `{env:HOME}`.
+    Namespaced {
+        namespace: String,
+        name: String,
+    },
+    /// A select block, branching on the rendered field value or on one of its facets.
+    Select {
+        index: usize,
+        facet: Option<String>,
+        arms: Vec<(String, Vec<TemplateElement>)>,
+    },
+    /// A conditional section, rendering `then` if the condition holds and `otherwise` if not, e.g.
+    /// `{?count > 1}items{:}item{/}`.
+    Conditional {
+        condition: Condition,
+        then: Vec<TemplateElement>,
+        otherwise: Vec<TemplateElement>,
+    },
+    /// A named block that child templates can override, e.g. `{%block body%}...{%endblock%}`.
+    Block {
+        name: String,
+        elements: Vec<TemplateElement>,
+    },
+}
+
+impl TemplateElement {
+    /// Call `f` with the index of every field referenced by the element, including in nested branches.
+    pub(crate) fn visit_fields(&self, f: &mut impl FnMut(usize)) {
+        match self {
+            Self::Text(_) | Self::Namespaced { .. } => {}
+            Self::Var(index) | Self::Filtered { index, .. } => f(*index),
+            Self::Formatted { index, spec } => {
+                f(*index);
+                spec.visit_fields(f);
+            }
+            Self::Expr(expr) => expr.visit_fields(f),
+            Self::Select { index, arms, .. } => {
+                f(*index);
+                for (_, branch) in arms {
+                    branch.iter().for_each(|element| element.visit_fields(f));
+                }
+            }
+            Self::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => {
+                condition.visit_fields(f);
+                then.iter().chain(otherwise).for_each(|element| element.visit_fields(f));
+            }
+            Self::Block { elements, .. } => elements.iter().for_each(|element| element.visit_fields(f)),
+        }
+    }
+
+    /// Call `f` with the index of the field of every placeholder with a `raw` filter.
+    pub(crate) fn visit_raw_fields(&self, f: &mut impl FnMut(usize)) {
+        match self {
+            Self::Filtered { index, filters } if filters.iter().any(|filter| filter.name == "raw") => f(*index),
+            Self::Select { arms, .. } => arms
+                .iter()
+                .flat_map(|(_, branch)| branch)
+                .for_each(|element| element.visit_raw_fields(f)),
+            Self::Conditional { then, otherwise, .. } => then
+                .iter()
+                .chain(otherwise)
+                .for_each(|element| element.visit_raw_fields(f)),
+            Self::Block { elements, .. } => elements.iter().for_each(|element| element.visit_raw_fields(f)),
+            _ => {}
+        }
+    }
+
+    /// The first filter applied by the placeholder that is not built in, including the default filters of its field.
+    pub(crate) fn unknown_filter<T: TemplateStringParams>(&self) -> Option<&str> {
+        let (defaults, filters) = match self {
+            Self::Var(index) | Self::Formatted { index, .. } => (T::default_filters(*index), &[][..]),
+            Self::Filtered { index, filters } => (T::default_filters(*index), &filters[..]),
+            Self::Expr(_) | Self::Namespaced { .. } => (T::default_value_filters(), &[][..]),
+            _ => return None,
+        };
+        let raw = filters.iter().any(|filter| filter.name == "raw");
+        let defaults = defaults.iter().copied().filter(|_| !raw);
+        filters
+            .iter()
+            .map(|filter| filter.name.as_str())
+            .chain(defaults)
+            .find(|name| !is_builtin(name))
+    }
+
+    /// Write the element in template syntax, with literal brackets escaped.
+    pub(crate) fn write_source(&self, f: &mut impl Write, fields: &[&str]) -> fmt::Result {
+        match self {
+            Self::Text(text) => {
+                for char in text.chars() {
+                    match char {
+                        '{' => f.write_str("{{")?,
+                        '}' => f.write_str("}}")?,
+                        _ => f.write_char(char)?,
+                    }
+                }
+                Ok(())
+            }
+            Self::Var(index) => write!(f, "{{{}}}", fields[*index]),
+            Self::Filtered { index, filters } => {
+                write!(f, "{{{}", fields[*index])?;
+                for filter in filters {
+                    filter.write_source(f)?;
+                }
+                f.write_char('}')
+            }
+            Self::Formatted { index, spec } => {
+                write!(f, "{{{}:", fields[*index])?;
+                spec.write_source(f, fields)?;
+                f.write_char('}')
+            }
+            Self::Expr(expr) => {
+                f.write_char('{')?;
+                expr.write_source(f, fields)?;
+                f.write_char('}')
+            }
+            Self::Namespaced { namespace, name } => write!(f, "{{{namespace}:{name}}}"),
+            Self::Select { index, facet, arms } => {
+                write!(f, "{{{}, select", fields[*index])?;
+                if let Some(facet) = facet {
+                    write!(f, "-{facet}")?;
+                }
+                f.write_char(',')?;
+                for (key, branch) in arms {
+                    write!(f, " {key} {{")?;
+                    for element in branch {
+                        element.write_source(f, fields)?;
+                    }
+                    f.write_char('}')?;
+                }
+                f.write_char('}')
+            }
+            Self::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => {
+                f.write_str("{?")?;
+                condition.write_source(f, fields)?;
+                f.write_char('}')?;
+                for element in then {
+                    element.write_source(f, fields)?;
+                }
+                if !otherwise.is_empty() {
+                    f.write_str("{:}")?;
+                    for element in otherwise {
+                        element.write_source(f, fields)?;
+                    }
+                }
+                f.write_str("{/}")
+            }
+            Self::Block { name, elements } => {
+                write!(f, "{{%block {name}%}}")?;
+                for element in elements {
+                    element.write_source(f, fields)?;
+                }
+                f.write_str("{%endblock%}")
+            }
+        }
+    }
+
+    fn render<T: TemplateStringParams>(
+        &self,
+        params: &T,
+        f: &mut fmt::Formatter,
+        isolate: bool,
+        ctx: Option<&RenderCtx>,
+    ) -> fmt::Result {
+        let value = |f: &mut fmt::Formatter| match self {
+            Self::Var(index) => write_value(ctx, params, f, *index, &[]),
+            Self::Filtered { index, filters } => write_value(ctx, params, f, *index, filters),
+            Self::Formatted { index, spec } => write_formatted(ctx, params, f, *index, spec),
+            Self::Expr(expr) => match expr.eval(params) {
+                Some(number) => write_number::<T>(ctx, number, f),
+                None => Ok(()),
+            },
+            Self::Namespaced { namespace, name } => write_namespaced::<T>(ctx, namespace, name, f),
+            _ => unreachable!("only placeholders have a value"),
+        };
+        match self {
+            Self::Text(text) => f.write_str(text),
+            Self::Var(_) | Self::Filtered { .. } | Self::Formatted { .. } | Self::Expr(_) | Self::Namespaced { .. } => {
+                if isolate {
+                    f.write_char('\u{2068}')?;
+                }
+                value(f)?;
+                if isolate {
+                    f.write_char('\u{2069}')?;
+                }
+                Ok(())
+            }
+            Self::Select { .. } | Self::Conditional { .. } | Self::Block { .. } => {
+                for element in self.nested(params) {
+                    element.render(params, f, isolate, ctx)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// The elements rendered in place of the element: the branch of a select block or conditional section chosen for
+    /// the parameters, the content of a block, or an empty slice for other elements.
+    pub(crate) fn nested<T: TemplateStringParams>(&self, params: &T) -> &[TemplateElement] {
+        match self {
+            Self::Select { arms, .. } => self.select_arm(params).map_or(&[], |arm| &arms[arm].1),
+            Self::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => match condition.eval(params) {
+                true => then,
+                false => otherwise,
+            },
+            Self::Block { elements, .. } => elements,
+            _ => &[],
+        }
+    }
+
+    /// The position of the arm of a select block chosen for the parameters, or `None` if no arm applies or the element
+    /// is not a select block.
+    pub(crate) fn select_arm<T: TemplateStringParams>(&self, params: &T) -> Option<usize> {
+        let Self::Select { index, facet, arms } = self else {
+            return None;
+        };
+        let value = match facet {
+            Some(facet) => params.field_facet(*index, facet),
+            None => Some(Cow::Owned(render(FieldDisplay(None, params, *index)))),
+        };
+        value
+            .and_then(|value| arms.iter().position(|(key, _)| *key == value))
+            .or_else(|| arms.iter().position(|(key, _)| key == "other"))
+    }
 }
 
 /// A type-safe template string that can be formatted with values of type `T`.
@@ -49,88 +585,1140 @@ enum TemplateElement {
 /// assert_eq!(template.format(&person), "Dear Dr. Smith");
 /// ```
 pub struct TemplateString<T> {
-    elements: Vec<TemplateElement>,
+    elements: Elements,
+    isolate: bool,
     phantom: PhantomData<T>,
 }
 
+/// The element storage of a template. With the `smallvec` feature enabled, short templates are stored inline without
+/// a heap allocation.
+#[cfg(not(feature = "smallvec"))]
+type Elements = Vec<TemplateElement>;
+#[cfg(feature = "smallvec")]
+type Elements = smallvec::SmallVec<[TemplateElement; 4]>;
+
+impl<T> TemplateString<T> {
+    pub(crate) fn from_elements(elements: Vec<TemplateElement>) -> Self {
+        Self {
+            elements: Elements::from(elements),
+            isolate: false,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Set whether interpolated values are wrapped in Unicode bidirectional isolates by default.
+    ///
+    /// When enabled, every rendered field value is surrounded by FIRST STRONG ISOLATE (U+2068) and POP DIRECTIONAL
+    /// ISOLATE (U+2069), so that right-to-left user content does not scramble the surrounding left-to-right text (and
+    /// vice versa). This can be overridden per render with [`TemplateString::format_with_isolation`].
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Data {
+    ///     name: String,
+    /// }
+    ///
+    /// let template: TemplateString<Data> = "{name} liked your post".parse().unwrap();
+    /// let template = template.with_bidi_isolation(true);
+    /// let data = Data { name: "داود".into() };
+    /// assert_eq!(template.format(&data), "\u{2068}داود\u{2069} liked your post");
+    /// ```
+    pub fn with_bidi_isolation(mut self, isolate: bool) -> Self {
+        self.isolate = isolate;
+        self
+    }
+
+    /// Whether interpolated values are wrapped in Unicode bidirectional isolates by default.
+    pub fn bidi_isolation(&self) -> bool {
+        self.isolate
+    }
+
+    pub(crate) fn elements(&self) -> &[TemplateElement] {
+        &self.elements
+    }
+}
+
+impl<T: TemplateStringParams> TemplateString<T> {
+    /// Parse a template string with the given options instead of the defaults used by [`FromStr`].
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{EmptyPlaceholders, ParseOptions, TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Point {
+    ///     x: i32,
+    ///     y: i32,
+    /// }
+    ///
+    /// let options = ParseOptions::builder().empty_placeholders(EmptyPlaceholders::Positional).build();
+    /// let template = TemplateString::<Point>::parse_with("({}, {})", &options).unwrap();
+    /// assert_eq!(template.format(&Point { x: 1, y: 2 }), "(1, 2)");
+    /// assert!("({}, {})".parse::<TemplateString<Point>>().is_err());
+    /// ```
+    pub fn parse_with(template: &str, options: &ParseOptions) -> Result<Self, TemplateParseError> {
+        Ok(Self::from_elements(Parser::<T>::new(template, options).parse()?))
+    }
+
+    /// Parse a template string, reporting every error in it instead of stopping at the first one.
+    ///
+    /// After an invalid placeholder, parsing resumes at the end of that placeholder. This is meant for validation
+    /// passes that should show all mistakes in a template at once.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{TemplateParams, TemplateParseError, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct User {
+    ///     name: String,
+    /// }
+    ///
+    /// let errors = TemplateString::<User>::parse_all_errors("{nmae} and {}").unwrap_err();
+    /// assert_eq!(errors, [
+    ///     TemplateParseError::UnknownField { name: "nmae".into(), span: 1..5 },
+    ///     TemplateParseError::EmptyPlaceholder { span: 11..13 },
+    /// ]);
+    /// ```
+    pub fn parse_all_errors(template: &str) -> Result<Self, Vec<TemplateParseError>> {
+        let options = ParseOptions::default();
+        Ok(Self::from_elements(Parser::<T>::new(template, &options).parse_all()?))
+    }
+
+    /// Parse a template that is expected to be valid, such as one generated by a build script, with a fast path, e.g.
+    /// to speed up loading many templates at startup.
+    ///
+    /// Text and placeholders that name a field exactly are scanned without the bookkeeping of the full parser.
+    /// Everything else, such as filters, expressions, conditionals and malformed syntax, falls back to the full parser,
+    /// so the result is always the same as with [`FromStr`], including the error for an invalid template.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Data {
+    ///     name: String,
+    /// }
+    ///
+    /// let template = TemplateString::<Data>::parse_trusted("Hello {name} {{}}");
+    /// assert_eq!(template, "Hello {name} {{}}".parse());
+    /// ```
+    pub fn parse_trusted(template: &str) -> Result<Self, TemplateParseError> {
+        let full = || template.parse();
+
+        let mut builder = Builder::<T>::new();
+        let mut rest = template;
+        while let Some(offset) = rest.find(['{', '}']) {
+            builder.push_str(&rest[..offset]);
+            rest = &rest[offset..];
+            if rest.starts_with("{{") || rest.starts_with("}}") {
+                builder.push_str(&rest[..1]);
+                rest = &rest[2..];
+                continue;
+            }
+            let Some((name, _)) = rest.strip_prefix('{').and_then(|inner| inner.split_once('}')) else {
+                return full();
+            };
+            // Anything but the exact name of a field, such as an expression, goes to the full parser.
+            if T::INLINE_FIELDS.iter().any(|&(inline, _)| inline == name) {
+                return full();
+            }
+            match T::FIELDS.iter().position(|&field| field == name) {
+                Some(index) => builder.push_element(TemplateElement::Var(index)),
+                None => return full(),
+            }
+            let end = name.len() + 2;
+            rest = &rest[end..];
+        }
+        builder.push_str(rest);
+        Ok(builder.finish())
+    }
+
+    /// Whether every field referenced by this template has a value in `params`.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Data {
+    ///     name: Option<String>,
+    /// }
+    ///
+    /// let template: TemplateString<Data> = "Hello {name}".parse().unwrap();
+    /// assert!(template.has_fields(&Data { name: Some("Alice".into()) }));
+    /// assert!(!template.has_fields(&Data { name: None }));
+    /// ```
+    pub fn has_fields(&self, params: &T) -> bool {
+        self.field_indices().into_iter().all(|index| params.has_field(index))
+    }
+
+    /// Bring the template into its canonical form, so that equivalent templates compare and hash equal.
+    ///
+    /// Adjacent text segments are merged, empty text segments are removed, and placeholders with an empty filter list
+    /// are turned into plain placeholders, including inside select branches and blocks. Templates parsed from strings
+    /// are always in canonical form, but templates assembled in other ways (such as from a compact serde format) may
+    /// not be.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Data {
+    ///     name: String,
+    /// }
+    ///
+    /// let mut template: TemplateString<Data> = "{{Hello}} {name}".parse().unwrap();
+    /// template.normalize();
+    /// assert_eq!(template, "{{Hello}} {name}".parse().unwrap());
+    /// assert_eq!(template.to_string(), "{{Hello}} {name}");
+    /// ```
+    pub fn normalize(&mut self) {
+        fn normalize<T: TemplateStringParams>(elements: &[TemplateElement]) -> Vec<TemplateElement> {
+            let mut builder = Builder::<T>::new();
+            for element in elements {
+                builder.push_element(match element {
+                    TemplateElement::Filtered { index, filters } if filters.is_empty() => TemplateElement::Var(*index),
+                    TemplateElement::Select { index, facet, arms } => TemplateElement::Select {
+                        index: *index,
+                        facet: facet.clone(),
+                        arms: arms
+                            .iter()
+                            .map(|(key, branch)| (key.clone(), normalize::<T>(branch)))
+                            .collect(),
+                    },
+                    TemplateElement::Conditional {
+                        condition,
+                        then,
+                        otherwise,
+                    } => TemplateElement::Conditional {
+                        condition: condition.clone(),
+                        then: normalize::<T>(then),
+                        otherwise: normalize::<T>(otherwise),
+                    },
+                    TemplateElement::Block { name, elements } => TemplateElement::Block {
+                        name: name.clone(),
+                        elements: normalize::<T>(elements),
+                    },
+                    element => element.clone(),
+                });
+            }
+            builder.into_elements()
+        }
+        self.elements = Elements::from(normalize::<T>(&self.elements));
+    }
+
+    /// Whether this template has the same structure as a template for another parameter type, comparing placeholders
+    /// by field name rather than by field index.
+    ///
+    /// Like `==`, this also compares the bidirectional isolation setting.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct User {
+    ///     id: u64,
+    ///     name: String,
+    /// }
+    ///
+    /// #[derive(TemplateParams)]
+    /// struct Admin {
+    ///     name: String,
+    ///     level: u8,
+    /// }
+    ///
+    /// let user: TemplateString<User> = "Hello {name}".parse().unwrap();
+    /// let admin: TemplateString<Admin> = "Hello {name}".parse().unwrap();
+    /// assert!(user.equivalent(&admin));
+    /// assert!(!user.equivalent(&"Hello {level}".parse::<TemplateString<Admin>>().unwrap()));
+    /// ```
+    pub fn equivalent<U: TemplateStringParams>(&self, other: &TemplateString<U>) -> bool {
+        fn equivalent<T: TemplateStringParams, U: TemplateStringParams>(
+            left: &[TemplateElement],
+            right: &[TemplateElement],
+        ) -> bool {
+            let same_field = |left: &usize, right: &usize| T::FIELDS[*left] == U::FIELDS[*right];
+            left.len() == right.len()
+                && left.iter().zip(right).all(|pair| match pair {
+                    (TemplateElement::Text(left), TemplateElement::Text(right)) => left == right,
+                    (TemplateElement::Var(left), TemplateElement::Var(right)) => same_field(left, right),
+                    (
+                        TemplateElement::Filtered { index, filters },
+                        TemplateElement::Filtered {
+                            index: other_index,
+                            filters: other_filters,
+                        },
+                    ) => same_field(index, other_index) && filters == other_filters,
+                    (
+                        TemplateElement::Formatted { index, spec },
+                        TemplateElement::Formatted {
+                            index: other_index,
+                            spec: other_spec,
+                        },
+                    ) => same_field(index, other_index) && spec.equivalent(other_spec, &same_field),
+                    (TemplateElement::Expr(left), TemplateElement::Expr(right)) => left.equivalent(right, &same_field),
+                    (
+                        TemplateElement::Select { index, facet, arms },
+                        TemplateElement::Select {
+                            index: other_index,
+                            facet: other_facet,
+                            arms: other_arms,
+                        },
+                    ) => {
+                        same_field(index, other_index)
+                            && facet == other_facet
+                            && arms.len() == other_arms.len()
+                            && arms
+                                .iter()
+                                .zip(other_arms)
+                                .all(|((key, branch), (other_key, other_branch))| {
+                                    key == other_key && equivalent::<T, U>(branch, other_branch)
+                                })
+                    }
+                    (
+                        TemplateElement::Conditional {
+                            condition,
+                            then,
+                            otherwise,
+                        },
+                        TemplateElement::Conditional {
+                            condition: other_condition,
+                            then: other_then,
+                            otherwise: other_otherwise,
+                        },
+                    ) => {
+                        condition.equivalent(other_condition, &same_field)
+                            && equivalent::<T, U>(then, other_then)
+                            && equivalent::<T, U>(otherwise, other_otherwise)
+                    }
+                    (
+                        TemplateElement::Block { name, elements },
+                        TemplateElement::Block {
+                            name: other_name,
+                            elements: other_elements,
+                        },
+                    ) => name == other_name && equivalent::<T, U>(elements, other_elements),
+                    _ => false,
+                })
+        }
+        self.isolate == other.isolate && equivalent::<T, U>(&self.elements, &other.elements)
+    }
+
+    /// The set of fields referenced by this template, including in all branches of select blocks, as indices into
+    /// [`TemplateStringParams::FIELDS`].
+    ///
+    /// This tells which inputs a template depends on, e.g. to compute only the values it needs before rendering.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Data {
+    ///     name: String,
+    ///     email: String,
+    ///     karma: u32,
+    /// }
+    ///
+    /// let template: TemplateString<Data> = "{karma} points for {name}".parse().unwrap();
+    /// let fields = template.referenced_fields();
+    /// assert!(fields.contains(0) && !fields.contains(1));
+    /// assert_eq!(fields.iter().collect::<Vec<_>>(), [0, 2]);
+    /// ```
+    pub fn referenced_fields(&self) -> FieldSet {
+        self.field_indices().into_iter().collect()
+    }
+
+    /// Check that formatting the template with `params` would succeed, without producing the output.
+    ///
+    /// Every field the render would use, following the branches that select blocks would take, must have a value
+    /// (see [`TemplateStringParams::has_field`]) and must format without an error. Fields used in arithmetic
+    /// expressions must also have a numeric value (see [`TemplateStringParams::field_number`]). Fields used in the
+    /// conditions of conditional sections may be missing, which makes the condition false. Nested template fields must
+    /// not render deeper than [`max_render_depth`](crate::max_render_depth). Filters, including the
+    /// [default filters](TemplateStringParams::default_filters) of fields, must be built in. Values are formatted into
+    /// a sink that discards them.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{RenderError, TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Order {
+    ///     id: u32,
+    ///     carrier: Option<String>,
+    /// }
+    ///
+    /// let template: TemplateString<Order> = "Order {id} was shipped via {carrier}".parse().unwrap();
+    /// assert_eq!(template.dry_run(&Order { id: 1, carrier: Some("UPS".into()) }), Ok(()));
+    /// assert_eq!(
+    ///     template.dry_run(&Order { id: 1, carrier: None }),
+    ///     Err(RenderError::MissingField { name: "carrier" }),
+    /// );
+    /// ```
+    pub fn dry_run(&self, params: &T) -> Result<(), RenderError> {
+        struct Sink;
+
+        impl Write for Sink {
+            fn write_str(&mut self, _: &str) -> fmt::Result {
+                Ok(())
+            }
+        }
+
+        crate::depth::dry_run(|| {
+            let mut stack = vec![self.elements.iter()];
+            while let Some(elements) = stack.last_mut() {
+                let Some(element) = elements.next() else {
+                    stack.pop();
+                    continue;
+                };
+                let index = match element {
+                    TemplateElement::Text(_) => continue,
+                    TemplateElement::Namespaced { namespace, name } => match resolve_builtin(namespace, name) {
+                        Some(_) => continue,
+                        None if !is_builtin_namespace(namespace) => continue,
+                        None => {
+                            return Err(RenderError::Unresolved {
+                                namespace: namespace.clone(),
+                                name: name.clone(),
+                            });
+                        }
+                    },
+                    TemplateElement::Block { elements, .. } => {
+                        stack.push(elements.iter());
+                        continue;
+                    }
+                    TemplateElement::Expr(expr) => {
+                        let mut result = Ok(());
+                        expr.visit_fields(&mut |index| {
+                            let name = T::FIELDS[index];
+                            if result.is_ok() && !params.has_field(index) {
+                                result = Err(RenderError::MissingField { name });
+                            } else if result.is_ok() && params.field_number(index).is_none() {
+                                result = Err(RenderError::NotNumeric { name });
+                            }
+                        });
+                        result?;
+                        continue;
+                    }
+                    TemplateElement::Conditional { condition, .. } => {
+                        let mut result = Ok(());
+                        condition.visit_fields(&mut |index| {
+                            let name = T::FIELDS[index];
+                            if result.is_ok() {
+                                result = write!(Sink, "{}", FieldDisplay(None, params, index))
+                                    .map_err(|_| RenderError::Format { name });
+                            }
+                        });
+                        result?;
+                        stack.push(element.nested(params).iter());
+                        continue;
+                    }
+                    TemplateElement::Var(index)
+                    | TemplateElement::Filtered { index, .. }
+                    | TemplateElement::Formatted { index, .. }
+                    | TemplateElement::Select { index, .. } => *index,
+                };
+                if let Some(filter) = element.unknown_filter::<T>() {
+                    return Err(RenderError::UnknownFilter {
+                        name: filter.to_string(),
+                    });
+                }
+                let name = T::FIELDS[index];
+                if !params.has_field(index) {
+                    return Err(RenderError::MissingField { name });
+                }
+                write!(Sink, "{}", FieldDisplay(None, params, index)).map_err(|_| RenderError::Format { name })?;
+                stack.push(element.nested(params).iter());
+            }
+            Ok(())
+        })
+    }
+
+    /// Iterate over the field names referenced by this template, in order of appearance.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Data {
+    ///     x: i32,
+    ///     y: i32,
+    /// }
+    ///
+    /// let template: TemplateString<Data> = "({y}, {x}, {y})".parse().unwrap();
+    /// assert_eq!(template.placeholders().collect::<Vec<_>>(), ["y", "x", "y"]);
+    /// ```
+    pub fn placeholders(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.field_indices().into_iter().map(|index| T::FIELDS[index])
+    }
+
+    /// Iterate over the field names of the placeholders with a `raw` filter, in order of appearance, e.g. to review
+    /// every value that bypasses escaping.
+    ///
+    /// A `raw` filter renders the value without the [default filters](TemplateStringParams::default_filters) of the
+    /// field and without the escaper of the [`RenderCtx`].
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{RenderCtx, TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Post {
+    ///     title: String,
+    ///     body: String,
+    /// }
+    ///
+    /// let template: TemplateString<Post> = "<h1>{title}</h1>{?body}{body|raw}{/}".parse().unwrap();
+    /// assert_eq!(template.raw_placeholders().collect::<Vec<_>>(), ["body"]);
+    ///
+    /// let ctx = RenderCtx::new().with_escaper(typlate::escape::html);
+    /// let post = Post { title: "<Hi>".into(), body: "<p>Hello</p>".into() };
+    /// assert_eq!(template.format_with_ctx(&post, &ctx), "<h1>&lt;Hi&gt;</h1><p>Hello</p>");
+    /// ```
+    pub fn raw_placeholders(&self) -> impl Iterator<Item = &'static str> + '_ {
+        let mut indices = vec![];
+        for element in &self.elements {
+            element.visit_raw_fields(&mut |index| indices.push(index));
+        }
+        indices.into_iter().map(|index| T::FIELDS[index])
+    }
+
+    /// Format the template with the provided parameter values.
+    pub fn format(&self, params: &T) -> String {
+        render(params.format_template(self))
+    }
+
+    /// Format the template with the provided parameter values, failing with the error reported by
+    /// [`dry_run`](Self::dry_run) instead of rendering it anyway, e.g. for an unset environment variable or for nested
+    /// templates that exceed the [render depth](crate::set_max_render_depth).
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{ParseOptions, RenderError, TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Config {
+    ///     name: String,
+    /// }
+    ///
+    /// let options = ParseOptions::builder().env_vars(true).build();
+    /// let template = TemplateString::<Config>::parse_with("{env:TYPLATE_DOC_UNSET}/{name}", &options).unwrap();
+    /// let config = Config { name: "app".into() };
+    /// assert_eq!(template.format(&config), "/app");
+    /// assert_eq!(
+    ///     template.try_format(&config),
+    ///     Err(RenderError::Unresolved { namespace: "env".into(), name: "TYPLATE_DOC_UNSET".into() }),
+    /// );
+    /// ```
+    pub fn try_format(&self, params: &T) -> Result<String, RenderError> {
+        self.dry_run(params)?;
+        Ok(self.format(params))
+    }
+
+    /// Return a value that renders the template with the provided parameter values when it is displayed.
+    ///
+    /// Nothing is rendered until the value is formatted, so it can be passed to `panic!`, `assert!` or logging macros
+    /// without formatting the message on success paths. The value is `Copy`, and `Send + Sync` if `T` is `Sync`. It
+    /// borrows the template and the parameters, so APIs that need an owned message, such as anyhow's `with_context`,
+    /// should call [`format`](Self::format) in their closure instead.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Limit {
+    ///     name: String,
+    ///     max: u32,
+    /// }
+    ///
+    /// let template: TemplateString<Limit> = "{name} must be at most {max}".parse().unwrap();
+    /// let limit = Limit { name: "count".into(), max: 10 };
+    /// let count = 3;
+    /// assert!(count <= limit.max, "{}", template.display(&limit));
+    /// assert_eq!(template.display(&limit).to_string(), "count must be at most 10");
+    /// ```
+    pub fn display<'i>(&'i self, params: &'i T) -> TemplateDisplay<'i, T> {
+        TemplateDisplay { template: self, params }
+    }
+
+    /// Format the template into UTF-8 encoded bytes.
+    pub fn format_bytes(&self, params: &T) -> Vec<u8> {
+        self.format(params).into_bytes()
+    }
+
+    /// Format the template directly into a writer as UTF-8, without building the output string first.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Header {
+    ///     subject: String,
+    /// }
+    ///
+    /// let template: TemplateString<Header> = "Subject: {subject}\r\n".parse().unwrap();
+    /// let mut output = vec![];
+    /// template.write_bytes(&Header { subject: "Hello".into() }, &mut output).unwrap();
+    /// assert_eq!(output, b"Subject: Hello\r\n");
+    /// ```
+    pub fn write_bytes(&self, params: &T, writer: &mut impl std::io::Write) -> std::io::Result<()> {
+        write!(writer, "{}", params.format_template(self))
+    }
+
+    /// Format the template, overriding whether interpolated values are wrapped in Unicode bidirectional isolates.
+    ///
+    /// See [`TemplateString::with_bidi_isolation`] for details.
+    pub fn format_with_isolation(&self, params: &T, isolate: bool) -> String {
+        render(Parameterized {
+            params,
+            elements: &self.elements,
+            isolate,
+            ctx: None,
+        })
+    }
+
+    /// Format the template with the settings of a render context, such as the locale used for numeric fields.
+    ///
+    /// See [`RenderCtx`] for details.
+    pub fn format_with_ctx(&self, params: &T, ctx: &RenderCtx) -> String {
+        self.format_observed(params, ctx, None)
+    }
+
+    /// Format the template with a render context, reporting the render under `name` to the observer of the context.
+    pub(crate) fn format_observed(&self, params: &T, ctx: &RenderCtx, name: Option<&str>) -> String {
+        let start = ctx.observer().map(|_| Stopwatch::start());
+        let output = render(Parameterized {
+            params,
+            elements: &self.elements,
+            isolate: self.isolate,
+            ctx: Some(ctx),
+        });
+        if let Some((observer, start)) = ctx.observer().zip(start) {
+            let mut hasher = std::hash::DefaultHasher::new();
+            self.hash(&mut hasher);
+            observer.on_render(&RenderEvent {
+                name,
+                id: hasher.finish(),
+                output_len: output.len(),
+                duration: start.elapsed(),
+            });
+        }
+        output
+    }
+
+    /// Estimate the length in bytes of the output of formatting the template with `params`, e.g. to pre-allocate a
+    /// buffer.
+    ///
+    /// The estimate adds the length of the literal text and the [`len_hint`](TemplateStringParams::len_hint) of every
+    /// interpolated field, following the branches that select blocks would take.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Data {
+    ///     name: String,
+    /// }
+    ///
+    /// let template: TemplateString<Data> = "Hello {name}!".parse().unwrap();
+    /// assert_eq!(template.size_hint(&Data { name: "Alice".into() }), 12);
+    /// ```
+    pub fn size_hint(&self, params: &T) -> usize {
+        fn size_hint<T: TemplateStringParams>(elements: &[TemplateElement], params: &T, isolate: bool) -> usize {
+            let isolates = if isolate {
+                '\u{2068}'.len_utf8() + '\u{2069}'.len_utf8()
+            } else {
+                0
+            };
+            elements
+                .iter()
+                .map(|element| match element {
+                    TemplateElement::Text(text) => text.len(),
+                    TemplateElement::Var(index)
+                    | TemplateElement::Filtered { index, .. }
+                    | TemplateElement::Formatted { index, .. } => params.len_hint(*index) + isolates,
+                    TemplateElement::Expr(expr) => {
+                        expr.eval(params).map_or(0, |number| number.to_string().len()) + isolates
+                    }
+                    TemplateElement::Namespaced { namespace, name } => {
+                        resolve_builtin(namespace, name).map_or(0, |value| value.len()) + isolates
+                    }
+                    TemplateElement::Select { .. }
+                    | TemplateElement::Conditional { .. }
+                    | TemplateElement::Block { .. } => size_hint(element.nested(params), params, isolate),
+                })
+                .sum()
+        }
+        size_hint(&self.elements, params, self.isolate)
+    }
+
+    /// Format the template once per parameter value.
+    ///
+    /// With the `rayon` feature enabled, the values are formatted in parallel on the rayon thread pool, which is why
+    /// they must be `Sync`.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Data {
+    ///     name: &'static str,
+    /// }
+    ///
+    /// let template: TemplateString<Data> = "Hello {name}".parse().unwrap();
+    /// let output = template.format_batch(&[Data { name: "Alice" }, Data { name: "Bob" }]);
+    /// assert_eq!(output, ["Hello Alice", "Hello Bob"]);
+    /// ```
+    pub fn format_batch(&self, params: &[T]) -> Vec<String>
+    where
+        T: Sync,
+    {
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+
+            params.par_iter().map(|params| self.format(params)).collect()
+        }
+        #[cfg(not(feature = "rayon"))]
+        params.iter().map(|params| self.format(params)).collect()
+    }
+
+    /// Format the template once per parameter value, padding interpolated values so that the rows line up in columns.
+    ///
+    /// Every placeholder (and select block) at the top level of the template is a column, padded to the widest value
+    /// rendered for it across all rows. Numeric fields are aligned to the right and other values to the left. A
+    /// placeholder at the very end of the template is not padded, so rows have no trailing whitespace. Widths are
+    /// measured in characters, or with the `unicode-width` feature in terminal columns, so that wide characters such as
+    /// CJK count twice and combining marks do not count.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Row {
+    ///     name: &'static str,
+    ///     size: u64,
+    ///     kind: &'static str,
+    /// }
+    ///
+    /// let template: TemplateString<Row> = "{name} {size} {kind}".parse().unwrap();
+    /// let rows = template.format_many(&[
+    ///     Row { name: "Cargo.toml", size: 512, kind: "file" },
+    ///     Row { name: "src", size: 4096, kind: "dir" },
+    /// ]);
+    /// assert_eq!(rows, ["Cargo.toml  512 file", "src        4096 dir"]);
+    /// ```
+    pub fn format_many(&self, params: &[T]) -> Vec<String> {
+        let cells: Vec<Vec<String>> = params
+            .iter()
+            .map(|params| {
+                self.elements
+                    .iter()
+                    .map(|element| match element {
+                        TemplateElement::Text(_) => String::new(),
+                        element => render(Parameterized {
+                            params,
+                            elements: std::slice::from_ref(element),
+                            isolate: self.isolate,
+                            ctx: None,
+                        }),
+                    })
+                    .collect()
+            })
+            .collect();
+        let mut widths = vec![0; self.elements.len()];
+        for row in &cells {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(display_width(cell));
+            }
+        }
+
+        let last = self.elements.len().saturating_sub(1);
+        params
+            .iter()
+            .zip(cells)
+            .map(|(params, row)| {
+                let mut output = String::new();
+                for (i, (element, cell)) in self.elements.iter().zip(row).enumerate() {
+                    if let TemplateElement::Text(text) = element {
+                        output.push_str(text);
+                        continue;
+                    }
+                    let padding = if i == last { 0 } else { widths[i] - display_width(&cell) };
+                    let numeric =
+                        matches!(element, TemplateElement::Var(index) if params.field_number(*index).is_some());
+                    if numeric {
+                        output.extend(std::iter::repeat_n(' ', padding));
+                    }
+                    output.push_str(&cell);
+                    if !numeric {
+                        output.extend(std::iter::repeat_n(' ', padding));
+                    }
+                }
+                output
+            })
+            .collect()
+    }
+
+    /// Iterate over the rendered output in chunks: literal text is borrowed from the template and every interpolated
+    /// value is rendered into its own chunk.
+    ///
+    /// Select blocks are resolved, so the chunks of the chosen branch are yielded in place of the block. This allows
+    /// streaming the output into a sink or post-processing values separately from the surrounding text.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Data {
+    ///     name: String,
+    /// }
+    ///
+    /// let template: TemplateString<Data> = "Hello {name}!".parse().unwrap();
+    /// let data = Data { name: "Alice".into() };
+    /// let chunks: Vec<_> = template.render_iter(&data).collect();
+    /// assert_eq!(chunks, ["Hello ", "Alice", "!"]);
+    /// ```
+    pub fn render_iter<'i>(&'i self, params: &'i T) -> impl Iterator<Item = Cow<'i, str>> + 'i {
+        self.segments(params).map(move |segment| match segment {
+            Segment::Text(text) => Cow::Borrowed(text),
+            Segment::Placeholder(element) => Cow::Owned(self.render_placeholder(element, params, self.isolate)),
+        })
+    }
+
+    /// Iterate over the literal text runs and placeholders rendered for the parameters, with select blocks and named
+    /// blocks resolved.
+    pub(crate) fn segments<'i>(&'i self, params: &'i T) -> impl Iterator<Item = Segment<'i>> + 'i {
+        let mut stack = vec![self.elements.iter()];
+        std::iter::from_fn(move || {
+            loop {
+                let Some(element) = stack.last_mut()?.next() else {
+                    stack.pop();
+                    continue;
+                };
+                match element {
+                    TemplateElement::Text(text) => return Some(Segment::Text(text)),
+                    TemplateElement::Select { .. }
+                    | TemplateElement::Conditional { .. }
+                    | TemplateElement::Block { .. } => stack.push(element.nested(params).iter()),
+                    element => return Some(Segment::Placeholder(element)),
+                }
+            }
+        })
+    }
+
+    /// Render a single placeholder yielded by [`TemplateString::segments`].
+    pub(crate) fn render_placeholder(&self, element: &TemplateElement, params: &T, isolate: bool) -> String {
+        render(Parameterized {
+            params,
+            elements: std::slice::from_ref(element),
+            isolate,
+            ctx: None,
+        })
+    }
+
+    /// Format the template and report where every part of the output came from, e.g. to highlight the values of
+    /// placeholders in a live preview.
+    ///
+    /// Each mapping is a byte range of the output and the origin of that range: literal text or a placeholder. Select
+    /// blocks and named blocks have no mapping of their own, but the text and placeholders they render do.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use typlate::{ElementOrigin, TemplateParams, TemplateString};
+    /// #[derive(TemplateParams)]
+    /// struct Data {
+    ///     name: String,
+    /// }
+    ///
+    /// let template: TemplateString<Data> = "Hello {name}!".parse().unwrap();
+    /// let (output, mappings) = template.format_mapped(&Data { name: "Alice".into() });
+    /// assert_eq!(output, "Hello Alice!");
+    /// assert_eq!(mappings, [
+    ///     (0..6, ElementOrigin::Text),
+    ///     (6..11, ElementOrigin::Placeholder { name: "name", position: 0 }),
+    ///     (11..12, ElementOrigin::Text),
+    /// ]);
+    /// ```
+    pub fn format_mapped(&self, params: &T) -> (String, Vec<(Range<usize>, ElementOrigin)>) {
+        let mut mapper = Mapper {
+            params,
+            isolate: self.isolate,
+            output: String::new(),
+            mappings: vec![],
+        };
+        mapper.map(&self.elements, &mut 0);
+        (mapper.output, mapper.mappings)
+    }
+
+    fn field_indices(&self) -> Vec<usize> {
+        let mut indices = vec![];
+        for element in &self.elements {
+            element.visit_fields(&mut |index| indices.push(index));
+        }
+        indices
+    }
+}
+
 impl<T: TemplateStringParams> TemplateParams for T {
     type Template = TemplateString<Self>;
 
     fn format_template<'i>(&'i self, template: &'i Self::Template) -> impl Display {
-        Parameterized(self, template)
+        Parameterized {
+            params: self,
+            elements: &template.elements,
+            isolate: template.isolate,
+            ctx: None,
+        }
     }
 }
 
-pub struct Parameterized<'i, T>(&'i T, &'i TemplateString<T>);
+pub struct Parameterized<'i, T> {
+    pub(crate) params: &'i T,
+    pub(crate) elements: &'i [TemplateElement],
+    pub(crate) isolate: bool,
+    pub(crate) ctx: Option<&'i RenderCtx>,
+}
 
 impl<'i, T: TemplateStringParams> fmt::Display for Parameterized<'i, T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for element in &self.1.elements {
-            match element {
-                TemplateElement::Text(text) => f.write_str(text)?,
-                TemplateElement::Var(index) => self.0.fmt_field(f, *index)?,
-            }
+        for element in self.elements {
+            element.render(self.params, f, self.isolate, self.ctx)?;
         }
         Ok(())
     }
 }
 
-impl<T: TemplateStringParams> FromStr for TemplateString<T> {
-    type Err = String;
+/// A template together with parameter values, rendered when displayed, returned by [`TemplateString::display`].
+pub struct TemplateDisplay<'i, T> {
+    template: &'i TemplateString<T>,
+    params: &'i T,
+}
 
-    fn from_str(template: &str) -> Result<Self, Self::Err> {
-        let mut elements = vec![];
-        let mut chars = template.chars().peekable();
-        let mut text = String::new();
-
-        'outer: while let Some(char) = chars.next() {
-            match char {
-                '{' => {
-                    if chars.peek() == Some(&'{') {
-                        chars.next();
-                        text.push('{');
-                        continue;
-                    }
+impl<T: TemplateStringParams> fmt::Display for TemplateDisplay<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.params.format_template(self.template), f)
+    }
+}
 
-                    if !text.is_empty() {
-                        elements.push(TemplateElement::Text(text.clone()));
-                        text.clear();
-                    }
+/// Debug-formats the rendered string.
+impl<T: TemplateStringParams> fmt::Debug for TemplateDisplay<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&render(self), f)
+    }
+}
 
-                    let mut name = String::new();
-                    for char in chars.by_ref() {
-                        if char == '}' {
-                            let index = T::FIELDS
-                                .iter()
-                                .position(|&f| f == name)
-                                .ok_or_else(|| format!("Unknown field name: {name}"))?;
-                            elements.push(TemplateElement::Var(index));
-                            continue 'outer;
-                        } else {
-                            name.push(char);
+impl<T> Clone for TemplateDisplay<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for TemplateDisplay<'_, T> {}
+
+/// A part of a template rendered for some parameters, yielded by [`TemplateString::segments`].
+pub(crate) enum Segment<'i> {
+    Text(&'i str),
+    Placeholder(&'i TemplateElement),
+}
+
+/// The origin of a part of the output of [`TemplateString::format_mapped`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ElementOrigin {
+    /// Literal text of the template.
+    Text,
+    /// The value of a placeholder, where `position` is the position of the placeholder among all placeholders of the
+    /// template, in the order of [`TemplateString::placeholders`].
+    Placeholder { name: &'static str, position: usize },
+    /// The value of an arithmetic expression, where `position` is the position of its first field among all
+    /// placeholders of the template, in the order of [`TemplateString::placeholders`].
+    Expression { position: usize },
+    /// The value of a namespaced placeholder, such as `{env:HOME}`.
+    Namespaced,
+}
+
+/// Renders elements while recording the origin of every part of the output.
+struct Mapper<'i, T> {
+    params: &'i T,
+    isolate: bool,
+    output: String,
+    mappings: Vec<(Range<usize>, ElementOrigin)>,
+}
+
+impl<T: TemplateStringParams> Mapper<'_, T> {
+    /// Render the elements, where `position` is the position of the next placeholder.
+    fn map(&mut self, elements: &[TemplateElement], position: &mut usize) {
+        for element in elements {
+            let start = self.output.len();
+            match element {
+                TemplateElement::Text(text) => {
+                    self.output.push_str(text);
+                    self.mappings.push((start..self.output.len(), ElementOrigin::Text));
+                }
+                TemplateElement::Var(index)
+                | TemplateElement::Filtered { index, .. }
+                | TemplateElement::Formatted { index, .. } => {
+                    let value = Parameterized {
+                        params: self.params,
+                        elements: std::slice::from_ref(element),
+                        isolate: self.isolate,
+                        ctx: None,
+                    };
+                    self.output.push_str(&render(value));
+                    let origin = ElementOrigin::Placeholder {
+                        name: T::FIELDS[*index],
+                        position: *position,
+                    };
+                    self.mappings.push((start..self.output.len(), origin));
+                    *position += 1;
+                }
+                TemplateElement::Expr(expr) => {
+                    let value = Parameterized {
+                        params: self.params,
+                        elements: std::slice::from_ref(element),
+                        isolate: self.isolate,
+                        ctx: None,
+                    };
+                    self.output.push_str(&render(value));
+                    let origin = ElementOrigin::Expression { position: *position };
+                    self.mappings.push((start..self.output.len(), origin));
+                    expr.visit_fields(&mut |_| *position += 1);
+                }
+                TemplateElement::Namespaced { .. } => {
+                    let value = Parameterized {
+                        params: self.params,
+                        elements: std::slice::from_ref(element),
+                        isolate: self.isolate,
+                        ctx: None,
+                    };
+                    self.output.push_str(&render(value));
+                    self.mappings
+                        .push((start..self.output.len(), ElementOrigin::Namespaced));
+                }
+                TemplateElement::Select { arms, .. } => {
+                    *position += 1;
+                    let chosen = element.select_arm(self.params);
+                    for (i, (_, branch)) in arms.iter().enumerate() {
+                        if Some(i) == chosen {
+                            self.map(branch, &mut position.clone());
                         }
+                        branch
+                            .iter()
+                            .for_each(|element| element.visit_fields(&mut |_| *position += 1));
                     }
-                    return Err("Unclosed bracket in template".to_string());
-                }
-                '}' => {
-                    if chars.peek() == Some(&'}') {
-                        chars.next();
-                        text.push('}');
-                    } else {
-                        return Err("Unmatched closing bracket".to_string());
+                }
+                TemplateElement::Conditional {
+                    condition,
+                    then,
+                    otherwise,
+                } => {
+                    condition.visit_fields(&mut |_| *position += 1);
+                    let holds = condition.eval(self.params);
+                    for (branch, chosen) in [(then, holds), (otherwise, !holds)] {
+                        if chosen {
+                            self.map(branch, &mut position.clone());
+                        }
+                        branch
+                            .iter()
+                            .for_each(|element| element.visit_fields(&mut |_| *position += 1));
                     }
                 }
-                _ => text.push(char),
+                TemplateElement::Block { elements, .. } => self.map(elements, position),
             }
         }
+    }
+}
 
-        if !text.is_empty() {
-            elements.push(TemplateElement::Text(text));
-        }
-        Ok(Self {
-            elements,
+/// The width of a rendered value when padded or laid out in columns: its number of characters, or with the
+/// `unicode-width` feature its number of terminal columns.
+pub(crate) fn display_width(value: &str) -> usize {
+    #[cfg(feature = "unicode-width")]
+    return unicode_width::UnicodeWidthStr::width(value);
+    #[cfg(not(feature = "unicode-width"))]
+    value.chars().count()
+}
+
+/// Incrementally builds a [`TemplateString`] from literal text and placeholder names.
+///
+/// Adjacent text is merged into a single element and placeholder names are validated against `T::FIELDS`.
+pub(crate) struct Builder<T> {
+    elements: Vec<TemplateElement>,
+    text: String,
+    phantom: PhantomData<T>,
+}
+
+impl<T: TemplateStringParams> Builder<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            elements: vec![],
+            text: String::new(),
             phantom: PhantomData,
-        })
+        }
+    }
+
+    pub(crate) fn push_char(&mut self, char: char) {
+        self.text.push(char);
+    }
+
+    pub(crate) fn push_str(&mut self, text: &str) {
+        self.text.push_str(text);
+    }
+
+    /// Push a placeholder, where `span` is the location of `name` in the source, used for error reporting.
+    pub(crate) fn push_var(&mut self, name: &str, span: std::ops::Range<usize>) -> Result<(), TemplateParseError> {
+        let index = crate::parse::field_index::<T>(name, span)?;
+        self.push_element(TemplateElement::Var(index));
+        Ok(())
+    }
+
+    pub(crate) fn push_element(&mut self, element: TemplateElement) {
+        match element {
+            TemplateElement::Text(text) => self.text.push_str(&text),
+            element => {
+                self.flush_text();
+                self.elements.push(element);
+            }
+        }
+    }
+
+    pub(crate) fn into_elements(mut self) -> Vec<TemplateElement> {
+        self.flush_text();
+        self.elements
+    }
+
+    pub(crate) fn finish(self) -> TemplateString<T> {
+        TemplateString::from_elements(self.into_elements())
+    }
+
+    fn flush_text(&mut self) {
+        if !self.text.is_empty() {
+            self.elements
+                .push(TemplateElement::Text(std::mem::take(&mut self.text)));
+        }
+    }
+}
+
+impl<T: TemplateStringParams> FromStr for TemplateString<T> {
+    type Err = TemplateParseError;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        Self::parse_with(template, &ParseOptions::default())
     }
 }
 
@@ -138,6 +1726,7 @@ impl<T> Clone for TemplateString<T> {
     fn clone(&self) -> Self {
         Self {
             elements: self.elements.clone(),
+            isolate: self.isolate,
             phantom: PhantomData,
         }
     }
@@ -145,7 +1734,7 @@ impl<T> Clone for TemplateString<T> {
 
 impl<T> PartialEq for TemplateString<T> {
     fn eq(&self, other: &Self) -> bool {
-        self.elements == other.elements
+        (&self.elements, self.isolate) == (&other.elements, other.isolate)
     }
 }
 
@@ -159,13 +1748,14 @@ impl<T> PartialOrd for TemplateString<T> {
 
 impl<T> Ord for TemplateString<T> {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.elements.cmp(&other.elements)
+        (&self.elements, self.isolate).cmp(&(&other.elements, other.isolate))
     }
 }
 
 impl<T> Hash for TemplateString<T> {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.elements.hash(state);
+        self.isolate.hash(state);
     }
 }
 
@@ -178,22 +1768,7 @@ impl<T: TemplateStringParams> fmt::Debug for TemplateString<T> {
 impl<T: TemplateStringParams> fmt::Display for TemplateString<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for element in &self.elements {
-            match element {
-                TemplateElement::Text(text) => {
-                    for char in text.chars() {
-                        match char {
-                            '{' => f.write_str("{{")?,
-                            '}' => f.write_str("}}")?,
-                            _ => f.write_char(char)?,
-                        }
-                    }
-                }
-                TemplateElement::Var(index) => {
-                    f.write_char('{')?;
-                    f.write_str(T::FIELDS[*index])?;
-                    f.write_char('}')?;
-                }
-            }
+            element.write_source(f, T::FIELDS)?;
         }
         Ok(())
     }
@@ -201,33 +1776,138 @@ impl<T: TemplateStringParams> fmt::Display for TemplateString<T> {
 
 #[cfg(feature = "serde")]
 mod serde_impl {
+    use serde::ser::SerializeMap;
     use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
 
     use super::*;
 
+    /// Human-readable formats (JSON, TOML, ...) use the template source string, or a `source` and `isolate` map for
+    /// templates with bidirectional isolation, as the source does not record it. Compact formats (bincode,
+    /// postcard, ...) store the pre-parsed template as the bytes of [`TemplateString::to_bytes`], so that loading does
+    /// not need to parse again. The bytes record the field names of the parameter type, which must match when loading,
+    /// and the bidirectional isolation flag.
     impl<T: TemplateStringParams> Serialize for TemplateString<T> {
         fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-            serializer.serialize_str(&self.to_string())
+            if serializer.is_human_readable() && self.isolate {
+                let mut map = serializer.serialize_map(Some(2))?;
+                map.serialize_entry("source", &self.to_string())?;
+                map.serialize_entry("isolate", &true)?;
+                map.end()
+            } else if serializer.is_human_readable() {
+                serializer.serialize_str(&self.to_string())
+            } else {
+                serializer.serialize_bytes(&self.to_bytes())
+            }
         }
     }
 
     impl<'de, T: TemplateStringParams> Deserialize<'de> for TemplateString<T> {
         fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
-            deserializer.deserialize_str(TemplateStringVisitor(PhantomData))
+            Self::deserialize_with(deserializer, &ParseOptions::default())
+        }
+    }
+
+    impl<T: TemplateStringParams> TemplateString<T> {
+        /// Deserialize any of the forms with the options: sources are parsed with them, segments are resolved with
+        /// them, and the compact form is decoded with [`TemplateString::from_bytes_with`], which checks the elements
+        /// against the options as the parser would check a template source.
+        pub(crate) fn deserialize_with<'de, D: Deserializer<'de>>(
+            deserializer: D,
+            options: &ParseOptions,
+        ) -> Result<Self, D::Error> {
+            if deserializer.is_human_readable() {
+                deserializer.deserialize_any(TemplateStringVisitor(options, PhantomData))
+            } else {
+                deserializer.deserialize_bytes(BytesVisitor(options, PhantomData))
+            }
+        }
+    }
+
+    struct BytesVisitor<'o, T>(&'o ParseOptions, PhantomData<T>);
+
+    impl<'de, T: TemplateStringParams> de::Visitor<'de> for BytesVisitor<'_, T> {
+        type Value = TemplateString<T>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("an encoded template")
         }
+
+        fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Self::Value, E> {
+            TemplateString::from_bytes_with(value, self.0).map_err(de::Error::custom)
+        }
+
+        // Some formats encode bytes as a sequence of integers.
+        fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+            while let Some(byte) = seq.next_element()? {
+                bytes.push(byte);
+            }
+            self.visit_bytes(&bytes)
+        }
+    }
+
+    /// A single segment of the structured template form, e.g. `["Hello ", {"var": "name"}]`.
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Segment {
+        Text(String),
+        Var { var: String },
     }
 
-    struct TemplateStringVisitor<T>(PhantomData<T>);
+    struct TemplateStringVisitor<'o, T>(&'o ParseOptions, PhantomData<T>);
 
-    impl<'de, T: TemplateStringParams> de::Visitor<'de> for TemplateStringVisitor<T> {
+    impl<'de, T: TemplateStringParams> de::Visitor<'de> for TemplateStringVisitor<'_, T> {
         type Value = TemplateString<T>;
 
         fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-            formatter.write_str("a template string")
+            formatter.write_str("a template string, a sequence of template segments or a map with the template source")
         }
 
         fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
-            value.parse().map_err(de::Error::custom)
+            TemplateString::parse_with(value, self.0).map_err(de::Error::custom)
+        }
+
+        fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut source = None::<String>;
+            let mut isolate = false;
+            while let Some(key) = map.next_key::<String>()? {
+                match key.as_str() {
+                    "source" => source = Some(map.next_value()?),
+                    "isolate" => isolate = map.next_value()?,
+                    _ => return Err(de::Error::unknown_field(&key, &["source", "isolate"])),
+                }
+            }
+            let source = source.ok_or_else(|| de::Error::missing_field("source"))?;
+            let template = TemplateString::<T>::parse_with(&source, self.0).map_err(de::Error::custom)?;
+            Ok(template.with_bidi_isolation(isolate))
+        }
+
+        fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut builder = Builder::new();
+            let mut position = 0;
+            while let Some(segment) = seq.next_element()? {
+                match segment {
+                    Segment::Text(text) => builder.push_str(&text),
+                    // Segments have no source to point into, so errors report the position of the segment instead.
+                    Segment::Var { var } => match T::FIELDS.iter().position(|&field| field == var) {
+                        Some(index) => builder.push_element(TemplateElement::Var(index)),
+                        // As in sources parsed leniently, unknown fields are kept as literal placeholders.
+                        None if self.0.lenient() => builder.push_str(&format!("{{{var}}}")),
+                        None if crate::parse::is_field_name(&var) => {
+                            return Err(de::Error::custom(format!(
+                                "Unknown field name in segment {position}: {var}"
+                            )));
+                        }
+                        None => {
+                            return Err(de::Error::custom(format!(
+                                "Invalid field name in segment {position}: {var}"
+                            )));
+                        }
+                    },
+                }
+                position += 1;
+            }
+            Ok(builder.finish())
         }
     }
 }
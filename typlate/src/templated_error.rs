@@ -0,0 +1,92 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::{TemplateString, TemplateStringParams};
+
+type Source = Box<dyn Error + Send + Sync + 'static>;
+
+/// An error whose message is a template rendered with its parameters, e.g. a user-facing message defined in
+/// configuration.
+///
+/// The message is rendered when the error is displayed. An underlying error can be attached with
+/// [`with_source`](Self::with_source), so the error chains like any other, e.g. with anyhow's `context`.
+///
+/// ## Examples
+///
+/// ```
+/// # use typlate::{TemplateParams, TemplatedError};
+/// #[derive(TemplateParams)]
+/// struct Quota {
+///     user: String,
+///     limit: u32,
+/// }
+///
+/// fn check(used: u32) -> Result<(), TemplatedError<Quota>> {
+///     let template = "{user} exceeded the quota of {limit} files".parse().unwrap();
+///     let quota = Quota { user: "alice".into(), limit: 100 };
+///     if used > quota.limit {
+///         return Err(TemplatedError::new(template, quota));
+///     }
+///     Ok(())
+/// }
+///
+/// assert_eq!(check(120).unwrap_err().to_string(), "alice exceeded the quota of 100 files");
+/// ```
+pub struct TemplatedError<T> {
+    template: TemplateString<T>,
+    params: T,
+    source: Option<Source>,
+}
+
+impl<T> TemplatedError<T> {
+    /// Create an error with a message template and its parameter values.
+    pub fn new(template: TemplateString<T>, params: T) -> Self {
+        Self {
+            template,
+            params,
+            source: None,
+        }
+    }
+
+    /// Attach the underlying error, returned by [`Error::source`].
+    pub fn with_source(mut self, source: impl Into<Source>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// The message template.
+    pub fn template(&self) -> &TemplateString<T> {
+        &self.template
+    }
+
+    /// The parameter values of the message.
+    pub fn params(&self) -> &T {
+        &self.params
+    }
+
+    /// Return the parameter values, dropping the template and the source.
+    pub fn into_params(self) -> T {
+        self.params
+    }
+}
+
+impl<T: TemplateStringParams> fmt::Display for TemplatedError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.template.display(&self.params), f)
+    }
+}
+
+impl<T: TemplateStringParams> fmt::Debug for TemplatedError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TemplatedError")
+            .field("message", &self.template.display(&self.params))
+            .field("source", &self.source)
+            .finish()
+    }
+}
+
+impl<T: TemplateStringParams> Error for TemplatedError<T> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source.as_deref().map(|source| source as &(dyn Error + 'static))
+    }
+}
@@ -0,0 +1,198 @@
+//! Helpers for testing templates and the code that consumes them.
+//!
+//! [`assert_renders!`](crate::assert_renders) checks the output of a template, and [`snapshot`] and [`snapshot_set`]
+//! render templates with sample values for snapshot frameworks such as [insta](https://docs.rs/insta), so message
+//! catalogs can be tested without writing parameter values for every template. [`sample_snapshot`] renders with the
+//! [`Sample`] parameters generated by `#[template(sample)]` instead.
+//!
+//! With the `proptest` feature, [`valid_template`] generates template strings that parse for a parameter type, and
+//! [`mutated_template`] breaks them in small ways (a stray bracket, an unknown field, a missing character or a cut-off
+//! end), so that the result may or may not parse. Together they make it easy to property-test code that loads
+//! templates and handles their errors.
+//!
+//! ## Examples
+//!
+//! ```
+//! # use typlate::testing::snapshot;
+//! # use typlate::{TemplateParams, TemplateString, assert_renders};
+//! #[derive(TemplateParams)]
+//! struct Data {
+//!     name: String,
+//!     count: u32,
+//! }
+//!
+//! let template: TemplateString<Data> = "{name} has {count} items".parse().unwrap();
+//! assert_renders!(template, Data { name: "Alice".into(), count: 3 }, "Alice has 3 items");
+//! assert_eq!(snapshot(&template), "{name} has {count} items\n=> <name> has <count> items\n");
+//! ```
+//!
+//! ```
+//! # use typlate::testing::{mutated_template, valid_template};
+//! # use typlate::{TemplateParams, TemplateString};
+//! # use proptest::prelude::*;
+//! #[derive(TemplateParams)]
+//! struct Data {
+//!     name: String,
+//!     count: u32,
+//! }
+//!
+//! proptest!(|(source in valid_template::<Data>())| {
+//!     prop_assert!(source.parse::<TemplateString<Data>>().is_ok());
+//! });
+//!
+//! proptest!(|(source in mutated_template::<Data>())| {
+//!     if let Err(error) = source.parse::<TemplateString<Data>>() {
+//!         prop_assert!(error.span().end <= source.len() + 1);
+//!     }
+//! });
+//! ```
+
+use std::fmt::Write;
+
+#[cfg(feature = "proptest")]
+use proptest::prelude::*;
+#[cfg(feature = "proptest")]
+use proptest::sample::{Index, select};
+
+use crate::{TemplateSet, TemplateString, TemplateStringParams};
+
+/// Assert that a template renders to the expected string with the given parameter values.
+///
+/// On failure, the message includes the template source along with both strings.
+///
+/// ## Examples
+///
+/// ```
+/// # use typlate::{TemplateParams, TemplateString, assert_renders};
+/// #[derive(TemplateParams)]
+/// struct Data {
+///     name: String,
+/// }
+///
+/// let template: TemplateString<Data> = "Hello {name|upper}!".parse().unwrap();
+/// assert_renders!(template, Data { name: "Bob".into() }, "Hello BOB!");
+/// ```
+#[macro_export]
+macro_rules! assert_renders {
+    ($template:expr, $params:expr, $expected:expr $(,)?) => {{
+        let template = &$template;
+        let rendered = template.format(&$params);
+        ::std::assert_eq!(rendered, $expected, "template `{}` rendered unexpectedly", template);
+    }};
+}
+
+#[doc(inline)]
+pub use crate::assert_renders;
+
+/// Render a template for a snapshot: its source, followed by its [preview](TemplateString::preview) with the field
+/// names as sample values.
+///
+/// The output only depends on the template, so the snapshot changes exactly when the template does.
+pub fn snapshot<T: TemplateStringParams>(template: &TemplateString<T>) -> String {
+    format!("{template}\n=> {}\n", template.preview())
+}
+
+/// Deterministic parameter values for previews and snapshots, implemented by `#[derive(TemplateParams)]` with
+/// `#[template(sample)]` on the struct.
+///
+/// Strings default to the field name, numbers to the 1-based field index, booleans to `true`, options to `Some` and
+/// other types to their [`Default`] value. A field can set its own value with `#[template(sample = "...")]`, which is
+/// used as is for strings and parsed with [`FromStr`](std::str::FromStr) for other types.
+///
+/// ## Examples
+///
+/// ```
+/// # use typlate::testing::Sample;
+/// # use typlate::{TemplateParams, TemplateString};
+/// #[derive(TemplateParams)]
+/// #[template(sample)]
+/// struct Cart {
+///     #[template(sample = "Alice")]
+///     name: String,
+///     count: u32,
+///     coupon: Option<String>,
+/// }
+///
+/// let template: TemplateString<Cart> = "{name} has {count} items ({coupon})".parse().unwrap();
+/// assert_eq!(template.format(&Cart::sample()), "Alice has 2 items (coupon)");
+/// ```
+pub trait Sample {
+    /// The sample parameter values.
+    fn sample() -> Self;
+}
+
+/// Render a template for a snapshot: its source, followed by its output with the [sample](Sample) parameter values.
+pub fn sample_snapshot<T: TemplateStringParams + Sample>(template: &TemplateString<T>) -> String {
+    format!("{template}\n=> {}\n", template.format(&T::sample()))
+}
+
+/// Render every template of a set for a snapshot, in sorted order of names, as with [`snapshot`].
+pub fn snapshot_set<T: TemplateStringParams>(set: &TemplateSet<T>) -> String {
+    let mut output = String::new();
+    for (name, template) in set.iter() {
+        let _ = write!(output, "[{name}]\n{}", snapshot(template));
+    }
+    output
+}
+
+/// A field name that no parameter type is expected to have, inserted by [`mutated_template`].
+#[cfg(feature = "proptest")]
+const UNKNOWN_FIELD: &str = "__typlate_unknown";
+
+/// Generate template strings that parse for `T`.
+///
+/// The templates mix literal text, escaped brackets, placeholders with built-in filters, arithmetic expressions and
+/// conditional sections over the fields of `T`. Templates for a parameter type without fields are plain text.
+#[cfg(feature = "proptest")]
+pub fn valid_template<T: TemplateStringParams>() -> BoxedStrategy<String> {
+    prop::collection::vec(segment(T::FIELDS), 0..8)
+        .prop_map(|segments| segments.concat())
+        .boxed()
+}
+
+/// Generate near-valid template strings for `T`: a [valid template](valid_template) with a single mutation, which
+/// may or may not make it fail to parse.
+#[cfg(feature = "proptest")]
+pub fn mutated_template<T: TemplateStringParams>() -> BoxedStrategy<String> {
+    (valid_template::<T>(), any::<Index>(), 0..5u8)
+        .prop_map(|(mut source, index, mutation)| {
+            let boundaries: Vec<_> = source.char_indices().map(|(i, _)| i).chain([source.len()]).collect();
+            let at = *index.get(&boundaries);
+            match mutation {
+                0 => source.insert(at, '{'),
+                1 => source.insert(at, '}'),
+                2 => source.insert_str(at, &format!("{{{UNKNOWN_FIELD}}}")),
+                3 => {
+                    if at < source.len() {
+                        source.remove(at);
+                    }
+                }
+                _ => source.truncate(at),
+            }
+            source
+        })
+        .boxed()
+}
+
+#[cfg(feature = "proptest")]
+fn segment(fields: &'static [&'static str]) -> BoxedStrategy<String> {
+    let text = prop_oneof![
+        4 => "[a-zA-Z0-9 ,.!?:-]{1,8}",
+        1 => Just("{{".to_string()),
+        1 => Just("}}".to_string()),
+    ];
+    if fields.is_empty() {
+        return text.boxed();
+    }
+    let field = select(fields);
+    let filter = select(&["upper", "lower", "trim", "truncate(5)"][..]);
+    let op = select(&['+', '-', '*', '/'][..]);
+    prop_oneof![
+        3 => text,
+        3 => field.clone().prop_map(|field| format!("{{{field}}}")),
+        1 => (field.clone(), filter).prop_map(|(field, filter)| format!("{{{field}|{filter}}}")),
+        1 => (field.clone(), op, 1..100u32).prop_map(|(field, op, n)| format!("{{{field} {op} {n}}}")),
+        1 => (field, "[a-z ]{0,6}").prop_map(|(field, body)| format!("{{?{field}}}{body}{{/}}")),
+    ]
+    .boxed()
+}
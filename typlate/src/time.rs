@@ -0,0 +1,61 @@
+//! Points in time: the display of `SystemTime` fields and relative times for the `relative` filter.
+
+use std::fmt;
+use std::time::SystemTime;
+
+/// Write a `SystemTime` in RFC 3339 format in UTC, with whole seconds, e.g. `2023-11-14T22:13:20Z`.
+///
+/// The derive macro renders `SystemTime` fields with this, as the type does not implement `Display`.
+pub fn fmt_system_time(time: &SystemTime, f: &mut fmt::Formatter) -> fmt::Result {
+    let seconds = match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs() as i64,
+        Err(error) => -(error.duration().as_secs_f64().ceil() as i64),
+    };
+    let (days, seconds) = (seconds.div_euclid(86400), seconds.rem_euclid(86400));
+    // The civil date of a day count from the Unix epoch, in eras of 400 years starting on March 1.
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    write!(
+        f,
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+    )
+}
+
+/// Format a time relative to `now` for the `relative` filter, in the largest whole unit, e.g. `5 minutes ago` or
+/// `in 2 days`. Months are 30 days and years 365 days.
+pub(crate) fn format_relative(time: SystemTime, now: SystemTime) -> String {
+    const UNITS: [(&str, u64); 7] = [
+        ("year", 365 * 86400),
+        ("month", 30 * 86400),
+        ("week", 7 * 86400),
+        ("day", 86400),
+        ("hour", 3600),
+        ("minute", 60),
+        ("second", 1),
+    ];
+    let (future, seconds) = match time.duration_since(now) {
+        Ok(duration) => (true, duration.as_secs()),
+        Err(error) => (false, error.duration().as_secs()),
+    };
+    let Some((unit, count)) = UNITS
+        .iter()
+        .find_map(|&(unit, length)| (seconds >= length).then(|| (unit, seconds / length)))
+    else {
+        return "now".to_string();
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    match future {
+        true => format!("in {count} {unit}{plural}"),
+        false => format!("{count} {unit}{plural} ago"),
+    }
+}
@@ -0,0 +1,259 @@
+use std::ops::Range;
+
+/// The kind of a token returned by [`tokenize`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum TokenKind {
+    /// Literal text.
+    Text,
+    /// An escaped bracket, `{{` or `}}`.
+    Escape,
+    /// A bracket or punctuation delimiting a placeholder, block tag or conditional section, such as `{`, `{%`, `{?`,
+    /// `{:}`, `|`, `,` or `(`.
+    Delimiter,
+    /// A field name, or the name of a block.
+    Name,
+    /// A filter name.
+    Filter,
+    /// A keyword, such as `select`, `select-gender`, `block` or `endblock`.
+    Keyword,
+    /// The key of a select arm.
+    Key,
+    /// An arithmetic, comparison or inline conditional operator.
+    Operator,
+    /// A number literal.
+    Number,
+    /// A string literal, or an unquoted filter argument.
+    String,
+    /// A character that is not valid where it appears, such as an unmatched `}`.
+    Invalid,
+}
+
+/// Split a template into classified tokens, e.g. for syntax highlighting in editors and command line tools.
+///
+/// Tokenizing does not depend on a parameter type, so field names are not checked, and it never fails: incomplete
+/// templates are tokenized up to their end, and stray characters are classified as [`TokenKind::Invalid`]. Whitespace
+/// inside placeholders is not part of any token.
+///
+/// ## Examples
+///
+/// ```
+/// # use typlate::TokenKind;
+/// let tokens = typlate::tokenize("Hi {name|upper}!");
+/// assert_eq!(
+///     tokens,
+///     [
+///         (0..3, TokenKind::Text),
+///         (3..4, TokenKind::Delimiter),
+///         (4..8, TokenKind::Name),
+///         (8..9, TokenKind::Delimiter),
+///         (9..14, TokenKind::Filter),
+///         (14..15, TokenKind::Delimiter),
+///         (15..16, TokenKind::Text),
+///     ],
+/// );
+/// ```
+pub fn tokenize(source: &str) -> Vec<(Range<usize>, TokenKind)> {
+    let mut tokenizer = Tokenizer {
+        source,
+        position: 0,
+        tokens: vec![],
+    };
+    tokenizer.text(false);
+    tokenizer.tokens
+}
+
+struct Tokenizer<'s> {
+    source: &'s str,
+    position: usize,
+    tokens: Vec<(Range<usize>, TokenKind)>,
+}
+
+impl Tokenizer<'_> {
+    /// Tokenize literal text up to the end of the source, or in a select branch, up to its closing `}`.
+    fn text(&mut self, branch: bool) {
+        let mut start = self.position;
+        while let Some(char) = self.peek() {
+            if char != '{' && char != '}' {
+                self.position += char.len_utf8();
+                continue;
+            }
+            if start < self.position {
+                self.tokens.push((start..self.position, TokenKind::Text));
+            }
+            let rest = self.rest();
+            if rest.starts_with("{{") || !branch && rest.starts_with("}}") {
+                self.token(2, TokenKind::Escape);
+            } else if char == '}' && branch {
+                return;
+            } else if char == '}' {
+                self.token(1, TokenKind::Invalid);
+            } else if rest.starts_with("{%") {
+                self.block_tag();
+            } else if rest.starts_with("{:}") || rest.starts_with("{/}") {
+                self.token(3, TokenKind::Delimiter);
+            } else if rest.starts_with("{?") {
+                self.token(2, TokenKind::Delimiter);
+                self.placeholder();
+            } else {
+                self.token(1, TokenKind::Delimiter);
+                self.placeholder();
+            }
+            start = self.position;
+        }
+        if start < self.position {
+            self.tokens.push((start..self.position, TokenKind::Text));
+        }
+    }
+
+    /// Tokenize a `{%...%}` tag.
+    fn block_tag(&mut self) {
+        self.token(2, TokenKind::Delimiter);
+        let mut kind = TokenKind::Keyword;
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => return,
+                Some('%') if self.rest().starts_with("%}") => return self.token(2, TokenKind::Delimiter),
+                Some(char) if is_name(char) => {
+                    self.run(is_name, kind);
+                    kind = TokenKind::Name;
+                }
+                Some(char) => self.token(char.len_utf8(), TokenKind::Invalid),
+            }
+        }
+    }
+
+    /// Tokenize a placeholder after its opening bracket, up to and including its closing `}`.
+    fn placeholder(&mut self) {
+        self.expression();
+        match self.peek() {
+            Some('}') => self.token(1, TokenKind::Delimiter),
+            Some('|') => self.filters(),
+            Some(',') => self.select(),
+            _ => {}
+        }
+    }
+
+    /// Tokenize a field name, expression or condition, up to a `}`, `|` or `,`.
+    fn expression(&mut self) {
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None | Some('}' | '|' | ',') => return,
+                Some('"') => self.string(),
+                Some(char) if char.is_ascii_digit() || char == '.' => self.run(is_name, TokenKind::Number),
+                Some(char) if is_name(char) => self.run(is_name, TokenKind::Name),
+                Some('(' | ')') => self.token(1, TokenKind::Delimiter),
+                Some(_) if ["==", "!=", "<=", ">="].iter().any(|op| self.rest().starts_with(op)) => {
+                    self.token(2, TokenKind::Operator)
+                }
+                Some('+' | '-' | '*' | '/' | '<' | '>' | '?' | ':') => self.token(1, TokenKind::Operator),
+                Some(char) => self.token(char.len_utf8(), TokenKind::Invalid),
+            }
+        }
+    }
+
+    /// Tokenize the filters of a placeholder, from the first `|` up to and including the closing `}`.
+    fn filters(&mut self) {
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => return,
+                Some('}') => return self.token(1, TokenKind::Delimiter),
+                Some('|') => self.token(1, TokenKind::Delimiter),
+                Some('(') => self.arguments(),
+                Some(char) if is_name(char) => self.run(is_name, TokenKind::Filter),
+                Some(char) => self.token(char.len_utf8(), TokenKind::Invalid),
+            }
+        }
+    }
+
+    /// Tokenize the arguments of a filter, from the `(` up to and including the `)`.
+    fn arguments(&mut self) {
+        self.token(1, TokenKind::Delimiter);
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None | Some('}') => return,
+                Some(')') => return self.token(1, TokenKind::Delimiter),
+                Some(',') => self.token(1, TokenKind::Delimiter),
+                Some('"') => self.string(),
+                Some(char) => {
+                    let kind = match char.is_ascii_digit() {
+                        true => TokenKind::Number,
+                        false => TokenKind::String,
+                    };
+                    self.run(|char| !matches!(char, ',' | ')' | '}') && !char.is_whitespace(), kind);
+                }
+            }
+        }
+    }
+
+    /// Tokenize a select block, from the `,` after the field name up to and including the closing `}`.
+    fn select(&mut self) {
+        self.token(1, TokenKind::Delimiter);
+        self.skip_whitespace();
+        self.run(|char| is_name(char) || char == '-', TokenKind::Keyword);
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => return,
+                Some('}') => return self.token(1, TokenKind::Delimiter),
+                Some(',') => self.token(1, TokenKind::Delimiter),
+                Some('{') => {
+                    self.token(1, TokenKind::Delimiter);
+                    self.text(true);
+                    if self.peek() == Some('}') {
+                        self.token(1, TokenKind::Delimiter);
+                    }
+                }
+                Some(_) => self.run(
+                    |char| !matches!(char, '{' | '}') && !char.is_whitespace(),
+                    TokenKind::Key,
+                ),
+            }
+        }
+    }
+
+    /// Tokenize a string literal in double quotes, or up to the end of the source if it is not closed.
+    fn string(&mut self) {
+        let start = self.position;
+        self.position += 1;
+        self.position = match self.rest().find('"') {
+            Some(end) => self.position + end + 1,
+            None => self.source.len(),
+        };
+        self.tokens.push((start..self.position, TokenKind::String));
+    }
+
+    /// Push a token of `len` bytes.
+    fn token(&mut self, len: usize, kind: TokenKind) {
+        self.tokens.push((self.position..self.position + len, kind));
+        self.position += len;
+    }
+
+    /// Push a token of the characters matching `f`.
+    fn run(&mut self, f: impl Fn(char) -> bool, kind: TokenKind) {
+        let len = self.rest().find(|char| !f(char)).unwrap_or(self.rest().len());
+        if len > 0 {
+            self.token(len, kind);
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn rest(&self) -> &str {
+        &self.source[self.position..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.position = self.source.len() - self.rest().trim_start().len();
+    }
+}
+
+fn is_name(char: char) -> bool {
+    char.is_alphanumeric() || char == '_' || char == '.'
+}
@@ -0,0 +1,110 @@
+//! Validate and preview templates from JavaScript with [wasm-bindgen](https://docs.rs/wasm-bindgen), e.g. in a web
+//! admin UI, with the same parser as the server.
+//!
+//! wasm-bindgen cannot export generic functions, so the functions of this module are exported through a thin wrapper
+//! for each parameter type. Errors are returned as [`TemplateError`]s, which are exported as a JavaScript class.
+//!
+//! ## Examples
+//!
+//! ```
+//! # use typlate::TemplateParams;
+//! # use typlate::wasm::TemplateError;
+//! # use wasm_bindgen::prelude::wasm_bindgen;
+//! #[derive(TemplateParams)]
+//! struct Cart {
+//!     name: String,
+//!     count: u32,
+//! }
+//!
+//! #[wasm_bindgen(js_name = validateCart)]
+//! pub fn validate_cart(source: &str) -> Vec<TemplateError> {
+//!     typlate::wasm::validate::<Cart>(source)
+//! }
+//!
+//! #[wasm_bindgen(js_name = previewCart)]
+//! pub fn preview_cart(source: &str) -> Result<String, TemplateError> {
+//!     typlate::wasm::preview::<Cart>(source)
+//! }
+//!
+//! assert_eq!(validate_cart("{name} has {count} items"), []);
+//! assert_eq!(validate_cart("{nam} has {count items")[0].message(), "Unknown field name: nam");
+//! assert_eq!(preview_cart("{name|upper} has {count} items").unwrap(), "<NAME> has <count> items");
+//! ```
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{TemplateParseError, TemplateString, TemplateStringParams};
+
+/// An error in a template source, with its location as offsets in UTF-16 code units, as used by JavaScript strings.
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateError {
+    message: String,
+    start: usize,
+    end: usize,
+}
+
+#[wasm_bindgen]
+impl TemplateError {
+    /// The error message.
+    #[wasm_bindgen(getter)]
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+
+    /// The offset of the start of the error in the source.
+    #[wasm_bindgen(getter)]
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// The offset of the end of the error in the source.
+    #[wasm_bindgen(getter)]
+    pub fn end(&self) -> usize {
+        self.end
+    }
+}
+
+impl TemplateError {
+    fn new(source: &str, error: &TemplateParseError) -> Self {
+        let span = error.span();
+        Self {
+            message: error.to_string(),
+            start: utf16_offset(source, span.start),
+            end: utf16_offset(source, span.end),
+        }
+    }
+}
+
+/// Parse a template and return every error in it, or nothing if it is valid.
+pub fn validate<T: TemplateStringParams>(source: &str) -> Vec<TemplateError> {
+    match TemplateString::<T>::parse_all_errors(source) {
+        Ok(_) => vec![],
+        Err(errors) => errors.iter().map(|error| TemplateError::new(source, error)).collect(),
+    }
+}
+
+/// Parse a template and return it in canonical form, as written by [`TemplateString`]'s `Display` implementation.
+pub fn parse<T: TemplateStringParams>(source: &str) -> Result<String, TemplateError> {
+    match source.parse::<TemplateString<T>>() {
+        Ok(template) => Ok(template.to_string()),
+        Err(error) => Err(TemplateError::new(source, &error)),
+    }
+}
+
+/// Parse a template and render it with sample values, as with [`TemplateString::preview`].
+pub fn preview<T: TemplateStringParams>(source: &str) -> Result<String, TemplateError> {
+    match source.parse::<TemplateString<T>>() {
+        Ok(template) => Ok(template.preview()),
+        Err(error) => Err(TemplateError::new(source, &error)),
+    }
+}
+
+/// Convert a byte offset in `source` to an offset in UTF-16 code units.
+fn utf16_offset(source: &str, offset: usize) -> usize {
+    source
+        .char_indices()
+        .take_while(|&(i, _)| i < offset)
+        .map(|(_, char)| char.len_utf16())
+        .sum()
+}
@@ -0,0 +1,97 @@
+//! Return rendered templates from web handlers.
+//!
+//! [`Rendered`] is a response body with a content type, which implements axum's `IntoResponse` and actix-web's
+//! `Responder`.
+//!
+//! ## Examples
+//!
+//! ```
+//! # use typlate::web::Rendered;
+//! # use typlate::{TemplateParams, TemplateString};
+//! #[derive(TemplateParams)]
+//! struct Page {
+//!     title: String,
+//! }
+//!
+//! async fn handler() -> Rendered {
+//!     let template: TemplateString<Page> = "<h1>{title}</h1>".parse().unwrap();
+//!     Rendered::html(&template, &Page { title: "Welcome".into() })
+//! }
+//! ```
+
+use std::borrow::Cow;
+
+use crate::{TemplateString, TemplateStringParams};
+
+/// A rendered template together with the content type of the response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rendered {
+    body: String,
+    content_type: Cow<'static, str>,
+}
+
+impl Rendered {
+    /// Render a template as `text/plain; charset=utf-8`.
+    pub fn new<T: TemplateStringParams>(template: &TemplateString<T>, params: &T) -> Self {
+        Self {
+            body: template.format(params),
+            content_type: Cow::Borrowed("text/plain; charset=utf-8"),
+        }
+    }
+
+    /// Render a template as `text/html; charset=utf-8`.
+    ///
+    /// Field values are not escaped; use [`TemplateString::format_with_ctx`] with an escaper and
+    /// [`Rendered::from_body`] for untrusted values.
+    pub fn html<T: TemplateStringParams>(template: &TemplateString<T>, params: &T) -> Self {
+        Self::new(template, params).with_content_type("text/html; charset=utf-8")
+    }
+
+    /// Wrap an already rendered body, as `text/plain; charset=utf-8`.
+    pub fn from_body(body: impl Into<String>) -> Self {
+        Self {
+            body: body.into(),
+            content_type: Cow::Borrowed("text/plain; charset=utf-8"),
+        }
+    }
+
+    /// Set the content type of the response.
+    pub fn with_content_type(mut self, content_type: impl Into<Cow<'static, str>>) -> Self {
+        self.content_type = content_type.into();
+        self
+    }
+
+    /// The rendered body.
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    /// The content type of the response.
+    pub fn content_type(&self) -> &str {
+        &self.content_type
+    }
+}
+
+impl axum_core::response::IntoResponse for Rendered {
+    fn into_response(self) -> axum_core::response::Response {
+        match http::HeaderValue::try_from(&*self.content_type) {
+            Ok(content_type) => ([(http::header::CONTENT_TYPE, content_type)], self.body).into_response(),
+            Err(_) => http::StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        }
+    }
+}
+
+impl actix_web::Responder for Rendered {
+    type Body = String;
+
+    fn respond_to(self, _: &actix_web::HttpRequest) -> actix_web::HttpResponse<Self::Body> {
+        let mut response = actix_web::HttpResponse::Ok();
+        response.insert_header((actix_web::http::header::CONTENT_TYPE, &*self.content_type));
+        match response.message_body(self.body) {
+            Ok(response) => response,
+            Err(_) => {
+                actix_web::HttpResponse::with_body(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR, String::new())
+            }
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{ParseOptions, Template, TemplateParams, TemplateParseError, TemplateString, TemplateStringParams};
+
+/// Types that can be parsed from a template source with [`ParseOptions`].
+pub trait ParseWithOptions: Sized {
+    /// Parse the template source with the given options.
+    fn parse_with_options(source: &str, options: &ParseOptions) -> Result<Self, TemplateParseError>;
+
+    /// Deserialize a template in any of the forms accepted by its `Deserialize` implementation, applying the options
+    /// as when parsing: a source string, a sequence of segments, or a pre-parsed template from a compact
+    /// (non-human-readable) format.
+    fn deserialize_with_options<'de, D: Deserializer<'de>>(
+        deserializer: D,
+        options: &ParseOptions,
+    ) -> Result<Self, D::Error>;
+}
+
+impl<T: TemplateStringParams> ParseWithOptions for TemplateString<T> {
+    fn parse_with_options(source: &str, options: &ParseOptions) -> Result<Self, TemplateParseError> {
+        Self::parse_with(source, options)
+    }
+
+    fn deserialize_with_options<'de, D: Deserializer<'de>>(
+        deserializer: D,
+        options: &ParseOptions,
+    ) -> Result<Self, D::Error> {
+        Self::deserialize_with(deserializer, options)
+    }
+}
+
+impl<T: TemplateParams> ParseWithOptions for Template<T>
+where
+    T::Template: ParseWithOptions,
+{
+    fn parse_with_options(source: &str, options: &ParseOptions) -> Result<Self, TemplateParseError> {
+        Ok(Template(T::Template::parse_with_options(source, options)?))
+    }
+
+    fn deserialize_with_options<'de, D: Deserializer<'de>>(
+        deserializer: D,
+        options: &ParseOptions,
+    ) -> Result<Self, D::Error> {
+        T::Template::deserialize_with_options(deserializer, options).map(Template)
+    }
+}
+
+/// Provides the [`ParseOptions`] that a [`WithOptions`] wrapper applies when deserializing.
+pub trait OptionsProvider {
+    /// The options to parse with.
+    fn options() -> ParseOptions;
+}
+
+/// An [`OptionsProvider`] for lenient parsing, see
+/// [`ParseOptionsBuilder::lenient`](crate::ParseOptionsBuilder::lenient).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct LenientOptions;
+
+impl OptionsProvider for LenientOptions {
+    fn options() -> ParseOptions {
+        ParseOptions::builder().lenient(true).build()
+    }
+}
+
+/// A template that is deserialized with lenient parse options, keeping placeholders for unknown fields as literal text.
+pub type Lenient<T> = WithOptions<T, LenientOptions>;
+
+/// A template wrapper whose [`Deserialize`] implementation parses the template source with the options of `O`, instead
+/// of the defaults used by [`FromStr`](std::str::FromStr).
+///
+/// Compact (non-human-readable) formats store pre-parsed templates, which are not parsed again but must only use
+/// namespaces and filters accepted by the options. The wrapper derefs to the template and serializes like it.
+///
+/// ## Examples
+///
+/// ```
+/// # use serde::Deserialize;
+/// # use typlate::{Lenient, TemplateParams, TemplateString};
+/// #[derive(TemplateParams)]
+/// struct User {
+///     name: String,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Messages {
+///     strict: TemplateString<User>,
+///     lenient: Lenient<TemplateString<User>>,
+/// }
+///
+/// let json = r#"{"strict": "Hi {name}", "lenient": "Hi {name} ({nickname})"}"#;
+/// let messages: Messages = serde_json::from_str(json).unwrap();
+/// let user = User { name: "Alice".into() };
+/// assert_eq!(messages.lenient.format(&user), "Hi Alice ({nickname})");
+/// ```
+pub struct WithOptions<T, O> {
+    inner: T,
+    phantom: PhantomData<fn() -> O>,
+}
+
+impl<T, O> WithOptions<T, O> {
+    /// Wrap a template.
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Unwrap the template.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T, O> From<T> for WithOptions<T, O> {
+    fn from(inner: T) -> Self {
+        Self::new(inner)
+    }
+}
+
+impl<T, O> Deref for WithOptions<T, O> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T, O> DerefMut for WithOptions<T, O> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+
+impl<T: Clone, O> Clone for WithOptions<T, O> {
+    fn clone(&self) -> Self {
+        Self::new(self.inner.clone())
+    }
+}
+
+impl<T: PartialEq, O> PartialEq for WithOptions<T, O> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<T: Eq, O> Eq for WithOptions<T, O> {}
+
+impl<T: fmt::Debug, O> fmt::Debug for WithOptions<T, O> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl<T: Serialize, O> Serialize for WithOptions<T, O> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.inner.serialize(serializer)
+    }
+}
+
+impl<'de, T, O> Deserialize<'de> for WithOptions<T, O>
+where
+    T: ParseWithOptions,
+    O: OptionsProvider,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize_with_options(deserializer, &O::options()).map(Self::new)
+    }
+}
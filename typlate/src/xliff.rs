@@ -0,0 +1,198 @@
+//! Import and export of XLIFF 1.2 and 2.0 files.
+//!
+//! Placeholders are preserved as `<ph>` elements, so localization tools can protect them from being edited. In
+//! XLIFF 1.2 the element contains the placeholder as native code (`<ph id="1">{name}</ph>`), while XLIFF 2.0 stores
+//! it in the `equiv` and `disp` attributes (`<ph id="1" equiv="{name}" disp="{name}"/>`).
+//!
+//! ## Examples
+//!
+//! ```
+//! # use typlate::xliff::{self, XliffVersion};
+//! # use typlate::{TemplateParams, TemplateString};
+//! #[derive(TemplateParams)]
+//! struct User {
+//!     name: String,
+//! }
+//!
+//! let source: TemplateString<User> = "Hello {name}".parse().unwrap();
+//! let translation: TemplateString<User> = "Bonjour {name}".parse().unwrap();
+//! let xliff = xliff::export_xliff([("greeting", &source, Some(&translation))], XliffVersion::V2_0, "en", "fr");
+//! assert!(xliff.contains(r#"<target>Bonjour <ph id="1" equiv="{name}" disp="{name}"/></target>"#));
+//!
+//! let entries = xliff::import_xliff::<User>(&xliff).unwrap();
+//! assert_eq!(entries[0].0, "greeting");
+//! assert_eq!(entries[0].1, translation);
+//! ```
+
+use std::fmt::{self, Display, Write};
+
+use crate::string::TemplateElement;
+use crate::{TemplateParseError, TemplateString, TemplateStringParams};
+
+/// The XLIFF version to export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum XliffVersion {
+    V1_2,
+    V2_0,
+}
+
+/// An error that occurred while importing an XLIFF file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XliffError {
+    /// The document is not well-formed XML.
+    Xml(String),
+    /// A translation unit has no `id` attribute.
+    MissingId,
+    /// A `<ph>` element does not hold a `{name}` placeholder.
+    InvalidPlaceholder { key: String },
+    /// The translation of a unit is not a valid template.
+    Template { key: String, error: TemplateParseError },
+}
+
+impl Display for XliffError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Xml(message) => write!(f, "Invalid XML: {message}"),
+            Self::MissingId => f.write_str("Translation unit without id"),
+            Self::InvalidPlaceholder { key } => write!(f, "{key}: Invalid placeholder element"),
+            Self::Template { key, error } => write!(f, "{key}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for XliffError {}
+
+/// Export templates as an XLIFF file.
+///
+/// Each entry is a unit id, the source template and an optional translation. Units without a translation have no
+/// `<target>` element.
+pub fn export_xliff<'a, T, K>(
+    entries: impl IntoIterator<Item = (K, &'a TemplateString<T>, Option<&'a TemplateString<T>>)>,
+    version: XliffVersion,
+    source_language: &str,
+    target_language: &str,
+) -> String
+where
+    T: TemplateStringParams + 'a,
+    K: Display,
+{
+    let source_language = escape(source_language);
+    let target_language = escape(target_language);
+    let mut output = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    match version {
+        XliffVersion::V1_2 => write!(
+            output,
+            "<xliff version=\"1.2\" xmlns=\"urn:oasis:names:tc:xliff:document:1.2\">\n  <file \
+             source-language=\"{source_language}\" target-language=\"{target_language}\" datatype=\"plaintext\" \
+             original=\"messages\">\n    <body>\n"
+        ),
+        XliffVersion::V2_0 => write!(
+            output,
+            "<xliff version=\"2.0\" xmlns=\"urn:oasis:names:tc:xliff:document:2.0\" srcLang=\"{source_language}\" \
+             trgLang=\"{target_language}\">\n  <file id=\"messages\">\n"
+        ),
+    }
+    .unwrap();
+
+    for (key, source, translation) in entries {
+        let key = escape(&key.to_string());
+        let source = to_xliff_content(source, version);
+        let target = translation.map(|template| to_xliff_content(template, version));
+        match version {
+            XliffVersion::V1_2 => {
+                writeln!(output, "      <trans-unit id=\"{key}\">").unwrap();
+                writeln!(output, "        <source>{source}</source>").unwrap();
+                if let Some(target) = target {
+                    writeln!(output, "        <target>{target}</target>").unwrap();
+                }
+                output.push_str("      </trans-unit>\n");
+            }
+            XliffVersion::V2_0 => {
+                writeln!(output, "    <unit id=\"{key}\">\n      <segment>").unwrap();
+                writeln!(output, "        <source>{source}</source>").unwrap();
+                if let Some(target) = target {
+                    writeln!(output, "        <target>{target}</target>").unwrap();
+                }
+                output.push_str("      </segment>\n    </unit>\n");
+            }
+        }
+    }
+
+    match version {
+        XliffVersion::V1_2 => output.push_str("    </body>\n  </file>\n</xliff>\n"),
+        XliffVersion::V2_0 => output.push_str("  </file>\n</xliff>\n"),
+    }
+    output
+}
+
+/// Import the translations of an XLIFF 1.2 or 2.0 file as `(id, template)` pairs.
+///
+/// The version is detected from the document. Units without a `<target>` element are skipped.
+pub fn import_xliff<T: TemplateStringParams>(input: &str) -> Result<Vec<(String, TemplateString<T>)>, XliffError> {
+    let document = roxmltree::Document::parse(input).map_err(|error| XliffError::Xml(error.to_string()))?;
+    let mut templates = vec![];
+    for unit in document.descendants() {
+        if !unit.has_tag_name("trans-unit") && !unit.has_tag_name("unit") {
+            continue;
+        }
+        let key = unit.attribute("id").ok_or(XliffError::MissingId)?.to_string();
+        let Some(target) = unit.descendants().find(|node| node.has_tag_name("target")) else {
+            continue;
+        };
+        let template = from_xliff_content(target, &key)?;
+        templates.push((key, template));
+    }
+    Ok(templates)
+}
+
+fn to_xliff_content<T: TemplateStringParams>(template: &TemplateString<T>, version: XliffVersion) -> String {
+    let mut output = String::new();
+    let mut id = 0;
+    for element in template.elements() {
+        match element {
+            TemplateElement::Text(text) => output.push_str(&escape(text)),
+            element => {
+                id += 1;
+                let mut code = String::new();
+                element.write_source(&mut code, T::FIELDS).unwrap();
+                let code = escape(&code);
+                match version {
+                    XliffVersion::V1_2 => write!(output, "<ph id=\"{id}\">{code}</ph>"),
+                    XliffVersion::V2_0 => write!(output, "<ph id=\"{id}\" equiv=\"{code}\" disp=\"{code}\"/>"),
+                }
+                .unwrap();
+            }
+        }
+    }
+    output
+}
+
+fn from_xliff_content<T: TemplateStringParams>(
+    target: roxmltree::Node,
+    key: &str,
+) -> Result<TemplateString<T>, XliffError> {
+    let mut source = String::new();
+    for node in target.children() {
+        if node.is_text() {
+            let text = node.text().unwrap_or_default();
+            source.push_str(&text.replace('{', "{{").replace('}', "}}"));
+        } else if node.has_tag_name("ph") {
+            let code = node.attribute("equiv").or_else(|| node.text()).unwrap_or_default();
+            if !code.starts_with('{') || !code.ends_with('}') {
+                return Err(XliffError::InvalidPlaceholder { key: key.to_string() });
+            }
+            source.push_str(code);
+        }
+    }
+    source.parse().map_err(|error| XliffError::Template {
+        key: key.to_string(),
+        error,
+    })
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
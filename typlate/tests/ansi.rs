@@ -0,0 +1,41 @@
+use typlate::{TemplateParams, TemplateString, TemplateStringParams};
+
+#[derive(TemplateParams)]
+struct Status {
+    level: &'static str,
+    msg: String,
+}
+
+#[test]
+fn test_ansi_filters() {
+    let template: TemplateString<Status> = "{level|upper|color(bright_yellow)|bg(blue)} {msg|underline} \
+                                            {msg|color(pink)}"
+        .parse()
+        .unwrap();
+    let status = Status {
+        level: "warn",
+        msg: "low disk".into(),
+    };
+
+    assert_eq!(
+        template.format(&status),
+        "\x1b[44m\x1b[93mWARN\x1b[0m\x1b[0m \x1b[4mlow disk\x1b[0m low disk"
+    );
+
+    typlate::ansi::set_enabled(false);
+    assert!(!typlate::ansi::is_enabled());
+    assert_eq!(template.format(&status), "WARN low disk low disk");
+    typlate::ansi::set_enabled(true);
+}
+
+#[test]
+fn test_ansi_default_filter() {
+    #[derive(TemplateParams)]
+    #[template(default_filter = "bold")]
+    struct Title {
+        #[allow(dead_code)]
+        text: &'static str,
+    }
+
+    assert_eq!(Title::default_filters(0), ["bold"]);
+}
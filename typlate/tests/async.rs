@@ -0,0 +1,92 @@
+use std::fmt;
+use std::sync::Mutex;
+use std::task::{Context, Poll, Waker};
+
+use typlate::{AsyncTemplateParams, TemplateParams, TemplateString, TemplateStringParams};
+
+#[derive(TemplateParams)]
+struct Order {
+    id: u32,
+    status: &'static str,
+    carrier: Option<String>,
+    eta: Lookups,
+}
+
+/// Records the fields that were resolved, rendering as `unknown` when not resolved.
+#[derive(Default)]
+struct Lookups(Mutex<Vec<&'static str>>);
+
+impl fmt::Display for Lookups {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("unknown")
+    }
+}
+
+impl AsyncTemplateParams for Order {
+    async fn resolve_field(&self, index: usize) -> Option<String> {
+        let name = Self::FIELDS[index];
+        self.eta.0.lock().unwrap().push(name);
+        match name {
+            "carrier" => Some("dhl".into()),
+            "eta" => Some(std::future::ready("tomorrow".to_string()).await),
+            _ => None,
+        }
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let Poll::Ready(output) = std::pin::pin!(future).poll(&mut Context::from_waker(Waker::noop())) else {
+        unreachable!()
+    };
+    output
+}
+
+#[test]
+fn test_format_async() {
+    let template: TemplateString<Order> = "#{id} {status, select, shipped {via {carrier|upper} ({carrier})} other \
+                                           {arrives {eta}}}"
+        .parse()
+        .unwrap();
+    let order = Order {
+        id: 1,
+        status: "shipped",
+        carrier: None,
+        eta: Lookups::default(),
+    };
+    assert_eq!(template.format(&order), "#1 via  ()");
+    assert_eq!(block_on(template.format_async(&order)), "#1 via DHL (dhl)");
+    assert_eq!(*order.eta.0.lock().unwrap(), ["id", "status", "carrier"]);
+
+    let order = Order {
+        status: "pending",
+        eta: Lookups::default(),
+        ..order
+    };
+    assert_eq!(template.format(&order), "#1 arrives unknown");
+    assert_eq!(block_on(template.format_async(&order)), "#1 arrives tomorrow");
+    assert_eq!(*order.eta.0.lock().unwrap(), ["id", "status", "eta"]);
+}
+
+#[derive(TemplateParams)]
+struct Cart {
+    count: u32,
+    label: &'static str,
+}
+
+impl AsyncTemplateParams for Cart {
+    async fn resolve_field(&self, index: usize) -> Option<String> {
+        (Self::FIELDS[index] == "count").then(|| "3".into())
+    }
+}
+
+#[test]
+fn test_format_async_numbers() {
+    let cart = Cart { count: 0, label: "ab" };
+    let template: TemplateString<Cart> = "{count} {count + 1} [{label:>count$}]".parse().unwrap();
+    assert_eq!(template.format(&cart), "0 1 [ab]");
+    assert_eq!(block_on(template.format_async(&cart)), "3 4 [ ab]");
+
+    // Fields that are only used in expressions and specs are resolved too.
+    let template: TemplateString<Cart> = "{count * 2} [{label:>count$}]".parse().unwrap();
+    assert_eq!(block_on(template.format_async(&cart)), "6 [ ab]");
+}
@@ -0,0 +1,42 @@
+use lettre::Message;
+use lettre::message::header::ContentType;
+use typlate::email::EmailTemplates;
+use typlate::{TemplateParams, TemplateParseError};
+
+#[derive(TemplateParams)]
+struct Order {
+    id: u32,
+    name: &'static str,
+}
+
+#[test]
+fn test_email_templates() {
+    let templates = EmailTemplates::<Order>::parse("Order #{id}", "<p>Hello {name}</p>").unwrap();
+    let order = Order { id: 7, name: "Ada" };
+    assert_eq!(
+        templates.render(&order),
+        ("Order #7".to_string(), "<p>Hello Ada</p>".to_string())
+    );
+
+    let builder = Message::builder()
+        .from("shop@example.com".parse().unwrap())
+        .to("ada@example.com".parse().unwrap())
+        .header(ContentType::TEXT_HTML);
+    let message = templates.message(builder, &order).unwrap();
+    assert_eq!(message.headers().get_raw("Subject"), Some("Order #7"));
+    assert_eq!(
+        message.headers().get_raw("Content-Type"),
+        Some("text/html; charset=utf-8")
+    );
+    let formatted = String::from_utf8(message.formatted()).unwrap();
+    assert!(formatted.ends_with("<p>Hello Ada</p>"));
+
+    assert!(templates.message(Message::builder(), &order).is_err());
+    assert_eq!(
+        EmailTemplates::<Order>::parse("Order #{id}", "Hello {nmae}").unwrap_err(),
+        TemplateParseError::UnknownField {
+            name: "nmae".into(),
+            span: 7..11
+        }
+    );
+}
@@ -0,0 +1,51 @@
+use std::ffi::{CStr, c_char};
+
+use typlate::TemplateParams;
+use typlate::ffi::*;
+
+#[derive(TemplateParams)]
+struct Cart {
+    name: &'static str,
+    count: u32,
+}
+
+unsafe fn string(value: *const c_char) -> Option<&'static str> {
+    (!value.is_null()).then(|| unsafe { CStr::from_ptr(value) }.to_str().unwrap())
+}
+
+#[test]
+fn test_ffi_validate() {
+    unsafe {
+        let validation = validate::<Cart>(c"{name}: {count} {count, select, 1 {item} other {items}}".as_ptr());
+        assert!(typlate_validation_is_ok(validation));
+        assert_eq!(string(typlate_validation_error_message(validation)), None);
+        assert_eq!(typlate_validation_placeholder_count(validation), 3);
+        let placeholders: Vec<_> = (0..4)
+            .map(|index| string(typlate_validation_placeholder(validation, index)))
+            .collect();
+        assert_eq!(placeholders, [Some("name"), Some("count"), Some("count"), None]);
+        typlate_validation_free(validation);
+
+        let validation = validate::<Cart>(c"{name} has {cout}".as_ptr());
+        assert!(!typlate_validation_is_ok(validation));
+        assert_eq!(
+            string(typlate_validation_error_message(validation)),
+            Some("Unknown field name: cout")
+        );
+        assert_eq!(typlate_validation_error_start(validation), 12);
+        assert_eq!(typlate_validation_error_end(validation), 16);
+        assert_eq!(typlate_validation_placeholder_count(validation), 0);
+        typlate_validation_free(validation);
+
+        let validation = validate::<Cart>(c"{name} \xff".as_ptr());
+        assert_eq!(
+            string(typlate_validation_error_message(validation)),
+            Some("Template is not valid UTF-8")
+        );
+        assert_eq!(typlate_validation_error_start(validation), 7);
+        typlate_validation_free(validation);
+
+        assert!(!typlate_validation_is_ok(std::ptr::null()));
+        typlate_validation_free(std::ptr::null_mut());
+    }
+}
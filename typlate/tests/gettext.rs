@@ -0,0 +1,95 @@
+use typlate::gettext::{self, PlaceholderStyle, PoError};
+use typlate::{TemplateParams, TemplateParseError, TemplateString};
+
+#[derive(TemplateParams)]
+struct Cart {
+    user: String,
+    count: u32,
+}
+
+#[test]
+fn test_po_round_trip() {
+    let source: TemplateString<Cart> = "{user} has {count} items (100%)\nin the {{cart}}".parse().unwrap();
+    let translation: TemplateString<Cart> = "{user} a {count} \"articles\"".parse().unwrap();
+
+    for style in [PlaceholderStyle::Brace, PlaceholderStyle::Python] {
+        let po = gettext::export_po(
+            [
+                ("cart.items", &source, Some(&translation)),
+                ("cart.empty", &source, None),
+            ],
+            style,
+        )
+        .unwrap();
+        let entries = gettext::import_po::<Cart>(&po, style).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].0, "cart.items");
+        assert_eq!(entries[0].1, translation);
+    }
+
+    let po = gettext::export_po([("cart.items", &source, None)], PlaceholderStyle::Python).unwrap();
+    assert!(po.contains(
+        "#, python-format\nmsgctxt \"cart.items\"\nmsgid \"\"\n\"%(user)s has %(count)s items (100%%)\\n\"\n"
+    ));
+}
+
+#[test]
+fn test_po_export_unsupported() {
+    let plain: TemplateString<Cart> = "{user}".parse().unwrap();
+    for (source, placeholder) in [
+        ("{user|upper}", "{user|upper}"),
+        (
+            "{count, select, 1 {one} other {many}}",
+            "{count, select, 1 {one} other {many}}",
+        ),
+        ("{count:>4}", "{count:>4}"),
+        ("{?count > 1}s{/}", "{?count > 1}s{/}"),
+    ] {
+        let template: TemplateString<Cart> = source.parse().unwrap();
+        let error = gettext::export_po([("cart", &plain, Some(&template))], PlaceholderStyle::Python).unwrap_err();
+        assert_eq!(
+            error,
+            PoError::Unsupported {
+                key: "cart".into(),
+                error: gettext::UnsupportedPlaceholder {
+                    placeholder: placeholder.into()
+                },
+            }
+        );
+        let po = gettext::export_po([("cart", &plain, Some(&template))], PlaceholderStyle::Brace).unwrap();
+        assert_eq!(
+            gettext::import_po::<Cart>(&po, PlaceholderStyle::Brace).unwrap()[0].1,
+            template
+        );
+    }
+    let template: TemplateString<Cart> = "{user|upper}".parse().unwrap();
+    let error = gettext::to_po_string(&template, PlaceholderStyle::Python).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Placeholder has no Python-style equivalent: {user|upper}"
+    );
+}
+
+#[test]
+fn test_po_import_errors() {
+    let po = "msgid \"Hello %(user)s\"\nmsgstr \"Hallo %s\"\n";
+    let error = gettext::import_po::<Cart>(po, PlaceholderStyle::Python).unwrap_err();
+    assert_eq!(
+        error,
+        PoError::Template {
+            key: "Hello %(user)s".into(),
+            error: TemplateParseError::UnsupportedConversion {
+                conversion: "%s".into(),
+                span: 6..8
+            },
+        }
+    );
+
+    let po = "msgid \"Hello\"\nmsgstr \"Hallo %(name)s\"\n";
+    let error = gettext::import_po::<Cart>(po, PlaceholderStyle::Python).unwrap_err();
+    assert_eq!(error.to_string(), "Hello: Unknown field name: name");
+
+    let po = "msgid \"Hello\"\nmsgid_plural \"Hellos\"\n";
+    let error = gettext::import_po::<Cart>(po, PlaceholderStyle::Brace).unwrap_err();
+    assert_eq!(error.to_string(), "line 2: Plural entries are not supported");
+}
@@ -0,0 +1,92 @@
+use typlate::{ParseOptions, RenderError, TemplateParams, TemplateParseError, TemplateString};
+
+#[derive(TemplateParams)]
+struct Foo {
+    bar: u32,
+    qux: &'static str,
+}
+
+// Global parameters are process-wide, so they are tested in their own test binary.
+#[test]
+fn test_global_params() {
+    typlate::set_global_params(|name: &str| match name {
+        "support_email" => Some("help@example.com".to_string()),
+        "qux" => Some("global".to_string()),
+        _ => None,
+    });
+    let template: TemplateString<Foo> = "{qux}: write to {global:support_email}".parse().unwrap();
+    assert_eq!(template.to_string(), "{qux}: write to {global:support_email}");
+    let foo = Foo { bar: 1, qux: "field" };
+    assert_eq!(template.format(&foo), "field: write to help@example.com");
+    assert_eq!(
+        TemplateString::<Foo>::parse_trusted("{bar} {global:support_email}")
+            .unwrap()
+            .to_string(),
+        "{bar} {global:support_email}"
+    );
+
+    // Plain placeholders only name global parameters that the options declare, and fields take precedence.
+    assert_eq!(
+        "{support_email}".parse::<TemplateString<Foo>>().err(),
+        Some(TemplateParseError::UnknownField {
+            name: "support_email".into(),
+            span: 1..14
+        })
+    );
+    let options = ParseOptions::builder().global("support_email").global("qux").build();
+    let bare = TemplateString::<Foo>::parse_with("{qux}: write to {support_email}", &options).unwrap();
+    assert_eq!(bare.to_string(), "{qux}: write to {global:support_email}");
+    assert_eq!(bare.format(&foo), "field: write to help@example.com");
+    assert_eq!(
+        TemplateString::<Foo>::parse_with("{support_emial}", &options).err(),
+        Some(TemplateParseError::UnknownField {
+            name: "support_emial".into(),
+            span: 1..14
+        })
+    );
+
+    // Values are looked up when rendering.
+    typlate::set_global_params(|name: &str| (name == "support_email").then(|| "support@example.com".to_string()));
+    assert_eq!(template.format(&foo), "field: write to support@example.com");
+
+    typlate::clear_global_params();
+    assert_eq!(template.format(&foo), "field: write to ");
+    assert_eq!(
+        template.try_format(&foo),
+        Err(RenderError::Unresolved {
+            namespace: "global".into(),
+            name: "support_email".into()
+        })
+    );
+
+    // Parsing does not depend on the registered provider: unknown names are only reported when rendering.
+    let unknown: TemplateString<Foo> = "{global:phone}".parse().unwrap();
+    assert_eq!(unknown.format(&foo), "");
+    assert_eq!(
+        unknown.try_format(&foo),
+        Err(RenderError::Unresolved {
+            namespace: "global".into(),
+            name: "phone".into()
+        })
+    );
+    typlate::set_global_params(|name: &str| (name == "phone").then(|| "555".to_string()));
+    assert_eq!(unknown.format(&foo), "555");
+
+    // Default filters apply to global and environment values as they do to fields.
+    #[derive(TemplateParams)]
+    #[template(default_filter = "html_escape")]
+    struct Page {
+        title: &'static str,
+    }
+
+    typlate::set_global_params(|name: &str| (name == "app").then(|| "<script>x</script>".to_string()));
+    // SAFETY: no other test in this binary reads or writes the environment.
+    unsafe { std::env::set_var("TYPLATE_TEST_MARKUP", "<b>") };
+    let options = ParseOptions::builder().env_vars(true).build();
+    let template =
+        TemplateString::<Page>::parse_with("{title} {global:app} {env:TYPLATE_TEST_MARKUP}", &options).unwrap();
+    assert_eq!(
+        template.format(&Page { title: "<i>" }),
+        "&lt;i&gt; &lt;script&gt;x&lt;/script&gt; &lt;b&gt;"
+    );
+}
@@ -0,0 +1,70 @@
+use std::panic;
+
+use proptest::prelude::*;
+use typlate::testing::{mutated_template, valid_template};
+use typlate::{ParseOptions, TemplateParams, TemplateParseError, TemplateString};
+
+#[derive(TemplateParams)]
+#[template(inline(pair = "{bar}/{qux}"))]
+struct Foo {
+    bar: u32,
+    qux: &'static str,
+}
+
+/// Whether the compile-time check of `include_template!` and `assert_template_fields!` accepts the source.
+fn validates(source: &str) -> bool {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let valid = panic::catch_unwind(|| typlate::__private::validate_source::<Foo>(source)).is_ok();
+    panic::set_hook(hook);
+    valid
+}
+
+/// Check that the compile-time check agrees with the parser on the field names of the source: it accepts every
+/// template that parses, and rejects every template with an unknown or empty name.
+fn check(source: &str) -> Result<(), TestCaseError> {
+    let options = ParseOptions::builder().env_vars(true).build();
+    match TemplateString::<Foo>::parse_with(source, &options) {
+        Ok(_) => prop_assert!(validates(source), "`{}` parses but fails the check", source),
+        Err(
+            TemplateParseError::UnknownField { .. }
+            | TemplateParseError::InvalidFieldName { .. }
+            | TemplateParseError::EmptyPlaceholder { .. }
+            | TemplateParseError::UnknownNamespace { .. },
+        ) => prop_assert!(
+            !validates(source),
+            "`{}` has an invalid name but passes the check",
+            source
+        ),
+        Err(_) => {}
+    }
+    Ok(())
+}
+
+// The check panics on invalid sources, and the panic hook is silenced while it runs, so it is tested in its own test
+// binary.
+#[test]
+fn test_validate_source_matches_parser() {
+    let sources = [
+        include_str!("templates/welcome.txt"),
+        "Hello {bar} {qux}",
+        "{qux|upper} has {bar + 1} items {?bar > 1}({bar:>4}){/} {env:HOME}",
+        "{bar:#x} {qux:<8} {pair} {{{qux}}}",
+        "{bar, select, 1 {one} other {{qux} more}}",
+        "{bar, plural, one {# item} other {# items}}",
+        "{nmae}",
+        "{nmae:>10}",
+        "{secret:token}",
+        "{ }",
+        "{bar} {}",
+        "{?nmae}x{/}",
+        "{bar, select, 1 {{nmae}} other {}}",
+        "{nmae * 2}",
+        "{bar + 1: {bar}",
+    ];
+    for source in sources {
+        check(source).unwrap();
+    }
+    proptest!(|(source in valid_template::<Foo>())| check(&source)?);
+    proptest!(|(source in mutated_template::<Foo>())| check(&source)?);
+}
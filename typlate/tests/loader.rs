@@ -0,0 +1,122 @@
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use typlate::loader::{Loader, LoaderError};
+use typlate::{TemplateParams, TemplateParseError, TemplateSet};
+
+#[derive(TemplateParams)]
+struct User {
+    name: &'static str,
+}
+
+/// Create an empty directory with the given files, unique to the test.
+fn setup(test: &str, files: &[(&str, &str)]) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("typlate-loader-{test}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    for (path, content) in files {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+    root
+}
+
+#[test]
+fn test_load_directory() {
+    let root = setup(
+        "load",
+        &[
+            ("greeting.txt", "Hello {name}\n"),
+            ("emails/welcome.txt", "Welcome, {name}!\r\n\n"),
+            ("emails/set.templates", "[a] A {name}\n[b] B"),
+            (
+                "messages.json",
+                r#"{"errors": {"not_found": "No {name}"}, "ok": "Fine"}"#,
+            ),
+            ("notes.md", "{unknown}"),
+        ],
+    );
+
+    let set: TemplateSet<User> = Loader::new(&root).load().unwrap();
+    let user = User { name: "Ada" };
+    let rendered: Vec<_> = set
+        .names()
+        .map(|name| (name, set.format(name, &user).unwrap()))
+        .collect();
+    assert_eq!(
+        rendered,
+        [
+            ("emails.set.a", "A Ada".to_string()),
+            ("emails.set.b", "B".to_string()),
+            ("emails.welcome", "Welcome, Ada!\r\n".to_string()),
+            ("greeting", "Hello Ada".to_string()),
+            ("messages.errors.not_found", "No Ada".to_string()),
+            ("messages.ok", "Fine".to_string()),
+        ]
+    );
+
+    let set: TemplateSet<User> = Loader::new(&root).glob("emails/**/*.txt").load().unwrap();
+    assert_eq!(set.names().collect::<Vec<_>>(), ["emails.welcome"]);
+    let set: TemplateSet<User> = Loader::new(&root).glob("*").load().unwrap();
+    assert_eq!(set.len(), 3);
+
+    match Loader::new(&root).extensions(["md"]).load::<User>() {
+        Err(LoaderError::Parse { path, name, error }) => {
+            assert_eq!(path, root.join("notes.md"));
+            assert_eq!(name, "notes");
+            assert_eq!(
+                error,
+                TemplateParseError::UnknownField {
+                    name: "unknown".into(),
+                    span: 1..8
+                }
+            );
+        }
+        result => panic!("unexpected result: {result:?}"),
+    }
+
+    std::fs::write(root.join("greeting.json"), r#"{"x": 1}"#).unwrap();
+    std::fs::write(root.join("messages.templates"), "[ok] Duplicate").unwrap();
+    match Loader::new(&root).load::<User>() {
+        Err(LoaderError::Format { path, .. }) => assert_eq!(path, root.join("greeting.json")),
+        result => panic!("unexpected result: {result:?}"),
+    }
+    std::fs::remove_file(root.join("greeting.json")).unwrap();
+    match Loader::new(&root).load::<User>() {
+        Err(LoaderError::DuplicateName { name, .. }) => assert_eq!(name, "messages.ok"),
+        result => panic!("unexpected result: {result:?}"),
+    }
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_load_symlinks() {
+    let root = setup("symlinks", &[("emails/welcome.txt", "Welcome, {name}!")]);
+    std::os::unix::fs::symlink(&root, root.join("emails/loop")).unwrap();
+    std::os::unix::fs::symlink(root.join("emails/welcome.txt"), root.join("greeting.txt")).unwrap();
+
+    let set: TemplateSet<User> = Loader::new(&root).load().unwrap();
+    assert_eq!(set.names().collect::<Vec<_>>(), ["emails.welcome", "greeting"]);
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_watch_directory() {
+    let root = setup("watch", &[("greeting.txt", "Hello {name}")]);
+    let (sender, receiver) = mpsc::channel();
+    let _watcher = Loader::new(&root)
+        .watch(move |result: Result<TemplateSet<User>, _>| {
+            let _ = sender.send(result.map(|set| set.format("greeting", &User { name: "Ada" })));
+        })
+        .unwrap();
+    assert_eq!(receiver.recv().unwrap().unwrap().unwrap(), "Hello Ada");
+
+    std::fs::write(root.join("greeting.txt"), "Hi {name}").unwrap();
+    let reloaded = std::iter::from_fn(|| receiver.recv_timeout(Duration::from_secs(5)).ok())
+        .filter_map(Result::ok)
+        .find(|greeting| greeting.as_deref() == Some("Hi Ada"));
+    assert!(reloaded.is_some());
+    std::fs::remove_dir_all(root).unwrap();
+}
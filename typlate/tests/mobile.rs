@@ -0,0 +1,73 @@
+use typlate::mobile::{self, LossyConversion};
+use typlate::{TemplateParams, TemplateString};
+
+#[derive(TemplateParams)]
+struct Cart {
+    user: String,
+    count: u32,
+}
+
+#[test]
+fn test_android_xml() {
+    let items: TemplateString<Cart> = "{count} items for {user} (100% \"fresh\" & <new>)".parse().unwrap();
+    let greeting: TemplateString<Cart> = "@{user}'s cart".parse().unwrap();
+    let export = mobile::to_android_xml([("cart.items", &items), ("greeting", &greeting), ("cart_items", &items)]);
+
+    assert_eq!(
+        export.content,
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<resources>\n    <string name=\"cart_items\">%2$s items for %1$s \
+         (100%% \\\"fresh\\\" &amp; &lt;new&gt;)</string>\n    <string name=\"greeting\">\\@%1$s\\'s \
+         cart</string>\n</resources>\n"
+    );
+    assert_eq!(
+        export.lossy,
+        [
+            LossyConversion::RenamedKey {
+                key: "cart.items".into(),
+                renamed: "cart_items".into()
+            },
+            LossyConversion::DuplicateKey {
+                key: "cart_items".into()
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_apple_strings() {
+    let items: TemplateString<Cart> = "{count} \"items\" for {user}\n(100%)".parse().unwrap();
+    let export = mobile::to_apple_strings([("cart.items", &items), ("cart.items", &items)]);
+
+    assert_eq!(
+        export.content,
+        "\"cart.items\" = \"%2$@ \\\"items\\\" for %1$@\\n(100%%)\";\n"
+    );
+    assert_eq!(
+        export.lossy,
+        [LossyConversion::DuplicateKey {
+            key: "cart.items".into()
+        }]
+    );
+}
+
+#[test]
+fn test_field_positions() {
+    #[derive(TemplateParams)]
+    struct Order {
+        note: String,
+        #[template(position = 1)]
+        user: String,
+        #[template(position = 3)]
+        count: u32,
+        total: f64,
+    }
+
+    use typlate::TemplateStringParams;
+    let positions: Vec<_> = (0..4).map(Order::field_position).collect();
+    assert_eq!(positions, [2, 1, 3, 4]);
+    assert_eq!(Cart::field_position(1), 2);
+
+    let template: TemplateString<Order> = "{user}: {count} for {total} ({note})".parse().unwrap();
+    let export = mobile::to_apple_strings([("order", &template)]);
+    assert_eq!(export.content, "\"order\" = \"%1$@: %3$@ for %4$@ (%2$@)\";\n");
+}
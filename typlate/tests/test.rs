@@ -1,5 +1,13 @@
+use std::borrow::Cow;
+
 use serde::{Deserialize, Serialize};
-use typlate::{Template, TemplateParams};
+use typlate::encoding::Encoding;
+use typlate::{
+    BindStyle, CatalogError, DecodeError, ElementOrigin, EmptyPlaceholders, ExtendError, Facets, FallbackTemplate,
+    FieldSet, FilterRegistry, Lenient, OptionsProvider, ParseOptions, ReloadableTemplate, RenderCtx, RenderError,
+    RenderEvent, SchemaVersion, Template, TemplateError, TemplateParams, TemplateParseError, TemplateSet,
+    TemplateSetError, TemplateString, TemplatedError, WithOptions,
+};
 
 #[derive(TemplateParams)]
 struct Foo<'i> {
@@ -41,6 +49,46 @@ fn test_serde() {
     assert_eq!(serde_json::to_string(&messages).unwrap(), r#"{"foo":"Value is {bar}"}"#);
 }
 
+#[test]
+fn test_serde_optional() {
+    #[derive(Deserialize, Serialize)]
+    struct Optional {
+        plain: Option<TemplateString<Foo<'static>>>,
+        #[serde(default, with = "typlate::empty_as_none")]
+        empty_as_none: Option<TemplateString<Foo<'static>>>,
+    }
+
+    let parse = |json: &str| serde_json::from_str::<Optional>(json).unwrap();
+    let optional = parse(r#"{"plain": "", "empty_as_none": ""}"#);
+    assert_eq!(optional.plain.unwrap().to_string(), "");
+    assert!(optional.empty_as_none.is_none());
+    let optional = parse(r#"{"plain": null, "empty_as_none": null}"#);
+    assert!(optional.plain.is_none() && optional.empty_as_none.is_none());
+    let optional = parse(r#"{}"#);
+    assert!(optional.plain.is_none() && optional.empty_as_none.is_none());
+    let optional = parse(r#"{"empty_as_none": "{bar}"}"#);
+    assert_eq!(optional.empty_as_none.as_ref().unwrap().to_string(), "{bar}");
+    assert_eq!(
+        serde_json::to_string(&optional).unwrap(),
+        r#"{"plain":null,"empty_as_none":"{bar}"}"#
+    );
+
+    assert!(serde_json::from_str::<TemplateString<Foo>>("null").is_err());
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    #[allow(clippy::large_enum_variant)]
+    enum Entry {
+        Count(u32),
+        Template(Option<TemplateString<Foo<'static>>>),
+    }
+    let entries: Vec<Entry> = serde_json::from_str(r#"[1, "{qux}", null]"#).unwrap();
+    assert!(matches!(
+        entries[..],
+        [Entry::Count(1), Entry::Template(Some(_)), Entry::Template(None)]
+    ));
+}
+
 #[test]
 fn test_invalid_field_error() {
     let json = r#"{
@@ -50,3 +98,3068 @@ fn test_invalid_field_error() {
     let result: Result<Messages, _> = serde_json::from_str(json);
     assert!(result.is_err());
 }
+
+#[test]
+fn test_serde_compact() {
+    let template: Template<Foo> = "{{bar}} is {bar}, {qux}!".parse().unwrap();
+    let bytes = bincode::serialize(&template).unwrap();
+    let restored: Template<Foo> = bincode::deserialize(&bytes).unwrap();
+    let params = Foo { bar: 42, qux: "test" };
+
+    assert_eq!(restored.format(&params), "{bar} is 42, test!");
+}
+
+#[test]
+fn test_serde_compact_invalid_index() {
+    let template: Template<Foo> = "{bar} {qux}".parse().unwrap();
+    let bytes = bincode::serialize(&template).unwrap();
+    let result: Result<Template<Foo<'static>>, _> = bincode::deserialize(&bytes);
+    assert!(result.is_ok());
+
+    #[derive(TemplateParams)]
+    struct Single {
+        bar: u32,
+    }
+    let result: Result<Template<Single>, _> = bincode::deserialize(&bytes);
+    assert!(result.is_err());
+
+    #[derive(TemplateParams)]
+    struct Renamed {
+        baz: u32,
+        qux: &'static str,
+    }
+    let result: Result<Template<Renamed>, _> = bincode::deserialize(&bytes);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_serde_compact_validation() {
+    struct Custom;
+
+    impl OptionsProvider for Custom {
+        fn options() -> ParseOptions {
+            ParseOptions::builder().env_vars(true).filter("shout").build()
+        }
+    }
+
+    for source in ["{env:HOME}", "{qux|shout}"] {
+        let template = TemplateString::<Foo>::parse_with(source, &Custom::options()).unwrap();
+        let bytes = bincode::serialize(&template).unwrap();
+        let error = bincode::deserialize::<TemplateString<Foo>>(&bytes).unwrap_err();
+        assert!(error.to_string().starts_with("Unknown"));
+        let restored: WithOptions<TemplateString<Foo>, Custom> = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(*restored, template);
+    }
+}
+
+#[test]
+fn test_serde_compact_depth() {
+    // Blocks nested far deeper than the limit must fail to load instead of overflowing the stack.
+    let mut bytes = b"TPL\x02\0\x02\0\0\0\x03\0\0\0bar\x03\0\0\0qux".to_vec();
+    for _ in 0..200_000 {
+        bytes.extend_from_slice(b"\x01\0\0\0\x04\0\0\0\0");
+    }
+    bytes.extend_from_slice(b"\0\0\0\0");
+    let payload = bincode::serialize(&bytes).unwrap();
+    let error = bincode::deserialize::<TemplateString<Foo>>(&payload).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        format!("Nesting too deep at offset {}", 23 + 129 * 9)
+    );
+}
+
+#[test]
+fn test_serde_compact_isolation() {
+    let template = "{bar} {qux}"
+        .parse::<TemplateString<Foo>>()
+        .unwrap()
+        .with_bidi_isolation(true);
+    let bytes = bincode::serialize(&template).unwrap();
+    let restored: TemplateString<Foo> = bincode::deserialize(&bytes).unwrap();
+    assert!(restored.bidi_isolation());
+    assert_eq!(restored, template);
+
+    // Human-readable formats keep the flag in a map, and plain templates stay strings.
+    let json = serde_json::to_string(&restored).unwrap();
+    assert_eq!(json, r#"{"source":"{bar} {qux}","isolate":true}"#);
+    let restored: TemplateString<Foo> = serde_json::from_str(&json).unwrap();
+    assert!(restored.bidi_isolation());
+    assert_eq!(bincode::serialize(&restored).unwrap(), bytes);
+    let plain = template.with_bidi_isolation(false);
+    assert_eq!(serde_json::to_string(&plain).unwrap(), r#""{bar} {qux}""#);
+    assert!(serde_json::from_str::<TemplateString<Foo>>(r#"{"source":"{bar}","other":1}"#).is_err());
+}
+
+#[test]
+fn test_serde_segments() {
+    let json = r#"{
+        "foo": ["Value ", "is ", {"var": "bar"}, " for {qux}"]
+    }"#;
+
+    let messages: Messages = serde_json::from_str(json).unwrap();
+    let params = Foo { bar: 100, qux: "Alice" };
+
+    assert_eq!(messages.foo.format(&params), "Value is 100 for {qux}");
+    assert_eq!(
+        serde_json::to_string(&messages).unwrap(),
+        r#"{"foo":"Value is {bar} for {{qux}}"}"#
+    );
+
+    let json = r#"{"foo": ["Value is ", {"var": "invalid_field"}]}"#;
+    let error = serde_json::from_str::<Messages>(json).err().unwrap();
+    assert!(
+        error
+            .to_string()
+            .starts_with("Unknown field name in segment 1: invalid_field")
+    );
+
+    // Wrappers with parse options accept segments too, and apply the options to them.
+    let json = r#"["Value is ", {"var": "bar"}, " for ", {"var": "nickname"}]"#;
+    let lenient: Lenient<TemplateString<Foo>> = serde_json::from_str(json).unwrap();
+    assert_eq!(lenient.format(&params), "Value is 100 for {nickname}");
+    assert!(serde_json::from_str::<TemplateString<Foo>>(json).is_err());
+}
+
+#[derive(Deserialize)]
+struct Catalog {
+    errors: Messages,
+}
+
+#[test]
+fn test_load_error_path() {
+    let error = typlate::load::from_json_str::<Catalog>(r#"{"errors": {"foo": "Value is {baz}"}}"#)
+        .err()
+        .unwrap();
+    assert_eq!(error.path(), "errors.foo");
+    assert_eq!(
+        error.to_string(),
+        "errors.foo: Unknown field name: baz at line 1 column 35"
+    );
+
+    let error = typlate::load::from_toml_str::<Catalog>("[errors]\nfoo = \"Value is {bar\"")
+        .err()
+        .unwrap();
+    assert_eq!(error.path(), "errors.foo");
+    assert!(error.message().contains("Unclosed bracket in template"));
+
+    let catalog = typlate::load::from_yaml_str::<Catalog>("errors:\n  foo: Value is {bar}").unwrap();
+    assert_eq!(catalog.errors.foo.format(&Foo { bar: 1, qux: "" }), "Value is 1");
+
+    let yaml = "errors:\n  foo: Value is {bar}\n---\nerrors:\n  foo: Value is {baz}";
+    let error = typlate::load::from_yaml_documents::<Catalog>(yaml).err().unwrap();
+    assert_eq!(error.path(), "[1].errors.foo");
+}
+
+#[derive(TemplateParams)]
+struct Shipment {
+    id: u32,
+    carrier: Option<&'static str>,
+    eta: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct ShipmentMessages {
+    shipped: FallbackTemplate<Shipment>,
+}
+
+#[test]
+fn test_fallback_template() {
+    let json = r#"{
+        "shipped": [
+            "Order {id} ships via {carrier} in {eta} days",
+            "Order {id} ships via {carrier}",
+            "Order {id} ships soon"
+        ]
+    }"#;
+    let messages: ShipmentMessages = serde_json::from_str(json).unwrap();
+
+    let params = Shipment {
+        id: 1,
+        carrier: Some("UPS"),
+        eta: Some(3),
+    };
+    assert_eq!(
+        messages.shipped.format(&params).unwrap(),
+        "Order 1 ships via UPS in 3 days"
+    );
+    let params = Shipment {
+        id: 2,
+        carrier: Some("DHL"),
+        eta: None,
+    };
+    assert_eq!(messages.shipped.format(&params).unwrap(), "Order 2 ships via DHL");
+    let params = Shipment {
+        id: 3,
+        carrier: None,
+        eta: Some(3),
+    };
+    assert_eq!(messages.shipped.format(&params).unwrap(), "Order 3 ships soon");
+
+    let template: Template<Shipment> = "{id}: {carrier}".parse().unwrap();
+    assert_eq!(template.format(&params), "3: ");
+    assert!(FallbackTemplate::<Shipment>::new(vec![]).format(&params).is_none());
+}
+
+#[test]
+fn test_validate_catalog() {
+    let reference = [
+        ("greeting", "Hello {qux}, you have {bar} messages"),
+        ("farewell", "Bye {qux}"),
+        ("broken", "{bar"),
+    ];
+    let catalog = [
+        ("greeting", "Bonjour {qux}"),
+        ("farewell", "Au revoir {qux} {bar}"),
+        ("broken", "{bar}"),
+        ("unknown", "{quux}"),
+        ("unclosed", "{qux"),
+    ];
+
+    let errors = typlate::validate_catalog::<Foo, _, _>(catalog);
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].to_string(), "unknown: Unknown placeholder {quux}");
+    assert_eq!(errors[1].to_string(), "unclosed: Unclosed bracket in template");
+
+    let errors = typlate::validate_catalog_against::<Foo, _, _, _>(reference, catalog);
+    let messages: Vec<_> = errors.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        messages,
+        [
+            "greeting: Missing placeholder {bar}",
+            "farewell: Placeholder {bar} not used in reference",
+            "unknown: Unknown placeholder {quux}",
+            "unclosed: Unclosed bracket in template",
+        ]
+    );
+}
+
+#[test]
+fn test_check_translations() {
+    let reference = [
+        ("greeting", "Hello {qux}, you have {bar} messages"),
+        ("farewell", "Bye {qux}"),
+    ];
+    let translations = [
+        (
+            "fr",
+            vec![("greeting", "Bonjour {qux}"), ("farewell", "Au revoir {qux} {bar}")],
+        ),
+        (
+            "de",
+            vec![
+                ("greeting", "Hallo {qux}, du hast {bar} Nachrichten"),
+                ("farewell", "{invalid"),
+            ],
+        ),
+    ];
+
+    let mismatches = typlate::check_translations::<Foo, _, _, _, _, _>(reference, translations);
+    let messages: Vec<_> = mismatches.iter().map(ToString::to_string).collect();
+    assert_eq!(messages, ["[fr] greeting: -{bar}", "[fr] farewell: +{bar}"]);
+}
+
+struct Person {
+    name: &'static str,
+    gender: Option<&'static str>,
+}
+
+impl std::fmt::Display for Person {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+impl Facets for Person {
+    fn facet(&self, name: &str) -> Option<Cow<'_, str>> {
+        match name {
+            "gender" => self.gender.map(Cow::Borrowed),
+            _ => None,
+        }
+    }
+}
+
+#[derive(TemplateParams)]
+struct Invite {
+    #[template(facets)]
+    host: Person,
+    count: u32,
+}
+
+#[test]
+fn test_select_facets() {
+    let source = "{host} {host, select-gender, female {invited you to her} male {invited you to his} other {invited \
+                  you to their}} party{count, select, 1 {} other { with {count} guests}}";
+    let template: Template<Invite> = source.parse().unwrap();
+
+    let params = Invite {
+        host: Person {
+            name: "Anna",
+            gender: Some("female"),
+        },
+        count: 1,
+    };
+    assert_eq!(template.format(&params), "Anna invited you to her party");
+    let params = Invite {
+        host: Person {
+            name: "Sam",
+            gender: None,
+        },
+        count: 3,
+    };
+    assert_eq!(template.format(&params), "Sam invited you to their party with 3 guests");
+
+    let template: TemplateString<Invite> = "{host,select,Sam{{{hi {count}}other{}}".parse().unwrap();
+    assert_eq!(template.to_string(), "{host, select, Sam {{{hi {count}} other {}}");
+    assert_eq!(
+        template.to_string().parse::<TemplateString<Invite>>().unwrap(),
+        template
+    );
+}
+
+#[test]
+fn test_select_errors() {
+    let parse = |source: &str| source.parse::<TemplateString<Invite>>().unwrap_err();
+    assert_eq!(
+        parse("{host, select, male {his}}"),
+        TemplateParseError::InvalidSelect { span: 0..26 }
+    );
+    assert_eq!(
+        parse("{host, plural, other {x}}"),
+        TemplateParseError::InvalidSelect { span: 6..13 }
+    );
+    assert_eq!(
+        parse("{host, select, other x}"),
+        TemplateParseError::InvalidSelect { span: 15..20 }
+    );
+    assert_eq!(
+        parse("{host, select, other {x"),
+        TemplateParseError::UnclosedBracket { offset: 21 }
+    );
+    assert_eq!(
+        parse("{guest, select, other {x}}").to_string(),
+        "Unknown field name: guest"
+    );
+    assert_eq!(
+        parse("{host, select, other {{count}}}}").to_string(),
+        "Unmatched closing bracket"
+    );
+}
+
+#[test]
+fn test_bidi_isolation() {
+    let template: TemplateString<Invite> = "{host} invited {count} {host, select, Sam {friends of {host}} other \
+                                            {people}}"
+        .parse()
+        .unwrap();
+    let params = Invite {
+        host: Person {
+            name: "שרה",
+            gender: None,
+        },
+        count: 3,
+    };
+
+    assert_eq!(template.format(&params), "שרה invited 3 people");
+    assert_eq!(
+        template.format_with_isolation(&params, true),
+        "\u{2068}שרה\u{2069} invited \u{2068}3\u{2069} people"
+    );
+
+    let template = template.with_bidi_isolation(true);
+    let params = Invite {
+        host: Person {
+            name: "Sam",
+            gender: None,
+        },
+        count: 3,
+    };
+    assert_eq!(
+        template.format(&params),
+        "\u{2068}Sam\u{2069} invited \u{2068}3\u{2069} friends of \u{2068}Sam\u{2069}"
+    );
+    assert_eq!(
+        template.format_with_isolation(&params, false),
+        "Sam invited 3 friends of Sam"
+    );
+}
+
+#[derive(TemplateParams)]
+struct Invoice<'i> {
+    customer: &'i str,
+    items: u64,
+    total: f64,
+    discount: Option<f32>,
+}
+
+#[test]
+fn test_locale_numbers() {
+    let template: TemplateString<Invoice> = "{customer}: {items} items, {total} (-{discount})".parse().unwrap();
+    let params = Invoice {
+        customer: "Zoë 1000",
+        items: 1234567,
+        total: 1234.5,
+        discount: Some(0.1),
+    };
+
+    assert_eq!(
+        template.format_with_ctx(&params, &RenderCtx::new()),
+        "Zoë 1000: 1234567 items, 1234.5 (-0.1)"
+    );
+
+    let ctx = RenderCtx::new().with_locale(&"de".parse().unwrap()).unwrap();
+    assert_eq!(ctx.locale().unwrap().to_string(), "de");
+    assert_eq!(
+        template.format_with_ctx(&params, &ctx),
+        "Zoë 1000: 1.234.567 items, 1.234,5 (-0,1)"
+    );
+
+    let ctx = RenderCtx::new().with_locale(&"en-US".parse().unwrap()).unwrap();
+    let params = Invoice {
+        discount: None,
+        ..params
+    };
+    assert_eq!(
+        template.format_with_ctx(&params, &ctx),
+        "Zoë 1000: 1,234,567 items, 1,234.5 (-)"
+    );
+}
+
+#[test]
+fn test_ratio_filters() {
+    #[derive(TemplateParams)]
+    struct Stats {
+        ratio: f64,
+        share: Option<f32>,
+        label: &'static str,
+    }
+
+    let stats = Stats {
+        ratio: 0.12345,
+        share: Some(0.5),
+        label: " 0.0625 ",
+    };
+    let template: TemplateString<Stats> = "{ratio|percent} {ratio|percent(1)} {ratio|permille(2)} {share|percent} \
+                                           {label|percent(2)} {label|trim|upper|percent}"
+        .parse()
+        .unwrap();
+    assert_eq!(template.format(&stats), "12% 12.3% 123.45\u{2030} 50% 6.25% 6%");
+
+    let ctx = RenderCtx::new().with_locale(&"de".parse().unwrap()).unwrap();
+    assert_eq!(
+        template.format_with_ctx(&stats, &ctx),
+        "12\u{a0}% 12,3\u{a0}% 123,45\u{a0}\u{2030} 50\u{a0}% 6.25% 6%"
+    );
+    let ctx = RenderCtx::new().with_locale(&"tr".parse().unwrap()).unwrap();
+    let template: TemplateString<Stats> = "{ratio|percent(1)}".parse().unwrap();
+    assert_eq!(template.format_with_ctx(&stats, &ctx), "%12,3");
+
+    let mut filters = FilterRegistry::new();
+    filters.register("percent", |value, _| format!("{value} (raw)"));
+    let ctx = RenderCtx::new().with_filters(filters);
+    assert_eq!(template.format_with_ctx(&stats, &ctx), "0.12345 (raw)");
+}
+
+#[test]
+fn test_ordinal_filter() {
+    #[derive(TemplateParams)]
+    struct Rank {
+        place: u32,
+        label: &'static str,
+    }
+
+    let template: TemplateString<Rank> = "{place|ordinal} {label|ordinal}".parse().unwrap();
+    let rank = |place, label| Rank { place, label };
+    assert_eq!(template.format(&rank(1, "2")), "1st 2nd");
+    assert_eq!(template.format(&rank(3, "11")), "3rd 11th");
+    assert_eq!(template.format(&rank(112, "first")), "112th first");
+    assert_eq!(template.format(&rank(1021, "-22")), "1021st -22nd");
+
+    let ctx = RenderCtx::new().with_locale(&"en".parse().unwrap()).unwrap();
+    assert_eq!(template.format_with_ctx(&rank(1023, "4"), &ctx), "1,023rd 4th");
+    let ctx = RenderCtx::new().with_locale(&"fr".parse().unwrap()).unwrap();
+    assert_eq!(template.format_with_ctx(&rank(1, "1"), &ctx), "1er 1st");
+    assert_eq!(template.format_with_ctx(&rank(2, "2"), &ctx), "2e 2nd");
+    let ctx = RenderCtx::new().with_locale(&"de".parse().unwrap()).unwrap();
+    assert_eq!(template.format_with_ctx(&rank(3, "3"), &ctx), "3. 3rd");
+    let ctx = RenderCtx::new().with_locale(&"ja".parse().unwrap()).unwrap();
+    assert_eq!(template.format_with_ctx(&rank(3, "3"), &ctx), "3 3rd");
+}
+
+#[test]
+fn test_relative_filter() {
+    use std::time::{Duration, SystemTime};
+
+    #[derive(TemplateParams)]
+    struct Event {
+        created_at: std::time::SystemTime,
+        #[template(time)]
+        due_at: Option<SystemTime>,
+    }
+
+    let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let ctx = RenderCtx::new().with_clock(move || now);
+    let template: TemplateString<Event> = "{created_at|relative|upper}, {due_at|relative}".parse().unwrap();
+    let event = |created: u64, due: Option<u64>| Event {
+        created_at: now - Duration::from_secs(created),
+        due_at: due.map(|due| now + Duration::from_secs(due)),
+    };
+    assert_eq!(
+        template.format_with_ctx(&event(300, Some(2 * 86400)), &ctx),
+        "5 MINUTES AGO, in 2 days"
+    );
+    assert_eq!(
+        template.format_with_ctx(&event(3600, Some(59)), &ctx),
+        "1 HOUR AGO, in 59 seconds"
+    );
+    assert_eq!(
+        template.format_with_ctx(&event(0, Some(400 * 86400)), &ctx),
+        "NOW, in 1 year"
+    );
+    assert_eq!(
+        template.format_with_ctx(&event(45 * 86400, None), &ctx),
+        "1 MONTH AGO, "
+    );
+    // The clock is only read by the `relative` filter.
+    let ctx = RenderCtx::new().with_clock(|| panic!("clock read"));
+    let template: TemplateString<Event> = "{created_at|upper}".parse().unwrap();
+    assert_eq!(template.format_with_ctx(&event(0, None), &ctx), "2023-11-14T22:13:20Z");
+
+    let template: TemplateString<Event> = "{created_at} ({due_at})".parse().unwrap();
+    assert_eq!(template.format(&event(0, None)), "2023-11-14T22:13:20Z ()");
+    let before_epoch = Event {
+        created_at: SystemTime::UNIX_EPOCH - Duration::from_secs(1),
+        due_at: None,
+    };
+    assert_eq!(template.format(&before_epoch), "1969-12-31T23:59:59Z ()");
+
+    let template: TemplateString<Event> = "{created_at|relative}".parse().unwrap();
+    let event = Event {
+        created_at: SystemTime::now() + Duration::from_secs(3 * 3600 + 60),
+        due_at: None,
+    };
+    assert_eq!(template.format(&event), "in 3 hours");
+
+    // Types that are only named like a time type are displayed as usual.
+    struct DateTime(&'static str);
+
+    impl std::fmt::Display for DateTime {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str(self.0)
+        }
+    }
+
+    #[derive(TemplateParams)]
+    struct Post {
+        published: DateTime,
+    }
+
+    let template: TemplateString<Post> = "{published} {published|relative}".parse().unwrap();
+    assert_eq!(
+        template.format(&Post {
+            published: DateTime("today")
+        }),
+        "today today"
+    );
+}
+
+#[test]
+fn test_mask_filters() {
+    #[derive(TemplateParams)]
+    struct Payment {
+        email: &'static str,
+        card: &'static str,
+    }
+
+    let template: TemplateString<Payment> = "{email|mask_email} {card|mask(4)}".parse().unwrap();
+    let payment = Payment {
+        email: "alice@example.com",
+        card: "4111 1111 1111 1234",
+    };
+    assert_eq!(template.format(&payment), "a****@example.com **** **** **** 1234");
+    let payment = Payment {
+        email: "not an email",
+        card: "123",
+    };
+    assert_eq!(template.format(&payment), "************ 123");
+
+    let template: TemplateString<Payment> = r##"{email|mask_email("•")} {card|mask} {card|mask(2, "#")}"##
+        .parse()
+        .unwrap();
+    let payment = Payment {
+        email: "bo@b.io",
+        card: "DE89-3704-0044",
+    };
+    assert_eq!(template.format(&payment), "b•@b.io ****-****-0044 ####-####-##44");
+}
+
+#[test]
+fn test_slug_filter() {
+    let template: TemplateString<Foo> = "/posts/{qux|slug}".parse().unwrap();
+    assert_eq!(
+        template.format(&Foo {
+            bar: 1,
+            qux: "Hello, World!"
+        }),
+        "/posts/hello-world"
+    );
+    assert_eq!(
+        template.format(&Foo {
+            bar: 1,
+            qux: "  --Rust 2024: What's new?--"
+        }),
+        "/posts/rust-2024-what-s-new"
+    );
+    assert_eq!(template.format(&Foo { bar: 1, qux: "!!!" }), "/posts/");
+    #[cfg(feature = "deunicode")]
+    assert_eq!(
+        template.format(&Foo {
+            bar: 1,
+            qux: "Crème brûlée"
+        }),
+        "/posts/creme-brulee"
+    );
+    #[cfg(not(feature = "deunicode"))]
+    assert_eq!(
+        template.format(&Foo {
+            bar: 1,
+            qux: "Crème brûlée"
+        }),
+        "/posts/crème-brûlée"
+    );
+}
+
+#[test]
+fn test_base64_filters() {
+    #[derive(TemplateParams)]
+    struct Token {
+        payload: Vec<u8>,
+        nonce: Option<[u8; 4]>,
+        label: &'static str,
+    }
+
+    let template: TemplateString<Token> = "{payload|base64} {payload|base64url} {nonce|base64url} {label|base64}"
+        .parse()
+        .unwrap();
+    let token = Token {
+        payload: vec![0xfb, 0xff, 0xbf, 0x61],
+        nonce: Some(*b"abcd"),
+        label: "typlate",
+    };
+    assert_eq!(template.format(&token), "+/+/YQ== -_-_YQ YWJjZA dHlwbGF0ZQ==");
+    let token = Token {
+        payload: b"hi".to_vec(),
+        nonce: None,
+        label: "",
+    };
+    assert_eq!(template.format(&token), "aGk= aGk  ");
+
+    let template: TemplateString<Token> = "{payload}: {payload|upper|base64}".parse().unwrap();
+    assert_eq!(template.format(&token), "hi: SEk=");
+}
+
+#[test]
+fn test_default_filter() {
+    #[derive(TemplateParams)]
+    #[template(default_filter = "trim | html_escape")]
+    struct Page {
+        title: &'static str,
+        body: &'static str,
+        views: u32,
+    }
+
+    let page = Page {
+        title: " <Tom & Jerry> ",
+        body: "<p>Hi</p>",
+        views: 1200,
+    };
+    let template: TemplateString<Page> = "<h1>{title}</h1>{body|raw}".parse().unwrap();
+    assert_eq!(template.format(&page), "<h1>&lt;Tom &amp; Jerry&gt;</h1><p>Hi</p>");
+    assert_eq!(template.to_string(), "<h1>{title}</h1>{body|raw}");
+    let template: TemplateString<Page> = "{title|upper} {body:.3} {views:05}".parse().unwrap();
+    assert_eq!(template.format(&page), "&lt;TOM &amp; JERRY&gt; &lt;p&gt; 01200");
+
+    let mut filters = FilterRegistry::new();
+    filters.register("html_escape", |value, _| value.replace('<', "[").replace('>', "]"));
+    let ctx = RenderCtx::new().with_filters(filters);
+    let template: TemplateString<Page> = "{title}".parse().unwrap();
+    assert_eq!(template.format_with_ctx(&page, &ctx), "[Tom & Jerry]");
+
+    let options = ParseOptions::builder().filter("uper").build();
+    let template = TemplateString::<Page>::parse_with("{title|uper}", &options).unwrap();
+    assert_eq!(template.format(&page), "&lt;Tom &amp; Jerry&gt;");
+    assert_eq!(
+        template.try_format(&page),
+        Err(RenderError::UnknownFilter { name: "uper".into() })
+    );
+
+    // Resolved values and expressions are filtered too.
+    let options = ParseOptions::builder().namespace("user").build();
+    let template = TemplateString::<Page>::parse_with("{user:name} ({views * 2})", &options).unwrap();
+    let ctx = RenderCtx::new().with_resolver("user", |_| Some("<b>Tom</b>".into()));
+    assert_eq!(template.format_with_ctx(&page, &ctx), "&lt;b&gt;Tom&lt;/b&gt; (2400)");
+}
+
+#[test]
+fn test_raw_filter() {
+    let template: TemplateString<Foo> = "{qux} {qux|raw} {?bar > 1}{qux|upper|raw}{/} {bar|raw}"
+        .parse()
+        .unwrap();
+    assert_eq!(template.raw_placeholders().collect::<Vec<_>>(), ["qux", "qux", "bar"]);
+    assert_eq!(
+        template.to_string(),
+        "{qux} {qux|raw} {?bar > 1}{qux|upper|raw}{/} {bar|raw}"
+    );
+
+    let ctx = RenderCtx::new().with_escaper(typlate::escape::html);
+    let params = Foo { bar: 2, qux: "a<b" };
+    assert_eq!(template.format_with_ctx(&params, &ctx), "a&lt;b a<b A<B 2");
+    assert_eq!(template.format(&params), "a<b a<b A<B 2");
+
+    let template: TemplateString<Foo> = "{qux|upper}".parse().unwrap();
+    assert_eq!(template.raw_placeholders().count(), 0);
+}
+
+#[test]
+fn test_render_ctx() {
+    let options = ParseOptions::builder().filter("shout").build();
+    let template =
+        TemplateString::<Foo>::parse_with("{qux | trim|upper} <{qux|shout(!, 3)}> {bar|lower}", &options).unwrap();
+    assert_eq!(template.to_string(), "{qux|trim|upper} <{qux|shout(!, 3)}> {bar|lower}");
+    let params = Foo { bar: 7, qux: " a&b " };
+
+    assert_eq!(template.format(&params), "A&B < a&b > 7");
+
+    let mut filters = FilterRegistry::new();
+    filters.register("shout", |value, args| {
+        format!("{}{}", value.trim(), args[0].repeat(args[1].parse().unwrap()))
+    });
+    let ctx = RenderCtx::new()
+        .with_filters(filters)
+        .with_escaper(|value| value.replace('&', "&amp;").replace('<', "&lt;"));
+    assert!(ctx.filters().contains("shout"));
+    assert_eq!(template.format_with_ctx(&params, &ctx), "A&amp;B <a&amp;b!!!> 7");
+
+    let errors = [
+        "{qux|}",
+        "{qux|upper(a,,b)}",
+        "{qux|upper x}",
+        "{qux|trim(}",
+        "{qux|upper|shout}",
+    ]
+    .map(|source| source.parse::<TemplateString<Foo>>().err().unwrap());
+    assert_eq!(
+        errors,
+        [
+            TemplateParseError::InvalidFilter { span: 5..6 },
+            TemplateParseError::InvalidFilter { span: 11..15 },
+            TemplateParseError::InvalidFilter { span: 11..12 },
+            TemplateParseError::InvalidFilter { span: 10..11 },
+            TemplateParseError::UnknownFilter {
+                name: "shout".into(),
+                span: 11..16,
+            },
+        ]
+    );
+}
+
+#[derive(TemplateParams)]
+struct Ctx<'a, T: std::fmt::Display, const N: usize>
+where
+    T: Clone,
+{
+    label: &'a str,
+    value: T,
+    limit: Limit<N>,
+}
+
+struct Limit<const N: usize>;
+
+impl<const N: usize> std::fmt::Display for Limit<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "max {N}")
+    }
+}
+
+#[derive(TemplateParams)]
+struct Wrapper<'a, T>(&'a T, usize)
+where
+    T: std::fmt::Display + ?Sized;
+
+#[test]
+fn test_derive_generics() {
+    let template: Template<Ctx<f64, 2>> = "{label}: {value} ({limit})".parse().unwrap();
+    let params = Ctx {
+        label: "ratio",
+        value: 2.5,
+        limit: Limit,
+    };
+    assert_eq!(template.format(&params), "ratio: 2.5 (max 2)");
+
+    let template: Template<Wrapper<str>> = "{0} x{1}".parse().unwrap();
+    assert_eq!(template.format(&Wrapper("ab", 3)), "ab x3");
+}
+
+#[derive(TemplateParams)]
+#[template(names("x", "y"))]
+struct Point(i32, i32);
+
+#[test]
+fn test_tuple_struct_names() {
+    let template: TemplateString<Point> = "({x}, {y}) {x + y}".parse().unwrap();
+    assert_eq!(template.format(&Point(3, -1)), "(3, -1) 2");
+    assert_eq!(<Point as typlate::TemplateStringParams>::FIELDS, ["x", "y"]);
+    assert_eq!(
+        "{0}".parse::<TemplateString<Point>>(),
+        Err(TemplateParseError::UnknownField {
+            name: "0".into(),
+            span: 1..2
+        })
+    );
+}
+
+#[derive(TemplateParams)]
+#[template(inline(r#loop = "{type} {prénom}"))]
+struct Keywords {
+    r#type: &'static str,
+    prénom: &'static str,
+}
+
+#[test]
+fn test_field_names() {
+    assert_eq!(<Keywords as typlate::TemplateStringParams>::FIELDS, ["type", "prénom"]);
+    let template: TemplateString<Keywords> = "{type}: {prénom|upper} ({loop})".parse().unwrap();
+    let params = Keywords {
+        r#type: "user",
+        prénom: "zoé",
+    };
+    assert_eq!(template.format(&params), "user: ZOÉ (user zoé)");
+
+    let parse = |source: &str| source.parse::<TemplateString<Keywords>>().err();
+    assert_eq!(
+        parse("{r#type}"),
+        Some(TemplateParseError::InvalidFieldName {
+            name: "r#type".into(),
+            span: 1..7
+        })
+    );
+    assert_eq!(
+        parse("{user.name}").unwrap().to_string(),
+        "Invalid field name: user.name"
+    );
+    assert_eq!(parse("{_}").unwrap().to_string(), "Invalid field name: _");
+    assert_eq!(parse("{nom}").unwrap().to_string(), "Unknown field name: nom");
+    assert_eq!(parse("{_nom}").unwrap().to_string(), "Unknown field name: _nom");
+    assert_eq!(parse("{12}").unwrap().to_string(), "Unknown field name: 12");
+
+    let options = ParseOptions::builder().lenient(true).build();
+    let template = TemplateString::<Keywords>::parse_with("{user.name} {type}", &options).unwrap();
+    assert_eq!(template.format(&params), "{user.name} user");
+}
+
+#[derive(TemplateParams)]
+struct Scores<const N: usize> {
+    player: &'static str,
+    scores: [u32; N],
+}
+
+#[test]
+fn test_array_fields() {
+    let template: TemplateString<Scores<3>> = "{player}: {scores} / {scores|join(\" - \")} / {scores|join|upper}"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        template.to_string(),
+        "{player}: {scores} / {scores|join(\" - \")} / {scores|join|upper}"
+    );
+    let params = Scores {
+        player: "ana",
+        scores: [3, 1, 2],
+    };
+    assert_eq!(template.format(&params), "ana: 3, 1, 2 / 3 - 1 - 2 / 3, 1, 2");
+
+    let template: TemplateString<Scores<3>> = "{player|join(+)}".parse().unwrap();
+    assert_eq!(template.format(&params), "ana");
+}
+
+#[test]
+fn test_render_iter() {
+    let template: TemplateString<Foo> = "{qux|upper} has {bar, select, 1 {one item} other {{bar} items}}."
+        .parse()
+        .unwrap();
+    let params = Foo { bar: 3, qux: "cart" };
+    let chunks: Vec<_> = template.render_iter(&params).collect();
+    assert_eq!(chunks, ["CART", " has ", "3", " items", "."]);
+    assert!(matches!(chunks[1], Cow::Borrowed(_)));
+    assert_eq!(chunks.concat(), template.format(&params));
+
+    let params = Foo { bar: 1, qux: "cart" };
+    let template = template.with_bidi_isolation(true);
+    let chunks: Vec<_> = template.render_iter(&params).collect();
+    assert_eq!(chunks, ["\u{2068}CART\u{2069}", " has ", "one item", "."]);
+}
+
+#[test]
+fn test_format_many() {
+    let template: TemplateString<Foo> = "| {qux|upper} | {bar} | {bar, select, 1 {item} other {items}} |"
+        .parse()
+        .unwrap();
+    let rows = template.format_many(&[Foo { bar: 1, qux: "äpfel" }, Foo { bar: 120, qux: "kiwi" }]);
+    assert_eq!(rows, ["| ÄPFEL |   1 | item  |", "| KIWI  | 120 | items |"]);
+    assert!(template.format_many(&[]).is_empty());
+
+    let template: TemplateString<Foo> = "{qux}|{bar}".parse().unwrap();
+    let rows = template.format_many(&[
+        Foo {
+            bar: 1,
+            qux: "\u{4e2d}\u{6587}",
+        },
+        Foo { bar: 2, qux: "abc" },
+    ]);
+    assert_eq!(rows, ["\u{4e2d}\u{6587}|1", "abc |2"]);
+}
+
+#[test]
+fn test_format_batch() {
+    let template: TemplateString<Foo> = "{qux} #{bar}".parse().unwrap();
+    let params: Vec<_> = (0..1000).map(|bar| Foo { bar, qux: "item" }).collect();
+    let output = template.format_batch(&params);
+    assert_eq!(output.len(), 1000);
+    assert_eq!(output[999], "item #999");
+}
+
+#[test]
+fn test_size_hint() {
+    let template: TemplateString<Foo> = "{qux}: {bar, select, 1 {one} other {many {qux}}}".parse().unwrap();
+    let params = Foo { bar: 1, qux: "apples" };
+    assert_eq!(template.size_hint(&params), 11);
+    assert_eq!(template.size_hint(&params), template.format(&params).len());
+
+    let params = Foo { bar: 12, qux: "apples" };
+    assert_eq!(template.size_hint(&params), 19);
+    let template = template.with_bidi_isolation(true);
+    assert_eq!(template.size_hint(&params), 31);
+    assert_eq!(template.size_hint(&params), template.format(&params).len());
+}
+
+#[test]
+fn test_format_bytes() {
+    let template: TemplateString<Foo> = "{qux}: {bar}".parse().unwrap();
+    let params = Foo { bar: 5, qux: "né" };
+    assert_eq!(template.format_bytes(&params), "né: 5".as_bytes());
+
+    let mut output = b"> ".to_vec();
+    template.write_bytes(&params, &mut output).unwrap();
+    assert_eq!(output, "> né: 5".as_bytes());
+
+    assert_eq!(
+        template.format_encoded(&params, Encoding::Utf16Be).unwrap(),
+        [0, b'n', 0, 0xe9, 0, b':', 0, b' ', 0, b'5']
+    );
+    assert_eq!(
+        template.format_encoded(&params, Encoding::Utf16Le).unwrap(),
+        [b'n', 0, 0xe9, 0, b':', 0, b' ', 0, b'5', 0]
+    );
+
+    let mut output = vec![];
+    let params = Foo { bar: 5, qux: "n€" };
+    let error = template
+        .write_encoded(&params, &mut output, Encoding::Latin1)
+        .unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    assert!(output.is_empty());
+}
+
+#[test]
+fn test_extends() {
+    let base: TemplateString<Foo> = "<{%block head%}{bar}{%endblock%}|{%block body%}default{%endblock%}>"
+        .parse()
+        .unwrap();
+    let data = Foo { bar: 1, qux: "x" };
+    assert_eq!(base.format(&data), "<1|default>");
+    assert_eq!(
+        base.to_string(),
+        "<{%block head%}{bar}{%endblock%}|{%block body%}default{%endblock%}>"
+    );
+
+    let child: TemplateString<Foo> = "{% block body %}{qux}{%block inner%}!{% endblock inner %}{%endblock%}\n"
+        .parse()
+        .unwrap();
+    let template = child.extends(&base).unwrap();
+    assert_eq!(template.format(&data), "<1|x!>");
+
+    let grandchild: TemplateString<Foo> = "{%block inner%}?{%endblock%}".parse().unwrap();
+    assert_eq!(grandchild.extends(&template).unwrap().format(&data), "<1|x?>");
+
+    let unknown: TemplateString<Foo> = "{%block foot%}{%endblock%}".parse().unwrap();
+    assert_eq!(
+        unknown.extends(&base),
+        Err(ExtendError::UnknownBlock { name: "foot".into() })
+    );
+    let loose: TemplateString<Foo> = "text {%block body%}{%endblock%}".parse().unwrap();
+    assert_eq!(loose.extends(&base), Err(ExtendError::ContentOutsideBlock));
+
+    let parse = |source: &str| source.parse::<TemplateString<Foo>>().err();
+    assert_eq!(
+        parse("{%block a%}"),
+        Some(TemplateParseError::UnclosedBlock {
+            name: "a".into(),
+            offset: 0
+        })
+    );
+    assert_eq!(
+        parse("{%endblock%}"),
+        Some(TemplateParseError::InvalidBlock { span: 0..12 })
+    );
+    assert_eq!(
+        parse("{%block a%}{%endblock%}{%block a%}{%endblock%}"),
+        Some(TemplateParseError::InvalidBlock { span: 23..34 })
+    );
+    assert_eq!(
+        parse("{%block a%}{%endblock b%}"),
+        Some(TemplateParseError::InvalidBlock { span: 11..25 })
+    );
+    assert_eq!(
+        parse("{%include a%}"),
+        Some(TemplateParseError::InvalidBlock { span: 0..13 })
+    );
+    assert_eq!(
+        parse("{%block a"),
+        Some(TemplateParseError::UnclosedBracket { offset: 0 })
+    );
+}
+
+#[test]
+fn test_template_set() {
+    let source = "\n[short] {bar}\n[long] {qux}:\n  {bar}\n\n[empty]\n";
+    let set: TemplateSet<Foo> = source.parse().unwrap();
+    assert_eq!(set.names().collect::<Vec<_>>(), ["empty", "long", "short"]);
+    let data = Foo { bar: 1, qux: "x" };
+    assert_eq!(set.format("short", &data).unwrap(), "1");
+    assert_eq!(set.format("long", &data).unwrap(), "x:\n  1");
+    assert_eq!(set.format("empty", &data).unwrap(), "");
+
+    let parse = |source: &str| source.parse::<TemplateSet<Foo>>().err();
+    assert_eq!(parse("{bar}\n[a] x"), Some(TemplateSetError::MissingHeader { line: 1 }));
+    assert_eq!(
+        parse("[a] x\n[a] y"),
+        Some(TemplateSetError::DuplicateName { name: "a".into() })
+    );
+    assert_eq!(
+        parse("[a] {baz}").map(|error| error.to_string()),
+        Some("a: Unknown field name: baz".into())
+    );
+}
+
+#[test]
+fn test_empty_placeholders() {
+    let parse = |source: &str| source.parse::<TemplateString<Foo>>().err();
+    assert_eq!(parse("a {}"), Some(TemplateParseError::EmptyPlaceholder { span: 2..4 }));
+    assert_eq!(parse("{ }"), Some(TemplateParseError::EmptyPlaceholder { span: 0..3 }));
+    assert_eq!(
+        parse("{|upper}"),
+        Some(TemplateParseError::EmptyPlaceholder { span: 0..2 })
+    );
+    assert_eq!(parse("{}").unwrap().to_string(), "Empty placeholder");
+
+    let options = ParseOptions::builder()
+        .empty_placeholders(EmptyPlaceholders::Positional)
+        .build();
+    let template = TemplateString::<Foo>::parse_with("{} {qux} { |upper}", &options).unwrap();
+    assert_eq!(template.format(&Foo { bar: 1, qux: "x" }), "1 x X");
+    assert_eq!(template.to_string(), "{bar} {qux} {qux|upper}");
+    assert_eq!(
+        TemplateString::<Foo>::parse_with("{}{}{}", &options).err(),
+        Some(TemplateParseError::EmptyPlaceholder { span: 4..6 })
+    );
+}
+
+#[test]
+fn test_parse_all_errors() {
+    let template = TemplateString::<Foo>::parse_all_errors("{bar} {qux|upper}").unwrap();
+    assert_eq!(template.to_string(), "{bar} {qux|upper}");
+
+    let errors = TemplateString::<Foo>::parse_all_errors(
+        "{baz} {bar|up per} } {qux, select, a {{nope}} } {bar, select, other {ok}} {%endblock%} {qux",
+    )
+    .unwrap_err();
+    assert_eq!(
+        errors,
+        [
+            TemplateParseError::UnknownField {
+                name: "baz".into(),
+                span: 1..4
+            },
+            TemplateParseError::UnknownFilter {
+                name: "up".into(),
+                span: 11..13
+            },
+            TemplateParseError::UnmatchedClosingBracket { offset: 19 },
+            TemplateParseError::UnknownField {
+                name: "nope".into(),
+                span: 39..43
+            },
+            TemplateParseError::InvalidSelect { span: 21..47 },
+            TemplateParseError::InvalidBlock { span: 74..86 },
+            TemplateParseError::UnclosedBracket { offset: 87 },
+        ]
+    );
+}
+
+#[test]
+fn test_format_mapped() {
+    let template: TemplateString<Foo> = "{qux, select, a {A{bar}} other {<{bar}{qux|upper}>}}{%block \
+                                         b%}{bar}{%endblock%}"
+        .parse()
+        .unwrap();
+    let (output, mappings) = template.format_mapped(&Foo { bar: 7, qux: "z" });
+    assert_eq!(output, "<7Z>7");
+    let placeholder = |name, position| ElementOrigin::Placeholder { name, position };
+    assert_eq!(
+        mappings,
+        [
+            (0..1, ElementOrigin::Text),
+            (1..2, placeholder("bar", 2)),
+            (2..3, placeholder("qux", 3)),
+            (3..4, ElementOrigin::Text),
+            (4..5, placeholder("bar", 4)),
+        ]
+    );
+    assert_eq!(
+        template.placeholders().collect::<Vec<_>>(),
+        ["qux", "bar", "bar", "qux", "bar"]
+    );
+
+    let (output, mappings) = template
+        .with_bidi_isolation(true)
+        .format_mapped(&Foo { bar: 7, qux: "a" });
+    assert_eq!(output, "A\u{2068}7\u{2069}\u{2068}7\u{2069}");
+    assert_eq!(mappings[1], (1..8, placeholder("bar", 1)));
+}
+
+#[test]
+fn test_preview() {
+    let template: TemplateString<Foo> = "{bar} {qux|upper}{{}} {qux, select, z {z} other {?}}".parse().unwrap();
+    assert_eq!(template.preview(), "<bar> <QUX>{} ?");
+    assert_eq!(
+        template.with_bidi_isolation(true).preview(),
+        "\u{2068}<bar>\u{2069} \u{2068}<QUX>\u{2069}{} ?"
+    );
+}
+
+#[test]
+fn test_dry_run() {
+    struct Failing;
+
+    impl std::fmt::Display for Failing {
+        fn fmt(&self, _: &mut std::fmt::Formatter) -> std::fmt::Result {
+            Err(std::fmt::Error)
+        }
+    }
+
+    #[derive(TemplateParams)]
+    struct Data {
+        flag: bool,
+        name: Option<String>,
+        broken: Failing,
+    }
+
+    let template: TemplateString<Data> = "{flag, select, true {{name}} other {-}}".parse().unwrap();
+    let data = |flag| Data {
+        flag,
+        name: None,
+        broken: Failing,
+    };
+    assert_eq!(template.dry_run(&data(false)), Ok(()));
+    assert_eq!(
+        template.dry_run(&data(true)),
+        Err(RenderError::MissingField { name: "name" })
+    );
+
+    let template: TemplateString<Data> = "{flag} {broken}".parse().unwrap();
+    assert_eq!(
+        template.dry_run(&data(true)),
+        Err(RenderError::Format { name: "broken" })
+    );
+}
+
+#[test]
+fn test_field_set() {
+    let template: TemplateString<Foo> = "{qux, select, a {{bar}} other {}}".parse().unwrap();
+    assert_eq!(template.referenced_fields().iter().collect::<Vec<_>>(), [0, 1]);
+    assert!(
+        "text"
+            .parse::<TemplateString<Foo>>()
+            .unwrap()
+            .referenced_fields()
+            .is_empty()
+    );
+
+    let mut set: FieldSet = [3, 200].into_iter().collect();
+    assert!(set.insert(64));
+    assert!(!set.insert(3));
+    assert_eq!(set.len(), 3);
+    assert!(set.contains(200) && !set.contains(201) && !set.contains(1000));
+    assert_eq!(set.iter().collect::<Vec<_>>(), [3, 64, 200]);
+}
+
+#[test]
+fn test_layered_params() {
+    assert_eq!(
+        <(Foo, Shipment) as typlate::TemplateStringParams>::FIELDS,
+        ["bar", "qux", "id", "carrier", "eta"]
+    );
+    let template: TemplateString<(&Foo, &Shipment)> = "{qux} #{id}{?carrier} via {carrier}{/} in {bar + eta} days"
+        .parse()
+        .unwrap();
+    let foo = Foo { bar: 2, qux: "Acme" };
+    let shipment = Shipment {
+        id: 7,
+        carrier: None,
+        eta: Some(3),
+    };
+    assert_eq!(template.format(&(&foo, &shipment)), "Acme #7 in 5 days");
+
+    #[derive(TemplateParams)]
+    struct Global {
+        id: &'static str,
+    }
+    let template: TemplateString<(Global, Shipment)> = "{id}".parse().unwrap();
+    let params = (Global { id: "app" }, shipment);
+    assert_eq!(template.format(&params), "app");
+}
+
+#[test]
+fn test_compose_params() {
+    #[derive(TemplateParams)]
+    struct Global {
+        app: &'static str,
+    }
+
+    typlate::compose_params!(
+        /// A shipment notification.
+        ShipmentMail<'a> = Global + Foo<'a> + Shipment
+    );
+
+    assert_eq!(
+        <ShipmentMail as typlate::TemplateStringParams>::FIELDS,
+        ["app", "bar", "qux", "id", "carrier", "eta"]
+    );
+    let template: TemplateString<ShipmentMail> = "{app}: {qux} #{id}{?carrier} via {carrier|upper}{/} in {bar + eta} \
+                                                  days{eta:>3}"
+        .parse()
+        .unwrap();
+    let shipment = Shipment {
+        id: 7,
+        carrier: Some("ups"),
+        eta: Some(3),
+    };
+    let mail = ShipmentMail(Global { app: "Acme" }, Foo { bar: 2, qux: "Box" }, shipment);
+    assert_eq!(template.format(&mail), "Acme: Box #7 via UPS in 5 days  3");
+    assert_eq!(template.dry_run(&mail), Ok(()));
+}
+
+#[test]
+fn test_partial_apply() {
+    let template: TemplateString<Shipment> = "#{id + 1} {carrier|upper}{?id > 1} late{/}{?eta} in {eta}{/}{id, \
+                                              select, 1 {first} other {{eta}}}"
+        .parse()
+        .unwrap();
+    let params = Shipment {
+        id: 1,
+        carrier: Some("ups"),
+        eta: None,
+    };
+    let baked = template.partial_apply(&params, FieldSet::from_iter([0]));
+    assert_eq!(baked.to_string(), "#2 {carrier|upper}{?eta} in {eta}{/}first");
+    let baked = template.partial_apply(&params, FieldSet::from_iter([1, 2]));
+    assert_eq!(
+        baked.to_string(),
+        "#{id + 1} UPS{?id > 1} late{/}{?eta} in {eta}{/}{id, select, 1 {first} other {{eta}}}"
+    );
+    assert_eq!(template.partial_apply(&params, FieldSet::new()), template);
+
+    let isolated = template.clone().with_bidi_isolation(true);
+    let baked = isolated.partial_apply(&params, FieldSet::from_iter([1]));
+    assert!(baked.bidi_isolation());
+    assert!(baked.to_string().contains("\u{2068}UPS\u{2069}"));
+}
+
+#[test]
+fn test_normalize() {
+    // Encoded elements are not merged when decoding: `Text("{a")`, `Text("")`, `Var(0)`, `Text("b")`, `Text("}")`.
+    let header = b"TPL\x02\0\x02\0\0\0\x03\0\0\0bar\x03\0\0\0qux";
+    let body = b"\x05\0\0\0\0\x02\0\0\0{a\0\0\0\0\0\x01\0\0\0\0\0\x01\0\0\0b\0\x01\0\0\0}";
+    let mut template = TemplateString::<Foo>::from_bytes(&[&header[..], body].concat()).unwrap();
+    let expected: TemplateString<Foo> = "{{a{bar}b}}".parse().unwrap();
+    assert_ne!(template, expected);
+    assert_eq!(template.to_string(), "{{a{bar}b}}");
+
+    template.normalize();
+    assert_eq!(template, expected);
+}
+
+#[test]
+fn test_equivalent() {
+    #[derive(TemplateParams)]
+    struct Other {
+        qux: String,
+        extra: bool,
+        bar: u8,
+    }
+
+    let source = "{bar} {qux|upper} {bar, select, 1 {{qux}} other {}}{%block b%}{qux}{%endblock%}";
+    let foo: TemplateString<Foo> = source.parse().unwrap();
+    let other: TemplateString<Other> = source.parse().unwrap();
+    assert!(foo.equivalent(&other));
+    assert!(other.equivalent(&foo));
+    assert!(!foo.equivalent(&other.clone().with_bidi_isolation(true)));
+
+    let differs = |source: &str| !foo.equivalent(&source.parse::<TemplateString<Other>>().unwrap());
+    assert!(differs(
+        "{extra} {qux|upper} {bar, select, 1 {{qux}} other {}}{%block b%}{qux}{%endblock%}"
+    ));
+    assert!(differs(
+        "{bar} {qux|lower} {bar, select, 1 {{qux}} other {}}{%block b%}{qux}{%endblock%}"
+    ));
+    assert!(differs(
+        "{bar} {qux|upper} {bar, select, 2 {{qux}} other {}}{%block b%}{qux}{%endblock%}"
+    ));
+    assert!(differs(
+        "{bar} {qux|upper} {bar, select, 1 {{qux}} other {}}{%block c%}{qux}{%endblock%}"
+    ));
+}
+
+#[test]
+fn test_parse_options() {
+    let parse = |source: &str, options: &ParseOptions| TemplateString::<Foo>::parse_with(source, options);
+    let data = Foo { bar: 1, qux: "x" };
+
+    let options = ParseOptions::builder().trim_names(true).build();
+    assert_eq!(parse("{ bar } {qux}", &options).unwrap().format(&data), "1 x");
+    assert!(parse("{ bar }", &ParseOptions::default()).is_err());
+
+    let options = ParseOptions::builder().lenient(true).build();
+    let template = parse(
+        "{baz} {bar} {baz|upper} {baz, select, a {{bar}} other {}} {qux, select, a {{baz}} other {!}}",
+        &options,
+    )
+    .unwrap();
+    assert_eq!(
+        template.format(&data),
+        "{baz} 1 {baz|upper} {baz, select, a {{bar}} other {}} !"
+    );
+    assert_eq!(
+        parse("{}", &options).err(),
+        Some(TemplateParseError::EmptyPlaceholder { span: 0..2 })
+    );
+
+    let options = ParseOptions::builder().max_length(5).build();
+    assert!(parse("{bar}", &options).is_ok());
+    let error = parse("{bar}!", &options).unwrap_err();
+    assert_eq!(error, TemplateParseError::TooLong { limit: 5, length: 6 });
+    assert_eq!(error.to_string(), "Template exceeds the maximum length of 5 bytes");
+}
+
+#[test]
+fn test_max_depth() {
+    let parse = |source: &str| source.parse::<TemplateString<Foo>>();
+    let error = parse(&"{?bar}".repeat(200_000)).unwrap_err();
+    assert_eq!(
+        error,
+        TemplateParseError::TooDeep {
+            limit: 128,
+            offset: 128 * 6
+        }
+    );
+    assert_eq!(error.to_string(), "Template exceeds the maximum nesting depth of 128");
+    let errors = TemplateString::<Foo>::parse_all_errors(&"{bar, select, other {".repeat(200_000)).unwrap_err();
+    assert!(matches!(errors[..], [TemplateParseError::TooDeep { limit: 128, .. }]));
+    assert!(matches!(
+        parse(&format!("{{{}bar{} + 1}}", "(".repeat(200_000), ")".repeat(200_000))),
+        Err(TemplateParseError::TooDeep { limit: 128, .. })
+    ));
+    assert!(matches!(
+        parse(&format!("{{bar{}}}", "+1".repeat(200_000))),
+        Err(TemplateParseError::TooDeep { limit: 128, .. })
+    ));
+
+    let options = ParseOptions::builder().max_depth(2).build();
+    let source = "{?bar}{?qux}{bar, select, other {x}}{/}{/}";
+    assert!(TemplateString::<Foo>::parse_with(source, &ParseOptions::default()).is_ok());
+    assert_eq!(
+        TemplateString::<Foo>::parse_with(source, &options),
+        Err(TemplateParseError::TooDeep { limit: 2, offset: 32 })
+    );
+}
+
+#[test]
+fn test_namespaces() {
+    let parse = |source: &str, options: &ParseOptions| TemplateString::<Foo>::parse_with(source, options);
+    let data = Foo { bar: 1, qux: "x" };
+    assert_eq!(
+        parse("{bar} {env:CARGO_PKG_NAME}", &ParseOptions::default()).err(),
+        Some(TemplateParseError::UnknownNamespace {
+            namespace: "env".into(),
+            span: 7..10
+        })
+    );
+
+    let options = ParseOptions::builder().env_vars(true).namespace("secret").build();
+    let template = parse(
+        "{bar}: {env:CARGO_PKG_NAME}/{env:TYPLATE_UNSET_VARIABLE} {secret:token}",
+        &options,
+    )
+    .unwrap();
+    assert_eq!(
+        template.to_string(),
+        "{bar}: {env:CARGO_PKG_NAME}/{env:TYPLATE_UNSET_VARIABLE} {secret:token}"
+    );
+    assert_eq!(template.format(&data), "1: typlate/ ");
+    assert_eq!(
+        TemplateString::<Foo>::from_bytes_with(&template.to_bytes(), &options).unwrap(),
+        template
+    );
+    let error = template.dry_run(&data).unwrap_err();
+    assert_eq!(
+        error,
+        RenderError::Unresolved {
+            namespace: "env".into(),
+            name: "TYPLATE_UNSET_VARIABLE".into()
+        }
+    );
+    assert_eq!(error.to_string(), "Unresolved placeholder: env:TYPLATE_UNSET_VARIABLE");
+    assert_eq!(template.try_format(&data), Err(error));
+    let resolved = parse("{bar}: {env:CARGO_PKG_NAME}", &options).unwrap();
+    assert_eq!(resolved.try_format(&data), Ok("1: typlate".into()));
+
+    let ctx = RenderCtx::new()
+        .with_resolver("secret", |name| Some(name.to_uppercase()))
+        .with_resolver("env", |name| Some(format!("${name}")));
+    assert_eq!(
+        template.format_with_ctx(&data, &ctx),
+        "1: $CARGO_PKG_NAME/$TYPLATE_UNSET_VARIABLE TOKEN"
+    );
+
+    assert_eq!(
+        parse("{ secret : token }", &options).unwrap().to_string(),
+        "{secret:token}"
+    );
+    assert_eq!(
+        parse("{env: }", &options).err(),
+        Some(TemplateParseError::EmptyPlaceholder { span: 0..7 })
+    );
+    assert_eq!(
+        parse("{vault:token}", &options).unwrap_err().to_string(),
+        "Unknown namespace: vault"
+    );
+    assert_eq!(
+        parse("{global:typlate_unknown}", &options).unwrap().try_format(&data),
+        Err(RenderError::Unresolved {
+            namespace: "global".into(),
+            name: "typlate_unknown".into()
+        })
+    );
+}
+
+#[test]
+fn test_serde_with_options() {
+    struct Trimmed;
+
+    impl OptionsProvider for Trimmed {
+        fn options() -> ParseOptions {
+            ParseOptions::builder().trim_names(true).build()
+        }
+    }
+
+    #[derive(Deserialize, Serialize)]
+    struct Messages<'a> {
+        #[serde(borrow)]
+        trimmed: WithOptions<Template<Foo<'a>>, Trimmed>,
+        lenient: Lenient<TemplateString<Foo<'a>>>,
+    }
+
+    let json = r#"{"trimmed": "{ bar }", "lenient": "{qux} {nope}"}"#;
+    let messages: Messages = serde_json::from_str(json).unwrap();
+    let data = Foo { bar: 1, qux: "x" };
+    assert_eq!(messages.trimmed.format(&data), "1");
+    assert_eq!(messages.lenient.format(&data), "x {nope}");
+    assert!(serde_json::from_str::<Messages>(r#"{"trimmed": "{nope}", "lenient": ""}"#).is_err());
+
+    let bytes = bincode::serialize(&messages).unwrap();
+    let restored: Messages = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(restored.lenient.format(&data), "x {nope}");
+    assert_eq!(restored.lenient, messages.lenient);
+}
+
+#[test]
+fn test_format_string() {
+    let template: TemplateString<Foo> = "{{{qux}}}{%block a%} {bar}{%endblock%}".parse().unwrap();
+    let format = template.to_format_string().unwrap();
+    assert_eq!(format.literal(), "{{{}}} {}");
+    assert_eq!(format.fields().collect::<Vec<_>>(), ["qux", "bar"]);
+    assert_eq!(format.args(&Foo { bar: 1, qux: "x" }), ["x", "1"]);
+    assert!(
+        "{bar|upper}"
+            .parse::<TemplateString<Foo>>()
+            .unwrap()
+            .to_format_string()
+            .is_none()
+    );
+
+    let parse = |source: &str| TemplateString::<Foo>::from_format_string(source);
+    assert_eq!(
+        parse("{} {} {0:#?} { qux }").unwrap().to_string(),
+        "{bar} {qux} {bar} {qux}"
+    );
+    assert_eq!(
+        parse("{}{}{}").err(),
+        Some(TemplateParseError::EmptyPlaceholder { span: 4..6 })
+    );
+    assert_eq!(
+        parse("{2}").err(),
+        Some(TemplateParseError::UnknownField {
+            name: "2".into(),
+            span: 1..2
+        })
+    );
+    assert_eq!(
+        parse("{baz:>4}").err(),
+        Some(TemplateParseError::UnknownField {
+            name: "baz".into(),
+            span: 1..4
+        })
+    );
+    assert_eq!(
+        parse("{bar").err(),
+        Some(TemplateParseError::UnclosedBracket { offset: 0 })
+    );
+    assert_eq!(
+        parse("}").err(),
+        Some(TemplateParseError::UnmatchedClosingBracket { offset: 0 })
+    );
+}
+
+#[test]
+fn test_parse_printf() {
+    let parse = |source: &str| TemplateString::<Foo>::parse_printf(source, &["bar", "qux"]);
+    assert_eq!(parse("%-4d%% of %.3s").unwrap().to_string(), "{bar}% of {qux}");
+    assert_eq!(parse("%2$s, %1$lld, %2$s").unwrap().to_string(), "{qux}, {bar}, {qux}");
+    assert_eq!(
+        parse("%s %s %s").err(),
+        Some(TemplateParseError::UnsupportedConversion {
+            conversion: "%s".into(),
+            span: 6..8
+        })
+    );
+    assert_eq!(
+        parse("%*d").err(),
+        Some(TemplateParseError::UnsupportedConversion {
+            conversion: "%*d".into(),
+            span: 0..3
+        })
+    );
+    assert_eq!(
+        parse("%n %0$s").err(),
+        Some(TemplateParseError::UnsupportedConversion {
+            conversion: "%n".into(),
+            span: 0..2
+        })
+    );
+    assert_eq!(
+        parse("100%").err(),
+        Some(TemplateParseError::UnsupportedConversion {
+            conversion: "%".into(),
+            span: 3..4
+        })
+    );
+    assert_eq!(
+        TemplateString::<Foo>::parse_printf("%s", &["baz"]).err(),
+        Some(TemplateParseError::UnknownField {
+            name: "baz".into(),
+            span: 0..2
+        })
+    );
+}
+
+#[test]
+fn test_from_mustache() {
+    let parse = |source: &str| TemplateString::<Foo>::from_mustache(source);
+    assert_eq!(
+        parse("{ {{bar}}{{!x}} {{{ qux }}}{{& bar}} }").unwrap().to_string(),
+        "{{ {bar} {qux}{bar} }}"
+    );
+    assert_eq!(
+        parse("a {{> partial}}").err(),
+        Some(TemplateParseError::UnsupportedTag {
+            tag: "{{> partial}}".into(),
+            span: 2..15
+        })
+    );
+    assert_eq!(
+        parse("{{ baz }}").err(),
+        Some(TemplateParseError::UnknownField {
+            name: "baz".into(),
+            span: 3..6
+        })
+    );
+    assert_eq!(
+        parse("{{ }}").err(),
+        Some(TemplateParseError::EmptyPlaceholder { span: 0..5 })
+    );
+    assert_eq!(
+        parse("x {{{bar}}").err(),
+        Some(TemplateParseError::UnclosedBracket { offset: 2 })
+    );
+}
+
+#[derive(Clone, TemplateParams)]
+#[template(inline(full_name = "{first} {last}", greeting = "Dear {full_name}"))]
+struct Author {
+    first: &'static str,
+    last: &'static str,
+}
+
+#[test]
+fn test_inline_fields() {
+    let author = Author {
+        first: "Ada",
+        last: "Lovelace",
+    };
+    let template: TemplateString<Author> = "{greeting}, {{full_name}} is {full_name}".parse().unwrap();
+    assert_eq!(
+        template.format(&author),
+        "Dear Ada Lovelace, {full_name} is Ada Lovelace"
+    );
+    assert_eq!(
+        template.to_string(),
+        "Dear {first} {last}, {{full_name}} is {first} {last}"
+    );
+    assert_eq!(
+        "{full_name|upper}".parse::<TemplateString<Author>>().err(),
+        Some(TemplateParseError::UnknownField {
+            name: "full_name".into(),
+            span: 1..10
+        })
+    );
+}
+
+#[derive(TemplateParams)]
+#[template(inline(a = "{b}", b = "{a}"))]
+struct Cyclic {
+    #[allow(dead_code)]
+    x: u32,
+}
+
+#[derive(TemplateParams)]
+#[template(inline(broken = "{nope}"))]
+struct Broken {
+    #[allow(dead_code)]
+    x: u32,
+}
+
+#[test]
+fn test_inline_fields_invalid() {
+    let error = "{x} {a}".parse::<TemplateString<Cyclic>>().unwrap_err();
+    assert_eq!(
+        error,
+        TemplateParseError::InvalidInlineField {
+            name: "a".into(),
+            span: 5..6
+        }
+    );
+    assert_eq!(error.to_string(), "Invalid template for inline field: a");
+    assert_eq!(
+        "{broken}".parse::<TemplateString<Broken>>(),
+        Err(TemplateParseError::InvalidInlineField {
+            name: "broken".into(),
+            span: 1..7
+        })
+    );
+}
+
+#[test]
+fn test_params_through_pointers() {
+    fn render<P: TemplateParams>(template: &Template<P>, params: P) -> String {
+        template.format(&params)
+    }
+
+    let foo = Foo { bar: 1, qux: "x" };
+    let source = "{qux}{bar}";
+    assert_eq!(render(&source.parse().unwrap(), &foo), "x1");
+    assert_eq!(
+        render(&source.parse().unwrap(), Box::new(Foo { bar: 2, qux: "y" })),
+        "y2"
+    );
+    assert_eq!(
+        render(&source.parse().unwrap(), std::sync::Arc::new(Foo { bar: 3, qux: "z" })),
+        "z3"
+    );
+
+    let author = Author {
+        first: "Ada",
+        last: "Lovelace",
+    };
+    let template: Template<Cow<Author>> = "{full_name}".parse().unwrap();
+    assert_eq!(render(&template, Cow::Borrowed(&author)), "Ada Lovelace");
+}
+
+#[derive(TemplateParams)]
+struct Parcel {
+    #[template(default = "n/a")]
+    carrier: Option<String>,
+    #[template(default = "anonymous")]
+    name: &'static str,
+    #[template(default = "none")]
+    tags: [&'static str; 0],
+    note: Option<String>,
+}
+
+#[test]
+fn test_field_defaults() {
+    let template: TemplateString<Parcel> = "{carrier}/{name}/{tags}/{note}".parse().unwrap();
+    let parcel = Parcel {
+        carrier: Some(String::new()),
+        name: "",
+        tags: [],
+        note: None,
+    };
+    assert_eq!(template.format(&parcel), "n/a/anonymous/none/");
+    assert!(!template.has_fields(&parcel));
+    assert!(
+        "{carrier}{name}"
+            .parse::<TemplateString<Parcel>>()
+            .unwrap()
+            .has_fields(&parcel)
+    );
+
+    let parcel = Parcel {
+        carrier: Some("UPS".into()),
+        name: "Ada",
+        ..parcel
+    };
+    assert_eq!(template.format(&parcel), "UPS/Ada/none/");
+}
+
+#[test]
+fn test_parse_trusted() {
+    for source in [
+        "",
+        "{bar}{{x}}{qux}}}",
+        "{bar|upper} {% block a %}{qux}{% endblock %}",
+        "{qux, select, x {a} other {b}}",
+        "n={bar + 1}",
+        "{?bar > 1}{qux}{/}",
+    ] {
+        assert_eq!(TemplateString::<Foo>::parse_trusted(source), source.parse());
+    }
+    assert_eq!(
+        TemplateString::<Author>::parse_trusted("{full_name}")
+            .unwrap()
+            .to_string(),
+        "{first} {last}"
+    );
+    for source in [
+        "{bar}}",
+        "{bar",
+        "{bar} {baz}",
+        "{bar|upper} {baz}",
+        "{qux, select, x {a}}",
+    ] {
+        assert_eq!(TemplateString::<Foo>::parse_trusted(source), source.parse());
+        assert!(TemplateString::<Foo>::parse_trusted(source).is_err());
+    }
+}
+
+#[derive(TemplateParams)]
+struct FooV2 {
+    baz: u32,
+    qux: String,
+}
+
+#[test]
+fn test_schema_version() {
+    let schema = SchemaVersion::of::<Foo>();
+    assert_eq!(schema.fields().collect::<Vec<_>>(), ["bar", "qux"]);
+    assert!(schema.matches::<Foo>());
+    assert!(!schema.matches::<FooV2>());
+    assert_eq!(schema.removed_fields::<FooV2>(), ["bar"]);
+    assert_eq!(serde_json::to_string(&schema).unwrap(), r#"["bar","qux"]"#);
+
+    let template: TemplateString<Foo> = "{bar}{qux|upper}{bar, select, 1 {one} other {{qux}}}".parse().unwrap();
+    assert_eq!(template.schema(), schema);
+    assert_eq!(
+        template.revalidate::<FooV2>().err(),
+        Some(vec![
+            TemplateParseError::UnknownField {
+                name: "bar".into(),
+                span: 1..4
+            },
+            TemplateParseError::UnknownField {
+                name: "bar".into(),
+                span: 17..20
+            },
+        ])
+    );
+    let migrated = template
+        .revalidate_with::<FooV2>(|name| if name == "bar" { "baz" } else { name })
+        .unwrap();
+    assert_eq!(
+        migrated.to_string(),
+        "{baz}{qux|upper}{baz, select, 1 {one} other {{qux}}}"
+    );
+}
+
+#[test]
+fn test_rewrite() {
+    let template: TemplateString<Foo> = "{{bar}} {bar|upper}{?bar > 1} bar{/}{bar, select, 1 {bar} other {{qux}}}"
+        .parse()
+        .unwrap();
+    let renamed = template.rename_placeholder("bar", "qux").unwrap();
+    assert_eq!(
+        renamed.to_string(),
+        "{{bar}} {qux|upper}{?qux > 1} bar{/}{qux, select, 1 {bar} other {{qux}}}"
+    );
+    assert_eq!(
+        template.rename_placeholder("bar", "baz").err(),
+        Some(TemplateParseError::UnknownField {
+            name: "baz".into(),
+            span: 9..12
+        })
+    );
+
+    let replaced = template.replace_literal("bar", "baz");
+    assert_eq!(
+        replaced.to_string(),
+        "{{baz}} {bar|upper}{?bar > 1} baz{/}{bar, select, 1 {baz} other {{qux}}}"
+    );
+    assert_eq!(
+        template.replace_literal("{", "").to_string(),
+        "bar}} {bar|upper}{?bar > 1} bar{/}{bar, select, 1 {bar} other {{qux}}}"
+    );
+}
+
+#[test]
+fn test_bytes() {
+    let template: TemplateString<Foo> = "{{}} {qux|truncate(3, \"…\")} {bar, select-x, 1 {one} other {{qux}}}{%block \
+                                         a%}é{%endblock%}"
+        .parse()
+        .unwrap();
+    let template = template.with_bidi_isolation(true);
+    let bytes = template.to_bytes();
+    let decoded = TemplateString::<Foo>::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded, template);
+    assert!(decoded.bidi_isolation());
+
+    let decode = |bytes: &[u8]| TemplateString::<Foo>::from_bytes(bytes).err();
+    assert_eq!(decode(b"TPL\x01\0\0\0\0\0"), Some(DecodeError::InvalidHeader));
+    assert_eq!(decode(&bytes[..bytes.len() - 1]), Some(DecodeError::UnexpectedEnd));
+    assert_eq!(
+        decode(&[&bytes[..], b"x"].concat()),
+        Some(DecodeError::TrailingBytes { offset: bytes.len() })
+    );
+
+    let header = b"TPL\x02\0\x02\0\0\0\x03\0\0\0bar\x03\0\0\0qux";
+    let decode = |body: &[u8]| decode(&[&header[..], body].concat());
+    assert_eq!(
+        decode(b"\x01\0\0\0\x09"),
+        Some(DecodeError::InvalidTag { tag: 9, offset: 27 })
+    );
+    assert_eq!(
+        decode(b"\x01\0\0\0\x01\x02\0\0\0"),
+        Some(DecodeError::FieldOutOfBounds { index: 2 })
+    );
+    assert_eq!(
+        decode(b"\x01\0\0\0\0\x01\0\0\0\xff"),
+        Some(DecodeError::InvalidUtf8 { offset: 32 })
+    );
+
+    let blocks = b"\x01\0\0\0\x04\0\0\0\0".repeat(200_000);
+    assert_eq!(decode(&blocks), Some(DecodeError::TooDeep { offset: 23 + 129 * 9 }));
+    let expr = [&b"\x01\0\0\0\x05"[..], &b"\x02+".repeat(200_000)].concat();
+    assert!(matches!(decode(&expr), Some(DecodeError::TooDeep { .. })));
+
+    let options = ParseOptions::builder().env_vars(true).filter("shout").build();
+    let template = TemplateString::<Foo>::parse_with("{env:SECRET} {qux|shout}", &options).unwrap();
+    let bytes = template.to_bytes();
+    assert_eq!(TemplateString::from_bytes_with(&bytes, &options), Ok(template));
+    assert_eq!(
+        TemplateString::<Foo>::from_bytes(&bytes),
+        Err(DecodeError::UnknownNamespace {
+            namespace: "env".into()
+        })
+    );
+    let options = ParseOptions::builder().env_vars(true).build();
+    assert_eq!(
+        TemplateString::<Foo>::from_bytes_with(&bytes, &options),
+        Err(DecodeError::UnknownFilter { name: "shout".into() })
+    );
+
+    // Decoded elements are checked against the parameter type and the syntax as the parser checks them.
+    #[derive(TemplateParams)]
+    struct Text {
+        bar: String,
+        qux: String,
+    }
+    let bytes = "{bar:#x}".parse::<TemplateString<Foo>>().unwrap().to_bytes();
+    assert_eq!(
+        TemplateString::<Text>::from_bytes(&bytes),
+        Err(DecodeError::UnsupportedFormat { index: 0 })
+    );
+    let replace = |bytes: &[u8], from: &[u8], to: &[u8]| {
+        let at = bytes.windows(from.len()).position(|window| window == from).unwrap();
+        [&bytes[..at], to, &bytes[at + from.len()..]].concat()
+    };
+    let bytes = "{qux, select, a {x} other {y}}"
+        .parse::<TemplateString<Foo>>()
+        .unwrap()
+        .to_bytes();
+    assert_eq!(
+        TemplateString::<Foo>::from_bytes(&replace(&bytes, b"other", b"OTHER")),
+        Err(DecodeError::InvalidSelect { index: 1 })
+    );
+    assert_eq!(
+        TemplateString::<Foo>::from_bytes(&replace(&bytes, b"\x01\0\0\0a", b"\x01\0\0\0 ")),
+        Err(DecodeError::InvalidSelect { index: 1 })
+    );
+    let bytes = "{%block a%}{%endblock%}{%block b%}{%endblock%}"
+        .parse::<TemplateString<Foo>>()
+        .unwrap()
+        .to_bytes();
+    assert_eq!(
+        TemplateString::<Foo>::from_bytes(&replace(&bytes, b"\x01\0\0\0b", b"\x01\0\0\0a")),
+        Err(DecodeError::InvalidBlock { name: "a".into() })
+    );
+
+    #[derive(TemplateParams)]
+    struct Swapped {
+        qux: u32,
+        bar: &'static str,
+    }
+    assert_eq!(
+        TemplateString::<Swapped>::from_bytes(&bytes),
+        Err(DecodeError::FieldMismatch {
+            fields: vec!["bar".into(), "qux".into()]
+        })
+    );
+}
+
+#[test]
+fn test_display() {
+    fn assert_send_sync<T: Send + Sync>(_: T) {}
+
+    let template: TemplateString<Foo> = "{bar} {qux|upper}".parse().unwrap();
+    let params = Foo { bar: 1, qux: "x" };
+    let display = template.display(&params);
+    assert_send_sync(display);
+    assert_eq!(display.to_string(), "1 X");
+    assert_eq!(format!("{display:?}"), "\"1 X\"");
+
+    let result = std::panic::catch_unwind(|| panic!("{}", template.display(&Foo { bar: 2, qux: "y" })));
+    assert_eq!(*result.unwrap_err().downcast::<String>().unwrap(), "2 Y");
+}
+
+#[test]
+fn test_templated_error() {
+    use std::error::Error;
+
+    let template: TemplateString<Foo<'static>> = "cannot open {qux} ({bar})".parse().unwrap();
+    let error = TemplatedError::new(template, Foo { bar: 2, qux: "a.txt" });
+    assert_eq!(error.to_string(), "cannot open a.txt (2)");
+    assert!(error.source().is_none());
+    assert_eq!(error.params().bar, 2);
+
+    let io = std::io::Error::new(std::io::ErrorKind::NotFound, "no such file");
+    let error = error.with_source(io);
+    assert_eq!(error.source().unwrap().to_string(), "no such file");
+    assert_eq!(
+        format!("{error:?}"),
+        "TemplatedError { message: \"cannot open a.txt (2)\", source: Some(Custom { kind: NotFound, error: \"no such \
+         file\" }) }"
+    );
+
+    let boxed: Box<dyn Error + Send + Sync> = Box::new(error);
+    assert_eq!(boxed.to_string(), "cannot open a.txt (2)");
+}
+
+#[test]
+fn test_derive_template_error() {
+    use std::collections::HashMap;
+    use std::error::Error;
+
+    #[derive(Debug, TemplateError)]
+    enum UploadError {
+        #[template(key = "upload.too_large", fallback = "File is larger than {limit} bytes")]
+        TooLarge { size: u64, limit: u64 },
+        #[template(key = "upload.io", fallback = "Cannot read {path}")]
+        Io {
+            path: String,
+            #[source]
+            error: std::io::Error,
+        },
+        #[template(key = "upload.quota", fallback = "{0} of {1} files")]
+        Quota(u32, Option<u32>),
+        #[template(key = "upload.cancelled")]
+        Cancelled,
+    }
+
+    let error = UploadError::TooLarge {
+        size: 2048,
+        limit: 1024,
+    };
+    assert_eq!(error.to_string(), "File is larger than 1024 bytes");
+    assert_eq!(error.message_key(), "upload.too_large");
+    assert!(error.source().is_none());
+    assert_eq!(UploadError::Cancelled.to_string(), "upload.cancelled");
+    assert_eq!(UploadError::Quota(3, None).to_string(), "3 of  files");
+
+    let io = std::io::Error::new(std::io::ErrorKind::NotFound, "missing");
+    let error = UploadError::Io {
+        path: "a.txt".into(),
+        error: io,
+    };
+    assert_eq!(error.to_string(), "Cannot read a.txt");
+    assert_eq!(error.source().unwrap().to_string(), "missing");
+
+    let catalog: HashMap<String, String> = [
+        (
+            "upload.too_large",
+            "{size} > {limit}: {?size - limit > 1000}way {/}too large",
+        ),
+        ("upload.io", "{path} is unreadable ({nmae})"),
+        ("upload.quota", "{0}/{1}"),
+    ]
+    .into_iter()
+    .map(|(key, source)| (key.to_string(), source.to_string()))
+    .collect();
+    assert_eq!(
+        UploadError::validate_catalog(&catalog),
+        [
+            CatalogError::UnknownPlaceholder {
+                key: "upload.io",
+                name: "nmae".into()
+            },
+            CatalogError::MissingKey {
+                key: "upload.cancelled"
+            },
+        ]
+    );
+    assert_eq!(UploadError::KEYS.len(), 4);
+
+    typlate::set_error_catalog(catalog);
+    let error = UploadError::TooLarge {
+        size: 2048,
+        limit: 1024,
+    };
+    assert_eq!(error.to_string(), "2048 > 1024: way too large");
+    assert_eq!(UploadError::Quota(3, Some(5)).to_string(), "3/5");
+    assert_eq!(error.to_string(), "2048 > 1024: way too large");
+    let io = std::io::Error::new(std::io::ErrorKind::NotFound, "missing");
+    assert_eq!(
+        UploadError::Io {
+            path: "a.txt".into(),
+            error: io
+        }
+        .to_string(),
+        "Cannot read a.txt"
+    );
+    typlate::clear_error_catalog();
+}
+
+#[test]
+fn test_borsh() {
+    let template: TemplateString<Foo> = "{bar} is {qux|upper}, {bar}!".parse().unwrap();
+    let template = template.with_bidi_isolation(true);
+    let bytes = borsh::to_vec(&template).unwrap();
+    let restored: TemplateString<Foo> = borsh::from_slice(&bytes).unwrap();
+    assert_eq!(restored, template);
+    assert!(restored.bidi_isolation());
+
+    #[derive(TemplateParams)]
+    struct Single {
+        bar: u32,
+    }
+    let result: Result<TemplateString<Single>, _> = borsh::from_slice(&bytes);
+    assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_serde_postcard() {
+    let template: Template<Foo> = "{{bar}} is {bar}, {qux}!".parse().unwrap();
+    let bytes = postcard::to_allocvec(&template).unwrap();
+    let restored: Template<Foo> = postcard::from_bytes(&bytes).unwrap();
+    assert_eq!(restored.format(&Foo { bar: 42, qux: "test" }), "{bar} is 42, test!");
+}
+
+#[test]
+fn test_arbitrary() {
+    use arbitrary::{Arbitrary, Unstructured};
+
+    for seed in 0..64u32 {
+        let data: Vec<u8> = (0..512u32)
+            .map(|i| (i * 31 + seed * 17 + (i * seed) % 7) as u8)
+            .collect();
+        let template = TemplateString::<Foo>::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        let reparsed: TemplateString<Foo> = template.to_string().parse().unwrap();
+        assert_eq!(reparsed, template);
+        template.format(&Foo { bar: 2, qux: "x" });
+    }
+}
+
+#[test]
+fn test_snapshot() {
+    use typlate::testing::{snapshot, snapshot_set};
+
+    let template: TemplateString<Foo> = "{bar, select, 1 {one} other {{qux|upper}}}!".parse().unwrap();
+    typlate::testing::assert_renders!(template, Foo { bar: 1, qux: "x" }, "one!");
+    typlate::assert_renders!(&template, Foo { bar: 2, qux: "x" }, "X!".to_string());
+    assert_eq!(
+        snapshot(&template),
+        "{bar, select, 1 {one} other {{qux|upper}}}!\n=> <QUX>!\n"
+    );
+
+    let set: TemplateSet<Foo> = "[b] {bar}\n[a] {qux}".parse().unwrap();
+    assert_eq!(snapshot_set(&set), "[a]\n{qux}\n=> <qux>\n[b]\n{bar}\n=> <bar>\n");
+}
+
+#[test]
+fn test_sample() {
+    use typlate::testing::{Sample, sample_snapshot};
+
+    #[derive(TemplateParams)]
+    #[template(sample)]
+    struct Order<'a> {
+        id: u64,
+        #[template(sample = "Alice")]
+        name: &'a str,
+        total: f64,
+        #[template(sample = "2.5")]
+        discount: Option<f32>,
+        express: bool,
+        tags: [String; 2],
+        note: Option<String>,
+        initial: char,
+    }
+
+    let order = Order::sample();
+    assert_eq!((order.id, order.name, order.total), (1, "Alice", 3.0));
+    assert_eq!((order.discount, order.express), (Some(2.5), true));
+    assert_eq!(order.tags, ["tags", "tags"]);
+    assert_eq!(order.note.as_deref(), Some("note"));
+    assert_eq!(order.initial, '\0');
+
+    let template: TemplateString<Order> = "{name}: {total - discount}{?express} (express){/}".parse().unwrap();
+    assert_eq!(
+        sample_snapshot(&template),
+        format!("{template}\n=> Alice: 0.5 (express)\n")
+    );
+
+    #[derive(TemplateParams)]
+    #[template(sample, names("x", "y"))]
+    struct Point(i32, #[template(sample = "-4")] i32);
+    let point = Point::sample();
+    assert_eq!((point.0, point.1), (1, -4));
+}
+
+#[test]
+#[should_panic(expected = "template `{bar}` rendered unexpectedly")]
+fn test_assert_renders_failure() {
+    let template: TemplateString<Foo> = "{bar}".parse().unwrap();
+    typlate::assert_renders!(template, Foo { bar: 1, qux: "x" }, "2");
+}
+
+#[test]
+fn test_proptest_strategies() {
+    use proptest::prelude::*;
+    use typlate::testing::{mutated_template, valid_template};
+
+    proptest!(|(source in valid_template::<Foo>())| {
+        let template: TemplateString<Foo> = source.parse().unwrap();
+        prop_assert_eq!(template.to_string().parse::<TemplateString<Foo>>().unwrap(), template);
+    });
+    proptest!(|(source in mutated_template::<Foo>())| {
+        if let Err(error) = source.parse::<TemplateString<Foo>>() {
+            prop_assert!(error.span().end <= source.len() + 1);
+        }
+    });
+}
+
+#[test]
+fn test_assert_template_fields() {
+    const MESSAGE: &str = "{qux|upper} has {bar + 1} items {?bar > 1}({bar:>4}){/} {env:HOME}";
+    typlate::assert_template_fields!(Foo<'static>, MESSAGE);
+    typlate::assert_template_fields!(Foo, "{bar:#x} {qux:<8}");
+    let options = ParseOptions::builder().env_vars(true).build();
+    let template = TemplateString::<Foo>::parse_with(MESSAGE, &options).unwrap();
+    assert_eq!(
+        template.placeholders().collect::<Vec<_>>(),
+        ["qux", "bar", "bar", "bar"]
+    );
+}
+
+#[test]
+fn test_derive_getter() {
+    enum Role {
+        Admin,
+        Guest,
+    }
+
+    #[derive(TemplateParams)]
+    #[template(descriptor)]
+    struct Member {
+        name: String,
+        #[template(getter = "role_label")]
+        role: Role,
+        #[template(getter = "score_label")]
+        score: Option<u32>,
+    }
+
+    impl Member {
+        fn role_label(&self) -> &'static str {
+            match self.role {
+                Role::Admin => "administrator",
+                Role::Guest => "guest",
+            }
+        }
+
+        fn score_label(&self) -> String {
+            self.score
+                .map_or("unranked".to_string(), |score| format!("{score} points"))
+        }
+    }
+
+    let template: TemplateString<Member> = "{name} ({role|upper}): {score}".parse().unwrap();
+    let member = Member {
+        name: "Ann".into(),
+        role: Role::Admin,
+        score: None,
+    };
+    assert_eq!(template.format(&member), "Ann (ADMINISTRATOR): unranked");
+    let member = Member {
+        name: "Bo".into(),
+        role: Role::Guest,
+        score: Some(7),
+    };
+    assert_eq!(template.format(&member), "Bo (GUEST): 7 points");
+    assert!(template.has_fields(&member));
+    assert!(Member::template_descriptor().contains(r#"{"name":"score","docs":"","kind":"string","optional":false}"#));
+}
+
+#[test]
+fn test_include_template() {
+    let template = typlate::include_template!("templates/welcome.txt", Foo);
+    assert_eq!(template.format(&Foo { bar: 1, qux: "x" }), "Hello one x {X}! (3)!\n");
+    assert_eq!(template.format(&Foo { bar: 2, qux: "x" }), "Hello many {X}! (5)+!\n");
+}
+
+#[test]
+fn test_reloadable_template() {
+    let template = ReloadableTemplate::<Foo>::new("{bar}".parse().unwrap());
+    let handle = template.clone();
+    let before = template.load();
+    assert!(handle.update("{qux}!").is_ok());
+    assert_eq!(before.to_string(), "{bar}");
+    assert_eq!(template.format(&Foo { bar: 1, qux: "x" }), "x!");
+    assert_eq!(
+        handle.update("{baz}"),
+        Err(TemplateParseError::UnknownField {
+            name: "baz".into(),
+            span: 1..4
+        })
+    );
+    let previous = handle.store("{bar}{bar}".parse().unwrap());
+    assert_eq!(previous.to_string(), "{qux}!");
+
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| assert_eq!(template.format(&Foo { bar: 2, qux: "x" }), "22"));
+        }
+    });
+}
+
+#[test]
+fn test_render_observer() {
+    let events = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+    let recorded = events.clone();
+    let ctx = RenderCtx::new().with_observer(move |event: &RenderEvent| {
+        let name = event.name.map(str::to_string);
+        recorded.lock().unwrap().push((name, event.id, event.output_len));
+    });
+
+    let set: TemplateSet<Foo> = "[a] {qux}{bar}\n[b] {bar}".parse().unwrap();
+    let foo = Foo { bar: 12, qux: "xyz" };
+    assert_eq!(set.format_with_ctx("a", &foo, &ctx).unwrap(), "xyz12");
+    assert_eq!(set.format_with_ctx("c", &foo, &ctx), None);
+    set.get("b").unwrap().format_with_ctx(&foo, &ctx);
+    set.get("a").unwrap().format_with_ctx(&foo, &ctx);
+    set.get("a").unwrap().format(&foo);
+
+    let events = events.lock().unwrap();
+    assert_eq!(events.len(), 3);
+    assert_eq!((events[0].0.as_deref(), events[0].2), (Some("a"), 5));
+    assert_eq!((events[1].0.as_deref(), events[1].2), (None, 2));
+    assert_eq!(events[0].1, events[2].1);
+    assert_ne!(events[0].1, events[1].1);
+}
+
+#[test]
+fn test_format_query() {
+    let template: TemplateString<Foo> = "SELECT * FROM t WHERE a = {qux|upper} AND {bar, select, 0 {b IS NULL} other \
+                                         {b = {bar} OR c = {bar}}}"
+        .parse()
+        .unwrap();
+    let (sql, values) = template.format_query(&Foo { bar: 3, qux: "x'; --" }, BindStyle::Numbered);
+    assert_eq!(sql, "SELECT * FROM t WHERE a = $1 AND b = $2 OR c = $3");
+    assert_eq!(values, ["X'; --", "3", "3"]);
+
+    let (sql, values) = template.format_query(&Foo { bar: 0, qux: "{y}" }, BindStyle::Question);
+    assert_eq!(sql, "SELECT * FROM t WHERE a = ? AND b IS NULL");
+    assert_eq!(values, ["{Y}"]);
+
+    let template = template.with_bidi_isolation(true);
+    let (_, values) = template.format_query(&Foo { bar: 0, qux: "y" }, BindStyle::Question);
+    assert_eq!(values, ["Y"]);
+}
+
+#[test]
+fn test_format_argv() {
+    let template: TemplateString<Foo> = "  run\t--name={qux} {qux}{bar} {qux, select, a {} other {-v}} '{qux}'\n"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        template.format_argv(&Foo { bar: 1, qux: "a b" }),
+        ["run", "--name=a b", "a b1", "-v", "'a b'"]
+    );
+    assert_eq!(
+        template.format_argv(&Foo { bar: 1, qux: "a" }),
+        ["run", "--name=a", "a1", "'a'"]
+    );
+    let template: TemplateString<Foo> = "rm {qux}".parse().unwrap();
+    assert_eq!(template.format_argv(&Foo { bar: 1, qux: "" }), ["rm", ""]);
+    let template: TemplateString<Foo> = " ".parse().unwrap();
+    assert!(template.format_argv(&Foo { bar: 1, qux: "" }).is_empty());
+}
+
+#[test]
+fn test_format_header_value() {
+    let template: TemplateString<Foo> = "{qux|trim}; v={bar}".parse().unwrap();
+    let foo = Foo { bar: 1, qux: " a\tb " };
+    assert_eq!(template.format_header_value(&foo).unwrap(), "a\tb; v=1");
+
+    let foo = Foo {
+        bar: 1,
+        qux: "a\r\nX-Evil: 1",
+    };
+    assert_eq!(
+        template.format_header_value(&foo),
+        Err(RenderError::InvalidChar {
+            name: "qux",
+            char: '\r'
+        })
+    );
+    assert_eq!(template.format_header_value_lossy(&foo), "aX-Evil: 1; v=1");
+
+    let foo = Foo {
+        bar: 1,
+        qux: "caf\u{e9}",
+    };
+    assert_eq!(
+        template.format_header_value(&foo).unwrap_err().to_string(),
+        "Invalid character '\u{e9}' in field: qux"
+    );
+    assert_eq!(template.format_header_value_lossy(&foo), "caf; v=1");
+
+    let template = template.with_bidi_isolation(true);
+    assert_eq!(template.format_header_value_lossy(&foo), "caf; v=1");
+}
+
+#[test]
+fn test_escape_filename() {
+    use typlate::escape::{MAX_FILENAME_LEN, filename};
+
+    assert_eq!(filename("report 2024"), "report 2024");
+    assert_eq!(filename("../a\\b/c\0d"), "..abcd");
+    assert_eq!(filename(".."), "");
+    assert_eq!(filename("name. . "), "name");
+    assert_eq!(filename("a<b>c:d\"e|f?g*h\ni"), "abcdefghi");
+    assert_eq!(filename("con"), "_con");
+    assert_eq!(filename("Lpt1.tar.gz"), "_Lpt1.tar.gz");
+    assert_eq!(filename("console"), "console");
+    assert_eq!(filename(".hidden"), ".hidden");
+
+    let long = "\u{e9}".repeat(200);
+    assert_eq!(filename(&long).len(), MAX_FILENAME_LEN - 1);
+    let long = format!("{}. .{}", "a".repeat(MAX_FILENAME_LEN - 3), "b".repeat(10));
+    assert_eq!(filename(&long), "a".repeat(MAX_FILENAME_LEN - 3));
+
+    let template: TemplateString<Foo> = "{qux}-{bar}.txt".parse().unwrap();
+    let ctx = RenderCtx::new().with_escaper(filename);
+    assert_eq!(
+        template.format_with_ctx(&Foo { bar: 1, qux: "/etc/" }, &ctx),
+        "etc-1.txt"
+    );
+}
+
+#[test]
+fn test_truncate_filter() {
+    let template: TemplateString<Foo> = "{qux|truncate(5)}|{qux|truncate(5, \"...\")}|{qux|truncate(x)}"
+        .parse()
+        .unwrap();
+    assert_eq!(template.format(&Foo { bar: 0, qux: "hello" }), "hello|hello|hello");
+    assert_eq!(
+        template.format(&Foo {
+            bar: 0,
+            qux: "hello world"
+        }),
+        "hell\u{2026}|he...|hello world"
+    );
+
+    // The ellipsis is left out when it does not fit in the length.
+    let template: TemplateString<Foo> = "{qux|truncate(2, \"...\")}|{qux|truncate(0)}".parse().unwrap();
+    assert_eq!(template.format(&Foo { bar: 0, qux: "hello" }), "he|");
+
+    let template: TemplateString<Foo> = "{qux|truncate(4)}".parse().unwrap();
+    let qux = "\u{1f469}\u{200d}\u{1f4bb}e\u{301}\u{4e2d}\u{6587}\u{5b57}";
+    assert_eq!(
+        template.format(&Foo { bar: 0, qux }),
+        "\u{1f469}\u{200d}\u{1f4bb}e\u{301}\u{4e2d}\u{2026}"
+    );
+}
+
+#[test]
+fn test_expressions() {
+    #[derive(TemplateParams)]
+    struct Line {
+        price: f64,
+        quantity: u32,
+        name: &'static str,
+        discount: Option<i32>,
+    }
+
+    let line = Line {
+        price: 2.5,
+        quantity: 4,
+        name: "pen",
+        discount: Some(-3),
+    };
+    let template: TemplateString<Line> = "{quantity+1}x {name}: {price * quantity} ({ quantity / 3 }, {quantity / 2}, \
+                                          {(quantity - discount) * 2 - 1})"
+        .parse()
+        .unwrap();
+    assert_eq!(template.format(&line), "5x pen: 10 (1.3333333333333333, 2, 13)");
+    assert_eq!(
+        template.to_string(),
+        "{quantity + 1}x {name}: {price * quantity} ({quantity / 3}, {quantity / 2}, {(quantity - discount) * 2 - 1})"
+    );
+    assert_eq!(template.placeholders().count(), 8);
+    assert_eq!(template.dry_run(&line), Ok(()));
+    assert_eq!(TemplateString::from_bytes(&template.to_bytes()), Ok(template.clone()));
+
+    let template: TemplateString<Line> = "{quantity - (discount - 1)}|{quantity - discount - 1}".parse().unwrap();
+    assert_eq!(
+        template.to_string(),
+        "{quantity - (discount - 1)}|{quantity - discount - 1}"
+    );
+    assert_eq!(template.format(&line), "8|6");
+    let line = Line { discount: None, ..line };
+    assert_eq!(template.format(&line), "|");
+    assert_eq!(
+        template.dry_run(&line),
+        Err(RenderError::MissingField { name: "discount" })
+    );
+    let template: TemplateString<Line> = "{price + name}".parse().unwrap();
+    assert_eq!(template.dry_run(&line), Err(RenderError::NotNumeric { name: "name" }));
+
+    let error = |source: &str| source.parse::<TemplateString<Line>>().unwrap_err();
+    assert_eq!(
+        error("{quantity +}"),
+        TemplateParseError::InvalidExpression { span: 11..11 }
+    );
+    assert_eq!(
+        error("{(price * 2}"),
+        TemplateParseError::InvalidExpression { span: 11..11 }
+    );
+    assert_eq!(
+        error("{price 2 + 1}"),
+        TemplateParseError::InvalidExpression { span: 7..8 }
+    );
+    assert_eq!(
+        error("{1.2.3 + price}"),
+        TemplateParseError::InvalidExpression { span: 1..6 }
+    );
+    assert_eq!(
+        error("{price + cost}"),
+        TemplateParseError::UnknownField {
+            name: "cost".into(),
+            span: 9..13
+        }
+    );
+}
+
+#[test]
+fn test_format_specs() {
+    #[derive(TemplateParams)]
+    struct Row {
+        name: &'static str,
+        price: f64,
+        count: i32,
+        width: usize,
+        digits: Option<u8>,
+    }
+
+    let row = Row {
+        name: "pen",
+        price: 2.5,
+        count: 7,
+        width: 6,
+        digits: Some(2),
+    };
+    let template: TemplateString<Row> = "[{name:width$}|{price:>width$.digits$}|{count:+04}|{name:*^7}|{name:.2}]"
+        .parse()
+        .unwrap();
+    assert_eq!(template.format(&row), "[pen   |  2.50|+007|**pen**|pe]");
+    assert_eq!(
+        template.to_string(),
+        "[{name:width$}|{price:>width$.digits$}|{count:+04}|{name:*^7}|{name:.2}]"
+    );
+    assert_eq!(TemplateString::from_bytes(&template.to_bytes()), Ok(template.clone()));
+    assert_eq!(
+        template.to_string().parse::<TemplateString<Row>>(),
+        Ok(template.clone())
+    );
+
+    let template: TemplateString<Row> = "{count:width$.digits$}".parse().unwrap();
+    assert_eq!(template.format(&row), "     7");
+    let row = Row { digits: None, ..row };
+    assert_eq!(
+        "{price:.digits$}".parse::<TemplateString<Row>>().unwrap().format(&row),
+        "2.5"
+    );
+    let row = Row {
+        width: usize::MAX,
+        ..row
+    };
+    assert_eq!(
+        "{name:width$}|{count:0width$}"
+            .parse::<TemplateString<Row>>()
+            .unwrap()
+            .format(&row),
+        "pen|7"
+    );
+    #[cfg(feature = "unicode-width")]
+    assert_eq!(
+        "[{name:<6}|{name:^7}]"
+            .parse::<TemplateString<Row>>()
+            .unwrap()
+            .format(&Row { name: "東京", ..row }),
+        "[東京  | 東京  ]"
+    );
+
+    let error = |source: &str| source.parse::<TemplateString<Row>>().unwrap_err();
+    assert_eq!(
+        error("{name:>8q}"),
+        TemplateParseError::InvalidFormatSpec { span: 8..9 }
+    );
+    assert_eq!(error("{name:8.}"), TemplateParseError::InvalidFormatSpec { span: 7..8 });
+    assert_eq!(
+        error("{price:.70000}"),
+        TemplateParseError::InvalidFormatSpec { span: 8..13 }
+    );
+    assert_eq!(
+        error("{name:99999999999999999999}"),
+        TemplateParseError::InvalidFormatSpec { span: 6..26 }
+    );
+    assert_eq!(
+        error("{name:size$}"),
+        TemplateParseError::UnknownField {
+            name: "size".into(),
+            span: 6..10
+        }
+    );
+}
+
+#[test]
+fn test_debug_format() {
+    #[allow(dead_code)]
+    #[derive(Debug)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    #[derive(TemplateParams)]
+    struct Event<'a> {
+        name: &'a str,
+        #[template(debug)]
+        tags: Vec<&'a str>,
+        #[template(debug)]
+        retry: Option<u32>,
+    }
+
+    let event = Event {
+        name: "sync",
+        tags: vec!["a", "b"],
+        retry: Some(3),
+    };
+    let template: TemplateString<Event> = "{name}: {tags:?} {retry:?} [{retry:>9?}]".parse().unwrap();
+    assert_eq!(template.format(&event), r#"sync: ["a", "b"] Some(3) [  Some(3)]"#);
+    assert_eq!(template.to_string(), "{name}: {tags:?} {retry:?} [{retry:>9?}]");
+    assert_eq!(TemplateString::from_bytes(&template.to_bytes()), Ok(template.clone()));
+    assert_eq!(
+        "{name:?}".parse::<TemplateString<Event>>(),
+        Err(TemplateParseError::UnsupportedFormat {
+            name: "name".into(),
+            span: 1..5
+        })
+    );
+    assert_eq!(
+        "{tags:#}".parse::<TemplateString<Event>>(),
+        Err(TemplateParseError::InvalidFormatSpec { span: 6..7 })
+    );
+
+    #[derive(TemplateParams)]
+    #[template(debug)]
+    struct Diagnostic {
+        at: Point,
+        code: u16,
+    }
+
+    let diagnostic = Diagnostic {
+        at: Point { x: 1, y: 2 },
+        code: 7,
+    };
+    let template: TemplateString<Diagnostic> = "{code:03?} {at:?}\n{at:#?}".parse().unwrap();
+    assert_eq!(
+        template.format(&diagnostic),
+        "007 Point { x: 1, y: 2 }\nPoint {\n    x: 1,\n    y: 2,\n}"
+    );
+    // Plain placeholders render with `Display` when the field type implements it, and with `Debug` otherwise.
+    let template: TemplateString<Diagnostic> = "{code} {at}".parse().unwrap();
+    assert_eq!(template.format(&diagnostic), "7 Point { x: 1, y: 2 }");
+}
+
+#[test]
+fn test_radix_format() {
+    #[derive(TemplateParams)]
+    struct Status {
+        code: u32,
+        flags: u8,
+        mode: Option<u16>,
+        offset: i64,
+        ratio: f64,
+    }
+
+    let status = Status {
+        code: 0xbeef,
+        flags: 0b101,
+        mode: Some(0o755),
+        offset: -255,
+        ratio: 0.5,
+    };
+    let template: TemplateString<Status> = "{code:x} {code:#X} {flags:#b} {flags:08b} {mode:o} {offset:x} {code:#010x}"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        template.format(&status),
+        "beef 0xBEEF 0b101 00000101 755 -ff 0x0000beef"
+    );
+    assert_eq!(
+        template.to_string(),
+        "{code:x} {code:#X} {flags:#b} {flags:08b} {mode:o} {offset:x} {code:#010x}"
+    );
+    assert_eq!(TemplateString::from_bytes(&template.to_bytes()), Ok(template.clone()));
+
+    assert_eq!(
+        "{ratio:x}".parse::<TemplateString<Status>>(),
+        Err(TemplateParseError::UnsupportedFormat {
+            name: "ratio".into(),
+            span: 1..6
+        })
+    );
+    assert_eq!(
+        "{ratio:#}".parse::<TemplateString<Status>>(),
+        Err(TemplateParseError::InvalidFormatSpec { span: 7..8 })
+    );
+}
+
+#[test]
+fn test_conditionals() {
+    #[derive(TemplateParams)]
+    struct Cart {
+        count: u32,
+        role: &'static str,
+        coupon: Option<String>,
+        total: f64,
+    }
+
+    let cart = Cart {
+        count: 3,
+        role: "admin",
+        coupon: None,
+        total: 9.5,
+    };
+    let template: TemplateString<Cart> = "{count} {?count > 1}items{:}item{/}{?role == \"admin\"} (admin){/}{?coupon} \
+                                          with {coupon}{/}"
+        .parse()
+        .unwrap();
+    assert_eq!(template.format(&cart), "3 items (admin)");
+    assert_eq!(
+        template.to_string(),
+        "{count} {?count > 1}items{:}item{/}{?role == \"admin\"} (admin){/}{?coupon} with {coupon}{/}"
+    );
+    assert_eq!(template.dry_run(&cart), Ok(()));
+    assert_eq!(TemplateString::from_bytes(&template.to_bytes()), Ok(template.clone()));
+
+    let cart = Cart {
+        count: 1,
+        role: "guest",
+        coupon: Some("SAVE10".into()),
+        ..cart
+    };
+    assert_eq!(template.format(&cart), "1 item with SAVE10");
+    assert_eq!(template.placeholders().count(), 5);
+
+    let template: TemplateString<Cart> = "{?total >= count * 3}{?role != \
+                                          \"guest\"}big{:}guest{/}{:}small{/}|{?count}some{/}|{?\"b\" < role}after{/}"
+        .parse()
+        .unwrap();
+    assert_eq!(template.format(&cart), "guest|some|after");
+    assert_eq!(
+        template.format(&Cart {
+            total: 2.0,
+            coupon: None,
+            ..cart
+        }),
+        "small|some|after"
+    );
+    assert_eq!(template.format(&Cart { count: 0, ..cart }), "guest||after");
+
+    let error = |source: &str| source.parse::<TemplateString<Cart>>().unwrap_err();
+    assert_eq!(
+        error("{?count > }x{/}"),
+        TemplateParseError::InvalidCondition { span: 10..11 }
+    );
+    assert_eq!(error("{?count}x"), TemplateParseError::InvalidCondition { span: 0..2 });
+    assert_eq!(error("x{/}"), TemplateParseError::InvalidCondition { span: 1..4 });
+    assert_eq!(
+        error("{?count}a{:}b{:}c{/}"),
+        TemplateParseError::InvalidCondition { span: 13..16 }
+    );
+    assert_eq!(
+        error("{?role == \"admin}x{/}"),
+        TemplateParseError::InvalidCondition { span: 10..16 }
+    );
+}
+
+#[test]
+fn test_inline_conditionals() {
+    #[derive(TemplateParams)]
+    struct User {
+        is_admin: bool,
+        name: &'static str,
+        unread: u32,
+    }
+
+    let user = User {
+        is_admin: true,
+        name: "Alice",
+        unread: 1,
+    };
+    let template: TemplateString<User> = "{name} ({is_admin ? \"Admin\" : \"User\"}): {unread} {unread != 1 \
+                                          ?\"messages\":\"message\"}{name == \"Alice\" ? \"!\" : \"\"}"
+        .parse()
+        .unwrap();
+    assert_eq!(template.format(&user), "Alice (Admin): 1 message!");
+    let user = User {
+        is_admin: false,
+        name: "Bob",
+        unread: 2,
+    };
+    assert_eq!(template.format(&user), "Bob (User): 2 messages");
+    assert_eq!(
+        template.to_string(),
+        "{name} ({?is_admin}Admin{:}User{/}): {unread} {?unread != 1}messages{:}message{/}{?name == \"Alice\"}!{/}"
+    );
+    assert_eq!(template, template.to_string().parse().unwrap());
+
+    let template: TemplateString<User> = "{is_admin ? \"{a, b}\" : \"|\"}".parse().unwrap();
+    assert_eq!(template.format(&user), "|");
+
+    let error = |source: &str| source.parse::<TemplateString<User>>().unwrap_err();
+    assert_eq!(
+        error("{is_admin ? Admin : \"User\"}"),
+        TemplateParseError::InvalidCondition { span: 12..13 }
+    );
+    assert_eq!(
+        error("{is_admin ? \"Admin\" \"User\"}"),
+        TemplateParseError::InvalidCondition { span: 20..21 }
+    );
+    assert_eq!(
+        error("{is_admin ? \"Admin\" : \"User\" x}"),
+        TemplateParseError::InvalidCondition { span: 29..30 }
+    );
+    assert_eq!(
+        error("{ ? \"a\" : \"b\"}"),
+        TemplateParseError::InvalidCondition { span: 2..3 }
+    );
+    assert_eq!(
+        error("{is_admin ? \"Admin"),
+        TemplateParseError::UnclosedBracket { offset: 0 }
+    );
+}
+
+#[test]
+fn test_nested_templates() {
+    #[derive(TemplateParams)]
+    struct Message<'a> {
+        sender: &'a str,
+        count: u32,
+    }
+
+    #[derive(TemplateParams)]
+    struct Notification {
+        user: String,
+        sender: String,
+        #[template(params = "message")]
+        body: TemplateString<Message<'static>>,
+        #[template(params = "message")]
+        footer: Option<TemplateString<Message<'static>>>,
+    }
+
+    impl Notification {
+        fn message(&self) -> Message<'_> {
+            Message {
+                sender: &self.sender,
+                count: 2,
+            }
+        }
+    }
+
+    let mut notification = Notification {
+        user: "Alice".into(),
+        sender: "Bob".into(),
+        body: "{sender} sent you {count} messages".parse().unwrap(),
+        footer: None,
+    };
+    let template: TemplateString<Notification> = "Hi {user}! {body|upper}.{footer}".parse().unwrap();
+    assert_eq!(template.format(&notification), "Hi Alice! BOB SENT YOU 2 MESSAGES.");
+    assert_eq!(
+        template.dry_run(&notification),
+        Err(RenderError::MissingField { name: "footer" })
+    );
+    notification.footer = Some(" Reply to {sender}.".parse().unwrap());
+    assert_eq!(
+        template.format(&notification),
+        "Hi Alice! BOB SENT YOU 2 MESSAGES. Reply to Bob."
+    );
+}
+
+#[test]
+fn test_render_depth() {
+    #[derive(TemplateParams)]
+    struct Node {
+        name: &'static str,
+        #[template(params = "this")]
+        body: TemplateString<Node>,
+    }
+
+    impl Node {
+        fn this(&self) -> &Self {
+            self
+        }
+    }
+
+    let node = Node {
+        name: "a",
+        body: "<{name}{body}>".parse().unwrap(),
+    };
+    let template: TemplateString<Node> = "{body}".parse().unwrap();
+    assert_eq!(
+        template.dry_run(&node),
+        Err(RenderError::DepthExceeded {
+            limit: typlate::DEFAULT_MAX_RENDER_DEPTH
+        })
+    );
+    assert_eq!(
+        template.try_format(&node),
+        Err(RenderError::DepthExceeded {
+            limit: typlate::DEFAULT_MAX_RENDER_DEPTH
+        })
+    );
+    assert_eq!(template.format(&node), "<a".repeat(typlate::DEFAULT_MAX_RENDER_DEPTH));
+    let mut output = String::new();
+    assert!(std::fmt::Write::write_fmt(&mut output, format_args!("{}", template.display(&node))).is_err());
+
+    let node = Node {
+        name: "a",
+        body: "{name}".parse().unwrap(),
+    };
+    assert_eq!(template.format(&node), "a");
+    assert_eq!(template.dry_run(&node), Ok(()));
+}
+
+#[test]
+fn test_descriptor() {
+    /// A shopping cart.
+    #[derive(TemplateParams)]
+    #[template(descriptor, inline(summary = "{count} items"))]
+    #[allow(dead_code)]
+    struct Cart {
+        /// The name of the "customer".
+        ///
+        /// Shown in greetings.
+        name: String,
+        count: u32,
+        coupon: Option<&'static str>,
+        #[template(default = "none")]
+        note: Option<String>,
+        express: bool,
+        tags: [&'static str; 2],
+    }
+
+    let descriptor: serde_json::Value = serde_json::from_str(Cart::template_descriptor()).unwrap();
+    assert_eq!(
+        descriptor,
+        serde_json::json!({
+            "name": "Cart",
+            "docs": "A shopping cart.",
+            "fields": [
+                { "name": "name", "docs": "The name of the \"customer\".\n\nShown in greetings.", "kind": "string", "optional": false },
+                { "name": "count", "docs": "", "kind": "number", "optional": false },
+                { "name": "coupon", "docs": "", "kind": "string", "optional": true },
+                { "name": "note", "docs": "", "kind": "string", "optional": false },
+                { "name": "express", "docs": "", "kind": "boolean", "optional": false },
+                { "name": "tags", "docs": "", "kind": "array", "optional": false },
+            ],
+            "inline": [{ "name": "summary", "template": "{count} items" }],
+        })
+    );
+}
+
+#[test]
+fn test_complete() {
+    let fields = ["name", "count", "country", "is_admin"];
+    let complete = |source: &str, cursor: usize| {
+        typlate::complete(source, cursor, &fields)
+            .into_iter()
+            .map(|completion| (completion.label, completion.span))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(complete("{co", 3), [("count".into(), 1..3), ("country".into(), 1..3)]);
+    assert_eq!(complete("Hi {nam} {{", 6), [("name".into(), 4..7)]);
+    assert_eq!(complete("{", 1).len(), 4);
+    assert_eq!(complete("{price * nam", 12), [("name".into(), 9..12)]);
+    assert_eq!(complete("{?count > 1}{is_", 16), [("is_admin".into(), 13..16)]);
+    assert_eq!(
+        complete("{count, select, 1 {one} other {{nam}}}", 35),
+        [("name".into(), 32..35)]
+    );
+
+    assert_eq!(complete("co", 2), []);
+    assert_eq!(complete("{{co", 4), []);
+    assert_eq!(complete("{name|up", 8), []);
+    assert_eq!(complete("{count, select, 1 {one} ot", 26), []);
+    assert_eq!(complete("{count, select, other {co", 25), []);
+    assert_eq!(complete("{name == \"co", 12), []);
+    assert_eq!(complete("{%block co", 10), []);
+    assert_eq!(complete("{x}", 2), []);
+}
+
+#[test]
+fn test_tokenize() {
+    use typlate::TokenKind::*;
+
+    let tokens = |source: &'static str| {
+        typlate::tokenize(source)
+            .into_iter()
+            .map(|(span, kind)| (&source[span], kind))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        tokens("{{{x}}} {n, select, 1 {one} other {{n} items}}"),
+        [
+            ("{{", Escape),
+            ("{", Delimiter),
+            ("x", Name),
+            ("}", Delimiter),
+            ("}}", Escape),
+            (" ", Text),
+            ("{", Delimiter),
+            ("n", Name),
+            (",", Delimiter),
+            ("select", Keyword),
+            (",", Delimiter),
+            ("1", Key),
+            ("{", Delimiter),
+            ("one", Text),
+            ("}", Delimiter),
+            ("other", Key),
+            ("{", Delimiter),
+            ("{", Delimiter),
+            ("n", Name),
+            ("}", Delimiter),
+            (" items", Text),
+            ("}", Delimiter),
+            ("}", Delimiter),
+        ]
+    );
+    assert_eq!(
+        tokens("{title|truncate(40, \"...\")}{%block body%}"),
+        [
+            ("{", Delimiter),
+            ("title", Name),
+            ("|", Delimiter),
+            ("truncate", Filter),
+            ("(", Delimiter),
+            ("40", Number),
+            (",", Delimiter),
+            ("\"...\"", String),
+            (")", Delimiter),
+            ("}", Delimiter),
+            ("{%", Delimiter),
+            ("block", Keyword),
+            ("body", Name),
+            ("%}", Delimiter),
+        ]
+    );
+    assert_eq!(
+        tokens("{?count >= 2}s{:}{/}{a ? \"x\" : \"y\"}{(p + 1) * q}"),
+        [
+            ("{?", Delimiter),
+            ("count", Name),
+            (">=", Operator),
+            ("2", Number),
+            ("}", Delimiter),
+            ("s", Text),
+            ("{:}", Delimiter),
+            ("{/}", Delimiter),
+            ("{", Delimiter),
+            ("a", Name),
+            ("?", Operator),
+            ("\"x\"", String),
+            (":", Operator),
+            ("\"y\"", String),
+            ("}", Delimiter),
+            ("{", Delimiter),
+            ("(", Delimiter),
+            ("p", Name),
+            ("+", Operator),
+            ("1", Number),
+            (")", Delimiter),
+            ("*", Operator),
+            ("q", Name),
+            ("}", Delimiter),
+        ]
+    );
+    assert_eq!(
+        tokens("a } {b;"),
+        [
+            ("a ", Text),
+            ("}", Invalid),
+            (" ", Text),
+            ("{", Delimiter),
+            ("b", Name),
+            (";", Invalid)
+        ]
+    );
+    assert_eq!(tokens(""), []);
+}
@@ -0,0 +1,31 @@
+use typlate::TemplateParams;
+use typlate::wasm::{parse, preview, validate};
+
+#[derive(TemplateParams)]
+struct Cart {
+    name: &'static str,
+    count: u32,
+}
+
+#[test]
+fn test_wasm_validate() {
+    assert!(validate::<Cart>("{name}: {count, select, 1 {one item} other {{count} items}}").is_empty());
+
+    let errors = validate::<Cart>("{nam} {cout}");
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[1].message(), "Unknown field name: cout");
+    assert_eq!((errors[1].start(), errors[1].end()), (7, 11));
+
+    // Offsets count UTF-16 code units, so the emoji before the error takes two.
+    let errors = validate::<Cart>("🛒 {nam}");
+    assert_eq!((errors[0].start(), errors[0].end()), (4, 7));
+}
+
+#[test]
+fn test_wasm_parse_and_preview() {
+    assert_eq!(parse::<Cart>("{ count + 1 }").unwrap(), "{count + 1}");
+    assert_eq!(preview::<Cart>("{name|upper} ({count})").unwrap(), "<NAME> (<count>)");
+    let error = preview::<Cart>("{name").unwrap_err();
+    assert_eq!(error.message(), "Unclosed bracket in template");
+    assert_eq!((error.start(), error.end()), (0, 1));
+}
@@ -0,0 +1,41 @@
+use actix_web::Responder;
+use axum_core::response::IntoResponse;
+use typlate::web::Rendered;
+use typlate::{TemplateParams, TemplateString};
+
+#[derive(TemplateParams)]
+struct Page {
+    title: &'static str,
+}
+
+#[test]
+fn test_rendered() {
+    let template: TemplateString<Page> = "<h1>{title}</h1>".parse().unwrap();
+    let rendered = Rendered::html(&template, &Page { title: "Hi" });
+    assert_eq!(rendered.body(), "<h1>Hi</h1>");
+    assert_eq!(rendered.content_type(), "text/html; charset=utf-8");
+
+    let response = rendered.clone().into_response();
+    assert_eq!(response.status(), http::StatusCode::OK);
+    assert_eq!(
+        response.headers()[http::header::CONTENT_TYPE],
+        "text/html; charset=utf-8"
+    );
+
+    let request = actix_web::test::TestRequest::default().to_http_request();
+    let response = rendered.respond_to(&request);
+    assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+    let content_type = response.headers().get(actix_web::http::header::CONTENT_TYPE).unwrap();
+    assert_eq!(content_type, "text/html; charset=utf-8");
+    assert_eq!(response.body(), "<h1>Hi</h1>");
+
+    let invalid = Rendered::from_body("x").with_content_type("text/plain\n");
+    assert_eq!(
+        invalid.clone().into_response().status(),
+        http::StatusCode::INTERNAL_SERVER_ERROR
+    );
+    assert_eq!(
+        invalid.respond_to(&request).status(),
+        actix_web::http::StatusCode::INTERNAL_SERVER_ERROR
+    );
+}
@@ -0,0 +1,52 @@
+use typlate::xliff::{self, XliffError, XliffVersion};
+use typlate::{TemplateParams, TemplateString};
+
+#[derive(TemplateParams)]
+struct Cart {
+    user: String,
+    count: u32,
+}
+
+#[test]
+fn test_xliff_round_trip() {
+    let source: TemplateString<Cart> = "{user} has {count} <items> & {{more}}".parse().unwrap();
+    let translation: TemplateString<Cart> = "{count} \"articles\" pour {user}".parse().unwrap();
+
+    for version in [XliffVersion::V1_2, XliffVersion::V2_0] {
+        let output = xliff::export_xliff(
+            [
+                ("cart.items", &source, Some(&translation)),
+                ("cart.empty", &source, None),
+            ],
+            version,
+            "en",
+            "fr",
+        );
+        let entries = xliff::import_xliff::<Cart>(&output).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].0, "cart.items");
+        assert_eq!(entries[0].1, translation);
+    }
+
+    let output = xliff::export_xliff([("cart.items", &source, None)], XliffVersion::V1_2, "en", "fr");
+    assert!(
+        output.contains(
+            r#"<source><ph id="1">{user}</ph> has <ph id="2">{count}</ph> &lt;items&gt; &amp; {more}</source>"#
+        )
+    );
+}
+
+#[test]
+fn test_xliff_import_errors() {
+    let input = r#"<xliff version="2.0"><file id="f"><unit id="a"><segment>
+        <target>Hallo <ph id="1" equiv="{name}"/></target>
+    </segment></unit></file></xliff>"#;
+    let error = xliff::import_xliff::<Cart>(input).unwrap_err();
+    assert_eq!(error.to_string(), "a: Unknown field name: name");
+
+    let input = r#"<xliff version="1.2"><file><body><trans-unit id="a">
+        <target>Hallo <ph id="1">%s</ph></target>
+    </trans-unit></body></file></xliff>"#;
+    let error = xliff::import_xliff::<Cart>(input).unwrap_err();
+    assert_eq!(error, XliffError::InvalidPlaceholder { key: "a".into() });
+}
@@ -1,50 +1,1215 @@
 #![doc = include_str!("../README.md")]
 
 use proc_macro::TokenStream;
-use quote::quote;
+use quote::{quote, quote_spanned};
+use syn::ext::IdentExt;
 use syn::spanned::Spanned;
-use syn::{Data, DeriveInput, Fields, LitStr, Member, parse_macro_input};
+use syn::{
+    Data, DeriveInput, Field, Fields, GenericArgument, Ident, LitInt, LitStr, Member, PathArguments, Type, TypePath,
+    Variant, parse_macro_input,
+};
 
-#[proc_macro_derive(TemplateParams)]
+/// Options set on a field with `#[template(...)]`.
+#[derive(Default)]
+struct FieldOptions {
+    /// The field type implements `typlate::Facets`.
+    facets: bool,
+    /// The field type implements `Debug`, for `{field:?}` placeholders.
+    debug: bool,
+    /// The explicit 1-based position of the field in positional formats.
+    position: Option<(usize, LitInt)>,
+    /// The text rendered when the field is `None` or empty.
+    default: Option<LitStr>,
+    /// The method returning the parameters of a nested template field.
+    params: Option<Ident>,
+    /// The value of the field in the generated sample parameters.
+    sample: Option<LitStr>,
+    /// The method returning the rendered value of the field, which replaces the field value.
+    getter: Option<Ident>,
+    /// The field type converts into a `SystemTime`, for `relative` placeholders.
+    time: bool,
+}
+
+impl FieldOptions {
+    fn parse(field: &Field) -> syn::Result<Self> {
+        let mut options = Self::default();
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("template")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("facets") {
+                    options.facets = true;
+                    Ok(())
+                } else if meta.path.is_ident("debug") {
+                    options.debug = true;
+                    Ok(())
+                } else if meta.path.is_ident("position") {
+                    let lit: LitInt = meta.value()?.parse()?;
+                    let position = lit.base10_parse()?;
+                    if position == 0 {
+                        return Err(syn::Error::new_spanned(lit, "template positions start at 1"));
+                    }
+                    options.position = Some((position, lit));
+                    Ok(())
+                } else if meta.path.is_ident("default") {
+                    options.default = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("params") {
+                    let lit: LitStr = meta.value()?.parse()?;
+                    options.params = Some(lit.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("sample") {
+                    options.sample = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("getter") {
+                    let lit: LitStr = meta.value()?.parse()?;
+                    options.getter = Some(lit.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("time") {
+                    options.time = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown template attribute"))
+                }
+            })?;
+        }
+        if options.getter.is_some()
+            && let Some(span) = options
+                .default
+                .as_ref()
+                .map(LitStr::span)
+                .or_else(|| options.params.as_ref().map(Ident::span))
+        {
+            return Err(syn::Error::new(
+                span,
+                "getters cannot be combined with params or defaults",
+            ));
+        }
+        Ok(options)
+    }
+}
+
+/// Options set on an error variant with `#[template(...)]`.
+struct VariantOptions {
+    /// The catalog key of the message.
+    key: LitStr,
+    /// The message used when the catalog has no valid message for the key.
+    fallback: Option<LitStr>,
+}
+
+impl VariantOptions {
+    fn parse(variant: &Variant) -> syn::Result<Self> {
+        let mut key = None;
+        let mut fallback = None;
+        for attr in variant.attrs.iter().filter(|attr| attr.path().is_ident("template")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("key") {
+                    key = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("fallback") {
+                    fallback = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown template attribute"))
+                }
+            })?;
+        }
+        match key {
+            Some(key) => Ok(Self { key, fallback }),
+            None => Err(syn::Error::new_spanned(
+                &variant.ident,
+                "expected `#[template(key = \"...\")]` on the variant",
+            )),
+        }
+    }
+}
+
+/// Options set on the struct with `#[template(...)]`.
+#[derive(Default)]
+struct StructOptions {
+    /// Virtual fields declared with `inline(name = "template")`, as pairs of a name and a template source.
+    inline: Vec<(LitStr, LitStr)>,
+    /// Generate a `template_descriptor` function returning a JSON description of the fields.
+    descriptor: bool,
+    /// Placeholder names for the fields of a tuple struct, declared with `names("x", "y")`.
+    names: Option<(Vec<LitStr>, syn::Path)>,
+    /// Implement `typlate::testing::Sample` with deterministic parameter values.
+    sample: bool,
+    /// All field types implement `Debug`, for `{field:?}` placeholders.
+    debug: bool,
+    /// The filters applied to every placeholder, declared with `default_filter = "trim|html_escape"`, spanned at the
+    /// attribute value.
+    default_filters: Vec<LitStr>,
+}
+
+impl StructOptions {
+    fn parse(input: &DeriveInput) -> syn::Result<Self> {
+        let mut options = Self::default();
+        for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("template")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("inline") {
+                    meta.parse_nested_meta(|meta| {
+                        let Some(ident) = meta.path.get_ident() else {
+                            return Err(meta.error("expected an inline field name"));
+                        };
+                        let name = LitStr::new(&ident.unraw().to_string(), ident.span());
+                        if options.inline.iter().any(|(other, _)| other.value() == name.value()) {
+                            return Err(meta.error("duplicate inline field"));
+                        }
+                        options.inline.push((name, meta.value()?.parse()?));
+                        Ok(())
+                    })
+                } else if meta.path.is_ident("names") {
+                    let content;
+                    syn::parenthesized!(content in meta.input);
+                    let names = syn::punctuated::Punctuated::<LitStr, syn::Token![,]>::parse_terminated(&content)?;
+                    if let Some(name) = names
+                        .iter()
+                        .enumerate()
+                        .find(|&(i, name)| names.iter().take(i).any(|other| other.value() == name.value()))
+                        .map(|(_, name)| name)
+                    {
+                        return Err(syn::Error::new_spanned(name, "duplicate field name"));
+                    }
+                    if let Some(name) = names.iter().find(|name| !is_field_name(&name.value())) {
+                        return Err(syn::Error::new_spanned(name, "field names must be identifiers"));
+                    }
+                    options.names = Some((names.into_iter().collect(), meta.path.clone()));
+                    Ok(())
+                } else if meta.path.is_ident("descriptor") {
+                    options.descriptor = true;
+                    Ok(())
+                } else if meta.path.is_ident("sample") {
+                    options.sample = true;
+                    Ok(())
+                } else if meta.path.is_ident("debug") {
+                    options.debug = true;
+                    Ok(())
+                } else if meta.path.is_ident("default_filter") {
+                    let chain: LitStr = meta.value()?.parse()?;
+                    let names: Vec<_> = chain.value().split('|').map(|name| name.trim().to_string()).collect();
+                    if names
+                        .iter()
+                        .any(|name| name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_'))
+                    {
+                        return Err(syn::Error::new_spanned(chain, "expected filter names separated by `|`"));
+                    }
+                    options.default_filters = names.iter().map(|name| LitStr::new(name, chain.span())).collect();
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown template attribute"))
+                }
+            })?;
+        }
+        Ok(options)
+    }
+}
+
+/// Whether a name can be used as a placeholder: a Unicode identifier, following the same rules as the parser.
+fn is_field_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some('_') => name.len() > 1 && chars.all(unicode_ident::is_xid_continue),
+        Some(char) => unicode_ident::is_xid_start(char) && chars.all(unicode_ident::is_xid_continue),
+        None => false,
+    }
+}
+
+/// Whether the type is syntactically an `Option<_>`.
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path.qself.is_none() && path.path.segments.last().is_some_and(|s| s.ident == "Option"),
+        _ => false,
+    }
+}
+
+/// The type argument of a syntactic `Option<_>`.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else { return None };
+    let segment = path.path.segments.last().filter(|_| is_option(ty))?;
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => match args.args.first()? {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Whether the type is syntactically a `String` or a `&str`.
+fn is_string(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path.qself.is_none() && path.path.segments.last().is_some_and(|s| s.ident == "String"),
+        Type::Reference(reference) => matches!(&*reference.elem, Type::Path(path) if path.path.is_ident("str")),
+        _ => false,
+    }
+}
+
+/// Whether the type is syntactically a primitive integer or float type, or a reference to one.
+fn is_number(ty: &Type) -> bool {
+    const NUMBERS: &[&str] = &[
+        "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize", "f32", "f64",
+    ];
+    match ty {
+        Type::Path(path) => {
+            path.qself.is_none()
+                && path
+                    .path
+                    .get_ident()
+                    .is_some_and(|ident| NUMBERS.contains(&&*ident.to_string()))
+        }
+        Type::Reference(reference) => is_number(&reference.elem),
+        _ => false,
+    }
+}
+
+/// Whether the type is syntactically a primitive integer type, or a reference to one.
+fn is_integer(ty: &Type) -> bool {
+    match ty {
+        Type::Reference(reference) => is_integer(&reference.elem),
+        _ => is_number(ty) && !matches!(ty, Type::Path(path) if path.path.is_ident("f32") || path.path.is_ident("f64")),
+    }
+}
+
+/// Whether the type is syntactically a `SystemTime`, which does not implement `Display`: `std::time::SystemTime`, or
+/// any path ending in `SystemTime` for a field marked with `#[template(time)]`.
+fn is_system_time(ty: &Type, time: bool) -> bool {
+    matches!(ty, Type::Path(path) if path.qself.is_none() && if time {
+        path.path.segments.last().is_some_and(|segment| segment.ident == "SystemTime")
+    } else {
+        is_path(path, &["std", "time", "SystemTime"])
+    })
+}
+
+/// Whether the segments of the path are exactly `segments`, ignoring generic arguments.
+fn is_path(path: &TypePath, segments: &[&str]) -> bool {
+    path.path.segments.len() == segments.len()
+        && path
+            .path
+            .segments
+            .iter()
+            .zip(segments)
+            .all(|(segment, name)| segment.ident == name)
+}
+
+/// Whether the type is syntactically a byte buffer: a `Vec<u8>`, `&[u8]`, `Box<[u8]>` or `[u8; N]`.
+fn is_bytes(ty: &Type) -> bool {
+    let is_u8 = |ty: &Type| matches!(ty, Type::Path(path) if path.path.is_ident("u8"));
+    let is_slice = |ty: &Type| matches!(ty, Type::Slice(slice) if is_u8(&slice.elem));
+    match ty {
+        Type::Array(array) => is_u8(&array.elem),
+        Type::Reference(reference) => is_slice(&reference.elem),
+        Type::Path(path) if path.qself.is_none() => path.path.segments.last().is_some_and(|segment| {
+            match (&segment.arguments, segment.ident.to_string().as_str()) {
+                (PathArguments::AngleBracketed(args), "Vec") => {
+                    matches!(args.args.first(), Some(GenericArgument::Type(ty)) if is_u8(ty))
+                }
+                (PathArguments::AngleBracketed(args), "Box") => {
+                    matches!(args.args.first(), Some(GenericArgument::Type(ty)) if is_slice(ty))
+                }
+                _ => false,
+            }
+        }),
+        _ => false,
+    }
+}
+
+/// Whether the type is syntactically a point in time that converts into a `SystemTime`, written as a fully qualified
+/// `std::time::SystemTime`, `time::OffsetDateTime`, `time::UtcDateTime` or `chrono::DateTime`. Other paths, such as an
+/// imported `SystemTime`, need `#[template(time)]`.
+fn is_time(ty: &Type) -> bool {
+    const TIMES: &[&[&str]] = &[
+        &["std", "time", "SystemTime"],
+        &["time", "OffsetDateTime"],
+        &["time", "UtcDateTime"],
+        &["chrono", "DateTime"],
+    ];
+    match ty {
+        Type::Path(path) => path.qself.is_none() && TIMES.iter().any(|segments| is_path(path, segments)),
+        _ => false,
+    }
+}
+
+/// Dereference an expression of a number type until it is a plain number.
+fn deref_number(ty: &Type, mut value: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    let mut ty = ty;
+    while let Type::Reference(reference) = ty {
+        value = quote! { *#value };
+        ty = &reference.elem;
+    }
+    value
+}
+
+/// The kind of a field in the JSON descriptor, from its syntactic type.
+fn field_kind(ty: &Type, options: &FieldOptions) -> &'static str {
+    let ty = option_inner(ty).unwrap_or(ty);
+    if options.params.is_some() {
+        "template"
+    } else if options.getter.is_some() || is_string(ty) {
+        "string"
+    } else if is_number(ty) {
+        "number"
+    } else if matches!(ty, Type::Path(path) if path.path.is_ident("bool")) {
+        "boolean"
+    } else if let Type::Array(_) = ty {
+        "array"
+    } else {
+        "other"
+    }
+}
+
+/// The sample value of a field: the `sample` text if given, the field name for strings, a number from the field index
+/// for numbers, `true` for booleans and the default value otherwise. Options and arrays are filled with samples.
+fn sample_value(ty: &Type, name: &str, index: usize, sample: Option<&LitStr>) -> proc_macro2::TokenStream {
+    if let Some(inner) = option_inner(ty) {
+        let value = sample_value(inner, name, index, sample);
+        return quote! { ::std::option::Option::Some(#value) };
+    }
+    if let Type::Array(array) = ty {
+        let value = sample_value(&array.elem, name, index, sample);
+        return quote! { ::std::array::from_fn(|_| #value) };
+    }
+    if let Type::Reference(reference) = ty
+        && sample.is_none()
+        && is_number(&reference.elem)
+    {
+        let value = sample_value(&reference.elem, name, index, None);
+        return quote! { &(#value) };
+    }
+    if is_string(ty) {
+        let text = sample.cloned().unwrap_or_else(|| LitStr::new(name, ty.span()));
+        return match ty {
+            Type::Reference(_) => quote! { #text },
+            _ => quote! { ::std::string::String::from(#text) },
+        };
+    }
+    match sample {
+        Some(text) => {
+            let message = format!("invalid sample for field `{name}`");
+            quote! {
+                match ::std::str::FromStr::from_str(#text) {
+                    ::std::result::Result::Ok(value) => value,
+                    ::std::result::Result::Err(_) => panic!(#message),
+                }
+            }
+        }
+        None if is_number(ty) => {
+            let number = LitInt::new(&(index + 1).to_string(), ty.span());
+            quote! { #number as #ty }
+        }
+        None if matches!(ty, Type::Path(path) if path.path.is_ident("bool")) => quote! { true },
+        None => quote! { ::std::default::Default::default() },
+    }
+}
+
+/// The documentation of an item from its `#[doc = "..."]` attributes.
+fn doc_comment(attrs: &[syn::Attribute]) -> String {
+    let lines: Vec<_> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(lit),
+                        ..
+                    }),
+                ..
+            }) => Some(lit.value()),
+            _ => None,
+        })
+        .collect();
+    lines
+        .iter()
+        .map(|line| line.strip_prefix(' ').unwrap_or(line))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// A JSON string literal.
+fn json_string(value: &str) -> String {
+    let mut json = String::from('"');
+    for char in value.chars() {
+        match char {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            char if char.is_control() => json.push_str(&format!("\\u{:04x}", char as u32)),
+            char => json.push(char),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[proc_macro_derive(TemplateParams, attributes(template))]
 pub fn derive_template_params(input: TokenStream) -> TokenStream {
     let input: DeriveInput = parse_macro_input!(input);
     let ident = &input.ident;
-    let generics = &input.generics;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
 
     let mut ident_names = vec![];
     let mut match_arms = vec![];
+    let mut presence_arms = vec![];
+    let mut facet_arms = vec![];
+    let mut number_arms = vec![];
+    let mut item_arms = vec![];
+    let mut debug_arms = vec![];
+    let mut debug_indices = vec![];
+    let mut integer_indices = vec![];
+    let mut time_arms = vec![];
+    let mut bytes_arms = vec![];
+    let mut len_arms = vec![];
+    let mut positions = vec![];
+    let mut descriptors = vec![];
+    let mut samples = vec![];
+
+    let struct_options = match StructOptions::parse(&input) {
+        Ok(options) => options,
+        Err(error) => return error.to_compile_error().into(),
+    };
 
-    match &input.data {
+    let members: Vec<_> = match &input.data {
         Data::Struct(data) => match &data.fields {
-            Fields::Named(fields) => {
-                for (index, field) in fields.named.iter().enumerate() {
-                    let ident = field.ident.as_ref().unwrap();
-                    ident_names.push(LitStr::new(&ident.to_string(), field.span()));
-                    match_arms.push(quote! { #index => ::std::fmt::Display::fmt(&self.#ident, f), });
-                }
+            Fields::Named(_) | Fields::Unit if struct_options.names.is_some() => {
+                let (_, path) = struct_options.names.as_ref().unwrap();
+                return syn::Error::new_spanned(path, "template names are only supported on tuple structs")
+                    .to_compile_error()
+                    .into();
             }
+            Fields::Named(fields) => fields
+                .named
+                .iter()
+                .map(|field| {
+                    let ident = field.ident.as_ref().unwrap();
+                    (field, ident.unraw().to_string(), Member::Named(ident.clone()))
+                })
+                .collect(),
             Fields::Unnamed(fields) => {
-                for (index, field) in fields.unnamed.iter().enumerate() {
-                    let member = Member::Unnamed(index.into());
-                    ident_names.push(LitStr::new(&index.to_string(), field.span()));
-                    match_arms.push(quote! { #index => ::std::fmt::Display::fmt(&self.#member, f), });
+                if let Some((names, path)) = &struct_options.names
+                    && names.len() != fields.unnamed.len()
+                {
+                    return syn::Error::new_spanned(path, "expected a name for every field")
+                        .to_compile_error()
+                        .into();
                 }
+                fields
+                    .unnamed
+                    .iter()
+                    .enumerate()
+                    .map(|(index, field)| {
+                        let name = match &struct_options.names {
+                            Some((names, _)) => names[index].value(),
+                            None => index.to_string(),
+                        };
+                        (field, name, Member::Unnamed(index.into()))
+                    })
+                    .collect()
             }
-            Fields::Unit => {}
+            Fields::Unit => vec![],
         },
         _ => panic!("TemplateParams can only be derived for structs"),
+    };
+
+    for (index, (field, name, member)) in members.iter().enumerate() {
+        let options = match FieldOptions::parse(field) {
+            Ok(options) => options,
+            Err(error) => return error.to_compile_error().into(),
+        };
+        ident_names.push(LitStr::new(name, field.span()));
+        if let Some(sample) = &options.sample
+            && !struct_options.sample
+        {
+            return syn::Error::new_spanned(sample, "field samples require `#[template(sample)]` on the struct")
+                .to_compile_error()
+                .into();
+        }
+        let value = sample_value(&field.ty, name, index, options.sample.as_ref());
+        samples.push(quote! { #member: #value });
+        if let Some((position, lit)) = &options.position {
+            if positions.contains(&Some(*position)) {
+                return syn::Error::new_spanned(lit, "duplicate template position")
+                    .to_compile_error()
+                    .into();
+            }
+            if *position > members.len() {
+                return syn::Error::new_spanned(lit, "template position exceeds the number of fields")
+                    .to_compile_error()
+                    .into();
+            }
+        }
+        positions.push(options.position.as_ref().map(|(position, _)| *position));
+        descriptors.push(format!(
+            "{{\"name\":{},\"docs\":{},\"kind\":\"{}\",\"optional\":{}}}",
+            json_string(name),
+            json_string(&doc_comment(&field.attrs)),
+            field_kind(&field.ty, &options),
+            is_option(&field.ty) && options.default.is_none() && options.getter.is_none(),
+        ));
+        let debug = options.debug || struct_options.debug;
+        if debug {
+            debug_arms.push(quote! { #index => ::std::fmt::Debug::fmt(&self.#member, f), });
+            debug_indices.push(index);
+        }
+        if options.facets {
+            facet_arms.push(quote! { #index => ::typlate::Facets::facet(&self.#member, facet), });
+        }
+        // Fields with a getter render its value, and are not treated as numbers, times, bytes or lists.
+        if let Some(getter) = &options.getter {
+            match_arms.push(quote! { #index => ::std::fmt::Display::fmt(&self.#getter(), f), });
+            continue;
+        }
+        if is_integer(&field.ty) || option_inner(&field.ty).is_some_and(is_integer) {
+            integer_indices.push(index);
+        }
+        if is_number(&field.ty) {
+            let value = deref_number(&field.ty, quote! { self.#member });
+            number_arms.push(quote! { #index => ::std::option::Option::Some(::typlate::Number::from(#value)), });
+        } else if let Some(inner) = option_inner(&field.ty).filter(|inner| is_number(inner)) {
+            let value = deref_number(inner, quote! { value });
+            number_arms.push(quote! { #index => self.#member.map(|value| ::typlate::Number::from(#value)), });
+        }
+        if let Some(inner) = option_inner(&field.ty)
+            && (options.time || is_time(inner))
+        {
+            time_arms.push(quote! {
+                #index => self.#member.as_ref().map(|value| ::std::time::SystemTime::from(::std::clone::Clone::clone(value))),
+            });
+        } else if options.time || is_time(&field.ty) {
+            time_arms.push(quote! {
+                #index => ::std::option::Option::Some(::std::time::SystemTime::from(::std::clone::Clone::clone(&self.#member))),
+            });
+        }
+        if is_bytes(&field.ty) {
+            bytes_arms.push(quote! {
+                #index => ::std::option::Option::Some(::std::borrow::Cow::Borrowed(&self.#member[..])),
+            });
+        } else if option_inner(&field.ty).is_some_and(is_bytes) {
+            bytes_arms.push(quote! {
+                #index => self.#member.as_ref().map(|value| ::std::borrow::Cow::Borrowed(&value[..])),
+            });
+        }
+        if is_string(&field.ty) {
+            len_arms.push(quote! { #index => self.#member.len(), });
+        } else if option_inner(&field.ty).is_some_and(is_string) {
+            len_arms.push(quote! { #index => self.#member.as_ref().map_or(0, |value| value.len()), });
+        }
+        if let Some(default) = &options.default {
+            let is_empty = if is_option(&field.ty) && option_inner(&field.ty).is_some_and(is_string) {
+                quote! { self.#member.as_ref().is_none_or(|value| value.is_empty()) }
+            } else if is_option(&field.ty) {
+                quote! { self.#member.is_none() }
+            } else if is_string(&field.ty) || matches!(field.ty, Type::Array(_)) {
+                quote! { self.#member.is_empty() }
+            } else {
+                return syn::Error::new_spanned(
+                    default,
+                    "template defaults require an `Option`, string or array field",
+                )
+                .to_compile_error()
+                .into();
+            };
+            match_arms.push(quote! {
+                #index if #is_empty => f.write_str(#default),
+            });
+        }
+        // Nested template fields are rendered with the parameters returned by their accessor, `SystemTime` fields in
+        // RFC 3339 format, byte fields as (lossy) UTF-8, and debug fields with `Debug` if they do not implement
+        // `Display`.
+        let display = match &options.params {
+            Some(params) => quote! {
+                ::typlate::__private::fmt_nested(value, ::std::borrow::Borrow::borrow(&self.#params()), f)
+            },
+            None if is_system_time(option_inner(&field.ty).unwrap_or(&field.ty), options.time) => {
+                quote! { ::typlate::__private::fmt_system_time(value, f) }
+            }
+            None if is_bytes(option_inner(&field.ty).unwrap_or(&field.ty)) => {
+                quote! { f.write_str(&::std::string::String::from_utf8_lossy(value)) }
+            }
+            None if debug => quote! {{
+                use ::typlate::__private::{ViaDebug as _, ViaDisplay as _};
+                (&::typlate::__private::DisplayOrDebug(value)).fmt_value(f)
+            }},
+            None => quote! { ::std::fmt::Display::fmt(value, f) },
+        };
+        if is_option(&field.ty) {
+            match_arms.push(quote! {
+                #index => match &self.#member {
+                    ::std::option::Option::Some(value) => #display,
+                    ::std::option::Option::None => ::std::result::Result::Ok(()),
+                },
+            });
+            if options.default.is_none() {
+                presence_arms.push(quote! { #index => self.#member.is_some(), });
+            }
+        } else if options.params.is_some() {
+            match_arms.push(quote! {
+                #index => {
+                    let value = &self.#member;
+                    #display
+                }
+            });
+        } else if let Type::Array(_) = &field.ty {
+            match_arms.push(quote! {
+                #index => {
+                    for (i, item) in self.#member.iter().enumerate() {
+                        if i > 0 {
+                            f.write_str(", ")?;
+                        }
+                        ::std::fmt::Display::fmt(item, f)?;
+                    }
+                    ::std::result::Result::Ok(())
+                }
+            });
+            item_arms.push(quote! {
+                #index => ::std::option::Option::Some(self.#member.iter().map(::std::string::ToString::to_string).collect()),
+            });
+        } else {
+            match_arms.push(quote! {
+                #index => {
+                    let value = &self.#member;
+                    #display
+                }
+            });
+        }
     }
 
+    if let Some((name, _)) = struct_options
+        .inline
+        .iter()
+        .find(|(name, _)| ident_names.iter().any(|field| field.value() == name.value()))
+    {
+        return syn::Error::new_spanned(name, "inline field has the same name as a field")
+            .to_compile_error()
+            .into();
+    }
+    let inline_fields = struct_options
+        .inline
+        .iter()
+        .map(|(name, source)| quote! { (#name, #source) });
+
+    let position_fn = positions.iter().any(Option::is_some).then(|| {
+        let explicit: Vec<_> = positions.iter().flatten().copied().collect();
+        let mut free = (1..).filter(|position| !explicit.contains(position));
+        let arms = positions.iter().enumerate().map(|(index, position)| {
+            let position = position.unwrap_or_else(|| free.next().unwrap());
+            quote! { #index => #position, }
+        });
+        quote! {
+            fn field_position(index: usize) -> usize {
+                match index {
+                    #(#arms)*
+                    _ => index + 1,
+                }
+            }
+        }
+    });
+
+    let integer_fn = (!integer_indices.is_empty()).then(|| {
+        quote! {
+            fn is_integer_field(index: usize) -> bool {
+                matches!(index, #(#integer_indices)|*)
+            }
+        }
+    });
+
+    let time_fn = (!time_arms.is_empty()).then(|| {
+        quote! {
+            fn field_time(&self, index: usize) -> ::std::option::Option<::std::time::SystemTime> {
+                match index {
+                    #(#time_arms)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+        }
+    });
+
+    let bytes_fn = (!bytes_arms.is_empty()).then(|| {
+        quote! {
+            fn field_bytes(&self, index: usize) -> ::std::option::Option<::std::borrow::Cow<'_, [u8]>> {
+                match index {
+                    #(#bytes_arms)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+        }
+    });
+
+    let default_filters = &struct_options.default_filters;
+    // Whether a filter is built in depends on the features of typlate, so it is checked in a constant.
+    let default_filter_checks = default_filters.iter().map(|name| {
+        let message = format!("unknown filter `{}`, default filters must be built in", name.value());
+        quote_spanned! {name.span()=>
+            const _: () = assert!(::typlate::__private::is_builtin_filter(#name), #message);
+        }
+    });
+    let default_filters_fn = (!default_filters.is_empty()).then(|| {
+        quote! {
+            fn default_filters(_index: usize) -> &'static [&'static str] {
+                &[#(#default_filters),*]
+            }
+
+            fn default_value_filters() -> &'static [&'static str] {
+                &[#(#default_filters),*]
+            }
+        }
+    });
+
+    let debug_fns = (!debug_arms.is_empty()).then(|| {
+        quote! {
+            fn is_debug_field(index: usize) -> bool {
+                matches!(index, #(#debug_indices)|*)
+            }
+
+            fn fmt_field_debug(&self, f: &mut ::std::fmt::Formatter, index: usize) -> ::std::fmt::Result {
+                match index {
+                    #(#debug_arms)*
+                    _ => self.fmt_field(f, index),
+                }
+            }
+        }
+    });
+
+    let descriptor_fn = struct_options.descriptor.then(|| {
+        let inline = struct_options.inline.iter().map(|(name, source)| {
+            format!(
+                "{{\"name\":{},\"template\":{}}}",
+                json_string(&name.value()),
+                json_string(&source.value())
+            )
+        });
+        let json = format!(
+            "{{\"name\":{},\"docs\":{},\"fields\":[{}],\"inline\":[{}]}}",
+            json_string(&ident.to_string()),
+            json_string(&doc_comment(&input.attrs)),
+            descriptors.join(","),
+            inline.collect::<Vec<_>>().join(","),
+        );
+        quote! {
+            impl #impl_generics #ident #type_generics #where_clause {
+                /// A JSON description of the template parameters: their names, documentation and kinds, e.g. for
+                /// autocompletion in template editors.
+                pub fn template_descriptor() -> &'static str {
+                    #json
+                }
+            }
+        }
+    });
+
+    let sample_impl = struct_options.sample.then(|| {
+        quote! {
+            impl #impl_generics ::typlate::testing::Sample for #ident #type_generics #where_clause {
+                fn sample() -> Self {
+                    Self { #(#samples),* }
+                }
+            }
+        }
+    });
+
     quote! {
-        impl #generics ::typlate::TemplateStringParams for #ident #generics {
+        #descriptor_fn
+
+        #sample_impl
+
+        #(#default_filter_checks)*
+
+        impl #impl_generics ::typlate::TemplateStringParams for #ident #type_generics #where_clause {
             const FIELDS: &'static [&'static str] = &[#(#ident_names),*];
 
+            const INLINE_FIELDS: &'static [(&'static str, &'static str)] = &[#(#inline_fields),*];
+
             fn fmt_field(&self, f: &mut ::std::fmt::Formatter, index: usize) -> ::std::fmt::Result {
                 match index {
                     #(#match_arms)*
                     _ => panic!("Index out of bounds"),
                 }
             }
+
+            fn has_field(&self, index: usize) -> bool {
+                match index {
+                    #(#presence_arms)*
+                    _ => true,
+                }
+            }
+
+            fn field_facet(&self, index: usize, facet: &str) -> ::std::option::Option<::std::borrow::Cow<'_, str>> {
+                match index {
+                    #(#facet_arms)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+
+            fn field_number(&self, index: usize) -> ::std::option::Option<::typlate::Number> {
+                match index {
+                    #(#number_arms)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+
+            fn len_hint(&self, index: usize) -> usize {
+                match index {
+                    #(#len_arms)*
+                    _ => 8,
+                }
+            }
+
+            fn field_items(&self, index: usize) -> ::std::option::Option<::std::vec::Vec<::std::string::String>> {
+                match index {
+                    #(#item_arms)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+
+            #position_fn
+
+            #integer_fn
+
+            #time_fn
+
+            #bytes_fn
+
+            #default_filters_fn
+
+            #debug_fns
+        }
+    }
+    .into()
+}
+
+#[proc_macro_derive(TemplateError, attributes(template, source))]
+pub fn derive_template_error(input: TokenStream) -> TokenStream {
+    let input: DeriveInput = parse_macro_input!(input);
+    match template_error(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn template_error(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let ident = &input.ident;
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            ident,
+            "TemplateError can only be derived for enums",
+        ));
+    };
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "TemplateError cannot be derived for generic enums",
+        ));
+    }
+
+    let mut views = vec![];
+    let mut display_arms = vec![];
+    let mut source_arms = vec![];
+    let mut key_arms = vec![];
+    let mut keys = vec![];
+    let mut checks = vec![];
+
+    for variant in &data.variants {
+        let options = VariantOptions::parse(variant)?;
+        let variant_ident = &variant.ident;
+        let bindings: Vec<_> = (0..variant.fields.len())
+            .map(|i| Ident::new(&format!("__field{i}"), variant_ident.span()))
+            .collect();
+        let members: Vec<_> = variant
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| match &field.ident {
+                Some(ident) => Member::Named(ident.clone()),
+                None => Member::Unnamed(i.into()),
+            })
+            .collect();
+        let pattern = quote! { #ident::#variant_ident { #(#members: #bindings),* } };
+
+        // The fields of the variant are borrowed into a parameter struct, with options borrowed inside.
+        let mut view_fields = vec![];
+        let mut view_values = vec![];
+        for ((field, binding), member) in variant.fields.iter().zip(&bindings).zip(&members) {
+            let attrs = field.attrs.iter().filter(|attr| attr.path().is_ident("template"));
+            let (ty, value) = match option_inner(&field.ty) {
+                Some(inner) => (
+                    quote! { ::std::option::Option<&'a #inner> },
+                    quote! { #binding.as_ref() },
+                ),
+                None => {
+                    let ty = &field.ty;
+                    (quote! { &'a #ty }, quote! { #binding })
+                }
+            };
+            view_fields.push(match &field.ident {
+                Some(ident) => quote! { #(#attrs)* #ident: #ty },
+                None => quote! { #(#attrs)* #ty },
+            });
+            view_values.push(quote! { #member: #value });
+        }
+        let view = Ident::new(&format!("__{ident}{variant_ident}"), variant_ident.span());
+        let (view_ty, view_def) = match &variant.fields {
+            Fields::Unit => (quote! { #view }, quote! { struct #view; }),
+            Fields::Named(_) => (
+                quote! { #view<'static> },
+                quote! { struct #view<'a> { #(#view_fields),* } },
+            ),
+            Fields::Unnamed(_) => (
+                quote! { #view<'static> },
+                quote! { struct #view<'a>(#(#view_fields),*); },
+            ),
+        };
+        views.push(quote! {
+            #[derive(::typlate::TemplateParams)]
+            #view_def
+        });
+
+        let key = &options.key;
+        let fallback = match &options.fallback {
+            Some(fallback) => {
+                checks.push(quote! { const _: () = ::typlate::__private::validate_source::<#view_ty>(#fallback); });
+                quote! { ::std::option::Option::Some(#fallback) }
+            }
+            None => quote! { ::std::option::Option::None },
+        };
+        display_arms.push(quote! {
+            #pattern => ::typlate::__private::fmt_error_message(#key, #fallback, &#view { #(#view_values),* }, f),
+        });
+        key_arms.push(quote! { #ident::#variant_ident { .. } => #key, });
+        keys.push((key.clone(), view_ty));
+
+        // The source is the field marked `#[source]`, or else the field named `source`.
+        let source = variant
+            .fields
+            .iter()
+            .position(|field| field.attrs.iter().any(|attr| attr.path().is_ident("source")))
+            .or_else(|| {
+                variant
+                    .fields
+                    .iter()
+                    .position(|field| field.ident.as_ref().is_some_and(|ident| ident == "source"))
+            });
+        if let Some(position) = source {
+            let binding = &bindings[position];
+            source_arms.push(quote! {
+                #pattern => ::std::option::Option::Some(#binding as &(dyn ::std::error::Error + 'static)),
+            });
+        }
+    }
+
+    let key_lits = keys.iter().map(|(key, _)| key);
+    let validations = keys.iter().map(|(key, view_ty)| {
+        quote! { ::typlate::__private::validate_error_message::<#view_ty>(catalog, #key, &mut errors); }
+    });
+
+    Ok(quote! {
+        #[allow(non_camel_case_types)]
+        const _: () = {
+            #(#views)*
+
+            #(#checks)*
+
+            impl ::std::fmt::Display for #ident {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                    match self {
+                        #(#display_arms)*
+                    }
+                }
+            }
+
+            impl ::std::error::Error for #ident {
+                #[allow(unused_variables)]
+                fn source(&self) -> ::std::option::Option<&(dyn ::std::error::Error + 'static)> {
+                    match self {
+                        #(#source_arms)*
+                        _ => ::std::option::Option::None,
+                    }
+                }
+            }
+
+            impl ::typlate::TemplateError for #ident {
+                const KEYS: &'static [&'static str] = &[#(#key_lits),*];
+
+                fn message_key(&self) -> &'static str {
+                    match self {
+                        #(#key_arms)*
+                    }
+                }
+
+                fn validate_catalog(
+                    catalog: &dyn ::typlate::ErrorCatalog,
+                ) -> ::std::vec::Vec<::typlate::CatalogError<&'static str>> {
+                    let mut errors = ::std::vec::Vec::new();
+                    #(#validations)*
+                    errors
+                }
+            }
+        };
+    })
+}
+
+/// The input of `compose_params!`: a tuple struct declaration followed by the parameter types it combines.
+struct ComposeInput {
+    attrs: Vec<syn::Attribute>,
+    vis: syn::Visibility,
+    ident: Ident,
+    generics: syn::Generics,
+    parts: Vec<Type>,
+}
+
+impl syn::parse::Parse for ComposeInput {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(syn::Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        let ident = input.parse()?;
+        let generics = input.parse()?;
+        input.parse::<syn::Token![=]>()?;
+        let parts = syn::punctuated::Punctuated::<Type, syn::Token![+]>::parse_separated_nonempty_with(
+            input,
+            Type::without_plus,
+        )?;
+        input.parse::<Option<syn::Token![;]>>()?;
+        if parts.len() < 2 {
+            return Err(syn::Error::new_spanned(
+                &parts,
+                "expected at least two parameter types separated by `+`",
+            ));
+        }
+        Ok(Self {
+            attrs,
+            vis,
+            ident,
+            generics,
+            parts: parts.into_iter().collect(),
+        })
+    }
+}
+
+/// Declare a tuple struct combining the fields of several parameter types, as in
+/// `compose_params!(pub OrderMail = User + Order)`, which implements `TemplateStringParams` like the pair `(User,
+/// Order)`.
+#[proc_macro]
+pub fn compose_params(input: TokenStream) -> TokenStream {
+    let ComposeInput {
+        attrs,
+        vis,
+        ident,
+        generics,
+        parts,
+    } = parse_macro_input!(input);
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+
+    // The names and inline fields are those of the nested pairs, which concatenate them at compile time.
+    let first = &parts[0];
+    let layered = parts[1..]
+        .iter()
+        .fold(quote! { #first }, |layered, part| quote! { (#layered, #part) });
+    let lens = parts
+        .iter()
+        .map(|part| quote! { <#part as ::typlate::TemplateStringParams>::FIELDS.len() });
+    let locate = quote! { ::typlate::__private::locate_field(&[#(#lens),*], index) };
+    // A match on the part that declares the field at `index`, with `index` rebound to the index within the part.
+    let dispatch = |call: &dyn Fn(&Type, syn::Index) -> proc_macro2::TokenStream| {
+        let arms = parts.iter().enumerate().map(|(i, part)| {
+            let body = call(part, syn::Index::from(i));
+            quote! { (#i, index) => #body, }
+        });
+        quote! {
+            match #locate {
+                #(#arms)*
+                _ => ::std::unreachable!(),
+            }
+        }
+    };
+    let fmt_field = dispatch(&|_, i| quote! { self.#i.fmt_field(f, index) });
+    let has_field = dispatch(&|_, i| quote! { self.#i.has_field(index) });
+    let field_facet = dispatch(&|_, i| quote! { self.#i.field_facet(index, facet) });
+    let field_number = dispatch(&|_, i| quote! { self.#i.field_number(index) });
+    let is_integer_field =
+        dispatch(&|part, _| quote! { <#part as ::typlate::TemplateStringParams>::is_integer_field(index) });
+    let field_time = dispatch(&|_, i| quote! { self.#i.field_time(index) });
+    let field_bytes = dispatch(&|_, i| quote! { self.#i.field_bytes(index) });
+    let default_filters =
+        dispatch(&|part, _| quote! { <#part as ::typlate::TemplateStringParams>::default_filters(index) });
+    let len_hint = dispatch(&|_, i| quote! { self.#i.len_hint(index) });
+    let field_position = dispatch(&|part, _| {
+        quote! { offset + <#part as ::typlate::TemplateStringParams>::field_position(index) }
+    });
+    let is_debug_field =
+        dispatch(&|part, _| quote! { <#part as ::typlate::TemplateStringParams>::is_debug_field(index) });
+    let fmt_field_debug = dispatch(&|_, i| quote! { self.#i.fmt_field_debug(f, index) });
+    let field_items = dispatch(&|_, i| quote! { self.#i.field_items(index) });
+
+    quote! {
+        #(#attrs)*
+        #vis struct #ident #generics (#(pub #parts),*) #where_clause;
+
+        impl #impl_generics ::typlate::TemplateStringParams for #ident #type_generics #where_clause {
+            const FIELDS: &'static [&'static str] = <#layered as ::typlate::TemplateStringParams>::FIELDS;
+
+            const INLINE_FIELDS: &'static [(&'static str, &'static str)] =
+                <#layered as ::typlate::TemplateStringParams>::INLINE_FIELDS;
+
+            fn fmt_field(&self, f: &mut ::std::fmt::Formatter, index: usize) -> ::std::fmt::Result {
+                #fmt_field
+            }
+
+            fn has_field(&self, index: usize) -> bool {
+                #has_field
+            }
+
+            fn field_facet(&self, index: usize, facet: &str) -> ::std::option::Option<::std::borrow::Cow<'_, str>> {
+                #field_facet
+            }
+
+            fn field_number(&self, index: usize) -> ::std::option::Option<::typlate::Number> {
+                #field_number
+            }
+
+            fn is_integer_field(index: usize) -> bool {
+                #is_integer_field
+            }
+
+            fn field_time(&self, index: usize) -> ::std::option::Option<::std::time::SystemTime> {
+                #field_time
+            }
+
+            fn field_bytes(&self, index: usize) -> ::std::option::Option<::std::borrow::Cow<'_, [u8]>> {
+                #field_bytes
+            }
+
+            fn default_filters(index: usize) -> &'static [&'static str] {
+                #default_filters
+            }
+
+            fn default_value_filters() -> &'static [&'static str] {
+                #(
+                    let filters = <#parts as ::typlate::TemplateStringParams>::default_value_filters();
+                    if !filters.is_empty() {
+                        return filters;
+                    }
+                )*
+                &[]
+            }
+
+            fn len_hint(&self, index: usize) -> usize {
+                #len_hint
+            }
+
+            fn field_position(index: usize) -> usize {
+                let offset = index - #locate.1;
+                #field_position
+            }
+
+            fn is_debug_field(index: usize) -> bool {
+                #is_debug_field
+            }
+
+            fn fmt_field_debug(&self, f: &mut ::std::fmt::Formatter, index: usize) -> ::std::fmt::Result {
+                #fmt_field_debug
+            }
+
+            fn field_items(&self, index: usize) -> ::std::option::Option<::std::vec::Vec<::std::string::String>> {
+                #field_items
+            }
         }
     }
     .into()